@@ -1,9 +1,11 @@
 import simpleGit, { type SimpleGit } from 'simple-git'
 import { stat, readFile } from 'fs/promises'
 import { join } from 'path'
-import type { DiffResult, FileDiffInfo, CommitInfo, BranchInfo, WorktreeInfo, PRInfo, PRListResult, PRDiffResult } from './types'
+import type { DiffResult, FileDiffInfo, CommitInfo, BranchInfo, WorktreeInfo, PRInfo, PRListResult, PRDiffResult, PRChecksResult, PRReview } from './types'
+import { createProviderClient, type ProviderClient } from './providers'
 
 export * from './types'
+export { createProviderClient, ProviderError, type ProviderClient } from './providers'
 
 // =============================================================================
 // CACHING
@@ -844,6 +846,13 @@ export async function listPRs(
   const repoRoot = (await git.revparse(['--show-toplevel'])).trim()
   const remoteInfo = await getRemoteUrl(git)
 
+  // Use the provider API directly when a token is configured
+  const client = remoteInfo ? createProviderClient(remoteInfo) : null
+  if (client) {
+    const prs = await client.listPRs(state, limit)
+    return { prs, provider: client.provider, hasGhCli: false }
+  }
+
   if (!remoteInfo || remoteInfo.provider !== 'github') {
     return {
       prs: [],
@@ -927,10 +936,16 @@ export async function listPRs(
 }
 
 /**
- * Get info for a specific PR using gh CLI
+ * Get info for a specific PR using the provider API or gh CLI
  */
 export async function getPRInfo(git: SimpleGit, prNumber: number): Promise<PRInfo | null> {
   const repoRoot = (await git.revparse(['--show-toplevel'])).trim()
+  const remoteInfo = await getRemoteUrl(git)
+  const client = remoteInfo ? createProviderClient(remoteInfo) : null
+  if (client) {
+    return client.getPR(prNumber)
+  }
+
   const ghAvailable = await hasGhCli()
 
   if (!ghAvailable) {
@@ -980,6 +995,34 @@ export async function getPRInfo(git: SimpleGit, prNumber: number): Promise<PRInf
   }
 }
 
+async function requireProviderClient(git: SimpleGit): Promise<ProviderClient> {
+  const remoteInfo = await getRemoteUrl(git)
+  if (!remoteInfo) {
+    throw new Error('No origin remote configured')
+  }
+  const client = createProviderClient(remoteInfo)
+  if (!client) {
+    throw new Error(`No API token configured for ${remoteInfo.provider} (set GITHUB_TOKEN or GITLAB_TOKEN)`)
+  }
+  return client
+}
+
+/**
+ * Get CI check results for the PR's head commit
+ */
+export async function getPRChecks(git: SimpleGit, prNumber: number): Promise<PRChecksResult> {
+  const client = await requireProviderClient(git)
+  return client.getChecks(prNumber)
+}
+
+/**
+ * Submit a review (approval, change request or comment) with optional line comments
+ */
+export async function submitPRReview(git: SimpleGit, prNumber: number, review: PRReview): Promise<void> {
+  const client = await requireProviderClient(git)
+  await client.submitReview(prNumber, review)
+}
+
 /**
  * Get PR diff by fetching the PR ref and comparing against base.
  * Does NOT checkout the PR - just fetches to FETCH_HEAD.
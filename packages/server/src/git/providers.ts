@@ -0,0 +1,318 @@
+import type { RemoteInfo } from './index'
+import type { PRInfo, PRCheck, PRChecksResult, PRReview } from './types'
+
+// =============================================================================
+// PROVIDER API CLIENTS
+// =============================================================================
+
+/**
+ * REST client for a hosting provider's pull/merge request API.
+ * Used when a token is configured; otherwise callers fall back to the gh CLI.
+ */
+export interface ProviderClient {
+  provider: 'github' | 'gitlab'
+  listPRs(state: 'open' | 'closed' | 'all', limit: number): Promise<PRInfo[]>
+  getPR(prNumber: number): Promise<PRInfo | null>
+  getChecks(prNumber: number): Promise<PRChecksResult>
+  submitReview(prNumber: number, review: PRReview): Promise<void>
+}
+
+export class ProviderError extends Error {
+  constructor(message: string, public status: number) {
+    super(message)
+    this.name = 'ProviderError'
+  }
+}
+
+type Env = Record<string, string | undefined>
+
+/**
+ * Create an API client for the remote, or null when the provider is
+ * unsupported or no token is configured.
+ *
+ * GitHub reads GITHUB_TOKEN (or GH_TOKEN) and GITHUB_API_URL; GitLab reads
+ * GITLAB_TOKEN and GITLAB_API_URL.
+ */
+export function createProviderClient(remote: RemoteInfo, env: Env = process.env): ProviderClient | null {
+  if (remote.provider === 'github') {
+    const token = env.GITHUB_TOKEN || env.GH_TOKEN
+    if (!token) return null
+    return createGitHubClient(remote, env.GITHUB_API_URL || 'https://api.github.com', token)
+  }
+  if (remote.provider === 'gitlab') {
+    const token = env.GITLAB_TOKEN
+    if (!token) return null
+    return createGitLabClient(remote, env.GITLAB_API_URL || 'https://gitlab.com/api/v4', token)
+  }
+  return null
+}
+
+async function request<T>(url: string, headers: Record<string, string>, init: { method?: string; body?: unknown } = {}): Promise<T | null> {
+  const response = await fetch(url, {
+    method: init.method || 'GET',
+    headers: init.body === undefined ? headers : { ...headers, 'Content-Type': 'application/json' },
+    body: init.body === undefined ? undefined : JSON.stringify(init.body),
+  })
+
+  if (response.status === 404) return null
+  if (!response.ok) {
+    let detail = response.statusText
+    try {
+      const body = await response.json() as { message?: unknown }
+      if (body && typeof body.message === 'string') detail = body.message
+    } catch {
+      // Keep the status text
+    }
+    throw new ProviderError(`${init.method || 'GET'} ${new URL(url).pathname} failed: ${response.status} ${detail}`, response.status)
+  }
+  if (response.status === 204) return null
+  return await response.json() as T
+}
+
+/** Full project path from the remote URL, keeping nested GitLab groups */
+function projectPath(remote: RemoteInfo): string {
+  try {
+    return new URL(remote.url).pathname.replace(/^\//, '').replace(/\.git$/, '')
+  } catch {
+    return `${remote.owner}/${remote.repo}`
+  }
+}
+
+// -----------------------------------------------------------------------------
+// GitHub
+// -----------------------------------------------------------------------------
+
+interface GitHubPull {
+  number: number
+  title: string
+  state: 'open' | 'closed'
+  merged_at: string | null
+  user: { login: string } | null
+  head: { ref: string; sha: string }
+  base: { ref: string }
+  updated_at: string
+  additions?: number
+  deletions?: number
+  changed_files?: number
+}
+
+interface GitHubCheckRun {
+  name: string
+  status: 'queued' | 'in_progress' | 'completed'
+  conclusion: string | null
+  html_url?: string
+}
+
+function fromGitHubPull(pr: GitHubPull): PRInfo {
+  return {
+    number: pr.number,
+    title: pr.title,
+    state: pr.merged_at ? 'merged' : pr.state,
+    author: pr.user?.login || 'unknown',
+    headRef: pr.head.ref,
+    baseRef: pr.base.ref,
+    updatedAt: pr.updated_at,
+    additions: pr.additions,
+    deletions: pr.deletions,
+    changedFiles: pr.changed_files,
+  }
+}
+
+function fromGitHubCheck(run: GitHubCheckRun): PRCheck {
+  let status: PRCheck['status']
+  if (run.status !== 'completed') {
+    status = 'pending'
+  } else if (run.conclusion === 'success') {
+    status = 'success'
+  } else if (run.conclusion === 'skipped') {
+    status = 'skipped'
+  } else if (run.conclusion === 'neutral') {
+    status = 'neutral'
+  } else {
+    status = 'failure'
+  }
+  return { name: run.name, status, url: run.html_url }
+}
+
+const GITHUB_REVIEW_EVENTS: Record<PRReview['event'], string> = {
+  approve: 'APPROVE',
+  request_changes: 'REQUEST_CHANGES',
+  comment: 'COMMENT',
+}
+
+function createGitHubClient(remote: RemoteInfo, apiUrl: string, token: string): ProviderClient {
+  const base = `${apiUrl.replace(/\/$/, '')}/repos/${remote.owner}/${remote.repo}`
+  const headers = {
+    Authorization: `Bearer ${token}`,
+    Accept: 'application/vnd.github+json',
+  }
+
+  return {
+    provider: 'github',
+
+    async listPRs(state, limit) {
+      const pulls = await request<GitHubPull[]>(`${base}/pulls?state=${state}&per_page=${Math.min(limit, 100)}`, headers)
+      return (pulls || []).slice(0, limit).map(fromGitHubPull)
+    },
+
+    async getPR(prNumber) {
+      const pr = await request<GitHubPull>(`${base}/pulls/${prNumber}`, headers)
+      return pr ? fromGitHubPull(pr) : null
+    },
+
+    async getChecks(prNumber) {
+      const pr = await request<GitHubPull>(`${base}/pulls/${prNumber}`, headers)
+      if (!pr) throw new ProviderError(`PR #${prNumber} not found`, 404)
+      const result = await request<{ check_runs: GitHubCheckRun[] }>(`${base}/commits/${pr.head.sha}/check-runs`, headers)
+      return { headSha: pr.head.sha, checks: (result?.check_runs || []).map(fromGitHubCheck) }
+    },
+
+    async submitReview(prNumber, review) {
+      await request(`${base}/pulls/${prNumber}/reviews`, headers, {
+        method: 'POST',
+        body: {
+          event: GITHUB_REVIEW_EVENTS[review.event],
+          body: review.body,
+          comments: (review.comments || []).map(c => ({ path: c.path, line: c.line, side: 'RIGHT', body: c.body })),
+        },
+      })
+    },
+  }
+}
+
+// -----------------------------------------------------------------------------
+// GitLab
+// -----------------------------------------------------------------------------
+
+interface GitLabMergeRequest {
+  iid: number
+  title: string
+  state: 'opened' | 'closed' | 'locked' | 'merged'
+  author: { username: string } | null
+  source_branch: string
+  target_branch: string
+  updated_at: string
+  sha: string
+  diff_refs?: { base_sha: string; head_sha: string; start_sha: string } | null
+}
+
+interface GitLabJob {
+  name: string
+  status: string
+  web_url?: string
+}
+
+function fromGitLabMergeRequest(mr: GitLabMergeRequest): PRInfo {
+  return {
+    number: mr.iid,
+    title: mr.title,
+    state: mr.state === 'merged' ? 'merged' : mr.state === 'opened' ? 'open' : 'closed',
+    author: mr.author?.username || 'unknown',
+    headRef: mr.source_branch,
+    baseRef: mr.target_branch,
+    updatedAt: mr.updated_at,
+  }
+}
+
+function fromGitLabJob(job: GitLabJob): PRCheck {
+  let status: PRCheck['status']
+  switch (job.status) {
+    case 'success':
+      status = 'success'
+      break
+    case 'failed':
+    case 'canceled':
+      status = 'failure'
+      break
+    case 'skipped':
+      status = 'skipped'
+      break
+    case 'manual':
+      status = 'neutral'
+      break
+    default:
+      status = 'pending'
+  }
+  return { name: job.name, status, url: job.web_url }
+}
+
+const GITLAB_STATES: Record<'open' | 'closed' | 'all', string> = {
+  open: 'opened',
+  closed: 'closed',
+  all: 'all',
+}
+
+function createGitLabClient(remote: RemoteInfo, apiUrl: string, token: string): ProviderClient {
+  const base = `${apiUrl.replace(/\/$/, '')}/projects/${encodeURIComponent(projectPath(remote))}`
+  const headers = { 'PRIVATE-TOKEN': token }
+
+  async function getMergeRequest(prNumber: number): Promise<GitLabMergeRequest> {
+    const mr = await request<GitLabMergeRequest>(`${base}/merge_requests/${prNumber}`, headers)
+    if (!mr) throw new ProviderError(`MR !${prNumber} not found`, 404)
+    return mr
+  }
+
+  return {
+    provider: 'gitlab',
+
+    async listPRs(state, limit) {
+      const mrs = await request<GitLabMergeRequest[]>(
+        `${base}/merge_requests?state=${GITLAB_STATES[state]}&per_page=${Math.min(limit, 100)}`,
+        headers
+      )
+      return (mrs || []).slice(0, limit).map(fromGitLabMergeRequest)
+    },
+
+    async getPR(prNumber) {
+      const mr = await request<GitLabMergeRequest>(`${base}/merge_requests/${prNumber}`, headers)
+      return mr ? fromGitLabMergeRequest(mr) : null
+    },
+
+    async getChecks(prNumber) {
+      const mr = await getMergeRequest(prNumber)
+      const pipelines = await request<Array<{ id: number; sha: string }>>(`${base}/merge_requests/${prNumber}/pipelines`, headers)
+      const latest = (pipelines || []).find(p => p.sha === mr.sha) || pipelines?.[0]
+      if (!latest) return { headSha: mr.sha, checks: [] }
+
+      const jobs = await request<GitLabJob[]>(`${base}/pipelines/${latest.id}/jobs`, headers)
+      return { headSha: mr.sha, checks: (jobs || []).map(fromGitLabJob) }
+    },
+
+    async submitReview(prNumber, review) {
+      const comments = review.comments || []
+      if (comments.length > 0) {
+        const mr = await getMergeRequest(prNumber)
+        if (!mr.diff_refs) {
+          throw new ProviderError(`MR !${prNumber} has no diff to comment on`, 422)
+        }
+        for (const comment of comments) {
+          await request(`${base}/merge_requests/${prNumber}/discussions`, headers, {
+            method: 'POST',
+            body: {
+              body: comment.body,
+              position: {
+                position_type: 'text',
+                base_sha: mr.diff_refs.base_sha,
+                start_sha: mr.diff_refs.start_sha,
+                head_sha: mr.diff_refs.head_sha,
+                new_path: comment.path,
+                new_line: comment.line,
+              },
+            },
+          })
+        }
+      }
+
+      // GitLab has no "request changes" state, so it becomes a plain note
+      if (review.body) {
+        await request(`${base}/merge_requests/${prNumber}/notes`, headers, {
+          method: 'POST',
+          body: { body: review.body },
+        })
+      }
+      if (review.event === 'approve') {
+        await request(`${base}/merge_requests/${prNumber}/approve`, headers, { method: 'POST', body: {} })
+      }
+    },
+  }
+}
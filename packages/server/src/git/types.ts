@@ -88,3 +88,26 @@ export interface PRDiffResult {
   }
   commitCount: number
 }
+
+export interface PRCheck {
+  name: string
+  status: 'pending' | 'success' | 'failure' | 'neutral' | 'skipped'
+  url?: string
+}
+
+export interface PRChecksResult {
+  headSha: string
+  checks: PRCheck[]
+}
+
+export interface PRReviewComment {
+  path: string
+  line: number  // Line in the new version of the file
+  body: string
+}
+
+export interface PRReview {
+  event: 'approve' | 'request_changes' | 'comment'
+  body: string
+  comments?: PRReviewComment[]
+}
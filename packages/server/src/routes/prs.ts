@@ -1,6 +1,6 @@
 import { Hono } from 'hono'
 import type { SimpleGit } from 'simple-git'
-import { listPRs, getPRInfo, getPRDiff } from '../git'
+import { listPRs, getPRInfo, getPRDiff, getPRChecks, submitPRReview, ProviderError, type PRReview } from '../git'
 import { getGitForRequest } from './utils'
 
 export function createPRRoutes(getGit: () => SimpleGit) {
@@ -67,5 +67,49 @@ export function createPRRoutes(getGit: () => SimpleGit) {
     }
   })
 
+  // GET /api/prs/:number/checks - Get CI checks for the PR head (needs an API token)
+  app.get('/:number/checks', async (c) => {
+    try {
+      const prNumber = parseInt(c.req.param('number'), 10)
+
+      if (isNaN(prNumber)) {
+        return c.json({ error: 'Invalid PR number' }, 400)
+      }
+
+      const git = getGitForRequest(c, getGit)
+      const result = await getPRChecks(git, prNumber)
+
+      return c.json(result)
+    } catch (error) {
+      const message = error instanceof Error ? error.message : 'Failed to get PR checks'
+      return c.json({ error: message }, error instanceof ProviderError && error.status === 404 ? 404 : 500)
+    }
+  })
+
+  // POST /api/prs/:number/reviews - Submit a review (needs an API token)
+  // Body: { event: 'approve'|'request_changes'|'comment', body: string, comments?: [{ path, line, body }] }
+  app.post('/:number/reviews', async (c) => {
+    try {
+      const prNumber = parseInt(c.req.param('number'), 10)
+
+      if (isNaN(prNumber)) {
+        return c.json({ error: 'Invalid PR number' }, 400)
+      }
+
+      const review = await c.req.json<PRReview>()
+      if (!['approve', 'request_changes', 'comment'].includes(review.event) || typeof review.body !== 'string') {
+        return c.json({ error: 'Invalid review' }, 400)
+      }
+
+      const git = getGitForRequest(c, getGit)
+      await submitPRReview(git, prNumber, review)
+
+      return c.json({ success: true })
+    } catch (error) {
+      const message = error instanceof Error ? error.message : 'Failed to submit review'
+      return c.json({ error: message }, error instanceof ProviderError && error.status === 404 ? 404 : 500)
+    }
+  })
+
   return app
 }
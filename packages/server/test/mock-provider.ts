@@ -0,0 +1,127 @@
+import { Hono } from 'hono'
+
+export interface RecordedRequest {
+  method: string
+  path: string
+  headers: Record<string, string>
+  body: unknown
+}
+
+/**
+ * In-memory stand-in for the GitHub and GitLab REST APIs.
+ * GitHub lives under /github, GitLab under /gitlab/api/v4. Every request is
+ * recorded so tests can assert on what the client sent.
+ */
+export function startMockProvider(options: { token?: string } = {}) {
+  const token = options.token ?? 'test-token'
+  const requests: RecordedRequest[] = []
+  const app = new Hono()
+
+  app.use('*', async (c, next) => {
+    const raw = c.req.method === 'POST' ? await c.req.text() : ''
+    requests.push({
+      method: c.req.method,
+      path: new URL(c.req.url).pathname + new URL(c.req.url).search,
+      headers: Object.fromEntries(c.req.raw.headers.entries()),
+      body: raw ? JSON.parse(raw) : undefined,
+    })
+    await next()
+  })
+
+  // ---------------------------------------------------------------------------
+  // GitHub
+  // ---------------------------------------------------------------------------
+
+  const pulls = [
+    {
+      number: 12, title: 'Add widgets', state: 'open', merged_at: null, user: { login: 'octo' },
+      head: { ref: 'feature/widgets', sha: 'aaa111' }, base: { ref: 'main' }, updated_at: '2026-01-02T00:00:00Z',
+      additions: 10, deletions: 2, changed_files: 3,
+    },
+    {
+      number: 11, title: 'Fix gears', state: 'closed', merged_at: '2026-01-01T00:00:00Z', user: { login: 'cat' },
+      head: { ref: 'fix/gears', sha: 'bbb222' }, base: { ref: 'main' }, updated_at: '2026-01-01T00:00:00Z',
+    },
+  ]
+
+  const github = new Hono()
+  github.use('*', async (c, next) => {
+    if (c.req.header('Authorization') !== `Bearer ${token}`) {
+      return c.json({ message: 'Bad credentials' }, 401)
+    }
+    await next()
+  })
+  github.get('/repos/acme/widgets/pulls', (c) => {
+    const state = c.req.query('state')
+    return c.json(state === 'all' ? pulls : pulls.filter(p => p.state === state))
+  })
+  github.get('/repos/acme/widgets/pulls/:number', (c) => {
+    const pr = pulls.find(p => p.number === Number(c.req.param('number')))
+    return pr ? c.json(pr) : c.json({ message: 'Not Found' }, 404)
+  })
+  github.get('/repos/acme/widgets/commits/aaa111/check-runs', (c) => c.json({
+    total_count: 4,
+    check_runs: [
+      { name: 'build', status: 'completed', conclusion: 'success', html_url: 'https://ci/build' },
+      { name: 'lint', status: 'completed', conclusion: 'failure' },
+      { name: 'docs', status: 'completed', conclusion: 'skipped' },
+      { name: 'e2e', status: 'in_progress', conclusion: null },
+    ],
+  }))
+  github.post('/repos/acme/widgets/pulls/:number/reviews', (c) => c.json({ id: 1, state: 'APPROVED' }))
+  app.route('/github', github)
+
+  // ---------------------------------------------------------------------------
+  // GitLab
+  // ---------------------------------------------------------------------------
+
+  const mergeRequests = [
+    {
+      iid: 7, title: 'Tune pipeline', state: 'opened', author: { username: 'fox' },
+      source_branch: 'ci/tune', target_branch: 'main', updated_at: '2026-02-01T00:00:00Z', sha: 'ccc333',
+      diff_refs: { base_sha: 'base000', start_sha: 'start000', head_sha: 'ccc333' },
+    },
+  ]
+
+  const gitlab = new Hono()
+  gitlab.use('*', async (c, next) => {
+    if (c.req.header('PRIVATE-TOKEN') !== token) {
+      return c.json({ message: '401 Unauthorized' }, 401)
+    }
+    await next()
+  })
+  const project = '/projects/group%2Fsub%2Fwidgets'
+  gitlab.get(`${project}/merge_requests`, (c) => {
+    const state = c.req.query('state')
+    return c.json(state === 'all' ? mergeRequests : mergeRequests.filter(m => m.state === state))
+  })
+  gitlab.get(`${project}/merge_requests/:iid`, (c) => {
+    const mr = mergeRequests.find(m => m.iid === Number(c.req.param('iid')))
+    return mr ? c.json(mr) : c.json({ message: '404 Not found' }, 404)
+  })
+  gitlab.get(`${project}/merge_requests/:iid/pipelines`, (c) => c.json([
+    { id: 99, sha: 'ccc333' },
+    { id: 98, sha: 'old000' },
+  ]))
+  gitlab.get(`${project}/pipelines/99/jobs`, (c) => c.json([
+    { name: 'test', status: 'success', web_url: 'https://ci/test' },
+    { name: 'deploy', status: 'manual' },
+    { name: 'lint', status: 'failed' },
+    { name: 'build', status: 'running' },
+  ]))
+  gitlab.post(`${project}/merge_requests/:iid/notes`, (c) => c.json({ id: 1 }, 201))
+  gitlab.post(`${project}/merge_requests/:iid/discussions`, (c) => c.json({ id: 'd1' }, 201))
+  gitlab.post(`${project}/merge_requests/:iid/approve`, (c) => c.json({ approved: true }, 201))
+  app.route('/gitlab/api/v4', gitlab)
+
+  const server = Bun.serve({ port: 0, fetch: app.fetch })
+  const origin = `http://127.0.0.1:${server.port}`
+
+  return {
+    githubUrl: `${origin}/github`,
+    gitlabUrl: `${origin}/gitlab/api/v4`,
+    token,
+    requests,
+    stop: () => server.stop(true),
+  }
+}
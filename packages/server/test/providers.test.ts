@@ -0,0 +1,218 @@
+import { afterAll, afterEach, beforeAll, describe, expect, test } from 'bun:test'
+import { mkdtemp, rm } from 'fs/promises'
+import { tmpdir } from 'os'
+import { join } from 'path'
+import simpleGit from 'simple-git'
+import { createGitClient, createProviderClient, ProviderError, type RemoteInfo } from '../src/git'
+import { createPRRoutes } from '../src/routes/prs'
+import { startMockProvider } from './mock-provider'
+
+const mock = startMockProvider()
+afterAll(() => mock.stop())
+afterEach(() => {
+  mock.requests.length = 0
+})
+
+const githubRemote: RemoteInfo = {
+  url: 'https://github.com/acme/widgets',
+  provider: 'github',
+  owner: 'acme',
+  repo: 'widgets',
+}
+
+const gitlabRemote: RemoteInfo = {
+  url: 'https://gitlab.com/group/sub/widgets',
+  provider: 'gitlab',
+  owner: 'group',
+  repo: 'sub',
+}
+
+const githubEnv = { GITHUB_TOKEN: mock.token, GITHUB_API_URL: mock.githubUrl }
+const gitlabEnv = { GITLAB_TOKEN: mock.token, GITLAB_API_URL: mock.gitlabUrl }
+
+describe('createProviderClient', () => {
+  test('needs a token', () => {
+    expect(createProviderClient(githubRemote, {})).toBeNull()
+    expect(createProviderClient(gitlabRemote, {})).toBeNull()
+    expect(createProviderClient({ ...githubRemote, provider: 'bitbucket' }, githubEnv)).toBeNull()
+    expect(createProviderClient(githubRemote, { GH_TOKEN: 'x' })?.provider).toBe('github')
+  })
+})
+
+describe('GitHub', () => {
+  const client = createProviderClient(githubRemote, githubEnv)!
+
+  test('lists pull requests and marks merged ones', async () => {
+    const prs = await client.listPRs('all', 50)
+    expect(prs.map(pr => [pr.number, pr.state, pr.author])).toEqual([
+      [12, 'open', 'octo'],
+      [11, 'merged', 'cat'],
+    ])
+    expect(prs[0]).toMatchObject({ headRef: 'feature/widgets', baseRef: 'main', additions: 10, changedFiles: 3 })
+
+    expect(mock.requests[0].path).toBe('/github/repos/acme/widgets/pulls?state=all&per_page=50')
+    expect(mock.requests[0].headers.authorization).toBe(`Bearer ${mock.token}`)
+    expect(mock.requests[0].headers.accept).toBe('application/vnd.github+json')
+  })
+
+  test('returns null for unknown pull requests', async () => {
+    expect(await client.getPR(404)).toBeNull()
+  })
+
+  test('maps check runs for the head commit', async () => {
+    const result = await client.getChecks(12)
+    expect(result.headSha).toBe('aaa111')
+    expect(result.checks).toEqual([
+      { name: 'build', status: 'success', url: 'https://ci/build' },
+      { name: 'lint', status: 'failure', url: undefined },
+      { name: 'docs', status: 'skipped', url: undefined },
+      { name: 'e2e', status: 'pending', url: undefined },
+    ])
+  })
+
+  test('submits reviews with line comments', async () => {
+    await client.submitReview(12, {
+      event: 'request_changes',
+      body: 'Needs work',
+      comments: [{ path: 'src/a.ts', line: 4, body: 'Off by one' }],
+    })
+
+    const post = mock.requests.find(r => r.method === 'POST')!
+    expect(post.path).toBe('/github/repos/acme/widgets/pulls/12/reviews')
+    expect(post.body).toEqual({
+      event: 'REQUEST_CHANGES',
+      body: 'Needs work',
+      comments: [{ path: 'src/a.ts', line: 4, side: 'RIGHT', body: 'Off by one' }],
+    })
+  })
+
+  test('surfaces API errors with their status', async () => {
+    const bad = createProviderClient(githubRemote, { ...githubEnv, GITHUB_TOKEN: 'wrong' })!
+    const error = await bad.listPRs('open', 10).catch(e => e)
+    expect(error).toBeInstanceOf(ProviderError)
+    expect(error.status).toBe(401)
+    expect(error.message).toContain('Bad credentials')
+  })
+})
+
+describe('GitLab', () => {
+  const client = createProviderClient(gitlabRemote, gitlabEnv)!
+
+  test('lists merge requests for nested group projects', async () => {
+    const prs = await client.listPRs('open', 20)
+    expect(prs).toEqual([{
+      number: 7,
+      title: 'Tune pipeline',
+      state: 'open',
+      author: 'fox',
+      headRef: 'ci/tune',
+      baseRef: 'main',
+      updatedAt: '2026-02-01T00:00:00Z',
+    }])
+    expect(mock.requests[0].path).toBe('/gitlab/api/v4/projects/group%2Fsub%2Fwidgets/merge_requests?state=opened&per_page=20')
+    expect(mock.requests[0].headers['private-token']).toBe(mock.token)
+  })
+
+  test('maps jobs from the pipeline for the head commit', async () => {
+    const result = await client.getChecks(7)
+    expect(result.headSha).toBe('ccc333')
+    expect(result.checks.map(c => [c.name, c.status])).toEqual([
+      ['test', 'success'],
+      ['deploy', 'neutral'],
+      ['lint', 'failure'],
+      ['build', 'pending'],
+    ])
+  })
+
+  test('posts line comments as positioned discussions, then notes and approves', async () => {
+    await client.submitReview(7, {
+      event: 'approve',
+      body: 'Looks good',
+      comments: [{ path: '.gitlab-ci.yml', line: 3, body: 'Nice' }],
+    })
+
+    const posts = mock.requests.filter(r => r.method === 'POST')
+    expect(posts.map(r => r.path.replace('/gitlab/api/v4/projects/group%2Fsub%2Fwidgets', ''))).toEqual([
+      '/merge_requests/7/discussions',
+      '/merge_requests/7/notes',
+      '/merge_requests/7/approve',
+    ])
+    expect(posts[0].body).toEqual({
+      body: 'Nice',
+      position: {
+        position_type: 'text',
+        base_sha: 'base000',
+        start_sha: 'start000',
+        head_sha: 'ccc333',
+        new_path: '.gitlab-ci.yml',
+        new_line: 3,
+      },
+    })
+    expect(posts[1].body).toEqual({ body: 'Looks good' })
+  })
+
+  test('does not approve comment-only reviews', async () => {
+    await client.submitReview(7, { event: 'comment', body: 'FYI' })
+    expect(mock.requests.map(r => r.path.split('/').pop())).toEqual(['notes'])
+  })
+
+  test('reports missing merge requests', async () => {
+    const error = await client.getChecks(404).catch(e => e)
+    expect(error).toBeInstanceOf(ProviderError)
+    expect(error.status).toBe(404)
+  })
+})
+
+describe('PR routes', () => {
+  let repoPath: string
+  let app: ReturnType<typeof createPRRoutes>
+  const savedEnv = { ...process.env }
+
+  beforeAll(async () => {
+    repoPath = await mkdtemp(join(tmpdir(), 'diffy-prs-'))
+    const git = simpleGit(repoPath)
+    await git.init()
+    await git.addRemote('origin', 'https://github.com/acme/widgets.git')
+
+    const client = createGitClient(repoPath)
+    app = createPRRoutes(() => client)
+    Object.assign(process.env, githubEnv)
+  })
+
+  afterAll(async () => {
+    process.env = savedEnv
+    await rm(repoPath, { recursive: true, force: true })
+  })
+
+  test('lists through the provider API when a token is set', async () => {
+    const res = await app.request('/?state=open')
+    expect(res.status).toBe(200)
+    const body = await res.json() as { prs: Array<{ number: number }>; provider: string }
+    expect(body.provider).toBe('github')
+    expect(body.prs.map(pr => pr.number)).toEqual([12])
+  })
+
+  test('returns checks and 404s unknown PRs', async () => {
+    const res = await app.request('/12/checks')
+    expect(res.status).toBe(200)
+    expect(((await res.json()) as { checks: unknown[] }).checks).toHaveLength(4)
+
+    expect((await app.request('/404/checks')).status).toBe(404)
+  })
+
+  test('validates and submits reviews', async () => {
+    const invalid = await app.request('/12/reviews', {
+      method: 'POST',
+      body: JSON.stringify({ event: 'merge', body: '' }),
+    })
+    expect(invalid.status).toBe(400)
+
+    const res = await app.request('/12/reviews', {
+      method: 'POST',
+      body: JSON.stringify({ event: 'approve', body: 'Ship it' }),
+    })
+    expect(res.status).toBe(200)
+    const post = mock.requests.find(r => r.method === 'POST')!
+    expect(post.body).toEqual({ event: 'APPROVE', body: 'Ship it', comments: [] })
+  })
+})
@@ -34,6 +34,7 @@ export interface DiffResult {
 
 export interface CompareBranchesResult extends DiffResult {
   commitCount: number
+  commits?: CommitInfo[] // First page of base..head, oldest first (desktop only)
   mergeBase?: string
   mergeBaseDate?: string
 }
@@ -97,11 +98,74 @@ let tauriListen: ((event: string, handler: (event: { payload: unknown }) => void
 async function getTauriInvoke() {
   if (!tauriInvoke) {
     const { invoke } = await import('@tauri-apps/api/core')
-    tauriInvoke = invoke
+    tauriInvoke = async (cmd, args) => decodeResponse(await invoke(cmd, args))
   }
   return tauriInvoke
 }
 
+// Large responses may come back compressed, or written to a file, instead of
+// as plain JSON, see IpcResponse in src-tauri/src/ipc.rs
+interface CompressedPayload {
+  compressed: true
+  encoding: 'gzip' | 'zstd'
+  originalSize: number
+  data: string
+}
+
+interface FileHandoff {
+  handoff: true
+  path: string
+  size: number
+}
+
+function isCompressedPayload(value: unknown): value is CompressedPayload {
+  if (typeof value !== 'object' || value === null) return false
+  const payload = value as Partial<CompressedPayload>
+  return payload.compressed === true && typeof payload.data === 'string'
+}
+
+function isFileHandoff(value: unknown): value is FileHandoff {
+  if (typeof value !== 'object' || value === null) return false
+  const handoff = value as Partial<FileHandoff>
+  return handoff.handoff === true && typeof handoff.path === 'string'
+}
+
+async function decodeResponse(value: unknown): Promise<unknown> {
+  if (isCompressedPayload(value)) {
+    return JSON.parse(await inflate(value))
+  }
+  if (isFileHandoff(value)) {
+    return JSON.parse(await readHandoff(value))
+  }
+  return value
+}
+
+// The file is released even when reading it fails, so it can't pile up
+async function readHandoff(handoff: FileHandoff): Promise<string> {
+  const [{ invoke }, { readTextFile }] = await Promise.all([
+    import('@tauri-apps/api/core'),
+    import('@tauri-apps/plugin-fs'),
+  ])
+  try {
+    return await readTextFile(handoff.path)
+  } finally {
+    await invoke('cmd_release_payload', { path: handoff.path }).catch(() => {})
+  }
+}
+
+async function inflate(payload: CompressedPayload): Promise<string> {
+  let stream: DecompressionStream
+  try {
+    stream = new DecompressionStream(payload.encoding as CompressionFormat)
+  } catch {
+    throw new Error(
+      `This webview cannot decompress ${payload.encoding} responses; set ipcCompression.encoding to "gzip"`
+    )
+  }
+  const bytes = Uint8Array.from(atob(payload.data), (c) => c.charCodeAt(0))
+  return new Response(new Blob([bytes]).stream().pipeThrough(stream)).text()
+}
+
 async function getTauriListen() {
   if (!tauriListen) {
     const { listen } = await import('@tauri-apps/api/event')
@@ -283,8 +347,8 @@ export async function getConfig(): Promise<DifferConfig> {
 export async function setConfig(config: Partial<DifferConfig>): Promise<void> {
   if (isTauri()) {
     const invoke = await getTauriInvoke()
-    const currentConfig = await getConfig()
-    await invoke('cmd_set_config', { config: { ...currentConfig, ...config } })
+    // Field-level update, so concurrent windows don't overwrite each other
+    await invoke('cmd_patch_config', { partial: config })
   } else {
     const res = await fetch('/api/config', {
       method: 'POST',
@@ -295,10 +359,15 @@ export async function setConfig(config: Partial<DifferConfig>): Promise<void> {
   }
 }
 
-export async function openInEditor(filePath: string, editor: string): Promise<void> {
+export async function openInEditor(
+  filePath: string,
+  editor: string,
+  line?: number,
+  column?: number
+): Promise<void> {
   if (isTauri()) {
     const invoke = await getTauriInvoke()
-    await invoke('cmd_open_in_editor', { filePath, editor })
+    await invoke('cmd_open_in_editor', { filePath, editor, line, column })
   } else {
     const res = await fetch('/api/editor/open', {
       method: 'POST',
@@ -0,0 +1,85 @@
+//! Performance baselines for the diff pipeline.
+//!
+//! Run with `cargo bench`; compare against a saved baseline with
+//! `cargo bench -- --save-baseline before` and `--baseline before`.
+
+mod fixtures;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use diffy_lib::git::{self, DifferConfig};
+
+fn bench_parse_diff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_diff");
+    group.sample_size(20);
+
+    let many = fixtures::many_files(2000);
+    let head = many.repo.head().unwrap().peel_to_tree().unwrap();
+    group.bench_function("2000_files", |b| {
+        b.iter(|| {
+            let diff = many
+                .repo
+                .diff_tree_to_workdir_with_index(Some(&head), None)
+                .unwrap();
+            git::parse_diff(&diff, usize::MAX).unwrap()
+        })
+    });
+
+    let huge = fixtures::huge_file(200_000);
+    let head = huge.repo.head().unwrap().peel_to_tree().unwrap();
+    group.bench_function("200k_line_file", |b| {
+        b.iter(|| {
+            let diff = huge
+                .repo
+                .diff_tree_to_workdir_with_index(Some(&head), None)
+                .unwrap();
+            git::parse_diff(&diff, usize::MAX).unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_current_diff(c: &mut Criterion) {
+    let config = DifferConfig::default();
+    let many = fixtures::many_files(2000);
+    c.bench_function("get_current_diff/2000_files", |b| {
+        b.iter(|| git::get_current_diff(&many.repo, None, &config).unwrap())
+    });
+}
+
+fn bench_commit_history(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_commit_history");
+    let history = fixtures::many_commits(1000);
+    let config = DifferConfig::default();
+
+    group.bench_function("first_page", |b| {
+        b.iter(|| git::get_commit_history(&history.repo, 20, 0, &config).unwrap())
+    });
+    group.bench_function("deep_page", |b| {
+        b.iter(|| git::get_commit_history(&history.repo, 20, 900, &config).unwrap())
+    });
+
+    group.finish();
+}
+
+fn bench_compare_branches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compare_branches");
+    group.sample_size(10);
+
+    let config = DifferConfig::default();
+    let branches = fixtures::diverged_branches(500);
+    group.bench_function("500_modified_500_added", |b| {
+        b.iter(|| git::compare_branches(&branches.repo, "base", "head", &config).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse_diff,
+    bench_current_diff,
+    bench_commit_history,
+    bench_compare_branches
+);
+criterion_main!(benches);
@@ -0,0 +1,133 @@
+//! Synthetic repositories for the benchmarks. Each fixture is built once in a
+//! temp directory that lives as long as the returned `Fixture`.
+
+use git2::{Oid, Repository, Signature};
+use std::path::Path;
+use tempfile::TempDir;
+
+pub struct Fixture {
+    pub repo: Repository,
+    // Held so the directory is removed when the fixture is dropped
+    _dir: TempDir,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let repo = Repository::init(dir.path()).expect("init repo");
+        Self { repo, _dir: dir }
+    }
+
+    pub fn workdir(&self) -> &Path {
+        self.repo.workdir().expect("non-bare repo")
+    }
+
+    fn write(&self, path: &str, contents: &str) {
+        let full = self.workdir().join(path);
+        if let Some(parent) = full.parent() {
+            std::fs::create_dir_all(parent).expect("create dirs");
+        }
+        std::fs::write(full, contents).expect("write file");
+    }
+
+    /// Stage everything and commit it on the current branch
+    fn commit_all(&self, message: &str) -> Oid {
+        let mut index = self.repo.index().expect("open index");
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .expect("stage files");
+        index.write().expect("write index");
+        let tree = self
+            .repo
+            .find_tree(index.write_tree().expect("write tree"))
+            .expect("find tree");
+
+        let sig = Signature::now("Bench", "bench@example.com").expect("signature");
+        let parent = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        self.repo
+            .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .expect("commit")
+    }
+
+    fn branch(&self, name: &str, target: Oid) {
+        let commit = self.repo.find_commit(target).expect("find commit");
+        self.repo
+            .branch(name, &commit, true)
+            .expect("create branch");
+    }
+}
+
+fn source_file(seed: usize, lines: usize) -> String {
+    (0..lines)
+        .map(|i| format!("fn item_{}_{}() -> usize {{ {} }}\n", seed, i, i * seed))
+        .collect()
+}
+
+/// `files` committed files, every other one modified in the working directory
+pub fn many_files(files: usize) -> Fixture {
+    let fixture = Fixture::new();
+    for i in 0..files {
+        fixture.write(
+            &format!("src/mod_{}/file_{}.rs", i % 50, i),
+            &source_file(i, 40),
+        );
+    }
+    fixture.commit_all("initial");
+
+    for i in (0..files).step_by(2) {
+        let mut contents = source_file(i, 40);
+        contents.push_str("// touched\n");
+        fixture.write(&format!("src/mod_{}/file_{}.rs", i % 50, i), &contents);
+    }
+    fixture
+}
+
+/// A linear history of `commits` commits, each touching one of a few files
+pub fn many_commits(commits: usize) -> Fixture {
+    let fixture = Fixture::new();
+    for i in 0..commits {
+        fixture.write(&format!("file_{}.txt", i % 10), &source_file(i, 20));
+        fixture.commit_all(&format!("commit {}", i));
+    }
+    fixture
+}
+
+/// One file of `lines` lines with every tenth line changed in the working directory
+pub fn huge_file(lines: usize) -> Fixture {
+    let fixture = Fixture::new();
+    fixture.write("generated.rs", &source_file(1, lines));
+    fixture.commit_all("initial");
+
+    let changed: String = source_file(1, lines)
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i % 10 == 0 {
+                format!("{} // changed\n", line)
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect();
+    fixture.write("generated.rs", &changed);
+    fixture
+}
+
+/// Branches `base` and `head`, where `head` modifies `files` files and adds as many
+pub fn diverged_branches(files: usize) -> Fixture {
+    let fixture = Fixture::new();
+    for i in 0..files {
+        fixture.write(&format!("src/file_{}.rs", i), &source_file(i, 60));
+    }
+    let base = fixture.commit_all("base");
+    fixture.branch("base", base);
+
+    for i in 0..files {
+        fixture.write(&format!("src/file_{}.rs", i), &source_file(i + 1, 60));
+        fixture.write(&format!("src/new_{}.rs", i), &source_file(i, 30));
+    }
+    let head = fixture.commit_all("head");
+    fixture.branch("head", head);
+    fixture
+}
@@ -0,0 +1,264 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+
+use crate::git::{
+    compare_branches, compare_tags, get_branch_page, get_commit_diff, get_commit_history,
+    get_current_diff, get_repo_status, get_staged_diff, get_status_line, get_tags,
+    get_working_changes, open_repo, BranchQuery, DifferConfig,
+};
+use crate::i18n::CommandError;
+
+const USAGE: &str = "\
+Usage: diffy json [--repo <path>] [--pretty] <command> [args]
+
+Runs one diff operation without opening a window and prints the result as
+JSON on stdout. Failures print {\"error\": {...}} on stderr.
+
+Commands:
+  current-diff [--base <ref>]          Working tree against HEAD or <ref>
+  staged-diff [--base <ref>]           Index against HEAD or <ref>
+  working-changes                      Staged and unstaged changes separately
+  compare <base> <head>                Changes on <head> since it forked from <base>
+  compare-tags <from> <to>             Diff and changelog between two tags
+  commit <sha>                         One commit against its first parent
+  commits [--page <n>] [--limit <n>]   Commit history of HEAD
+  branches [--filter <text>] [--page <n>] [--limit <n>]
+                                       Local branches, optionally filtered
+  tags                                 Tags, newest first
+  status                               HEAD and the default branch
+  statusline                           Branch, change counts and ahead/behind
+";
+
+/// Exit codes
+const EXIT_OK: i32 = 0;
+const EXIT_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+/// Entry point for `diffy json ...` / `diffy --json ...`; `args` are the
+/// arguments after the mode flag. Returns the process exit code.
+pub fn run_json(args: &[String]) -> i32 {
+    run_json_to(args, &mut std::io::stdout(), &mut std::io::stderr())
+}
+
+/// [`run_json`] writing its result to `out` and failures to `err`
+pub fn run_json_to(args: &[String], out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let mut repo_path = String::from(".");
+    let mut pretty = false;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--repo" => match args.next() {
+                Some(path) => repo_path = path.clone(),
+                None => return usage_error(err, "--repo needs a path"),
+            },
+            "--pretty" => pretty = true,
+            "-h" | "--help" => {
+                let _ = write!(out, "{}", USAGE);
+                return EXIT_OK;
+            }
+            _ => rest.push(arg.as_str()),
+        }
+    }
+
+    let Some((&command, command_args)) = rest.split_first() else {
+        return usage_error(err, "missing command");
+    };
+    let result = match parse_command(command, command_args) {
+        Ok(command) => run_command(&repo_path, command),
+        Err(message) => return usage_error(err, &message),
+    };
+
+    match result {
+        Ok(value) => {
+            let json = if pretty {
+                serde_json::to_string_pretty(&value)
+            } else {
+                serde_json::to_string(&value)
+            };
+            let _ = writeln!(out, "{}", json.unwrap_or_default());
+            EXIT_OK
+        }
+        Err(error) => {
+            let body = serde_json::json!({ "error": error });
+            let _ = writeln!(err, "{}", body);
+            EXIT_FAILED
+        }
+    }
+}
+
+enum Command<'a> {
+    CurrentDiff {
+        base: Option<&'a str>,
+    },
+    StagedDiff {
+        base: Option<&'a str>,
+    },
+    WorkingChanges,
+    Compare {
+        base: &'a str,
+        head: &'a str,
+    },
+    CompareTags {
+        from: &'a str,
+        to: &'a str,
+    },
+    Commit {
+        sha: &'a str,
+    },
+    Commits {
+        page: usize,
+        limit: usize,
+    },
+    Branches {
+        filter: Option<&'a str>,
+        page: usize,
+        limit: Option<usize>,
+    },
+    Tags,
+    Status,
+    StatusLine,
+}
+
+fn parse_command<'a>(name: &str, args: &[&'a str]) -> Result<Command<'a>, String> {
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut iter = args.iter();
+    while let Some(&arg) = iter.next() {
+        match arg.strip_prefix("--") {
+            Some(option) => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("--{} needs a value", option))?;
+                options.push((option, *value));
+            }
+            None => positional.push(arg),
+        }
+    }
+
+    let option = |name: &str| {
+        options
+            .iter()
+            .find(|(option, _)| *option == name)
+            .map(|(_, value)| *value)
+    };
+    let number = |name: &str, default: usize| match option(name) {
+        Some(value) => value
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("--{} must be a positive number", name)),
+        None => Ok(default),
+    };
+    let known = |allowed: &[&str]| match options.iter().find(|(o, _)| !allowed.contains(o)) {
+        Some((unknown, _)) => Err(format!("unknown option --{} for {}", unknown, name)),
+        None => Ok(()),
+    };
+    let arity = |count: usize| {
+        if positional.len() == count {
+            Ok(())
+        } else {
+            Err(format!("{} takes {} argument(s)", name, count))
+        }
+    };
+
+    let command = match name {
+        "current-diff" => {
+            known(&["base"])?;
+            arity(0)?;
+            Command::CurrentDiff {
+                base: option("base"),
+            }
+        }
+        "staged-diff" => {
+            known(&["base"])?;
+            arity(0)?;
+            Command::StagedDiff {
+                base: option("base"),
+            }
+        }
+        "commits" => {
+            known(&["page", "limit"])?;
+            arity(0)?;
+            Command::Commits {
+                page: number("page", 1)?,
+                limit: number("limit", 20)?,
+            }
+        }
+        "branches" => {
+            known(&["filter", "page", "limit"])?;
+            arity(0)?;
+            Command::Branches {
+                filter: option("filter"),
+                page: number("page", 1)?,
+                limit: option("limit").map(|_| number("limit", 0)).transpose()?,
+            }
+        }
+        _ => {
+            known(&[])?;
+            match name {
+                "working-changes" => arity(0).map(|_| Command::WorkingChanges)?,
+                "compare" => arity(2).map(|_| Command::Compare {
+                    base: positional[0],
+                    head: positional[1],
+                })?,
+                "compare-tags" => arity(2).map(|_| Command::CompareTags {
+                    from: positional[0],
+                    to: positional[1],
+                })?,
+                "commit" => arity(1).map(|_| Command::Commit { sha: positional[0] })?,
+                "tags" => arity(0).map(|_| Command::Tags)?,
+                "status" => arity(0).map(|_| Command::Status)?,
+                "statusline" => arity(0).map(|_| Command::StatusLine)?,
+                _ => return Err(format!("unknown command {}", name)),
+            }
+        }
+    };
+    Ok(command)
+}
+
+fn run_command(repo_path: &str, command: Command) -> Result<Value, CommandError> {
+    let repo = open_repo(repo_path).map_err(CommandError::from)?;
+    let config = DifferConfig::default();
+
+    match command {
+        Command::CurrentDiff { base } => to_value(get_current_diff(&repo, base, &config)),
+        Command::StagedDiff { base } => to_value(get_staged_diff(&repo, base, &config)),
+        Command::WorkingChanges => to_value(get_working_changes(&repo, &config)),
+        Command::Compare { base, head } => to_value(compare_branches(&repo, base, head, &config)),
+        Command::CompareTags { from, to } => to_value(compare_tags(&repo, from, to, &config)),
+        Command::Commit { sha } => to_value(get_commit_diff(&repo, sha, &config)),
+        Command::Commits { page, limit } => to_value(get_commit_history(
+            &repo,
+            limit,
+            (page - 1) * limit,
+            &config,
+        )),
+        Command::Branches {
+            filter,
+            page,
+            limit,
+        } => {
+            let query = BranchQuery {
+                filter: filter.map(str::to_string),
+                offset: (page - 1) * limit.unwrap_or(0),
+                limit,
+            };
+            to_value(get_branch_page(&repo, &query))
+        }
+        Command::Tags => to_value(get_tags(&repo)),
+        Command::Status => to_value(get_repo_status(&repo)),
+        Command::StatusLine => to_value(get_status_line(&repo)),
+    }
+}
+
+fn to_value<T: Serialize>(result: crate::git::Result<T>) -> Result<Value, CommandError> {
+    let value = result.map_err(CommandError::from)?;
+    Ok(serde_json::to_value(value)?)
+}
+
+fn usage_error(err: &mut dyn Write, message: &str) -> i32 {
+    let _ = writeln!(err, "diffy json: {}\n\n{}", message, USAGE);
+    EXIT_USAGE
+}
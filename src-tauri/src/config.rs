@@ -0,0 +1,70 @@
+use serde_json::Value;
+
+use crate::git::DifferConfig;
+use crate::i18n::CommandError;
+
+/// Replace the whole config, provided it was edited from the current revision
+pub fn replace_config(
+    current: &DifferConfig,
+    next: DifferConfig,
+) -> Result<DifferConfig, CommandError> {
+    check_revision(current, next.revision)?;
+    Ok(DifferConfig {
+        revision: current.revision + 1,
+        ..next
+    })
+}
+
+/// Apply a JSON merge patch (RFC 7386) to the config: objects merge field by
+/// field and `null` resets a field to its default.
+///
+/// The revision is only checked when the patch carries one, so independent
+/// field updates from different windows never conflict.
+pub fn patch_config(current: &DifferConfig, patch: &Value) -> Result<DifferConfig, CommandError> {
+    if let Some(revision) = patch.get("revision").and_then(Value::as_u64) {
+        check_revision(current, revision)?;
+    }
+
+    let mut merged = serde_json::to_value(current)?;
+    merge(&mut merged, patch);
+    let next: DifferConfig = serde_json::from_value(merged)
+        .map_err(|e| CommandError::new("error.invalidConfig", &[("detail", &e.to_string())]))?;
+
+    Ok(DifferConfig {
+        revision: current.revision + 1,
+        ..next
+    })
+}
+
+fn check_revision(current: &DifferConfig, revision: u64) -> Result<(), CommandError> {
+    if revision != current.revision {
+        return Err(CommandError::new(
+            "error.configConflict",
+            &[("revision", &current.revision.to_string())],
+        ));
+    }
+    Ok(())
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(fields) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(target_fields) = target else {
+        return;
+    };
+    for (key, value) in fields {
+        if value.is_null() {
+            target_fields.remove(key);
+        } else {
+            merge(
+                target_fields.entry(key.clone()).or_insert(Value::Null),
+                value,
+            );
+        }
+    }
+}
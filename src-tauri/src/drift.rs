@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::time::{Duration, SystemTime};
+use tauri::AppHandle;
+
+use crate::events;
+use crate::git::{check_drift, open_repo, DriftMonitor, DriftTracker};
+
+/// How often to look for a new fetch
+const POLL_INTERVAL_SECS: u64 = 30;
+
+/// Background task that rechecks monitored ref pairs after each fetch and
+/// emits `branch-drift` with a `DriftReport` when a pair is over its
+/// threshold. A pair is reported again only when its drift changes.
+/// Dropping the watcher stops the task.
+pub struct DriftWatcher {
+    #[allow(dead_code)]
+    stop: Sender<()>,
+}
+
+impl DriftWatcher {
+    pub fn new(repo_path: PathBuf, monitors: Vec<DriftMonitor>, app_handle: AppHandle) -> Self {
+        let (stop, stopped) = channel();
+
+        std::thread::spawn(move || {
+            let mut last_fetch: Option<Option<SystemTime>> = None;
+            let mut tracker = DriftTracker::new();
+            loop {
+                let fetch = fetch_time(&repo_path);
+                // Also runs once at startup to take the initial snapshot
+                if last_fetch != Some(fetch) {
+                    last_fetch = Some(fetch);
+                    check_monitors(&repo_path, &monitors, &mut tracker, &app_handle);
+                }
+
+                match stopped.recv_timeout(Duration::from_secs(POLL_INTERVAL_SECS)) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            }
+        });
+
+        Self { stop }
+    }
+}
+
+/// When the repository was last fetched, from `FETCH_HEAD`
+fn fetch_time(repo_path: &Path) -> Option<SystemTime> {
+    let repo = open_repo(repo_path.to_str()?).ok()?;
+    std::fs::metadata(repo.path().join("FETCH_HEAD"))
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+fn check_monitors(
+    repo_path: &Path,
+    monitors: &[DriftMonitor],
+    tracker: &mut DriftTracker,
+    app_handle: &AppHandle,
+) {
+    let Ok(repo) = open_repo(repo_path.to_str().unwrap_or("")) else {
+        return;
+    };
+
+    for monitor in monitors {
+        let report = match check_drift(&repo, monitor) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!(
+                    "Drift check failed for {}..{}: {}",
+                    monitor.base, monitor.head, e
+                );
+                continue;
+            }
+        };
+
+        if let Some(report) = tracker.update(report) {
+            events::emit(app_handle, "branch-drift", report);
+        }
+    }
+}
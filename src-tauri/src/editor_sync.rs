@@ -0,0 +1,468 @@
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::events;
+use crate::git::{
+    get_status_line, get_working_changes, open_repo, DifferConfig, EditorSyncSettings, FileStatus,
+};
+
+/// Bumped when an endpoint changes incompatibly; endpoints are served under
+/// `/v<version>/` so plugins written against an older version keep working
+/// or fail loudly
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How often the accept loop checks whether it should stop
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+/// Clients that haven't sent their whole request after this long are dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+/// Longest request line plus headers accepted
+const MAX_REQUEST_BYTES: u64 = 8192;
+/// Connections handled at once; more are closed straight away
+const MAX_CONNECTIONS: usize = 16;
+
+/// What companion plugins need to talk to the local server
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolInfo {
+    pub version: u32,
+    pub app_version: String,
+    pub enabled: bool,
+    /// Base URL of the server, e.g. `http://127.0.0.1:47600`
+    pub base_url: String,
+    /// Bearer token for `/diff`, `/status` and `/focus`, new every session.
+    /// Only the app itself hands it out; `/info` leaves it out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    pub endpoints: Vec<ProtocolEndpoint>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolEndpoint {
+    pub method: String,
+    pub path: String,
+    pub description: String,
+}
+
+/// Payload of the `focus-file` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusRequest {
+    /// Path relative to the repository root when the editor sent a path inside it
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+}
+
+/// Body of `GET /v1/status`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSyncStatus {
+    pub file: String,
+    /// Change between HEAD and the index, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staged: Option<FileStatus>,
+    /// Change between the index and the working tree, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unstaged: Option<FileStatus>,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// A token for this session that other local programs can't guess: 128
+/// bits from the operating system's random source, hex-encoded
+pub fn session_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("the operating system has no random source");
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Describe the protocol for the given settings, with the session token
+/// when the caller may see it
+pub fn protocol_info(settings: &EditorSyncSettings, token: Option<&str>) -> ProtocolInfo {
+    let endpoint = |method: &str, path: &str, description: &str| ProtocolEndpoint {
+        method: method.to_string(),
+        path: format!("/v{}{}", PROTOCOL_VERSION, path),
+        description: description.to_string(),
+    };
+    ProtocolInfo {
+        version: PROTOCOL_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        enabled: settings.enabled,
+        base_url: format!("http://{}:{}", Ipv4Addr::LOCALHOST, settings.port),
+        token: token.map(String::from),
+        endpoints: vec![
+            endpoint("GET", "/info", "This document"),
+            endpoint(
+                "GET",
+                "/diff",
+                "Staged and unstaged changes of the open repository; needs the token",
+            ),
+            endpoint(
+                "GET",
+                "/statusline?format=json|text",
+                "Branch, change counts and ahead/behind, for shell prompts; also at /statusline",
+            ),
+            endpoint(
+                "GET",
+                "/status?file=<path>",
+                "Staged and unstaged status of one file; needs the token",
+            ),
+            endpoint(
+                "GET",
+                "/focus?file=<path>&line=<n>&column=<n>",
+                "Show the file in the diff view, scrolled to the line; needs the token",
+            ),
+        ],
+    }
+}
+
+/// Loopback HTTP server for editor companion plugins, speaking the JSON
+/// protocol described by `protocol_info`. `GET /focus?...` from before the
+/// protocol was versioned is still accepted, so `differ://focus?...` links
+/// work by swapping the scheme for the base URL.
+///
+/// Requests must name the server itself in `Host`, so pages on other sites
+/// can't reach it by rebinding their domain to the loopback address. The
+/// endpoints that return repository contents or drive the diff view also
+/// need the session token as `Authorization: Bearer <token>`, which a
+/// browser won't send cross-origin without asking the server first.
+///
+/// Each connection is handled on its own thread, and has `READ_TIMEOUT` to
+/// send at most `MAX_REQUEST_BYTES` of request, so a slow client can't hold
+/// up the others. Dropping the server stops listening and frees the port
+/// before returning.
+pub struct EditorSyncServer {
+    stop: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// What each request handler needs
+struct Context {
+    settings: EditorSyncSettings,
+    token: String,
+    config: DifferConfig,
+    repo_path: Option<PathBuf>,
+    app_handle: AppHandle,
+}
+
+impl EditorSyncServer {
+    pub fn new(
+        config: DifferConfig,
+        repo_path: Option<PathBuf>,
+        token: String,
+        app_handle: AppHandle,
+    ) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, config.editor_sync.port))?;
+        listener.set_nonblocking(true)?;
+        let (stop, stopped) = channel();
+        let context = Arc::new(Context {
+            settings: config.editor_sync.clone(),
+            token,
+            config,
+            repo_path,
+            app_handle,
+        });
+        let open = Arc::new(AtomicUsize::new(0));
+
+        let thread = std::thread::spawn(move || loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                        open.fetch_sub(1, Ordering::SeqCst);
+                        continue;
+                    }
+                    let context = context.clone();
+                    let open = open.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = handle(stream, &context) {
+                            eprintln!("Editor sync request failed: {}", e);
+                        }
+                        open.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    match stopped.recv_timeout(ACCEPT_INTERVAL) {
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        _ => break,
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Editor sync listener failed: {}", e);
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for EditorSyncServer {
+    fn drop(&mut self) {
+        // Wait for the loop to exit so a replacement can bind the same port
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// HTTP status line plus optional content type and body
+type Response = (&'static str, Option<(&'static str, String)>);
+
+/// The parts of a request the server looks at
+#[derive(Default)]
+struct Request {
+    line: String,
+    host: Option<String>,
+    authorization: Option<String>,
+}
+
+/// Reads from a stream until a deadline, so a client can't keep its
+/// connection open by trickling bytes
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+fn handle(stream: TcpStream, context: &Context) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+
+    let deadline = Deadline {
+        stream: &stream,
+        deadline: Instant::now() + READ_TIMEOUT,
+    };
+    let mut reader = BufReader::new(deadline.take(MAX_REQUEST_BYTES));
+    let mut request = Request::default();
+    reader.read_line(&mut request.line)?;
+    // Read every header so closing the socket doesn't reset the connection
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            let value = Some(value.trim().to_string());
+            if name.eq_ignore_ascii_case("host") {
+                request.host = value;
+            } else if name.eq_ignore_ascii_case("authorization") {
+                request.authorization = value;
+            }
+        }
+        header.clear();
+    }
+
+    let (status, body) = if reader.get_ref().limit() == 0 {
+        error("431 Request Header Fields Too Large", "Request is too long")
+    } else {
+        route(&request, context)
+    };
+    let mut stream = stream;
+    match body {
+        Some((content_type, body)) => write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        ),
+        None => write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status
+        ),
+    }
+}
+
+fn route(request: &Request, context: &Context) -> Response {
+    let mut parts = request.line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return error("400 Bad Request", "Malformed request");
+    };
+    let port = context.settings.port;
+    let own_host = |host: &str| {
+        host == format!("{}:{}", Ipv4Addr::LOCALHOST, port) || host == format!("localhost:{}", port)
+    };
+    if !request.host.as_deref().is_some_and(own_host) {
+        return error("403 Forbidden", "Unexpected Host header");
+    }
+    let Ok(url) = url::Url::parse(&format!("http://localhost{}", target)) else {
+        return error("400 Bad Request", "Malformed request target");
+    };
+    if method != "GET" {
+        return error("405 Method Not Allowed", "Only GET is supported");
+    }
+
+    let query = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let versioned = format!("/v{}/", PROTOCOL_VERSION);
+    let endpoint = match url.path() {
+        // Short unversioned paths for links and shell prompts
+        "/focus" => "focus",
+        "/statusline" => "statusline",
+        path => match path.strip_prefix(&versioned) {
+            Some(endpoint) => endpoint,
+            None => return error("404 Not Found", "Unknown endpoint"),
+        },
+    };
+
+    let authorized = request
+        .authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token.trim() == context.token);
+    if matches!(endpoint, "diff" | "status" | "focus") && !authorized {
+        return error("401 Unauthorized", "Missing or wrong session token");
+    }
+
+    match endpoint {
+        "info" => json(&protocol_info(&context.settings, None)),
+        "diff" => diff(context),
+        "statusline" => status_line(context, query("format").as_deref() == Some("text")),
+        "status" => match query("file").filter(|f| !f.is_empty()) {
+            Some(file) => status(context, &relative_path(&file, context)),
+            None => error("400 Bad Request", "Missing file parameter"),
+        },
+        "focus" => match query("file").filter(|f| !f.is_empty()) {
+            Some(file) => {
+                let request = FocusRequest {
+                    file: relative_path(&file, context),
+                    line: query("line").and_then(|l| l.parse().ok()),
+                    column: query("column").and_then(|c| c.parse().ok()),
+                };
+                events::emit(&context.app_handle, "focus-file", request);
+                ("204 No Content", None)
+            }
+            None => error("400 Bad Request", "Missing file parameter"),
+        },
+        _ => error("404 Not Found", "Unknown endpoint"),
+    }
+}
+
+fn diff(context: &Context) -> Response {
+    let Some(repo_path) = &context.repo_path else {
+        return error("409 Conflict", "No repository is open");
+    };
+    let changes = open_repo(repo_path.to_str().unwrap_or(""))
+        .and_then(|repo| get_working_changes(&repo, &context.config));
+    match changes {
+        Ok(changes) => json(&changes),
+        Err(e) => error("500 Internal Server Error", &e.to_string()),
+    }
+}
+
+fn status_line(context: &Context, as_text: bool) -> Response {
+    let Some(repo_path) = &context.repo_path else {
+        return error("409 Conflict", "No repository is open");
+    };
+    let line =
+        match open_repo(repo_path.to_str().unwrap_or("")).and_then(|repo| get_status_line(&repo)) {
+            Ok(line) => line,
+            Err(e) => return error("500 Internal Server Error", &e.to_string()),
+        };
+    if !as_text {
+        return json(&line);
+    }
+
+    // e.g. `main ↑2 ↓1 +3 ~1 ?4`, zero counts left out
+    let mut text = line.branch.unwrap_or_default();
+    for (symbol, count) in [
+        ("↑", line.ahead),
+        ("↓", line.behind),
+        ("+", line.staged),
+        ("~", line.unstaged),
+        ("?", line.untracked),
+        ("!", line.conflicted),
+    ] {
+        if count > 0 {
+            text.push_str(&format!(" {}{}", symbol, count));
+        }
+    }
+    text.push('\n');
+    ("200 OK", Some(("text/plain; charset=utf-8", text)))
+}
+
+fn status(context: &Context, file: &str) -> Response {
+    let Some(repo_path) = &context.repo_path else {
+        return error("409 Conflict", "No repository is open");
+    };
+    let changes = match open_repo(repo_path.to_str().unwrap_or(""))
+        .and_then(|repo| get_working_changes(&repo, &context.config))
+    {
+        Ok(changes) => changes,
+        Err(e) => return error("500 Internal Server Error", &e.to_string()),
+    };
+
+    let staged = changes.staged.files.iter().find(|f| f.path == file);
+    let unstaged = changes.unstaged.files.iter().find(|f| f.path == file);
+    let counts =
+        |f: Option<&crate::git::FileDiffInfo>| f.map_or((0, 0), |f| (f.additions, f.deletions));
+    let (staged_add, staged_del) = counts(staged);
+    let (unstaged_add, unstaged_del) = counts(unstaged);
+
+    json(&FileSyncStatus {
+        file: file.to_string(),
+        staged: staged.map(|f| f.status.clone()),
+        unstaged: unstaged.map(|f| f.status.clone()),
+        additions: staged_add + unstaged_add,
+        deletions: staged_del + unstaged_del,
+    })
+}
+
+/// Editors usually know absolute paths; the diff view keys files by
+/// repository-relative path
+fn relative_path(file: &str, context: &Context) -> String {
+    match &context.repo_path {
+        Some(root) => Path::new(file)
+            .strip_prefix(root)
+            .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| file.to_string()),
+        None => file.to_string(),
+    }
+}
+
+fn json<T: Serialize>(value: &T) -> Response {
+    match serde_json::to_string(value) {
+        Ok(body) => ("200 OK", Some(("application/json", body))),
+        Err(e) => error("500 Internal Server Error", &e.to_string()),
+    }
+}
+
+fn error(status: &'static str, message: &str) -> Response {
+    let body = serde_json::to_string(&ErrorBody {
+        error: message.to_string(),
+    })
+    .ok();
+    (status, body.map(|body| ("application/json", body)))
+}
@@ -0,0 +1,83 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use tauri::{AppHandle, Emitter};
+
+/// Events kept for replay; older ones are dropped first
+const CAPACITY: usize = 1000;
+
+static LOG: Mutex<EventLog> = Mutex::new(EventLog {
+    events: VecDeque::new(),
+    last_cursor: 0,
+});
+
+struct EventLog {
+    events: VecDeque<RecordedEvent>,
+    last_cursor: u64,
+}
+
+/// A backend event as it was emitted, numbered in emission order
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedEvent {
+    pub cursor: u64,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventsSince {
+    pub events: Vec<RecordedEvent>,
+    /// Pass back as `cursor` on the next call
+    pub cursor: u64,
+    /// Events after the requested cursor were dropped or never recorded by
+    /// this process, so the caller should reload its state instead
+    pub missed: bool,
+}
+
+/// Emit an event to all windows and record it for replay
+pub fn emit<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    if let Ok(payload_value) = serde_json::to_value(payload.clone()) {
+        record(event, payload_value);
+    }
+    if let Err(e) = app_handle.emit(event, payload) {
+        eprintln!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+fn record(event: &str, payload: serde_json::Value) {
+    let mut log = LOG.lock();
+    log.last_cursor += 1;
+    let recorded = RecordedEvent {
+        cursor: log.last_cursor,
+        event: event.to_string(),
+        payload,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    };
+    if log.events.len() == CAPACITY {
+        log.events.pop_front();
+    }
+    log.events.push_back(recorded);
+}
+
+/// Events recorded after `cursor`, oldest first; all retained events when
+/// `cursor` is `None`
+pub fn events_since(cursor: Option<u64>) -> EventsSince {
+    let log = LOG.lock();
+    let after = cursor.unwrap_or(0);
+    let oldest = log.events.front().map_or(log.last_cursor + 1, |e| e.cursor);
+
+    EventsSince {
+        events: log
+            .events
+            .iter()
+            .filter(|e| e.cursor > after)
+            .cloned()
+            .collect(),
+        cursor: log.last_cursor,
+        // A cursor from the future means the backend restarted since
+        missed: cursor.is_some() && (after + 1 < oldest || after > log.last_cursor),
+    }
+}
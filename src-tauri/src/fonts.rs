@@ -0,0 +1,48 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// An installed monospace font family and the faces available for it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonospaceFont {
+    pub family: String,
+    /// Available weights, e.g. `[400, 700]`
+    pub weights: Vec<u16>,
+    pub has_italic: bool,
+}
+
+/// Enumerate installed monospace fonts, sorted by family name.
+///
+/// Only faces that declare themselves fixed-pitch are included, so every entry
+/// is something the diff view can actually render with aligned columns.
+pub fn list_monospace_fonts() -> Vec<MonospaceFont> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+
+    let mut families: BTreeMap<String, MonospaceFont> = BTreeMap::new();
+    for face in db.faces().filter(|face| face.monospaced) {
+        // English names sort first; fall back to whatever the font provides
+        let Some((name, _)) = face.families.first() else {
+            continue;
+        };
+        let font = families
+            .entry(name.to_lowercase())
+            .or_insert_with(|| MonospaceFont {
+                family: name.clone(),
+                weights: Vec::new(),
+                has_italic: false,
+            });
+        if !font.weights.contains(&face.weight.0) {
+            font.weights.push(face.weight.0);
+        }
+        font.has_italic |= face.style != fontdb::Style::Normal;
+    }
+
+    families
+        .into_values()
+        .map(|mut font| {
+            font.weights.sort_unstable();
+            font
+        })
+        .collect()
+}
@@ -0,0 +1,245 @@
+use git2::build::TreeUpdateBuilder;
+use git2::{DiffOptions, FileMode, Repository, Tree};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use super::discard::file_mode;
+use super::types::{ArchiveDiff, DifferConfig};
+use super::{local_diff_result, resolve_commit, GitError, Result};
+
+/// Archive formats CI systems commonly upload source snapshots as
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+}
+
+impl ArchiveKind {
+    fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
+/// Diff a source archive (e.g. what a CI job built from) against the working
+/// tree, or against `base_ref` when given. The archive is the old side, so
+/// additions are what exists locally but not in the archive.
+///
+/// A single top-level directory wrapping everything, as GitHub and GitLab
+/// source archives have, is treated as the repository root. The archive's
+/// objects are only kept in memory and never written to the repository.
+/// Archives unpacking to more than `config.max_archive_bytes` are refused
+/// as soon as they pass it.
+pub fn diff_against_archive(
+    repo: &Repository,
+    archive_path: &Path,
+    base_ref: Option<&str>,
+    config: &DifferConfig,
+) -> Result<ArchiveDiff> {
+    let invalid = |detail: String| {
+        GitError::InvalidArchive(format!("{}: {}", archive_path.display(), detail))
+    };
+    let kind = ArchiveKind::detect(archive_path)
+        .ok_or_else(|| invalid("expected .zip, .tar, .tar.gz or .tar.zst".to_string()))?;
+
+    let extracted = tempfile::tempdir()?;
+    extract(
+        archive_path,
+        kind,
+        extracted.path(),
+        config.max_archive_bytes,
+    )
+    .map_err(|e| invalid(e.to_string()))?;
+    let (root, wrapper) = archive_root(extracted.path())?;
+
+    // A second handle whose writes land in memory, so hashing the archive
+    // leaves no loose objects behind in the user's repository
+    let scratch = Repository::open(repo.path())?;
+    let odb = scratch.odb()?;
+    let _mempack = odb.add_new_mempack_backend(1000)?;
+
+    let archive_tree = build_tree(&scratch, &root)?;
+    let mut diff_opts = DiffOptions::new();
+    let diff = match base_ref {
+        Some(rev) => {
+            let tree = resolve_commit(&scratch, rev)?.tree()?;
+            scratch.diff_tree_to_tree(Some(&archive_tree), Some(&tree), Some(&mut diff_opts))?
+        }
+        None => {
+            diff_opts.include_untracked(true);
+            diff_opts.recurse_untracked_dirs(true);
+            scratch.diff_tree_to_workdir_with_index(Some(&archive_tree), Some(&mut diff_opts))?
+        }
+    };
+    Ok(ArchiveDiff {
+        archive: archive_path.display().to_string(),
+        target: base_ref.unwrap_or("working").to_string(),
+        root: wrapper,
+        diff: local_diff_result(&diff, config)?,
+    })
+}
+
+fn extract(archive_path: &Path, kind: ArchiveKind, into: &Path, limit: u64) -> io::Result<()> {
+    let file = File::open(archive_path)?;
+    let mut left = limit;
+    match kind {
+        ArchiveKind::Zip => unpack_zip(file, into, &mut left),
+        ArchiveKind::Tar => unpack_tar(file, into, &mut left),
+        ArchiveKind::TarGz => unpack_tar(flate2::read::GzDecoder::new(file), into, &mut left),
+        ArchiveKind::TarZst => unpack_tar(zstd::Decoder::new(file)?, into, &mut left),
+    }
+    .map_err(|e| match e.kind() {
+        io::ErrorKind::FileTooLarge => {
+            io::Error::other(format!("unpacks to more than {} bytes", limit))
+        }
+        _ => e,
+    })
+}
+
+/// Unpack a tar stream; the tar crate refuses entries that would land
+/// outside `into`
+fn unpack_tar(reader: impl Read, into: &Path, left: &mut u64) -> io::Result<()> {
+    tar::Archive::new(Capped {
+        inner: reader,
+        left,
+    })
+    .unpack(into)
+}
+
+/// Unpack a zip archive. Names that would land outside `into` are skipped,
+/// and symlinks are only made once every file is written, so nothing is
+/// ever written through one.
+fn unpack_zip(file: File, into: &Path, left: &mut u64) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+    let mut links = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(io::Error::other)?;
+        let Some(path) = entry.enclosed_name().map(|name| into.join(name)) else {
+            continue;
+        };
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let (is_link, mode) = (entry.is_symlink(), entry.unix_mode());
+        let mut contents = Capped {
+            inner: &mut entry,
+            left: &mut *left,
+        };
+        if is_link {
+            let mut target = Vec::new();
+            contents.read_to_end(&mut target)?;
+            links.push((path, target));
+            continue;
+        }
+        io::copy(&mut contents, &mut File::create(&path)?)?;
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode & 0o777))?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+    }
+    for (path, target) in links {
+        make_link(&path, &target)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_link(path: &Path, target: &[u8]) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(target), path)
+}
+
+/// Symlinks need extra privileges on Windows, so do what git does with
+/// `core.symlinks` off and write the target as a plain file
+#[cfg(not(unix))]
+fn make_link(path: &Path, target: &[u8]) -> io::Result<()> {
+    std::fs::write(path, target)
+}
+
+/// Reader that fails with `FileTooLarge` once more than `left` bytes have
+/// come through, shared between the entries of one archive
+struct Capped<'a, R> {
+    inner: R,
+    left: &'a mut u64,
+}
+
+impl<R: Read> Read for Capped<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        *self.left = self
+            .left
+            .checked_sub(read as u64)
+            .ok_or(io::ErrorKind::FileTooLarge)?;
+        Ok(read)
+    }
+}
+
+/// Directory to treat as the repository root, plus the name of the wrapper
+/// directory when the archive had one
+fn archive_root(extracted: &Path) -> Result<(PathBuf, Option<String>)> {
+    let entries: Vec<_> = std::fs::read_dir(extracted)?.collect::<std::io::Result<_>>()?;
+    if let [only] = entries.as_slice() {
+        if only.file_type()?.is_dir() && only.file_name() != ".git" {
+            let name = only.file_name().to_string_lossy().into_owned();
+            return Ok((only.path(), Some(name)));
+        }
+    }
+    Ok((extracted.to_path_buf(), None))
+}
+
+/// Hash every file under `root` into a tree, skipping any `.git` directory.
+/// Symlinks are stored as their target, the way git does, and never
+/// followed, so a link can't pull in files from outside the archive.
+fn build_tree<'r>(repo: &'r Repository, root: &Path) -> Result<Tree<'r>> {
+    let mut update = TreeUpdateBuilder::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if entry.file_name() != ".git" {
+                    pending.push(path);
+                }
+                continue;
+            }
+
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if file_type.is_symlink() {
+                let target = std::fs::read_link(&path)?;
+                let target = target.to_string_lossy().replace('\\', "/");
+                update.upsert(relative, repo.blob(target.as_bytes())?, FileMode::Link);
+            } else {
+                update.upsert(relative, repo.blob_path(&path)?, file_mode(&path));
+            }
+        }
+    }
+
+    let empty = repo.find_tree(repo.treebuilder(None)?.write()?)?;
+    Ok(repo.find_tree(update.create_updated(repo, &empty)?)?)
+}
@@ -0,0 +1,154 @@
+//! A record of what the app changed in each repository.
+//!
+//! Every write made through the app appends a line to an audit file in the
+//! git dir: the operation, when it ran and who ran it, the refs it moved
+//! with their old and new shas, and the paths it touched. Refs are compared
+//! before and after rather than taken from the request, so an entry shows
+//! what actually happened, including for a write that failed partway.
+
+use git2::{Oid, Repository};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::types::{AuditEntry, RefUpdate};
+use super::Result;
+
+/// Stored inside the git dir so it is per-repository and never committed.
+/// One JSON entry per line, oldest first, so writing one is an append.
+const AUDIT_FILE: &str = "differ-audit.jsonl";
+
+/// Paths kept per entry; a large merge or checkout lists the rest as a count
+const MAX_PATHS: usize = 1000;
+
+fn log_path(repo: &Repository) -> PathBuf {
+    repo.path().join(AUDIT_FILE)
+}
+
+/// Where HEAD and every ref point, taken before a write to compare with
+/// after it
+#[derive(Debug, Clone, Default)]
+pub struct RefSnapshot {
+    refs: BTreeMap<String, Oid>,
+}
+
+impl RefSnapshot {
+    pub fn new(repo: &Repository) -> Result<Self> {
+        let mut refs = BTreeMap::new();
+        if let Ok(head) = repo.head() {
+            if let Some(oid) = head.target() {
+                refs.insert("HEAD".to_string(), oid);
+            }
+        }
+        for reference in repo.references()? {
+            let reference = reference?;
+            if let (Some(name), Some(oid)) = (reference.name(), reference.target()) {
+                refs.insert(name.to_string(), oid);
+            }
+        }
+        Ok(Self { refs })
+    }
+
+    /// Refs that differ in `after`, HEAD first and the rest by name
+    fn updates(&self, after: &RefSnapshot) -> Vec<RefUpdate> {
+        let mut names: Vec<&String> = self.refs.keys().chain(after.refs.keys()).collect();
+        names.sort_by_key(|name| (*name != "HEAD", *name));
+        names.dedup();
+        names
+            .into_iter()
+            .filter(|name| self.refs.get(*name) != after.refs.get(*name))
+            .map(|name| RefUpdate {
+                name: name.clone(),
+                old: self.refs.get(name).map(Oid::to_string),
+                new: after.refs.get(name).map(Oid::to_string),
+            })
+            .collect()
+    }
+}
+
+/// Append an entry for the write `operation`, given where refs pointed
+/// before it, the paths it was asked to change and its error if it failed
+pub fn record_operation(
+    repo: &Repository,
+    operation: &str,
+    before: &RefSnapshot,
+    paths: &[String],
+    error: Option<String>,
+) -> Result<AuditEntry> {
+    let after = RefSnapshot::new(repo)?;
+    let mut touched = paths.to_vec();
+    if let (Some(old), Some(new)) = (before.refs.get("HEAD"), after.refs.get("HEAD")) {
+        if old != new {
+            touched.extend(changed_paths(repo, *old, *new)?);
+        }
+    }
+    touched.sort();
+    touched.dedup();
+    let omitted_paths = touched.len().saturating_sub(MAX_PATHS);
+    touched.truncate(MAX_PATHS);
+
+    let entry = AuditEntry {
+        operation: operation.to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        user: repo
+            .signature()
+            .ok()
+            .map(|s| format!("{} <{}>", s.name().unwrap_or(""), s.email().unwrap_or(""))),
+        refs: before.updates(&after),
+        paths: touched,
+        omitted_paths,
+        error,
+    };
+
+    let path = log_path(repo);
+    let mut line = serde_json::to_string(&entry).map_err(std::io::Error::from)?;
+    line.push('\n');
+    if ends_mid_line(&path) {
+        line.insert(0, '\n');
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+    Ok(entry)
+}
+
+/// Logged writes, newest first, skipping `offset` of them
+pub fn get_audit_log(repo: &Repository, limit: usize, offset: usize) -> Result<Vec<AuditEntry>> {
+    let file = match std::fs::File::open(log_path(repo)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        // A line cut short by a crash loses that entry, not the log
+        if let Ok(entry) = serde_json::from_str::<AuditEntry>(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries.into_iter().rev().skip(offset).take(limit).collect())
+}
+
+/// Whether the log's last line was cut short, as by a crash mid-write;
+/// appending straight after it would lose the new entry too
+fn ends_mid_line(path: &Path) -> bool {
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut last = [0u8];
+    file.seek(SeekFrom::End(-1)).is_ok() && file.read_exact(&mut last).is_ok() && last[0] != b'\n'
+}
+
+/// Files that differ between the trees of two commits
+fn changed_paths(repo: &Repository, old: Oid, new: Oid) -> Result<Vec<String>> {
+    let old_tree = repo.find_commit(old)?.tree()?;
+    let new_tree = repo.find_commit(new)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+    Ok(diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().or_else(|| delta.old_file().path()))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
@@ -0,0 +1,357 @@
+use git2::{BlameOptions, DiffOptions, Oid, Patch, Repository};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::file_history::path_in_parent;
+use super::git_config::config_path;
+use super::types::{BlameLine, FileBlame, PreviousBlame};
+use super::{format_commit_time, resolve_commit, Result};
+
+/// Ignore-revs file picked up when `blame.ignoreRevsFile` is not configured
+const DEFAULT_IGNORE_REVS_FILE: &str = ".git-blame-ignore-revs";
+
+/// Longest chain of ignored revisions followed for a single line
+const MAX_IGNORED_HOPS: usize = 16;
+
+/// Where a line came from: the commit that last changed it, and the path and
+/// 1-based line number it had in that commit
+#[derive(Debug, Clone)]
+struct Attribution {
+    pub commit: Oid,
+    pub path: String,
+    pub line: usize,
+}
+
+/// Attribute every line of a file to the commit that last changed it.
+///
+/// `git_ref` picks the revision whose copy of the file is blamed, defaulting
+/// to HEAD. Revisions listed in the ignore-revs file (`blame.ignoreRevsFile`,
+/// or `.git-blame-ignore-revs` at the repository root) are looked through:
+/// their lines are attributed to whatever the line was before them and
+/// marked `adjusted`. Lines are returned in file order.
+pub fn get_blame(repo: &Repository, file_path: &str, git_ref: Option<&str>) -> Result<FileBlame> {
+    let git_ref = git_ref.unwrap_or("HEAD");
+    let newest = resolve_commit(repo, git_ref)?.id();
+    let (ignore_revs_file, ignored) = ignored_revisions(repo);
+
+    let mut reblamer = Reblamer::new(repo);
+    let attributions = reblamer.blame(file_path, newest)?.to_vec();
+
+    // Lines from the same commit share one lookup
+    let mut commits: HashMap<Oid, BlameLine> = HashMap::new();
+    let mut lines = Vec::with_capacity(attributions.len());
+    for (idx, original) in attributions.iter().enumerate() {
+        let (attribution, ignored_sha) = reblamer.skip_ignored(original, &ignored)?;
+        let template = match commits.entry(attribution.commit) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(describe_commit(repo, attribution.commit)?),
+        };
+        lines.push(blame_line(
+            idx + 1,
+            file_path,
+            &attribution,
+            ignored_sha,
+            template,
+        ));
+    }
+
+    Ok(FileBlame {
+        path: file_path.to_string(),
+        git_ref: git_ref.to_string(),
+        lines,
+        ignore_revs_file: ignore_revs_file.map(|p| p.to_string_lossy().to_string()),
+    })
+}
+
+/// Blame the line a [`BlameLine`] points at one step further back: in the
+/// first parent of `commit`, where `path` and `line` are the line's
+/// `orig_path`/`orig_line` in that commit.
+///
+/// The line is carried through the commit's own diff, so the result is the
+/// same logical line even when lines above it were added or removed, and the
+/// file may have been renamed. Ignore-revs apply as in [`get_blame`].
+pub fn get_blame_at_parent(
+    repo: &Repository,
+    file_path: &str,
+    line: usize,
+    commit: &str,
+) -> Result<PreviousBlame> {
+    let commit = resolve_commit(repo, commit)?;
+    let mut previous = PreviousBlame {
+        commit: commit.id().to_string(),
+        parent: None,
+        path: None,
+        line: None,
+        blame: None,
+    };
+    if commit.parent_count() == 0 {
+        return Ok(previous);
+    }
+    previous.parent = Some(commit.parent_id(0)?.to_string());
+
+    let mut reblamer = Reblamer::new(repo);
+    let here = Attribution {
+        commit: commit.id(),
+        path: file_path.to_string(),
+        line,
+    };
+    let Some((parent_path, parent_line)) = reblamer.map_to_parent(&here)? else {
+        return Ok(previous);
+    };
+    previous.path = Some(parent_path.clone());
+    previous.line = Some(parent_line);
+
+    let parent = commit.parent_id(0)?;
+    let Some(attribution) = reblamer
+        .blame(&parent_path, parent)?
+        .get(parent_line - 1)
+        .cloned()
+    else {
+        return Ok(previous);
+    };
+    let (_, ignored) = ignored_revisions(repo);
+    let (attribution, ignored_sha) = reblamer.skip_ignored(&attribution, &ignored)?;
+    let template = describe_commit(repo, attribution.commit)?;
+    previous.blame = Some(blame_line(
+        parent_line,
+        &parent_path,
+        &attribution,
+        ignored_sha,
+        &template,
+    ));
+    Ok(previous)
+}
+
+fn blame_line(
+    line: usize,
+    path: &str,
+    attribution: &Attribution,
+    ignored_sha: Option<Oid>,
+    template: &BlameLine,
+) -> BlameLine {
+    BlameLine {
+        line,
+        orig_line: attribution.line,
+        orig_path: (attribution.path != path).then(|| attribution.path.clone()),
+        adjusted: ignored_sha.is_some(),
+        ignored_sha: ignored_sha.map(|oid| oid.to_string()),
+        ..template.clone()
+    }
+}
+
+/// Blames files and follows lines back through single commits, caching the
+/// blames and line maps it computes along the way
+struct Reblamer<'r> {
+    repo: &'r Repository,
+    blames: HashMap<(Oid, String), Vec<Attribution>>,
+    line_maps: HashMap<(Oid, String), Option<LineMap>>,
+}
+
+impl<'r> Reblamer<'r> {
+    pub fn new(repo: &'r Repository) -> Self {
+        Self {
+            repo,
+            blames: HashMap::new(),
+            line_maps: HashMap::new(),
+        }
+    }
+
+    /// Attribution of every line of `path` as of `newest`, in file order
+    pub fn blame(&mut self, path: &str, newest: Oid) -> Result<&[Attribution]> {
+        let key = (newest, path.to_string());
+        if !self.blames.contains_key(&key) {
+            let lines = blame_lines(self.repo, path, newest)?;
+            self.blames.insert(key.clone(), lines);
+        }
+        Ok(&self.blames[&key])
+    }
+
+    /// Follow `attribution` back past any commits in `ignored`, returning
+    /// where it ends up and the first ignored commit it was moved past
+    pub fn skip_ignored(
+        &mut self,
+        attribution: &Attribution,
+        ignored: &HashSet<Oid>,
+    ) -> Result<(Attribution, Option<Oid>)> {
+        let mut attribution = attribution.clone();
+        let mut ignored_sha = None;
+        for _ in 0..MAX_IGNORED_HOPS {
+            if !ignored.contains(&attribution.commit) {
+                break;
+            }
+            match self.at_parent(&attribution)? {
+                Some(previous) => {
+                    ignored_sha.get_or_insert(attribution.commit);
+                    attribution = previous;
+                }
+                // Added outright by the ignored revision; nothing to look through
+                None => break,
+            }
+        }
+        Ok((attribution, ignored_sha))
+    }
+
+    /// Blame the same logical line as it was just before `attribution.commit`
+    pub fn at_parent(&mut self, attribution: &Attribution) -> Result<Option<Attribution>> {
+        let Some((path, line)) = self.map_to_parent(attribution)? else {
+            return Ok(None);
+        };
+        let parent = self.repo.find_commit(attribution.commit)?.parent_id(0)?;
+        let lines = self.blame(&path, parent)?;
+        Ok(lines.get(line - 1).cloned())
+    }
+
+    /// Path and line number of `attribution`'s line in the first parent of its
+    /// commit. `None` when the commit added the line outright, the file did
+    /// not exist in the parent, or the commit has no parent.
+    pub fn map_to_parent(&mut self, attribution: &Attribution) -> Result<Option<(String, usize)>> {
+        let key = (attribution.commit, attribution.path.clone());
+        if !self.line_maps.contains_key(&key) {
+            let map = LineMap::to_parent(self.repo, attribution.commit, &attribution.path)?;
+            self.line_maps.insert(key.clone(), map);
+        }
+        let Some(map) = &self.line_maps[&key] else {
+            return Ok(None);
+        };
+        Ok(map
+            .parent_line(attribution.line)
+            .map(|line| (map.parent_path.clone(), line)))
+    }
+}
+
+fn blame_lines(repo: &Repository, path: &str, newest: Oid) -> Result<Vec<Attribution>> {
+    let mut opts = BlameOptions::new();
+    opts.newest_commit(newest);
+    let blame = repo.blame_file(Path::new(path), Some(&mut opts))?;
+
+    let mut lines = Vec::new();
+    for hunk in blame.iter() {
+        let commit = hunk.final_commit_id();
+        let orig_path = hunk
+            .path()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string());
+        for offset in 0..hunk.lines_in_hunk() {
+            lines.push(Attribution {
+                commit,
+                path: orig_path.clone(),
+                line: hunk.orig_start_line() + offset,
+            });
+        }
+    }
+    Ok(lines)
+}
+
+/// Maps line numbers of a file in a commit to the same file in its parent
+struct LineMap {
+    /// The file's path in the parent, which differs after a rename
+    parent_path: String,
+    /// `(old_start, old_lines, new_start, new_lines)` of each zero-context hunk
+    hunks: Vec<(usize, usize, usize, usize)>,
+}
+
+impl LineMap {
+    fn to_parent(repo: &Repository, commit: Oid, path: &str) -> Result<Option<Self>> {
+        let commit = repo.find_commit(commit)?;
+        if commit.parent_count() == 0 {
+            return Ok(None);
+        }
+        let parent_tree = commit.parent(0)?.tree()?;
+        let tree = commit.tree()?;
+        let Some(parent_path) = path_in_parent(repo, &parent_tree, &tree, path)? else {
+            return Ok(None);
+        };
+
+        let mut opts = DiffOptions::new();
+        opts.pathspec(&parent_path).pathspec(path).context_lines(0);
+        let mut diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(&mut opts))?;
+        if parent_path != path {
+            diff.find_similar(None)?;
+        }
+
+        let mut hunks = Vec::new();
+        if diff.deltas().len() > 0 {
+            if let Some(patch) = Patch::from_diff(&diff, 0)? {
+                for idx in 0..patch.num_hunks() {
+                    let (hunk, _) = patch.hunk(idx)?;
+                    hunks.push((
+                        hunk.old_start() as usize,
+                        hunk.old_lines() as usize,
+                        hunk.new_start() as usize,
+                        hunk.new_lines() as usize,
+                    ));
+                }
+            }
+        }
+
+        Ok(Some(Self { parent_path, hunks }))
+    }
+
+    /// Parent line for `line`, or `None` when the commit introduced it.
+    ///
+    /// Unchanged lines shift by the lines added and removed above them. A
+    /// rewritten line maps to the removed line at the same position in its
+    /// hunk; lines beyond the removed ones count as introduced.
+    fn parent_line(&self, line: usize) -> Option<usize> {
+        let mut shift: isize = 0;
+        for &(old_start, old_lines, new_start, new_lines) in &self.hunks {
+            if new_lines > 0 && (new_start..new_start + new_lines).contains(&line) {
+                let offset = line - new_start;
+                return (offset < old_lines).then_some(old_start + offset);
+            }
+            // A pure deletion sits after `new_start`; everything else before it
+            let before = if new_lines == 0 {
+                new_start < line
+            } else {
+                new_start + new_lines <= line
+            };
+            if !before {
+                break;
+            }
+            shift += old_lines as isize - new_lines as isize;
+        }
+        usize::try_from(line as isize + shift).ok()
+    }
+}
+
+/// The configured or conventional ignore-revs file and the revisions it lists.
+/// Entries that do not resolve are skipped, as `git blame` does.
+fn ignored_revisions(repo: &Repository) -> (Option<PathBuf>, HashSet<Oid>) {
+    let Some(workdir) = repo.workdir() else {
+        return (None, HashSet::new());
+    };
+    let path = config_path(repo, "blame.ignoreRevsFile")
+        .unwrap_or_else(|| workdir.join(DEFAULT_IGNORE_REVS_FILE));
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return (None, HashSet::new());
+    };
+
+    let revisions = contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|rev| !rev.is_empty())
+        .filter_map(|rev| resolve_commit(repo, rev).ok())
+        .map(|commit| commit.id())
+        .collect();
+    (Some(path), revisions)
+}
+
+fn describe_commit(repo: &Repository, oid: Oid) -> Result<BlameLine> {
+    let commit = repo.find_commit(oid)?;
+    let sha = oid.to_string();
+    let author = commit.author();
+    Ok(BlameLine {
+        line: 0,
+        short_sha: sha[..7].to_string(),
+        sha,
+        author: author.name().unwrap_or("").to_string(),
+        author_email: author.email().unwrap_or("").to_string(),
+        date: format_commit_time(&commit),
+        summary: commit.summary().unwrap_or("").to_string(),
+        orig_line: 0,
+        orig_path: None,
+        adjusted: false,
+        ignored_sha: None,
+    })
+}
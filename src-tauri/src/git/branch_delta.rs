@@ -0,0 +1,93 @@
+use git2::{BranchType, Delta, DiffOptions, Repository};
+use std::collections::HashSet;
+
+use super::repo_status::detect_default_branch;
+use super::types::{BranchDelta, BranchDeltaFile, FileStatus};
+use super::{resolve_commit, GitError, Result};
+
+/// Split the current working-tree changes into files the branch already
+/// touched since it diverged from `base_ref` and files only edited locally.
+///
+/// Without `base_ref` the default branch is used, or the current branch's
+/// upstream when HEAD is the default branch itself.
+pub fn get_branch_delta(repo: &Repository, base_ref: Option<&str>) -> Result<BranchDelta> {
+    let base_ref = match base_ref {
+        Some(r) => r.to_string(),
+        None => comparison_base(repo).ok_or_else(|| {
+            GitError::Git(git2::Error::from_str(
+                "No default branch or upstream to compare against",
+            ))
+        })?,
+    };
+
+    let head = repo.head()?.peel_to_commit()?;
+    let base = resolve_commit(repo, &base_ref)?;
+    let merge_base = repo.find_commit(repo.merge_base(base.id(), head.id())?)?;
+
+    // Everything the branch changed since it diverged
+    let branch_diff =
+        repo.diff_tree_to_tree(Some(&merge_base.tree()?), Some(&head.tree()?), None)?;
+    let mut branch_files = HashSet::new();
+    for delta in branch_diff.deltas() {
+        for file in [delta.old_file(), delta.new_file()] {
+            if let Some(path) = file.path() {
+                branch_files.insert(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    // Uncommitted changes on top of HEAD
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.include_untracked(true);
+    diff_opts.recurse_untracked_dirs(true);
+    let local_diff =
+        repo.diff_tree_to_workdir_with_index(Some(&head.tree()?), Some(&mut diff_opts))?;
+
+    let files: Vec<BranchDeltaFile> = local_diff
+        .deltas()
+        .filter_map(|delta| {
+            let path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())?
+                .to_string_lossy()
+                .to_string();
+            let status = match delta.status() {
+                Delta::Added | Delta::Untracked => FileStatus::Added,
+                Delta::Deleted => FileStatus::Deleted,
+                Delta::Renamed => FileStatus::Renamed,
+                _ => FileStatus::Modified,
+            };
+            Some(BranchDeltaFile {
+                changed_on_branch: branch_files.contains(&path),
+                path,
+                status,
+            })
+        })
+        .collect();
+
+    let mut branch_files: Vec<String> = branch_files.into_iter().collect();
+    branch_files.sort();
+
+    Ok(BranchDelta {
+        base_ref,
+        merge_base: merge_base.id().to_string(),
+        branch_files,
+        files,
+    })
+}
+
+/// The default branch, unless HEAD is on it, then HEAD's upstream
+fn comparison_base(repo: &Repository) -> Option<String> {
+    let current = repo.head().ok()?.shorthand().map(String::from);
+    let default = detect_default_branch(repo).map(|(name, _)| name);
+    if default.is_some() && default != current {
+        return default;
+    }
+
+    let branch = repo
+        .find_branch(current.as_deref()?, BranchType::Local)
+        .ok()?;
+    let upstream = branch.upstream().ok()?;
+    upstream.name().ok()?.map(String::from)
+}
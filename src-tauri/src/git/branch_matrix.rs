@@ -0,0 +1,79 @@
+use git2::{BranchType, Repository};
+use glob::Pattern;
+
+use super::types::{BranchMatrix, BranchMatrixCell};
+use super::{resolve_commit, Result};
+
+/// Pairwise ahead/behind counts between branches.
+///
+/// Entries may be branch names, any revision, or glob patterns such as
+/// `release/*`, which expand to the matching local branches in name order.
+/// Each pair appears once per direction: `ahead` is how many commits `head`
+/// has that `base` lacks, `behind` the reverse.
+pub fn compare_branch_matrix(repo: &Repository, branches: &[String]) -> Result<BranchMatrix> {
+    let names = expand_branches(repo, branches)?;
+
+    let mut oids = Vec::with_capacity(names.len());
+    for name in &names {
+        oids.push(resolve_commit(repo, name)?.id());
+    }
+
+    let mut cells = Vec::new();
+    for (i, base) in names.iter().enumerate() {
+        for (j, head) in names.iter().enumerate().skip(i + 1) {
+            let (ahead, behind) = repo.graph_ahead_behind(oids[j], oids[i])?;
+            cells.push(BranchMatrixCell {
+                base: base.clone(),
+                head: head.clone(),
+                ahead,
+                behind,
+            });
+            cells.push(BranchMatrixCell {
+                base: head.clone(),
+                head: base.clone(),
+                ahead: behind,
+                behind: ahead,
+            });
+        }
+    }
+
+    Ok(BranchMatrix {
+        branches: names,
+        cells,
+    })
+}
+
+fn is_glob(entry: &str) -> bool {
+    entry.contains(['*', '?', '['])
+}
+
+fn expand_branches(repo: &Repository, branches: &[String]) -> Result<Vec<String>> {
+    let mut local = Vec::new();
+    if branches.iter().any(|b| is_glob(b)) {
+        for branch in repo.branches(Some(BranchType::Local))? {
+            if let Some(name) = branch?.0.name()? {
+                local.push(name.to_string());
+            }
+        }
+        local.sort();
+    }
+
+    let mut expanded: Vec<String> = Vec::new();
+    for entry in branches {
+        let matches = match Pattern::new(entry) {
+            Ok(pattern) if is_glob(entry) => local
+                .iter()
+                .filter(|name| pattern.matches(name))
+                .cloned()
+                .collect(),
+            // Plain names, revisions, and malformed patterns are used as-is
+            _ => vec![entry.clone()],
+        };
+        for name in matches {
+            if !expanded.contains(&name) {
+                expanded.push(name);
+            }
+        }
+    }
+    Ok(expanded)
+}
@@ -0,0 +1,91 @@
+use git2::{Branch, BranchType, ErrorCode, Repository};
+
+use super::types::BranchInfo;
+use super::{is_merged, resolve_commit, BranchContext, GitError, Result};
+
+/// Create local branch `name` at `start_point`, which may be any revision.
+/// Starting from a remote-tracking branch sets it as the upstream, as
+/// `git branch` does by default.
+pub fn create_branch(repo: &Repository, name: &str, start_point: &str) -> Result<BranchInfo> {
+    check_name(name)?;
+    let start = resolve_commit(repo, start_point)?;
+    let mut branch = repo
+        .branch(name, &start, false)
+        .map_err(|e| exists_error(e, name))?;
+
+    if let Ok(reference) = repo.resolve_reference_from_short_name(start_point) {
+        if reference.is_remote() {
+            branch.set_upstream(reference.shorthand())?;
+        }
+    }
+
+    BranchContext::new(repo)?.branch_info(repo, name.to_string(), start.id())
+}
+
+/// Delete local branch `name` and return the sha it pointed at, so the
+/// deletion can be undone. Like `git branch -d`, a branch whose commits are
+/// not all in its upstream (or in HEAD when it has none) is only deleted
+/// with `force`. The checked-out branch is never deleted.
+pub fn delete_branch(repo: &Repository, name: &str, force: bool) -> Result<String> {
+    let mut branch = find_local(repo, name)?;
+    if branch.is_head() {
+        return Err(GitError::BranchCheckedOut(name.to_string()));
+    }
+    let tip = branch.get().peel_to_commit()?.id();
+
+    if !force {
+        let target = match branch.upstream() {
+            Ok(upstream) => upstream.get().peel_to_commit()?.id(),
+            Err(_) => repo.head()?.peel_to_commit()?.id(),
+        };
+        if !is_merged(repo, tip, target) {
+            return Err(GitError::BranchNotMerged(name.to_string()));
+        }
+    }
+
+    branch.delete()?;
+    Ok(tip.to_string())
+}
+
+/// Rename local branch `old_name`, carrying its config (such as the
+/// upstream) along. Renaming the checked-out branch keeps it checked out.
+/// `force` replaces an existing branch called `new_name`.
+pub fn rename_branch(
+    repo: &Repository,
+    old_name: &str,
+    new_name: &str,
+    force: bool,
+) -> Result<BranchInfo> {
+    check_name(new_name)?;
+    let mut branch = find_local(repo, old_name)?;
+    let renamed = branch
+        .rename(new_name, force)
+        .map_err(|e| exists_error(e, new_name))?;
+    let tip = renamed.get().peel_to_commit()?.id();
+
+    BranchContext::new(repo)?.branch_info(repo, new_name.to_string(), tip)
+}
+
+/// Local branch `name`, or `BranchNotFound`
+pub(crate) fn find_local<'r>(repo: &'r Repository, name: &str) -> Result<Branch<'r>> {
+    repo.find_branch(name, BranchType::Local)
+        .map_err(|e| match e.code() {
+            ErrorCode::NotFound => GitError::BranchNotFound(name.to_string()),
+            _ => e.into(),
+        })
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if Branch::name_is_valid(name)? {
+        Ok(())
+    } else {
+        Err(GitError::InvalidBranchName(name.to_string()))
+    }
+}
+
+fn exists_error(error: git2::Error, name: &str) -> GitError {
+    match error.code() {
+        ErrorCode::Exists => GitError::BranchExists(name.to_string()),
+        _ => error.into(),
+    }
+}
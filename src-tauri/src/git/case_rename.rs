@@ -0,0 +1,232 @@
+use git2::{Diff, ErrorCode, ObjectType, Oid, Patch, Repository, Tree};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::minimap::MinimapBuilder;
+use super::navigation::AnchorBuilder;
+use super::object_cache;
+use super::paths::join_relative;
+use super::types::{FileDiffInfo, FileStatus};
+use super::{push_patch_line, Result};
+
+/// Whether two paths differ only in letter case, like `Foo.ts` and `foo.ts`
+fn differs_only_in_case(a: &str, b: &str) -> bool {
+    a != b && a.to_lowercase() == b.to_lowercase()
+}
+
+/// Fold a deletion and an addition whose paths differ only in case into one
+/// rename entry marked `case_rename`, diffing the two sides against each
+/// other. Without rename detection git reports such a rename as two unrelated
+/// files, which on case-insensitive filesystems look like duplicates.
+///
+/// `files` must hold one entry per delta of `diff`, in the same order.
+pub(crate) fn merge_case_renames(
+    diff: &Diff,
+    files: &mut Vec<FileDiffInfo>,
+    max_patch_size: usize,
+) -> Result<()> {
+    // Renames git already paired up
+    for file in files.iter_mut() {
+        if let Some(old_path) = &file.old_path {
+            if differs_only_in_case(old_path, &file.path) {
+                file.case_rename = Some(true);
+            }
+        }
+    }
+
+    let added: HashMap<String, usize> = files
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| matches!(f.status, FileStatus::Added))
+        .map(|(idx, f)| (f.path.to_lowercase(), idx))
+        .collect();
+    let pairs: Vec<(usize, usize)> = files
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| matches!(f.status, FileStatus::Deleted))
+        .filter_map(|(idx, f)| {
+            let added_idx = *added.get(&f.path.to_lowercase())?;
+            differs_only_in_case(&f.path, &files[added_idx].path).then_some((idx, added_idx))
+        })
+        .collect();
+    if pairs.is_empty() {
+        return Ok(());
+    }
+
+    let mut removed = Vec::new();
+    for (deleted_idx, added_idx) in pairs {
+        // A side that can't be read stays as its own entry with its error
+        let (Ok(Some(old_patch)), Ok(Some(new_patch))) = (
+            Patch::from_diff(diff, deleted_idx),
+            Patch::from_diff(diff, added_idx),
+        ) else {
+            continue;
+        };
+        let old_path = files[deleted_idx].path.clone();
+        let file = &mut files[added_idx];
+        file.status = FileStatus::Renamed;
+        file.old_path = Some(old_path);
+        file.case_rename = Some(true);
+
+        // Binary sides carry no lines to compare
+        let binary = old_patch.delta().flags().is_binary() || new_patch.delta().flags().is_binary();
+        if !binary {
+            let old = side_content(&old_patch, '-')?;
+            let new = side_content(&new_patch, '+')?;
+            rediff(file, &old, &new, max_patch_size)?;
+        }
+        removed.push(deleted_idx);
+    }
+
+    removed.sort_unstable();
+    for idx in removed.into_iter().rev() {
+        files.remove(idx);
+    }
+    Ok(())
+}
+
+/// The whole content of an added or deleted file, rebuilt from its patch
+fn side_content(patch: &Patch, origin: char) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    for hunk in 0..patch.num_hunks() {
+        for idx in 0..patch.num_lines_in_hunk(hunk)? {
+            let line = patch.line_in_hunk(hunk, idx)?;
+            if line.origin() == origin {
+                content.extend_from_slice(line.content());
+            }
+        }
+    }
+    Ok(content)
+}
+
+/// Replace a file's counts, patch, minimap and anchors with a diff of `old` to `new`,
+/// in the same form `parse_diff` produces
+fn rediff(file: &mut FileDiffInfo, old: &[u8], new: &[u8], max_patch_size: usize) -> Result<()> {
+    let mut patch = Patch::from_buffers(old, None, new, None, None)?;
+    let (mut additions, mut deletions) = (0, 0);
+    let mut text = String::new();
+    let mut minimap = MinimapBuilder::default();
+    let mut anchors = AnchorBuilder::default();
+    patch.print(&mut |_delta, hunk, line| {
+        match line.origin() {
+            '+' => additions += 1,
+            '-' => deletions += 1,
+            // File and hunk headers aren't part of parsed patches
+            'F' | 'H' => return true,
+            _ => {}
+        }
+        minimap.push_line(hunk.as_ref(), &line);
+        anchors.push_line(hunk.as_ref(), &line);
+        push_patch_line(&mut text, &line);
+        true
+    })?;
+
+    file.additions = additions;
+    file.deletions = deletions;
+    file.is_large = Some(text.len() > max_patch_size);
+    file.patch = Some(if text.len() > max_patch_size {
+        String::new()
+    } else {
+        text
+    });
+    file.minimap = Some(minimap.finish());
+    file.hunks = anchors.finish(&file.path);
+    Ok(())
+}
+
+/// Whether git compares paths case-insensitively here; `git init` turns
+/// `core.ignorecase` on for case-insensitive filesystems
+fn ignores_case(repo: &Repository) -> bool {
+    repo.config()
+        .and_then(|config| config.get_bool("core.ignorecase"))
+        .unwrap_or(false)
+}
+
+/// Contents of the file at `path` in `rev`, through the shared object cache.
+/// When the repository ignores case and no entry matches exactly, an entry
+/// differing only in case is used, so a path typed or cached with the old
+/// casing still resolves after a case-only rename.
+pub(crate) fn blob_content(
+    repo: &Repository,
+    rev: &str,
+    path: &str,
+) -> std::result::Result<Arc<[u8]>, git2::Error> {
+    let tree = repo.revparse_single(rev)?.peel_to_tree()?;
+    let oid = tree_blob(repo, &tree, path)?;
+    object_cache::global().blob(repo, oid)
+}
+
+/// Id of the blob at `path` in `tree`, with the same case fallback as
+/// `blob_content`
+pub(crate) fn tree_blob(
+    repo: &Repository,
+    tree: &Tree,
+    path: &str,
+) -> std::result::Result<Oid, git2::Error> {
+    object_cache::global().tree_entry(tree.id(), path, || {
+        let entry = match tree.get_path(Path::new(path)) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == ErrorCode::NotFound && ignores_case(repo) => {
+                match tree_path(repo, tree, path) {
+                    Some(actual) => tree.get_path(Path::new(&actual))?,
+                    None => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        match entry.kind() {
+            Some(ObjectType::Blob) => Ok(entry.id()),
+            _ => Err(git2::Error::from_str(&format!("{} is not a file", path))),
+        }
+    })
+}
+
+/// `path` as spelled in `tree`, matching each component case-insensitively
+/// when there is no exact match
+fn tree_path(repo: &Repository, tree: &Tree, path: &str) -> Option<String> {
+    let components: Vec<&str> = path.split('/').collect();
+    let mut current = tree.clone();
+    let mut resolved = Vec::new();
+    for (idx, component) in components.iter().enumerate() {
+        let lower = component.to_lowercase();
+        let entry = current.get_name(component).or_else(|| {
+            current
+                .iter()
+                .find(|e| e.name().is_some_and(|name| name.to_lowercase() == lower))
+        })?;
+        resolved.push(entry.name()?.to_string());
+        if idx + 1 < components.len() {
+            let next = entry.to_object(repo).ok()?.peel_to_tree().ok()?;
+            drop(entry);
+            current = next;
+        }
+    }
+    Some(resolved.join("/"))
+}
+
+/// Where `path` lives in the working tree. When the repository ignores case,
+/// each component takes the casing it actually has on disk, which
+/// case-insensitive filesystems otherwise hide from `workdir.join(path)`.
+pub(crate) fn workdir_file(repo: &Repository, workdir: &Path, path: &str) -> PathBuf {
+    if !ignores_case(repo) {
+        return join_relative(workdir, path);
+    }
+    let mut resolved = workdir.to_path_buf();
+    for component in path.split('/') {
+        let lower = component.to_lowercase();
+        let on_disk = std::fs::read_dir(&resolved).ok().and_then(|entries| {
+            let names: Vec<String> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect();
+            names
+                .iter()
+                .find(|name| *name == component)
+                .or_else(|| names.iter().find(|name| name.to_lowercase() == lower))
+                .cloned()
+        });
+        resolved.push(on_disk.as_deref().unwrap_or(component));
+    }
+    resolved
+}
@@ -0,0 +1,97 @@
+use git2::build::CheckoutBuilder;
+use git2::{Repository, RepositoryState, StashFlags, Status, StatusOptions};
+
+use super::branches::find_local;
+use super::stash::stash_apply;
+use super::types::CheckoutResult;
+use super::{GitError, Result};
+
+/// Switch the working tree and HEAD to the local branch `name`.
+///
+/// Staged or unstaged changes to tracked files refuse the switch unless
+/// `auto_stash` is set, in which case they are stashed first and popped onto
+/// the new branch like `git switch` with `rebase.autoStash`. A stash that
+/// conflicts there stays in the stash list. Untracked files are left alone,
+/// but the switch still fails if the branch would overwrite one.
+pub fn checkout_branch(
+    repo: &mut Repository,
+    name: &str,
+    auto_stash: bool,
+) -> Result<CheckoutResult> {
+    if repo.state() != RepositoryState::Clean {
+        return Err(GitError::MergeInProgress);
+    }
+    let branch = find_local(repo, name)?;
+    let reference = branch
+        .get()
+        .name()
+        .ok_or_else(|| GitError::BranchNotFound(name.to_string()))?
+        .to_string();
+    let target_id = branch.get().peel_to_commit()?.id();
+    drop(branch);
+
+    let previous = current_position(repo);
+    let mut result = CheckoutResult {
+        branch: name.to_string(),
+        sha: target_id.to_string(),
+        previous: previous.clone(),
+        auto_stash: None,
+    };
+    if previous.as_deref() == Some(name) {
+        return Ok(result);
+    }
+
+    let changed = changed_files(repo)?;
+    let stashed = if changed.is_empty() {
+        false
+    } else if auto_stash {
+        let signature = repo.signature().map_err(|_| GitError::IdentityMissing)?;
+        let message = format!("autostash before switching to {}", name);
+        repo.stash_save2(&signature, Some(&message), Some(StashFlags::DEFAULT))?;
+        true
+    } else {
+        return Err(GitError::UncommittedChanges(changed));
+    };
+
+    let switched = repo
+        .find_commit(target_id)
+        .and_then(|commit| {
+            repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))
+        })
+        .and_then(|()| repo.set_head(&reference));
+    if let Err(e) = switched {
+        // Nothing moved, so the stash goes straight back where it came from;
+        // if even that fails it is still in the stash list
+        if stashed {
+            let _ = stash_apply(repo, 0, true);
+        }
+        return Err(e.into());
+    }
+
+    if stashed {
+        result.auto_stash = Some(stash_apply(repo, 0, true)?);
+    }
+    Ok(result)
+}
+
+/// The checked-out branch, or the short sha of a detached HEAD
+fn current_position(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    if head.is_branch() {
+        head.shorthand().map(str::to_string)
+    } else {
+        head.target().map(|oid| oid.to_string()[..7].to_string())
+    }
+}
+
+/// Tracked files with staged or unstaged changes
+pub(crate) fn changed_files(repo: &Repository) -> Result<Vec<String>> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut options))?;
+    Ok(statuses
+        .iter()
+        .filter(|entry| !(entry.status() - Status::IGNORED).is_empty())
+        .filter_map(|entry| entry.path().map(str::to_string))
+        .collect())
+}
@@ -0,0 +1,74 @@
+//! Spotting commits that already landed under another sha, like `git cherry`.
+//!
+//! A commit cherry-picked or rebased onto the base branch has a new id but
+//! the same change. Comparing patch ids, a hash of the diff that ignores line
+//! numbers and whitespace, finds those so a review can skip them.
+
+use git2::{Commit, Oid, Repository};
+use std::collections::HashMap;
+
+/// Base-branch commits since the merge base, by patch id
+pub(crate) struct Upstream {
+    by_patch: HashMap<Oid, Oid>,
+    /// `head..base` had more than the commits looked at, so equivalents
+    /// among the older ones go unnoticed
+    pub(crate) truncated: bool,
+}
+
+impl Upstream {
+    /// Patch ids of the newest `limit` commits in `head..base`; each needs
+    /// a diff, and a base far ahead of the merge base has thousands
+    pub(crate) fn new(
+        repo: &Repository,
+        base: Oid,
+        head: Oid,
+        limit: usize,
+    ) -> Result<Self, git2::Error> {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(base)?;
+        revwalk.hide(head)?;
+        // Newest first even when clocks disagree, so the limit keeps the
+        // commits nearest the base tip
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+        let mut by_patch = HashMap::new();
+        let mut revwalk = revwalk.peekable();
+        for oid in revwalk.by_ref().take(limit) {
+            let commit = repo.find_commit(oid?)?;
+            if let Some(patch) = patch_id(repo, &commit)? {
+                // The oldest equivalent is the one that first landed it
+                by_patch.insert(patch, commit.id());
+            }
+        }
+        Ok(Self {
+            by_patch,
+            truncated: revwalk.peek().is_some(),
+        })
+    }
+
+    /// The base commit making the same change as `commit`, if any
+    pub(crate) fn equivalent(&self, repo: &Repository, commit: &Commit) -> Option<Oid> {
+        if self.by_patch.is_empty() {
+            return None;
+        }
+        let patch = patch_id(repo, commit).ok().flatten()?;
+        self.by_patch.get(&patch).copied()
+    }
+}
+
+/// Patch id of a commit's change against its parent. Merges and commits
+/// that change nothing have none.
+fn patch_id(repo: &Repository, commit: &Commit) -> Result<Option<Oid>, git2::Error> {
+    if commit.parent_count() > 1 {
+        return Ok(None);
+    }
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    if diff.deltas().len() == 0 {
+        return Ok(None);
+    }
+    diff.patchid(None).map(Some)
+}
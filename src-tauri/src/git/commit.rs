@@ -0,0 +1,206 @@
+use git2::{Commit, ObjectType, Oid, Repository, RepositoryState, Signature, Tree};
+
+use super::commit_template::comment_char;
+use super::hooks::run_required_hook;
+use super::refs::list_refs;
+use super::signing::sign_commit;
+use super::types::{CommitInfo, HookPolicy};
+use super::{commit_to_info, GitError, Result};
+
+/// Commit the staged changes on the current branch, like `git commit`.
+///
+/// The `pre-commit` and `commit-msg` hooks run as `hooks` allows, and either
+/// failing aborts the commit. Comment lines and surrounding blank lines are
+/// stripped from the message the way git's default cleanup does. When a merge
+/// is being concluded, the merged commits become extra parents; a revert or
+/// cherry-pick stopped by conflicts is concluded the same way, a pick keeping
+/// its original author. The commit is signed when `commit.gpgsign` is set.
+pub fn create_commit(repo: &Repository, message: &str, hooks: &HookPolicy) -> Result<CommitInfo> {
+    let signature = repo.signature().map_err(|_| GitError::IdentityMissing)?;
+    let head = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let merge_heads = merge_heads(repo)?;
+    if merge_heads.is_empty() && !has_staged_changes(repo, head.as_ref())? {
+        return Err(GitError::NothingToCommit);
+    }
+
+    let message = run_commit_hooks(repo, message, hooks)?;
+
+    // Re-read the index in case pre-commit restaged files
+    let mut index = repo.index()?;
+    index.read(true)?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    let mut parents: Vec<Commit> = head.into_iter().collect();
+    for oid in &merge_heads {
+        parents.push(repo.find_commit(*oid)?);
+    }
+    let parents: Vec<&Commit> = parents.iter().collect();
+    let reflog_kind = if parents.is_empty() {
+        "commit (initial)"
+    } else if merge_heads.is_empty() {
+        "commit"
+    } else {
+        "commit (merge)"
+    };
+    let author = match repo.state() {
+        RepositoryState::CherryPick => picked_author(repo)?,
+        _ => None,
+    };
+    let oid = write_commit(
+        repo,
+        author.as_ref().unwrap_or(&signature),
+        &signature,
+        &message,
+        &tree,
+        &parents,
+        reflog_kind,
+    )?;
+
+    let sequencing = matches!(
+        repo.state(),
+        RepositoryState::Revert | RepositoryState::CherryPick
+    );
+    if !merge_heads.is_empty() || sequencing {
+        repo.cleanup_state()?;
+    }
+    Ok(commit_to_info(&repo.find_commit(oid)?, repo))
+}
+
+/// Amend HEAD with the currently staged changes, like `git commit --amend`.
+///
+/// The original author is kept and the message is reused unless a new one is
+/// given; hooks run as in [`create_commit`]. Refuses while a merge is in
+/// progress, and when HEAD is already on a remote-tracking branch unless
+/// `force` is set, since amending then rewrites published history.
+pub fn amend_commit(
+    repo: &Repository,
+    message: Option<&str>,
+    force: bool,
+    hooks: &HookPolicy,
+) -> Result<CommitInfo> {
+    if repo.state() != RepositoryState::Clean {
+        return Err(GitError::MergeInProgress);
+    }
+    let committer = repo.signature().map_err(|_| GitError::IdentityMissing)?;
+    let head = repo.head()?.peel_to_commit()?;
+    if !force {
+        if let Some(remote_ref) = published_in(repo, head.id())? {
+            return Err(GitError::AlreadyPushed(remote_ref));
+        }
+    }
+
+    let message = message
+        .map(str::to_string)
+        .unwrap_or_else(|| head.message().unwrap_or("").to_string());
+    let message = run_commit_hooks(repo, &message, hooks)?;
+
+    let mut index = repo.index()?;
+    index.read(true)?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parents: Vec<Commit> = head.parents().collect();
+    let parents: Vec<&Commit> = parents.iter().collect();
+    let oid = write_commit(
+        repo,
+        &head.author(),
+        &committer,
+        &message,
+        &tree,
+        &parents,
+        "commit (amend)",
+    )?;
+    Ok(commit_to_info(&repo.find_commit(oid)?, repo))
+}
+
+/// Write a commit, signed when `commit.gpgsign` is set, and move HEAD (or the
+/// branch it points at) to it with a reflog entry like git's
+pub(crate) fn write_commit(
+    repo: &Repository,
+    author: &Signature,
+    committer: &Signature,
+    message: &str,
+    tree: &Tree,
+    parents: &[&Commit],
+    reflog_kind: &str,
+) -> Result<Oid> {
+    let buffer = repo.commit_create_buffer(author, committer, message, tree, parents)?;
+    let buffer = buffer
+        .as_str()
+        .ok_or_else(|| GitError::Conversion("commit is not valid UTF-8".to_string()))?;
+    let oid = match sign_commit(repo, buffer, committer)? {
+        Some(signature) => repo.commit_signed(buffer, &signature, None)?,
+        None => repo.odb()?.write(ObjectType::Commit, buffer.as_bytes())?,
+    };
+
+    let summary = message.lines().next().unwrap_or("");
+    let reflog = format!("{}: {}", reflog_kind, summary);
+    match repo.find_reference("HEAD")?.symbolic_target() {
+        Some(branch) => {
+            repo.reference(branch, oid, true, &reflog)?;
+        }
+        None => repo.set_head_detached(oid)?,
+    }
+    Ok(oid)
+}
+
+/// Run `pre-commit`, then `commit-msg` on `message`, and clean up what the
+/// hook leaves the way git's default cleanup does
+fn run_commit_hooks(repo: &Repository, message: &str, hooks: &HookPolicy) -> Result<String> {
+    run_required_hook(repo, hooks, "pre-commit", &[], None)?;
+
+    // commit-msg gets the message as a file it may rewrite
+    let message_path = repo.path().join("COMMIT_EDITMSG");
+    std::fs::write(&message_path, message)?;
+    run_required_hook(
+        repo,
+        hooks,
+        "commit-msg",
+        &[&message_path.to_string_lossy()],
+        None,
+    )?;
+    let message = std::fs::read_to_string(&message_path)?;
+    let comment = comment_char(&repo.config()?).bytes().next();
+    let message = git2::message_prettify(&message, comment)?;
+    if message.trim().is_empty() {
+        return Err(GitError::EmptyMessage);
+    }
+    Ok(message)
+}
+
+/// First remote-tracking branch that already contains `oid`
+fn published_in(repo: &Repository, oid: Oid) -> Result<Option<String>> {
+    for (name, tip) in list_refs(repo, "refs/remotes/")? {
+        if tip == oid || repo.graph_descendant_of(tip, oid).unwrap_or(false) {
+            return Ok(Some(name["refs/remotes/".len()..].to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether the index differs from HEAD (or has anything, before the first
+/// commit)
+fn has_staged_changes(repo: &Repository, head: Option<&Commit>) -> Result<bool> {
+    let head_tree = head.map(|c| c.tree()).transpose()?;
+    let diff = repo.diff_tree_to_index(head_tree.as_ref(), None, None)?;
+    Ok(diff.deltas().len() > 0)
+}
+
+/// Author of the commit named in `CHERRY_PICK_HEAD`
+fn picked_author(repo: &Repository) -> Result<Option<Signature<'static>>> {
+    let contents = std::fs::read_to_string(repo.path().join("CHERRY_PICK_HEAD"))?;
+    let Ok(oid) = Oid::from_str(contents.trim()) else {
+        return Ok(None);
+    };
+    Ok(Some(repo.find_commit(oid)?.author().to_owned()))
+}
+
+/// Commits named in `MERGE_HEAD` while a merge is waiting to be committed
+fn merge_heads(repo: &Repository) -> Result<Vec<Oid>> {
+    if repo.state() != RepositoryState::Merge {
+        return Ok(Vec::new());
+    }
+    let contents = std::fs::read_to_string(repo.path().join("MERGE_HEAD"))?;
+    Ok(contents
+        .lines()
+        .filter_map(|line| Oid::from_str(line.trim()).ok())
+        .collect())
+}
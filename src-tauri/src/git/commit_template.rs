@@ -0,0 +1,59 @@
+use git2::{Config, Repository};
+
+use super::git_config::config_path;
+use super::types::{CommitTemplate, PreparedMessageSource};
+use super::Result;
+
+/// Work out how the commit message box should be pre-filled, the same way
+/// `git commit` seeds its editor.
+///
+/// A message prepared by an interrupted merge (`MERGE_MSG`) or a squash merge
+/// (`SQUASH_MSG`) wins over the `commit.template` file. Template paths may
+/// start with `~/` or be relative to the repository root.
+pub fn get_commit_template(repo: &Repository) -> Result<CommitTemplate> {
+    let config = repo.config()?;
+
+    let template_path = config_path(repo, "commit.template");
+    let template = match &template_path {
+        Some(path) => Some(std::fs::read_to_string(path)?),
+        None => None,
+    };
+
+    let (prepared_message, prepared_source) = prepared_message(repo);
+
+    let message = prepared_message
+        .clone()
+        .or_else(|| template.clone())
+        .unwrap_or_default();
+
+    let comment_char = comment_char(&config);
+
+    Ok(CommitTemplate {
+        template,
+        template_path: template_path.map(|p| p.to_string_lossy().to_string()),
+        prepared_message,
+        prepared_source,
+        message,
+        comment_char,
+    })
+}
+
+/// Character that starts a comment line in commit messages
+pub(crate) fn comment_char(config: &Config) -> String {
+    config
+        .get_string("core.commentChar")
+        .ok()
+        // `auto` picks a character at commit time; `#` is what it starts from
+        .filter(|c| !c.is_empty() && c != "auto")
+        .unwrap_or_else(|| "#".to_string())
+}
+
+fn prepared_message(repo: &Repository) -> (Option<String>, Option<PreparedMessageSource>) {
+    if let Ok(message) = repo.message() {
+        return (Some(message), Some(PreparedMessageSource::Merge));
+    }
+    match std::fs::read_to_string(repo.path().join("SQUASH_MSG")) {
+        Ok(message) => (Some(message), Some(PreparedMessageSource::Squash)),
+        Err(_) => (None, None),
+    }
+}
@@ -0,0 +1,159 @@
+//! Conflicted files with every version involved, for a three-pane view.
+//!
+//! A stopped merge, revert or cherry-pick records up to three versions of
+//! each conflicted file in the index: the common ancestor (stage 1), ours
+//! (stage 2) and theirs (stage 3). A side that deleted the file has no
+//! entry. The working tree holds the file as the merge left it, with
+//! conflict markers around the parts that didn't merge.
+//!
+//! Resolving a file writes the chosen version to the working tree and
+//! stages it, which replaces the three stages with one, as `git add` does.
+
+use git2::build::CheckoutBuilder;
+use git2::{IndexConflict, IndexEntry, Repository};
+use std::path::Path;
+
+use super::types::{ConflictFile, ConflictKind, ConflictList, ConflictResolution};
+use super::{conflicted_paths, paths, GitError, Result};
+
+/// Bytes looked at for a NUL when deciding whether a file is binary, as git
+/// does
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Every conflicted file in the index with its ancestor, ours and theirs
+/// contents and the working tree file. Binary files are listed without
+/// contents.
+pub fn get_conflicts(repo: &Repository) -> Result<ConflictList> {
+    let index = repo.index()?;
+    let mut files = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let Some(entry) = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref())
+        else {
+            continue;
+        };
+        let path = String::from_utf8_lossy(&entry.path).into_owned();
+        let kind = match (&conflict.ancestor, &conflict.our, &conflict.their) {
+            (None, _, _) => ConflictKind::BothAdded,
+            (Some(_), None, _) => ConflictKind::DeletedByUs,
+            (Some(_), _, None) => ConflictKind::DeletedByThem,
+            _ => ConflictKind::BothModified,
+        };
+
+        let mut binary = false;
+        let mut text = |entry: Option<&IndexEntry>| -> Result<Option<String>> {
+            let Some(entry) = entry else {
+                return Ok(None);
+            };
+            let blob = repo.find_blob(entry.id)?;
+            Ok(to_text(blob.content(), &mut binary))
+        };
+        let ancestor = text(conflict.ancestor.as_ref())?;
+        let ours = text(conflict.our.as_ref())?;
+        let theirs = text(conflict.their.as_ref())?;
+
+        let working = match repo.workdir() {
+            Some(workdir) => match std::fs::read(paths::long_path(&workdir.join(&path))) {
+                Ok(content) => to_text(&content, &mut binary),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e.into()),
+            },
+            None => None,
+        };
+
+        // One version that isn't text is enough to rule out a text view
+        let [ancestor, ours, theirs, working] =
+            [ancestor, ours, theirs, working].map(|content| content.filter(|_| !binary));
+        files.push(ConflictFile {
+            path,
+            kind,
+            ancestor,
+            ours,
+            theirs,
+            working,
+            binary,
+        });
+    }
+    Ok(ConflictList { files })
+}
+
+/// Resolve the conflict in `path` with our version, theirs, or content
+/// merged by hand, and mark it resolved in the index. Taking the side that
+/// deleted the file deletes it. Returns the paths still conflicted.
+pub fn resolve_conflict(
+    repo: &Repository,
+    path: &str,
+    resolution: &ConflictResolution,
+) -> Result<Vec<String>> {
+    let mut index = repo.index()?;
+    let conflict =
+        find_conflict(&index, path)?.ok_or_else(|| GitError::NotConflicted(path.to_string()))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::NotConflicted(path.to_string()))?;
+    let file = paths::long_path(&workdir.join(path));
+
+    let keep = match resolution {
+        ConflictResolution::Ours => conflict.our.is_some(),
+        ConflictResolution::Theirs => conflict.their.is_some(),
+        ConflictResolution::Content { text } => {
+            std::fs::write(&file, text)?;
+            true
+        }
+    };
+    if keep {
+        if !matches!(resolution, ConflictResolution::Content { .. }) {
+            // Checkout writes the stage with its mode, so symlinks and
+            // executables come out right
+            let mut checkout = CheckoutBuilder::new();
+            checkout
+                .force()
+                .path(path)
+                .use_ours(matches!(resolution, ConflictResolution::Ours))
+                .use_theirs(matches!(resolution, ConflictResolution::Theirs));
+            repo.checkout_index(Some(&mut index), Some(&mut checkout))?;
+        }
+        index.add_path(Path::new(path))?;
+    } else {
+        if let Err(e) = std::fs::remove_file(&file) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+        index.remove_path(Path::new(path))?;
+    }
+    index.write()?;
+
+    conflicted_paths(&index)
+}
+
+fn find_conflict(index: &git2::Index, path: &str) -> Result<Option<IndexConflict>> {
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let entry = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref());
+        if entry.is_some_and(|entry| entry.path == path.as_bytes()) {
+            return Ok(Some(conflict));
+        }
+    }
+    Ok(None)
+}
+
+/// `content` as text, or `None` with `binary` set when it isn't
+fn to_text(content: &[u8], binary: &mut bool) -> Option<String> {
+    let sniffed = &content[..content.len().min(BINARY_SNIFF_LEN)];
+    match std::str::from_utf8(content) {
+        Ok(text) if !sniffed.contains(&0) => Some(text.to_string()),
+        _ => {
+            *binary = true;
+            None
+        }
+    }
+}
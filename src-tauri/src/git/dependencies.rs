@@ -0,0 +1,397 @@
+use git2::Repository;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use super::types::{DependencyAnalysis, DependencyChange, DependencyChangeKind};
+use super::{read_file_at, resolve_commit, Result};
+
+/// Manifest/lockfile pairs per ecosystem. Go keeps both roles in `go.mod`.
+const ECOSYSTEMS: &[(&str, &str, &str)] = &[
+    ("cargo", "Cargo.toml", "Cargo.lock"),
+    ("npm", "package.json", "package-lock.json"),
+    ("go", "go.mod", "go.mod"),
+];
+
+/// Dependency name -> version (or requirement) for one side of a comparison
+type VersionMap = BTreeMap<String, String>;
+
+/// Report added/removed/upgraded dependencies between two refs.
+///
+/// Every directory where a known manifest or lockfile changed is analyzed.
+/// Resolved lockfile versions are preferred; manifest requirements are used
+/// for ecosystems or directories without a lockfile.
+pub fn analyze_dependency_changes(
+    repo: &Repository,
+    base: &str,
+    head: &str,
+) -> Result<DependencyAnalysis> {
+    let base_commit = resolve_commit(repo, base)?;
+    let head_commit = resolve_commit(repo, head)?;
+    let base_sha = base_commit.id().to_string();
+    let head_sha = head_commit.id().to_string();
+
+    let diff =
+        repo.diff_tree_to_tree(Some(&base_commit.tree()?), Some(&head_commit.tree()?), None)?;
+
+    // (ecosystem index, directory) pairs touched by the diff
+    let mut touched: BTreeSet<(usize, String)> = BTreeSet::new();
+    for delta in diff.deltas() {
+        for path in [delta.old_file().path(), delta.new_file().path()]
+            .into_iter()
+            .flatten()
+        {
+            let file_name = path
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let dir = path
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            for (i, (_, manifest, lockfile)) in ECOSYSTEMS.iter().enumerate() {
+                if file_name == *manifest || file_name == *lockfile {
+                    touched.insert((i, dir.clone()));
+                }
+            }
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut manifests = Vec::new();
+
+    for (i, dir) in touched {
+        let (ecosystem, manifest_name, lockfile_name) = ECOSYSTEMS[i];
+        let manifest_path = join_path(&dir, manifest_name);
+        let lockfile_path = join_path(&dir, lockfile_name);
+
+        let read = |sha: &str, path: &str| read_file_at(repo, path, Some(sha));
+        let old_manifest = read(&base_sha, &manifest_path)?;
+        let new_manifest = read(&head_sha, &manifest_path)?;
+        let old_lock = read(&base_sha, &lockfile_path)?;
+        let new_lock = read(&head_sha, &lockfile_path)?;
+
+        let (old_direct, new_direct, old_resolved, new_resolved) = match ecosystem {
+            "cargo" => (
+                parse_cargo_manifest(old_manifest.as_deref()),
+                parse_cargo_manifest(new_manifest.as_deref()),
+                parse_cargo_lock(old_lock.as_deref()),
+                parse_cargo_lock(new_lock.as_deref()),
+            ),
+            "npm" => (
+                parse_package_json(old_manifest.as_deref()),
+                parse_package_json(new_manifest.as_deref()),
+                parse_package_lock(old_lock.as_deref()),
+                parse_package_lock(new_lock.as_deref()),
+            ),
+            _ => {
+                let (old_direct, old_all) = parse_go_mod(old_manifest.as_deref());
+                let (new_direct, new_all) = parse_go_mod(new_manifest.as_deref());
+                (old_direct, new_direct, old_all, new_all)
+            }
+        };
+
+        let has_lock = old_lock.is_some() || new_lock.is_some();
+        let (old_versions, new_versions) = if has_lock {
+            (old_resolved, new_resolved)
+        } else {
+            (old_direct.clone(), new_direct.clone())
+        };
+
+        let source = if has_lock {
+            lockfile_path
+        } else {
+            manifest_path
+        };
+        let mut reported = BTreeSet::new();
+
+        let names: BTreeSet<&String> = old_versions.keys().chain(new_versions.keys()).collect();
+        for name in names {
+            let old_version = old_versions.get(name);
+            let new_version = new_versions.get(name);
+            if old_version == new_version {
+                continue;
+            }
+            reported.insert(name.clone());
+            changes.push(DependencyChange {
+                name: name.clone(),
+                ecosystem: ecosystem.to_string(),
+                manifest: source.clone(),
+                kind: classify(old_version, new_version),
+                old_version: old_version.cloned(),
+                new_version: new_version.cloned(),
+                direct: new_direct.contains_key(name) || old_direct.contains_key(name),
+            });
+        }
+
+        // Requirement-only edits that did not move the resolved version
+        if has_lock {
+            let direct_names: BTreeSet<&String> =
+                old_direct.keys().chain(new_direct.keys()).collect();
+            for name in direct_names {
+                if reported.contains(name) {
+                    continue;
+                }
+                let old_req = old_direct.get(name);
+                let new_req = new_direct.get(name);
+                if old_req == new_req {
+                    continue;
+                }
+                changes.push(DependencyChange {
+                    name: name.clone(),
+                    ecosystem: ecosystem.to_string(),
+                    manifest: join_path(&dir, manifest_name),
+                    kind: classify(old_req, new_req),
+                    old_version: old_req.cloned(),
+                    new_version: new_req.cloned(),
+                    direct: true,
+                });
+            }
+        }
+
+        manifests.push(join_path(&dir, manifest_name));
+    }
+
+    Ok(DependencyAnalysis {
+        base: base.to_string(),
+        head: head.to_string(),
+        manifests,
+        changes,
+    })
+}
+
+fn join_path(dir: &str, file: &str) -> String {
+    if dir.is_empty() {
+        file.to_string()
+    } else {
+        Path::new(dir)
+            .join(file)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+fn classify(old: Option<&String>, new: Option<&String>) -> DependencyChangeKind {
+    match (old, new) {
+        (None, _) => DependencyChangeKind::Added,
+        (_, None) => DependencyChangeKind::Removed,
+        (Some(old), Some(new)) => match (parse_version(old), parse_version(new)) {
+            (Some(o), Some(n)) if n > o => DependencyChangeKind::Upgraded,
+            (Some(o), Some(n)) if n < o => DependencyChangeKind::Downgraded,
+            _ => DependencyChangeKind::Changed,
+        },
+    }
+}
+
+/// Parse a concrete version, tolerating a leading `v` (Go) or a single
+/// requirement operator (`^1.2.3`, `~1.2`) so manifest-only changes still order
+fn parse_version(version: &str) -> Option<semver::Version> {
+    let trimmed = version
+        .trim()
+        .trim_start_matches(['^', '~', '=', 'v'])
+        .split(',')
+        .next()?
+        .trim();
+
+    semver::Version::parse(trimmed).ok().or_else(|| {
+        // Pad partial versions like "1.2" or "1"
+        let parts: Vec<&str> = trimmed.split('.').collect();
+        if parts.len() < 3 && parts.iter().all(|p| p.parse::<u64>().is_ok()) {
+            let mut padded = parts.join(".");
+            for _ in parts.len()..3 {
+                padded.push_str(".0");
+            }
+            semver::Version::parse(&padded).ok()
+        } else {
+            None
+        }
+    })
+}
+
+fn parse_cargo_manifest(content: Option<&str>) -> VersionMap {
+    let mut deps = VersionMap::new();
+    let Some(table) = content.and_then(|c| toml::from_str::<toml::Table>(c).ok()) else {
+        return deps;
+    };
+
+    let mut collect = |section: Option<&toml::Value>| {
+        if let Some(section) = section.and_then(toml::Value::as_table) {
+            for (name, spec) in section {
+                let requirement = match spec {
+                    toml::Value::String(v) => v.clone(),
+                    toml::Value::Table(t) => t
+                        .get("version")
+                        .and_then(toml::Value::as_str)
+                        .map(str::to_string)
+                        .or_else(|| {
+                            t.get("git")
+                                .and_then(toml::Value::as_str)
+                                .map(|g| format!("git+{}", g))
+                        })
+                        .or_else(|| {
+                            t.get("path")
+                                .and_then(toml::Value::as_str)
+                                .map(|p| format!("path+{}", p))
+                        })
+                        .unwrap_or_else(|| "*".to_string()),
+                    _ => "*".to_string(),
+                };
+                // Renamed dependencies resolve under their package name
+                let package = spec
+                    .get("package")
+                    .and_then(toml::Value::as_str)
+                    .unwrap_or(name);
+                deps.insert(package.to_string(), requirement);
+            }
+        }
+    };
+
+    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        collect(table.get(section));
+        if let Some(targets) = table.get("target").and_then(toml::Value::as_table) {
+            for target in targets.values() {
+                collect(target.get(section));
+            }
+        }
+    }
+    collect(table.get("workspace").and_then(|w| w.get("dependencies")));
+
+    deps
+}
+
+fn parse_cargo_lock(content: Option<&str>) -> VersionMap {
+    let mut versions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let Some(table) = content.and_then(|c| toml::from_str::<toml::Table>(c).ok()) else {
+        return VersionMap::new();
+    };
+
+    if let Some(packages) = table.get("package").and_then(toml::Value::as_array) {
+        for package in packages {
+            let name = package.get("name").and_then(toml::Value::as_str);
+            let version = package.get("version").and_then(toml::Value::as_str);
+            if let (Some(name), Some(version)) = (name, version) {
+                versions
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(version.to_string());
+            }
+        }
+    }
+
+    flatten_versions(versions)
+}
+
+fn parse_package_json(content: Option<&str>) -> VersionMap {
+    let mut deps = VersionMap::new();
+    let Some(json) = content.and_then(|c| serde_json::from_str::<JsonValue>(c).ok()) else {
+        return deps;
+    };
+
+    for section in [
+        "dependencies",
+        "devDependencies",
+        "peerDependencies",
+        "optionalDependencies",
+    ] {
+        if let Some(map) = json.get(section).and_then(JsonValue::as_object) {
+            for (name, version) in map {
+                deps.insert(name.clone(), version.as_str().unwrap_or("*").to_string());
+            }
+        }
+    }
+
+    deps
+}
+
+fn parse_package_lock(content: Option<&str>) -> VersionMap {
+    let mut versions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let Some(json) = content.and_then(|c| serde_json::from_str::<JsonValue>(c).ok()) else {
+        return VersionMap::new();
+    };
+
+    if let Some(packages) = json.get("packages").and_then(JsonValue::as_object) {
+        // lockfileVersion 2/3: keys are install paths like "node_modules/a/node_modules/b"
+        for (key, info) in packages {
+            let Some(idx) = key.rfind("node_modules/") else {
+                continue;
+            };
+            let name = &key[idx + "node_modules/".len()..];
+            if let Some(version) = info.get("version").and_then(JsonValue::as_str) {
+                versions
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(version.to_string());
+            }
+        }
+    } else if let Some(deps) = json.get("dependencies").and_then(JsonValue::as_object) {
+        // lockfileVersion 1: nested "dependencies" trees
+        let mut stack: Vec<&serde_json::Map<String, JsonValue>> = vec![deps];
+        while let Some(map) = stack.pop() {
+            for (name, info) in map {
+                if let Some(version) = info.get("version").and_then(JsonValue::as_str) {
+                    versions
+                        .entry(name.clone())
+                        .or_default()
+                        .push(version.to_string());
+                }
+                if let Some(nested) = info.get("dependencies").and_then(JsonValue::as_object) {
+                    stack.push(nested);
+                }
+            }
+        }
+    }
+
+    flatten_versions(versions)
+}
+
+/// Parse `go.mod` requirements into (direct, all) version maps.
+/// Requirements marked `// indirect` are transitive.
+fn parse_go_mod(content: Option<&str>) -> (VersionMap, VersionMap) {
+    let mut direct = VersionMap::new();
+    let mut all = VersionMap::new();
+    let Some(content) = content else {
+        return (direct, all);
+    };
+
+    let mut in_block = false;
+    for line in content.lines() {
+        let line = line.trim();
+        let spec = if in_block {
+            if line.starts_with(')') {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if line.starts_with("require (") || line == "require(" {
+            in_block = true;
+            continue;
+        } else if let Some(rest) = line.strip_prefix("require ") {
+            rest
+        } else {
+            continue;
+        };
+
+        let indirect = spec.contains("// indirect");
+        let mut parts = spec.split("//").next().unwrap_or("").split_whitespace();
+        if let (Some(module), Some(version)) = (parts.next(), parts.next()) {
+            all.insert(module.to_string(), version.to_string());
+            if !indirect {
+                direct.insert(module.to_string(), version.to_string());
+            }
+        }
+    }
+
+    (direct, all)
+}
+
+/// Collapse multiple resolved versions of one package into a stable string
+fn flatten_versions(versions: BTreeMap<String, Vec<String>>) -> VersionMap {
+    versions
+        .into_iter()
+        .map(|(name, mut list)| {
+            list.sort();
+            list.dedup();
+            (name, list.join(", "))
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+//! What changed between two refreshes of the same diff.
+//!
+//! Every save reloads the review. Rather than re-mounting every file, the
+//! frontend patches in the files this says were added, removed or modified,
+//! matched by their stable ids (see `navigation`). A snapshot keeps one
+//! fingerprint per file, not the patches, so holding the previous one costs
+//! little.
+
+use std::collections::HashMap;
+
+use super::types::{DiffDelta, DiffResult, FileDiffInfo};
+
+/// Fingerprints of a diff's files by file id
+#[derive(Debug, Clone, Default)]
+pub struct DiffSnapshot {
+    files: HashMap<String, Option<String>>,
+    order: Vec<String>,
+}
+
+impl DiffSnapshot {
+    pub fn new(result: &DiffResult) -> Self {
+        Self {
+            files: result
+                .files
+                .iter()
+                .map(|file| (file.id.clone(), fingerprint(file)))
+                .collect(),
+            order: result.files.iter().map(|file| file.id.clone()).collect(),
+        }
+    }
+
+    /// Files added, removed and modified since `previous`. Without a previous
+    /// snapshot the whole diff is new and `full` is set.
+    pub fn delta(&self, previous: Option<&DiffSnapshot>) -> DiffDelta {
+        let Some(previous) = previous else {
+            return DiffDelta {
+                full: true,
+                added: self.order.clone(),
+                ..DiffDelta::default()
+            };
+        };
+
+        let mut delta = DiffDelta::default();
+        for id in &self.order {
+            match previous.files.get(id) {
+                None => delta.added.push(id.clone()),
+                // Files without hunks can't be compared, so they're reloaded
+                Some(before) if before.is_none() || *before != self.files[id] => {
+                    delta.modified.push(id.clone())
+                }
+                Some(_) => delta.unchanged += 1,
+            }
+        }
+        delta.removed = previous
+            .order
+            .iter()
+            .filter(|id| !self.files.contains_key(*id))
+            .cloned()
+            .collect();
+        delta
+    }
+}
+
+/// Everything the view renders for a file, or `None` when its content can't
+/// be told apart from hunks (binary files, unexpanded directories)
+fn fingerprint(file: &FileDiffInfo) -> Option<String> {
+    if file.hunks.is_empty() {
+        return None;
+    }
+    // Line numbers are part of what's shown, so a hunk that moved counts
+    let hunks: Vec<String> = file
+        .hunks
+        .iter()
+        .map(|h| format!("{}@{},{}", h.id, h.old_start, h.new_start))
+        .collect();
+    Some(format!(
+        "{:?}|{}|{}|{}|{:?}|{:?}|{}",
+        file.status,
+        file.old_path.as_deref().unwrap_or(""),
+        file.additions,
+        file.deletions,
+        file.is_large,
+        file.error,
+        hunks.join(",")
+    ))
+}
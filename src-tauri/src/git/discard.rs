@@ -0,0 +1,198 @@
+use git2::build::{CheckoutBuilder, TreeUpdateBuilder};
+use git2::{ApplyLocation, Delta, DiffOptions, FileMode, Repository, Signature};
+use std::path::Path;
+
+use super::hunks::target_diff;
+use super::staging::{apply_hunk, hunk_position};
+use super::types::DiscardBackup;
+use super::{format_commit_time, GitError, Result};
+
+/// Backups are commits under this namespace, so they survive `git gc` and
+/// can be inspected with plain git until they are restored
+const BACKUP_REF_PREFIX: &str = "refs/diffy/discarded/";
+
+/// Trailer recording which file a backup belongs to; the tree alone can't say
+/// when the discarded change was a deletion
+const PATH_TRAILER: &str = "Path: ";
+
+/// Throw away the unstaged changes to one file, restoring it to its staged
+/// (or, when nothing is staged, committed) state. Untracked files are
+/// deleted. The discarded contents are saved first and can be put back with
+/// `restore_discarded`.
+pub fn discard_file(repo: &Repository, path: &str) -> Result<DiscardBackup> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::RepoNotFound(repo.path().display().to_string()))?;
+    let full_path = workdir.join(path);
+    let exists = full_path.symlink_metadata().is_ok();
+    let tracked = repo.index()?.get_path(Path::new(path), 0).is_some();
+    if !exists && !tracked {
+        return Err(GitError::PathNotFound(path.to_string()));
+    }
+
+    let backup = backup_file(repo, path, exists)?;
+
+    if tracked {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.path(path).force();
+        repo.checkout_index(None, Some(&mut checkout))?;
+    } else {
+        std::fs::remove_file(&full_path)?;
+    }
+    Ok(backup)
+}
+
+/// Revert one unstaged hunk in the working tree, keeping the file's other
+/// edits. `hunk_id` comes from `get_file_hunks` with the `"unstaged"` target.
+/// The whole file is backed up first, so `restore_discarded` undoes this too.
+pub fn discard_hunk(repo: &Repository, path: &str, hunk_id: &str) -> Result<DiscardBackup> {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.pathspec(path);
+    let diff = target_diff(repo, "unstaged", &mut diff_opts)?;
+    let position = hunk_position(&diff, path, hunk_id)?;
+
+    // An untracked file's only hunk is the whole file
+    if diff.deltas().any(|d| d.status() == Delta::Untracked) {
+        return discard_file(repo, path);
+    }
+
+    let exists = repo
+        .workdir()
+        .is_some_and(|workdir| workdir.join(path).symlink_metadata().is_ok());
+    let backup = backup_file(repo, path, exists)?;
+
+    let mut reverse_opts = DiffOptions::new();
+    reverse_opts.pathspec(path).reverse(true);
+    let reversed = target_diff(repo, "unstaged", &mut reverse_opts)?;
+    apply_hunk(repo, &reversed, position, ApplyLocation::WorkDir)?;
+    Ok(backup)
+}
+
+/// Put the working-tree contents saved by `discard_file` back, then drop the
+/// backup
+pub fn restore_discarded(repo: &Repository, id: &str) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::RepoNotFound(repo.path().display().to_string()))?;
+    let mut reference = repo
+        .find_reference(&format!("{}{}", BACKUP_REF_PREFIX, id))
+        .map_err(|_| GitError::BackupNotFound(id.to_string()))?;
+    let commit = reference.peel_to_commit()?;
+    let path = backup_path(&commit).ok_or_else(|| GitError::BackupNotFound(id.to_string()))?;
+    let full_path = workdir.join(&path);
+
+    match commit.tree()?.get_path(Path::new(&path)) {
+        Ok(entry) => {
+            let blob = repo.find_blob(entry.id())?;
+            if let Some(parent) = full_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            if full_path.symlink_metadata().is_ok() {
+                std::fs::remove_file(&full_path)?;
+            }
+            write_entry(&full_path, blob.content(), entry.filemode())?;
+        }
+        // The discarded change was a deletion
+        Err(_) => {
+            if full_path.exists() {
+                std::fs::remove_file(&full_path)?;
+            }
+        }
+    }
+
+    reference.delete()?;
+    Ok(())
+}
+
+/// Save the working-tree state of `path` as a commit under
+/// `BACKUP_REF_PREFIX`, parented on HEAD for context
+fn backup_file(repo: &Repository, path: &str, exists: bool) -> Result<DiscardBackup> {
+    let empty_tree = repo.find_tree(repo.treebuilder(None)?.write()?)?;
+    let mut update = TreeUpdateBuilder::new();
+    if exists {
+        let full_path = repo.workdir().unwrap_or(Path::new(".")).join(path);
+        let blob = repo.blob_path(&full_path)?;
+        update.upsert(path, blob, file_mode(&full_path));
+    }
+    let tree = repo.find_tree(update.create_updated(repo, &empty_tree)?)?;
+
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("diffy", "diffy@localhost"))?;
+    let head = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = head.iter().collect();
+    let message = format!(
+        "Discarded changes to {}\n\n{}{}\n",
+        path, PATH_TRAILER, path
+    );
+    let oid = repo.commit(None, &signature, &signature, &message, &tree, &parents)?;
+
+    let sha = oid.to_string();
+    let id = sha[..12].to_string();
+    repo.reference(
+        &format!("{}{}", BACKUP_REF_PREFIX, id),
+        oid,
+        false,
+        &format!("diffy: back up {} before discarding", path),
+    )?;
+
+    Ok(DiscardBackup {
+        id,
+        path: path.to_string(),
+        sha,
+        existed: exists,
+        date: format_commit_time(&repo.find_commit(oid)?),
+    })
+}
+
+/// Path recorded in a backup commit's trailer
+fn backup_path(commit: &git2::Commit) -> Option<String> {
+    commit
+        .message()?
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(PATH_TRAILER))
+        .map(str::to_string)
+}
+
+/// Recreate a backed-up file, as a symlink or with its executable bit where
+/// the platform supports it
+fn write_entry(path: &Path, content: &[u8], mode: i32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if mode == i32::from(FileMode::Link) {
+            let target = String::from_utf8_lossy(content).into_owned();
+            std::os::unix::fs::symlink(target, path)?;
+            return Ok(());
+        }
+        std::fs::write(path, content)?;
+        if mode == i32::from(FileMode::BlobExecutable) {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        std::fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+/// Git file mode for a path in the working tree
+pub(crate) fn file_mode(path: &Path) -> FileMode {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return FileMode::Blob;
+    };
+    if metadata.file_type().is_symlink() {
+        return FileMode::Link;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 != 0 {
+            return FileMode::BlobExecutable;
+        }
+    }
+    FileMode::Blob
+}
@@ -0,0 +1,201 @@
+use git2::Repository;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use super::process::output_within;
+use super::types::DocumentDiff;
+use super::{diff_text, read_bytes_at, GitError, Result};
+
+/// Placeholder in a converter command that is replaced with the input file path
+const FILE_PLACEHOLDER: &str = "%f";
+
+/// How long a converter may run before it is killed
+const CONVERTER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Diff the extracted text of a binary document between two refs.
+///
+/// `converters` maps a lowercase file extension to an external command that
+/// prints the document's text to stdout, e.g. `"pdf" => "pdftotext -layout %f -"`.
+/// A configured converter takes precedence over the built-in docx/odt extractors.
+/// Commands are split with shell quoting rules but not run through a shell,
+/// and one still running after `CONVERTER_TIMEOUT` is killed.
+pub fn get_document_diff(
+    repo: &Repository,
+    file_path: &str,
+    base: Option<&str>,
+    head: Option<&str>,
+    converters: &HashMap<String, String>,
+) -> Result<DocumentDiff> {
+    let extension = Path::new(file_path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let converter = match converters.get(&extension) {
+        Some(command) => command.clone(),
+        None if is_builtin(&extension) => extension.clone(),
+        None => {
+            return Err(GitError::Conversion(format!(
+                "No text converter configured for .{} files",
+                extension
+            )))
+        }
+    };
+
+    let old_text = match read_bytes_at(repo, file_path, base)? {
+        Some(bytes) => extract_text(&bytes, &extension, converters)?,
+        None => String::new(),
+    };
+    let new_text = match read_bytes_at(repo, file_path, head)? {
+        Some(bytes) => extract_text(&bytes, &extension, converters)?,
+        None => String::new(),
+    };
+
+    let (patch, additions, deletions) = diff_text(&old_text, &new_text)?;
+
+    Ok(DocumentDiff {
+        path: file_path.to_string(),
+        converter,
+        patch,
+        additions,
+        deletions,
+    })
+}
+
+fn is_builtin(extension: &str) -> bool {
+    matches!(extension, "docx" | "odt")
+}
+
+fn extract_text(
+    bytes: &[u8],
+    extension: &str,
+    converters: &HashMap<String, String>,
+) -> Result<String> {
+    if let Some(command) = converters.get(extension) {
+        return run_converter(command, bytes, extension);
+    }
+
+    match extension {
+        "docx" => extract_xml_text(bytes, "word/document.xml", "p", "t"),
+        "odt" => extract_xml_text(bytes, "content.xml", "p", ""),
+        _ => Err(GitError::Conversion(format!(
+            "No text converter configured for .{} files",
+            extension
+        ))),
+    }
+}
+
+/// Pull paragraph text out of a zipped XML document (OOXML/ODF).
+///
+/// Each `paragraph` element becomes one line. When `text` is non-empty only
+/// character data inside that element is kept (docx `w:t` runs); otherwise all
+/// character data within a paragraph is used.
+fn extract_xml_text(bytes: &[u8], entry: &str, paragraph: &str, text: &str) -> Result<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| GitError::Conversion(format!("Not a valid document archive: {}", e)))?;
+    let mut xml = String::new();
+    archive
+        .by_name(entry)
+        .map_err(|e| GitError::Conversion(format!("Missing {}: {}", entry, e)))?
+        .read_to_string(&mut xml)?;
+
+    let mut reader = Reader::from_str(&xml);
+    let mut out = String::new();
+    let mut in_paragraph = 0usize;
+    let mut in_text = text.is_empty();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = e.local_name();
+                if name.as_ref() == paragraph {
+                    in_paragraph += 1;
+                } else if !text.is_empty() && name.as_ref() == text {
+                    in_text = true;
+                }
+            }
+            Ok(Event::Empty(e)) => match e.local_name().as_ref() {
+                "tab" => out.push('\t'),
+                "br" | "line-break" => out.push('\n'),
+                name if name == paragraph => out.push('\n'),
+                _ => {}
+            },
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+                if name.as_ref() == paragraph {
+                    in_paragraph = in_paragraph.saturating_sub(1);
+                    out.push('\n');
+                } else if !text.is_empty() && name.as_ref() == text {
+                    in_text = false;
+                }
+            }
+            Ok(Event::Text(t)) if in_paragraph > 0 && in_text => {
+                out.push_str(&t.xml10_content());
+            }
+            Ok(Event::GeneralRef(r)) if in_paragraph > 0 && in_text => {
+                if let Ok(Some(c)) = r.resolve_char_ref() {
+                    out.push(c);
+                } else {
+                    out.push_str(match &*r {
+                        "amp" => "&",
+                        "lt" => "<",
+                        "gt" => ">",
+                        "quot" => "\"",
+                        "apos" => "'",
+                        _ => "",
+                    });
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(GitError::Conversion(format!("Malformed {}: {}", entry, e))),
+            _ => {}
+        }
+    }
+
+    Ok(out)
+}
+
+/// Run an external converter over a temp copy of the document, textconv-style
+fn run_converter(command: &str, bytes: &[u8], extension: &str) -> Result<String> {
+    let mut file = tempfile::Builder::new()
+        .prefix("diffy-textconv-")
+        .suffix(&format!(".{}", extension))
+        .tempfile()?;
+    file.write_all(bytes)?;
+    file.flush()?;
+    let file_path = file.path().to_string_lossy().to_string();
+
+    // Quoted like a shell command line, so paths with spaces survive
+    let words = shell_words::split(command)
+        .map_err(|e| GitError::Conversion(format!("Invalid converter command: {}", e)))?;
+    let (program, rest) = words
+        .split_first()
+        .ok_or_else(|| GitError::Conversion("Empty converter command".to_string()))?;
+
+    let mut args: Vec<String> = rest
+        .iter()
+        .map(|p| p.replace(FILE_PLACEHOLDER, &file_path))
+        .collect();
+    if !command.contains(FILE_PLACEHOLDER) {
+        args.push(file_path);
+    }
+
+    let output = output_within(Command::new(program).args(&args), None, CONVERTER_TIMEOUT)
+        .map_err(|e| GitError::Conversion(format!("Failed to run {}: {}", program, e)))?;
+
+    if !output.status.success() {
+        return Err(GitError::Conversion(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
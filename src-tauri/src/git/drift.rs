@@ -0,0 +1,66 @@
+use git2::Repository;
+use std::collections::HashMap;
+
+use super::types::{DriftMonitor, DriftReport};
+use super::{resolve_commit, Result};
+
+/// Measure how far a monitored pair of refs has drifted apart
+pub fn check_drift(repo: &Repository, monitor: &DriftMonitor) -> Result<DriftReport> {
+    let base = resolve_commit(repo, &monitor.base)?;
+    let head = resolve_commit(repo, &monitor.head)?;
+
+    let (ahead, behind) = repo.graph_ahead_behind(head.id(), base.id())?;
+    let changed_files = repo
+        .diff_tree_to_tree(Some(&base.tree()?), Some(&head.tree()?), None)?
+        .deltas()
+        .len();
+
+    let over = |value: usize, limit: Option<usize>| limit.is_some_and(|max| value > max);
+    let exceeded =
+        over(ahead + behind, monitor.max_commits) || over(changed_files, monitor.max_files);
+
+    Ok(DriftReport {
+        base: monitor.base.clone(),
+        head: monitor.head.clone(),
+        ahead,
+        behind,
+        changed_files,
+        exceeded,
+        notify: monitor.notify,
+    })
+}
+
+/// Current drift for every monitored pair, in configuration order
+pub fn get_branch_drift(repo: &Repository, monitors: &[DriftMonitor]) -> Result<Vec<DriftReport>> {
+    monitors
+        .iter()
+        .map(|monitor| check_drift(repo, monitor))
+        .collect()
+}
+
+/// Which drift reports are news: a pair over its threshold is reported once,
+/// then again only when its drift changes or after it drops back under
+#[derive(Debug, Default)]
+pub struct DriftTracker {
+    reported: HashMap<(String, String), DriftReport>,
+}
+
+impl DriftTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `report`, returning it when it should be announced
+    pub fn update(&mut self, report: DriftReport) -> Option<DriftReport> {
+        let key = (report.base.clone(), report.head.clone());
+        if !report.exceeded {
+            self.reported.remove(&key);
+            return None;
+        }
+        if self.reported.get(&key) == Some(&report) {
+            return None;
+        }
+        self.reported.insert(key, report.clone());
+        Some(report)
+    }
+}
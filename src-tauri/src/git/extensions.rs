@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use super::types::{ExtensionStats, FileDiffInfo};
+
+/// Group additions/deletions by file extension, largest change first
+pub fn stats_by_extension(files: &[FileDiffInfo]) -> Vec<ExtensionStats> {
+    let mut groups: HashMap<String, ExtensionStats> = HashMap::new();
+
+    for file in files {
+        let extension = file_extension(&file.path);
+        let entry = groups
+            .entry(extension.clone())
+            .or_insert_with(|| ExtensionStats {
+                language: language_for(&extension).to_string(),
+                extension,
+                files: 0,
+                additions: 0,
+                deletions: 0,
+            });
+        entry.files += 1;
+        entry.additions += file.additions;
+        entry.deletions += file.deletions;
+    }
+
+    let mut stats: Vec<ExtensionStats> = groups.into_values().collect();
+    stats.sort_by(|a, b| {
+        (b.additions + b.deletions)
+            .cmp(&(a.additions + a.deletions))
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+    stats
+}
+
+/// Lowercased extension without the dot; dotfiles and extensionless files
+/// are grouped under their full name (e.g. `Makefile`, `.gitignore`)
+fn file_extension(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name.rfind('.') {
+        Some(idx) if idx > 0 => name[idx + 1..].to_lowercase(),
+        _ => name.to_string(),
+    }
+}
+
+fn language_for(extension: &str) -> &'static str {
+    match extension {
+        "rs" => "Rust",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "py" | "pyi" => "Python",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "swift" => "Swift",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "scala" => "Scala",
+        "sh" | "bash" | "zsh" => "Shell",
+        "sql" => "SQL",
+        "html" | "htm" => "HTML",
+        "css" | "scss" | "sass" | "less" => "CSS",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "md" | "mdx" | "rst" | "txt" => "Docs",
+        "json" | "yaml" | "yml" | "toml" | "xml" | "ini" => "Config",
+        "lock" => "Lockfile",
+        "ipynb" => "Notebook",
+        "svg" | "png" | "jpg" | "jpeg" | "gif" | "webp" | "ico" => "Image",
+        _ => "Other",
+    }
+}
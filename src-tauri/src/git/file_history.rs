@@ -0,0 +1,167 @@
+use git2::{Commit, Delta, Oid, Repository, Tree};
+use std::path::Path;
+
+use super::large_repo::degradations;
+use super::types::{Degradation, DifferConfig, FileHistory, FileHistoryEntry, FileStatus};
+use super::{commit_info, Result};
+
+/// A commit that touched the file, before its details are loaded
+struct Touch {
+    commit: Oid,
+    path: String,
+    old_path: Option<String>,
+    status: FileStatus,
+}
+
+/// Commits reachable from HEAD that changed `file_path`, newest first.
+///
+/// Renames are followed like `git log --follow`: once a commit is found to
+/// have renamed the file, older commits are matched against the old path.
+/// Each entry carries the path the file had at that commit. Merges that kept
+/// one parent's version of the file are skipped. Huge repositories neither
+/// follow renames nor load commit stats.
+pub fn get_file_history(
+    repo: &Repository,
+    file_path: &str,
+    limit: usize,
+    offset: usize,
+    config: &DifferConfig,
+) -> Result<FileHistory> {
+    let degraded: Vec<Degradation> = degradations(repo, config)
+        .into_iter()
+        .filter(|d| matches!(d, Degradation::CommitStats | Degradation::RenameDetection))
+        .collect();
+    let follow_renames = !degraded.contains(&Degradation::RenameDetection);
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    // Children before parents, so a rename is seen before the commits that
+    // still used the old path
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    // Finding touches only compares blob ids, so the whole walk is cheap
+    // enough to count everything; full commit info is built for one page
+    let mut touches = Vec::new();
+    let mut current = file_path.to_string();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if let Some(touch) = touch(repo, &commit, &current, follow_renames)? {
+            if let Some(old_path) = &touch.old_path {
+                current = old_path.clone();
+            }
+            touches.push(touch);
+        }
+    }
+
+    let total = touches.len();
+    let mut entries = Vec::new();
+    for touch in touches.into_iter().skip(offset).take(limit) {
+        let commit = repo.find_commit(touch.commit)?;
+        entries.push(FileHistoryEntry {
+            commit: commit_info(&commit, repo, !degraded.contains(&Degradation::CommitStats)),
+            path: touch.path,
+            old_path: touch.old_path,
+            status: touch.status,
+        });
+    }
+
+    Ok(FileHistory {
+        path: file_path.to_string(),
+        entries,
+        total,
+        degraded,
+    })
+}
+
+fn blob_at(tree: &Tree, path: &str) -> Option<Oid> {
+    tree.get_path(Path::new(path)).ok().map(|entry| entry.id())
+}
+
+/// How `commit` changed `path`, or `None` if it left the file alone. Without
+/// `follow_renames` an added file is never checked for being a rename.
+fn touch(
+    repo: &Repository,
+    commit: &Commit,
+    path: &str,
+    follow_renames: bool,
+) -> Result<Option<Touch>> {
+    let tree = commit.tree()?;
+    let blob = blob_at(&tree, path);
+
+    let parent_trees = commit
+        .parents()
+        .map(|parent| parent.tree())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if parent_trees
+        .iter()
+        .any(|parent| blob_at(parent, path) == blob)
+    {
+        return Ok(None);
+    }
+
+    let found = |status| {
+        Some(Touch {
+            commit: commit.id(),
+            path: path.to_string(),
+            old_path: None,
+            status,
+        })
+    };
+    let parent_blob = parent_trees
+        .first()
+        .and_then(|parent| blob_at(parent, path));
+    match (parent_blob, blob) {
+        (Some(_), Some(_)) => Ok(found(FileStatus::Modified)),
+        (Some(_), None) => Ok(found(FileStatus::Deleted)),
+        (None, None) => Ok(None),
+        (None, Some(_)) => {
+            let renamed_from = match parent_trees.first() {
+                Some(parent) if follow_renames => rename_source(repo, parent, &tree, path)?,
+                _ => None,
+            };
+            Ok(Some(Touch {
+                commit: commit.id(),
+                path: path.to_string(),
+                status: if renamed_from.is_some() {
+                    FileStatus::Renamed
+                } else {
+                    FileStatus::Added
+                },
+                old_path: renamed_from,
+            }))
+        }
+    }
+}
+
+/// Where `path` in `tree` lived in `parent_tree`: the same path, or the
+/// source of a rename. `None` when the file was added.
+pub(crate) fn path_in_parent(
+    repo: &Repository,
+    parent_tree: &Tree,
+    tree: &Tree,
+    path: &str,
+) -> Result<Option<String>> {
+    if parent_tree.get_path(Path::new(path)).is_ok() {
+        return Ok(Some(path.to_string()));
+    }
+
+    rename_source(repo, parent_tree, tree, path)
+}
+
+/// Where the file at `path` was renamed from between two trees, if anywhere
+fn rename_source(
+    repo: &Repository,
+    parent: &Tree,
+    tree: &Tree,
+    path: &str,
+) -> Result<Option<String>> {
+    let mut diff = repo.diff_tree_to_tree(Some(parent), Some(tree), None)?;
+    diff.find_similar(None)?;
+    let source = diff
+        .deltas()
+        .filter(|delta| delta.status() == Delta::Renamed)
+        .find(|delta| delta.new_file().path() == Some(Path::new(path)))
+        .and_then(|delta| delta.old_file().path())
+        .map(|p| p.to_string_lossy().to_string());
+    Ok(source)
+}
@@ -0,0 +1,86 @@
+use git2::{Config, ConfigLevel, Repository};
+use std::path::{Path, PathBuf};
+
+use super::types::{GitConfigEntry, GitConfigScope, GitConfigValue};
+use super::Result;
+
+/// Effective value of a git config `key` for this repository, plus every
+/// value set for it and where each came from, lowest priority first.
+///
+/// Reads the same files git does, including `include`/`includeIf` files;
+/// an included value reports the file that includes it.
+pub fn get_git_config(repo: &Repository, key: &str) -> Result<GitConfigValue> {
+    let config = repo.config()?.snapshot()?;
+
+    let mut entries = Vec::new();
+    config.multivar(key, None)?.for_each(|entry| {
+        entries.push(GitConfigEntry {
+            value: entry.value().unwrap_or("").to_string(),
+            scope: scope(entry.level()),
+            file: level_file(repo, entry.level()).map(|p| p.to_string_lossy().to_string()),
+            included: entry.include_depth() > 0,
+        });
+    })?;
+    entries.sort_by_key(|entry| entry.scope);
+
+    Ok(GitConfigValue {
+        key: key.to_string(),
+        value: config.get_string(key).ok(),
+        entries,
+    })
+}
+
+/// Path-valued config such as `core.hooksPath`, resolved the way git does:
+/// `~/` expands to the home directory and relative paths are taken from the
+/// top of the working tree (the git dir for bare repositories)
+pub(crate) fn config_path(repo: &Repository, key: &str) -> Option<PathBuf> {
+    let path = repo.config().ok()?.get_path(key).ok()?;
+    Some(resolve_path(repo, &path))
+}
+
+fn resolve_path(repo: &Repository, path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+    if path.is_relative() {
+        return repo.workdir().unwrap_or_else(|| repo.path()).join(path);
+    }
+    path.to_path_buf()
+}
+
+/// The main repository's git dir, which linked worktrees share for config,
+/// hooks and refs; it is named in the worktree git dir's `commondir` file
+pub(crate) fn common_dir(repo: &Repository) -> PathBuf {
+    let git_dir = repo.path();
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(contents) => git_dir.join(contents.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+fn scope(level: ConfigLevel) -> GitConfigScope {
+    match level {
+        ConfigLevel::ProgramData => GitConfigScope::ProgramData,
+        ConfigLevel::System => GitConfigScope::System,
+        ConfigLevel::XDG => GitConfigScope::Xdg,
+        ConfigLevel::Global => GitConfigScope::Global,
+        ConfigLevel::Local => GitConfigScope::Local,
+        ConfigLevel::Worktree => GitConfigScope::Worktree,
+        ConfigLevel::App | ConfigLevel::Highest => GitConfigScope::App,
+    }
+}
+
+/// The config file backing a level, when it has one on disk
+fn level_file(repo: &Repository, level: ConfigLevel) -> Option<PathBuf> {
+    match level {
+        // Linked worktrees share the main repository's config
+        ConfigLevel::Local => Some(common_dir(repo).join("config")),
+        ConfigLevel::Worktree => Some(repo.path().join("config.worktree")),
+        ConfigLevel::Global => Config::find_global().ok(),
+        ConfigLevel::XDG => Config::find_xdg().ok(),
+        ConfigLevel::System => Config::find_system().ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,108 @@
+use git2::Repository;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use super::git_config::{common_dir, config_path};
+use super::process::run_within;
+use super::types::{HookPolicy, HookResult};
+use super::{GitError, Result};
+
+/// Run the repository's `name` hook, if it exists and the policy allows it.
+///
+/// The hook runs from the top of the working tree with `args`, and `stdin`
+/// piped in when given (e.g. the ref list for `pre-push`). Returns `None`
+/// when the hook is disabled, missing or not executable. Output is captured
+/// whether or not the hook succeeds; a hook that outlives the policy's
+/// timeout is killed and reported as failed with what it wrote until then.
+pub fn run_hook(
+    repo: &Repository,
+    policy: &HookPolicy,
+    name: &str,
+    args: &[&str],
+    stdin: Option<&str>,
+) -> Result<Option<HookResult>> {
+    if !policy.enabled || policy.skip.iter().any(|skipped| skipped == name) {
+        return Ok(None);
+    }
+    let Some(path) = hook_path(repo, name) else {
+        return Ok(None);
+    };
+
+    let mut command = hook_command(&path);
+    command
+        .args(args)
+        .current_dir(repo.workdir().unwrap_or_else(|| repo.path()));
+
+    let started = Instant::now();
+    let timeout = Duration::from_secs(policy.timeout_secs);
+    let run = run_within(&mut command, stdin.map(str::as_bytes), timeout)?;
+    let mut stderr = String::from_utf8_lossy(&run.stderr).to_string();
+    if run.status.is_none() {
+        if !stderr.is_empty() && !stderr.ends_with('\n') {
+            stderr.push('\n');
+        }
+        stderr.push_str(&format!(
+            "{} hook timed out after {}s\n",
+            name, policy.timeout_secs
+        ));
+    }
+
+    Ok(Some(HookResult {
+        hook: name.to_string(),
+        success: run.status.is_some_and(|status| status.success()),
+        exit_code: run.status.and_then(|status| status.code()),
+        stdout: String::from_utf8_lossy(&run.stdout).to_string(),
+        stderr,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }))
+}
+
+/// Like [`run_hook`], but a failing hook aborts the operation with
+/// `GitError::HookFailed` carrying its output
+pub fn run_required_hook(
+    repo: &Repository,
+    policy: &HookPolicy,
+    name: &str,
+    args: &[&str],
+    stdin: Option<&str>,
+) -> Result<Option<HookResult>> {
+    match run_hook(repo, policy, name, args, stdin)? {
+        Some(result) if !result.success => Err(GitError::HookFailed(Box::new(result))),
+        result => Ok(result),
+    }
+}
+
+/// Hooks live in `core.hooksPath` when set, otherwise in the `hooks`
+/// directory of the main git dir, which linked worktrees share
+fn hook_path(repo: &Repository, name: &str) -> Option<PathBuf> {
+    let dir = config_path(repo, "core.hooksPath").unwrap_or_else(|| common_dir(repo).join("hooks"));
+    let path = dir.join(name);
+    is_executable(&path).then_some(path)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(unix)]
+fn hook_command(path: &Path) -> Command {
+    Command::new(path)
+}
+
+/// Hooks are usually shell scripts; Git for Windows runs them through `sh`
+#[cfg(not(unix))]
+fn hook_command(path: &Path) -> Command {
+    let mut command = Command::new("sh");
+    command.arg(path);
+    command
+}
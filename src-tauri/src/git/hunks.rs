@@ -0,0 +1,159 @@
+use git2::{Diff, DiffOptions, Patch, Repository};
+use std::collections::HashMap;
+
+use super::types::{FileHunk, FileHunkPage};
+use super::{push_patch_line, resolve_commit, Result};
+
+/// Diff a single file for a target, or `None` when it is unchanged or binary.
+///
+/// `target` selects what the file is diffed against:
+/// - `"working"` (or empty): HEAD against the working directory, staged + unstaged
+/// - `"staged"`: HEAD against the index
+/// - `"unstaged"`: the index against the working directory
+/// - `"<base>..<head>"`: the tree of `base` against the tree of `head`
+/// - any other revision: that commit against its first parent
+pub fn file_patch<'r>(
+    repo: &'r Repository,
+    file_path: &str,
+    target: &str,
+) -> Result<Option<Patch<'r>>> {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.pathspec(file_path);
+    let diff = target_diff(repo, target, &mut diff_opts)?;
+
+    if diff.deltas().len() == 0 {
+        return Ok(None);
+    }
+    Ok(Patch::from_diff(&diff, 0)?)
+}
+
+/// Diff for a `target` as described on [`file_patch`], using the caller's
+/// pathspec and other options
+pub(crate) fn target_diff<'r>(
+    repo: &'r Repository,
+    target: &str,
+    diff_opts: &mut DiffOptions,
+) -> Result<Diff<'r>> {
+    let diff = match target {
+        "" | "working" => {
+            let head = repo.head()?.peel_to_tree()?;
+            diff_opts.include_untracked(true);
+            diff_opts.show_untracked_content(true);
+            repo.diff_tree_to_workdir_with_index(Some(&head), Some(diff_opts))?
+        }
+        "staged" => {
+            // An unborn HEAD stages everything against the empty tree
+            let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+            repo.diff_tree_to_index(head.as_ref(), None, Some(diff_opts))?
+        }
+        "unstaged" => {
+            diff_opts.include_untracked(true);
+            diff_opts.recurse_untracked_dirs(true);
+            diff_opts.show_untracked_content(true);
+            repo.diff_index_to_workdir(None, Some(diff_opts))?
+        }
+        _ => match target.split_once("..") {
+            Some((base, head)) => {
+                let base_tree = resolve_commit(repo, base)?.tree()?;
+                let head_tree = resolve_commit(repo, head)?.tree()?;
+                repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), Some(diff_opts))?
+            }
+            None => {
+                let commit = resolve_commit(repo, target)?;
+                let parent_tree = if commit.parent_count() > 0 {
+                    Some(commit.parent(0)?.tree()?)
+                } else {
+                    None
+                };
+                repo.diff_tree_to_tree(
+                    parent_tree.as_ref(),
+                    Some(&commit.tree()?),
+                    Some(diff_opts),
+                )?
+            }
+        },
+    };
+    Ok(diff)
+}
+
+/// Body of one hunk with `+`/`-`/` ` prefixed lines
+fn hunk_content(patch: &Patch, idx: usize) -> Result<String> {
+    let mut content = String::new();
+    for line_idx in 0..patch.num_lines_in_hunk(idx)? {
+        push_patch_line(&mut content, &patch.line_in_hunk(idx, line_idx)?);
+    }
+    Ok(content)
+}
+
+/// Stable identifiers for the first `count` hunks of a patch.
+///
+/// An id is the hash of the hunk's body, so it survives other hunks of the
+/// file being staged or unstaged and the line numbers shifting. Identical
+/// hunks get a `-<n>` suffix by order of appearance.
+pub(crate) fn hunk_ids(patch: &Patch, count: usize) -> Result<Vec<String>> {
+    let mut seen: HashMap<git2::Oid, usize> = HashMap::new();
+    let mut ids = Vec::new();
+    for idx in 0..count.min(patch.num_hunks()) {
+        let hash =
+            git2::Oid::hash_object(git2::ObjectType::Blob, hunk_content(patch, idx)?.as_bytes())?;
+        let occurrence = seen.entry(hash).or_default();
+        let short = &hash.to_string()[..12];
+        ids.push(match *occurrence {
+            0 => short.to_string(),
+            n => format!("{}-{}", short, n),
+        });
+        *occurrence += 1;
+    }
+    Ok(ids)
+}
+
+/// Load one page of hunks for a single file, diffed against `target` as in
+/// [`file_patch`].
+///
+/// Only the requested hunks are materialized, so huge generated files can be
+/// scrolled without shipping the whole patch at once.
+pub fn get_file_hunks(
+    repo: &Repository,
+    file_path: &str,
+    target: &str,
+    offset: usize,
+    limit: usize,
+) -> Result<FileHunkPage> {
+    let Some(patch) = file_patch(repo, file_path, target)? else {
+        return Ok(FileHunkPage {
+            path: file_path.to_string(),
+            hunks: Vec::new(),
+            offset,
+            total: 0,
+        });
+    };
+
+    let total = patch.num_hunks();
+    let end = total.min(offset.saturating_add(limit));
+    // Ids depend on earlier hunks, so hash those too even though only the
+    // page is returned
+    let ids = hunk_ids(&patch, end)?;
+    let mut hunks = Vec::new();
+    for (idx, id) in ids.into_iter().enumerate().skip(offset) {
+        let (hunk, _) = patch.hunk(idx)?;
+        hunks.push(FileHunk {
+            index: idx,
+            id,
+            header: String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string(),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            content: hunk_content(&patch, idx)?,
+        });
+    }
+
+    Ok(FileHunkPage {
+        path: file_path.to_string(),
+        hunks,
+        offset,
+        total,
+    })
+}
@@ -0,0 +1,100 @@
+//! Huge-repository mode.
+//!
+//! Some defaults cost time proportional to the whole repository rather than
+//! to the change being looked at: recursing into untracked directories, diffing
+//! every commit in a list for its stats, and similarity searches to follow
+//! renames. Past a size threshold these are switched off, and results say
+//! which ones were, so the UI can explain the reduced detail. Sizing reads
+//! only file headers, so it is cheap enough to repeat on every call.
+
+use git2::Repository;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::git_config::common_dir;
+use super::types::{Degradation, DifferConfig, LargeRepoMode, LargeRepoSettings, LargeRepoStatus};
+
+/// Size up a repository and decide what to degrade
+pub fn get_large_repo_status(repo: &Repository, settings: &LargeRepoSettings) -> LargeRepoStatus {
+    let files = index_entries(repo).unwrap_or(0);
+    let objects = object_count(&common_dir(repo).join("objects"));
+    let huge = match settings.mode {
+        LargeRepoMode::On => true,
+        LargeRepoMode::Off => false,
+        LargeRepoMode::Auto => {
+            files > settings.file_threshold || objects > settings.object_threshold
+        }
+    };
+    let degraded = if huge {
+        Degradation::ALL
+            .into_iter()
+            .filter(|d| !settings.keep.contains(d))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    LargeRepoStatus {
+        huge,
+        files,
+        objects,
+        degraded,
+    }
+}
+
+/// Features degraded for `repo` under `config`
+pub(crate) fn degradations(repo: &Repository, config: &DifferConfig) -> Vec<Degradation> {
+    match config.large_repo.mode {
+        // Skip sizing entirely when it can't change the answer
+        LargeRepoMode::Off => Vec::new(),
+        _ => get_large_repo_status(repo, &config.large_repo).degraded,
+    }
+}
+
+/// Entry count from the index header, without loading the index
+fn index_entries(repo: &Repository) -> Option<usize> {
+    let header = read_prefix(&repo.path().join("index"), 12)?;
+    if &header[..4] != b"DIRC" {
+        return None;
+    }
+    Some(u32::from_be_bytes(header[8..12].try_into().ok()?) as usize)
+}
+
+/// Objects in every pack, from the last fanout entry of each `.idx`, plus
+/// loose objects estimated from one fan-out directory the way `git gc --auto`
+/// does
+fn object_count(objects: &Path) -> usize {
+    let packed: usize = std::fs::read_dir(objects.join("pack"))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "idx"))
+                .filter_map(|path| pack_objects(&path))
+                .sum()
+        })
+        .unwrap_or(0);
+    let loose = std::fs::read_dir(objects.join("17"))
+        .map(|entries| entries.count() * 256)
+        .unwrap_or(0);
+    packed + loose
+}
+
+/// Object count of a pack index. Version 2 indexes start with a magic number
+/// and version before the fanout table; version 1 starts with the table.
+fn pack_objects(idx: &Path) -> Option<usize> {
+    const FANOUT: usize = 256 * 4;
+    let header = read_prefix(idx, 8 + FANOUT)?;
+    let fanout = if header[..4] == [0xff, b't', b'O', b'c'] {
+        &header[8..]
+    } else {
+        &header[..FANOUT]
+    };
+    Some(u32::from_be_bytes(fanout[FANOUT - 4..FANOUT].try_into().ok()?) as usize)
+}
+
+fn read_prefix(path: &Path, len: usize) -> Option<Vec<u8>> {
+    let mut buffer = vec![0; len];
+    File::open(path).ok()?.read_exact(&mut buffer).ok()?;
+    Some(buffer)
+}
@@ -0,0 +1,303 @@
+use git2::{DiffOptions, Oid, Patch, Repository, Tree};
+use regex::Regex;
+use std::collections::HashMap;
+
+use super::case_rename::tree_blob;
+use super::file_history::path_in_parent;
+use super::object_cache;
+use super::types::{LineHistory, LineHistoryEntry};
+use super::{commit_to_info, push_patch_line, GitError, Result};
+
+/// Which part of a file to trace
+pub enum RegionSelector<'a> {
+    /// Inclusive 1-based line numbers at HEAD
+    Lines { start: usize, end: usize },
+    /// From the first line matching this regex down to the line before the
+    /// next top-level declaration, like `git log -L :<funcname>:<file>`
+    Function(&'a str),
+}
+
+/// A line range of a file as it was in one commit
+#[derive(Clone)]
+struct Region {
+    path: String,
+    start: usize,
+    end: usize,
+}
+
+/// How a region looks one commit further back
+struct Step {
+    /// `None` when the commit created every line of the region
+    region: Option<Region>,
+    /// Hunks touching the region; empty when the commit left it alone
+    patch: String,
+}
+
+/// A zero-context hunk: `(old_start, old_lines, new_start, new_lines)`
+type Span = (usize, usize, usize, usize);
+
+/// Commits that changed a region of a file, newest first, like `git log -L`.
+///
+/// The region is carried back through each commit's diff, so it keeps
+/// pointing at the same code as lines are added above it, it grows or
+/// shrinks, or the file is renamed. Tracing stops when the region was
+/// created or after `limit` commits.
+pub fn get_line_history(
+    repo: &Repository,
+    file_path: &str,
+    selector: RegionSelector,
+    limit: usize,
+) -> Result<LineHistory> {
+    let head = repo.head()?.peel_to_commit()?;
+    let lines = file_lines(repo, &head.tree()?, file_path)?;
+    let (start, end) = resolve_region(&lines, selector)?;
+
+    let mut pending: HashMap<Oid, Region> = HashMap::new();
+    pending.insert(
+        head.id(),
+        Region {
+            path: file_path.to_string(),
+            start,
+            end,
+        },
+    );
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head.id())?;
+    // Children before parents, so every commit's region is known when reached
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    let mut entries = Vec::new();
+    for oid in revwalk {
+        if entries.len() >= limit || pending.is_empty() {
+            break;
+        }
+        let oid = oid?;
+        let Some(region) = pending.remove(&oid) else {
+            continue;
+        };
+        let commit = repo.find_commit(oid)?;
+        let tree = commit.tree()?;
+
+        // Like history simplification: a merge that kept one parent's version
+        // of the region is skipped and only that parent is followed
+        let mut steps = Vec::new();
+        let mut kept_from = None;
+        for parent in commit.parents() {
+            let step = step_to_parent(repo, &parent.tree()?, &tree, &region)?;
+            if step.patch.is_empty() {
+                kept_from = Some((parent.id(), step.region));
+                break;
+            }
+            steps.push((parent.id(), step));
+        }
+        if let Some((parent, Some(parent_region))) = kept_from {
+            merge_region(&mut pending, parent, parent_region);
+            continue;
+        }
+
+        let patch = match steps.first() {
+            Some((_, step)) => step.patch.clone(),
+            // A root commit creates the whole region
+            None => added_patch(repo, &tree, &region)?,
+        };
+        entries.push(LineHistoryEntry {
+            commit: commit_to_info(&commit, repo),
+            path: region.path.clone(),
+            start_line: region.start,
+            end_line: region.end,
+            patch,
+        });
+        for (parent, step) in steps {
+            if let Some(parent_region) = step.region {
+                merge_region(&mut pending, parent, parent_region);
+            }
+        }
+    }
+
+    Ok(LineHistory {
+        path: file_path.to_string(),
+        start_line: start,
+        end_line: end,
+        entries,
+    })
+}
+
+fn file_lines(repo: &Repository, tree: &Tree, path: &str) -> Result<Vec<String>> {
+    let oid = tree_blob(repo, tree, path)?;
+    let content = object_cache::global().blob(repo, oid)?;
+    Ok(String::from_utf8_lossy(&content)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+fn resolve_region(lines: &[String], selector: RegionSelector) -> Result<(usize, usize)> {
+    match selector {
+        RegionSelector::Lines { start, end } => {
+            if start == 0 || start > end || end > lines.len() {
+                return Err(GitError::InvalidRange(format!(
+                    "lines {}-{} of a {}-line file",
+                    start,
+                    end,
+                    lines.len()
+                )));
+            }
+            Ok((start, end))
+        }
+        RegionSelector::Function(pattern) => {
+            let regex = Regex::new(pattern)
+                .map_err(|e| GitError::InvalidRange(format!("{}: {}", pattern, e)))?;
+            let first = lines
+                .iter()
+                .position(|line| regex.is_match(line))
+                .ok_or_else(|| GitError::InvalidRange(format!("no line matches {}", pattern)))?;
+
+            // git's default funcname rule: declarations start in column one
+            // with a letter, `_` or `$`
+            let mut last = lines[first + 1..]
+                .iter()
+                .position(|line| {
+                    line.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+                })
+                .map_or(lines.len() - 1, |offset| first + offset);
+            while last > first && lines[last].trim().is_empty() {
+                last -= 1;
+            }
+            Ok((first + 1, last + 1))
+        }
+    }
+}
+
+/// Carry `region` from `tree` back to `parent_tree`
+fn step_to_parent(
+    repo: &Repository,
+    parent_tree: &Tree,
+    tree: &Tree,
+    region: &Region,
+) -> Result<Step> {
+    let Some(parent_path) = path_in_parent(repo, parent_tree, tree, &region.path)? else {
+        return Ok(Step {
+            region: None,
+            patch: added_patch(repo, tree, region)?,
+        });
+    };
+
+    let mut opts = DiffOptions::new();
+    opts.pathspec(&parent_path)
+        .pathspec(&region.path)
+        .context_lines(0);
+    let mut diff = repo.diff_tree_to_tree(Some(parent_tree), Some(tree), Some(&mut opts))?;
+    if parent_path != region.path {
+        diff.find_similar(None)?;
+    }
+
+    let mut spans = Vec::new();
+    let mut patch_text = String::new();
+    if diff.deltas().len() > 0 {
+        if let Some(patch) = Patch::from_diff(&diff, 0)? {
+            for idx in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(idx)?;
+                let span = (
+                    hunk.old_start() as usize,
+                    hunk.old_lines() as usize,
+                    hunk.new_start() as usize,
+                    hunk.new_lines() as usize,
+                );
+                if overlaps(span, region.start, region.end) {
+                    patch_text.push_str(&String::from_utf8_lossy(hunk.header()));
+                    for line_idx in 0..line_count {
+                        push_patch_line(&mut patch_text, &patch.line_in_hunk(idx, line_idx)?);
+                    }
+                }
+                spans.push(span);
+            }
+        }
+    }
+
+    Ok(Step {
+        region: parent_range(&spans, region.start, region.end).map(|(start, end)| Region {
+            path: parent_path,
+            start,
+            end,
+        }),
+        patch: patch_text,
+    })
+}
+
+/// Whether a hunk changes any line of `start..=end`; a pure deletion counts
+/// when it falls between two lines of the range
+fn overlaps((_, _, new_start, new_lines): Span, start: usize, end: usize) -> bool {
+    if new_lines == 0 {
+        start <= new_start && new_start < end
+    } else {
+        new_start <= end && new_start + new_lines > start
+    }
+}
+
+/// The range in the parent covering the region's surviving lines and
+/// everything the overlapping hunks replaced. `None` when every line of the
+/// region was added.
+fn parent_range(spans: &[Span], start: usize, end: usize) -> Option<(usize, usize)> {
+    let mut covered: Vec<usize> = (start..=end)
+        .filter_map(|line| unchanged_parent_line(spans, line))
+        .collect();
+    for &(old_start, old_lines, new_start, new_lines) in spans {
+        if old_lines > 0 && overlaps((old_start, old_lines, new_start, new_lines), start, end) {
+            covered.push(old_start);
+            covered.push(old_start + old_lines - 1);
+        }
+    }
+    Some((*covered.iter().min()?, *covered.iter().max()?))
+}
+
+/// Parent line number of a line no hunk touched
+fn unchanged_parent_line(spans: &[Span], line: usize) -> Option<usize> {
+    let mut shift: isize = 0;
+    for &(_, old_lines, new_start, new_lines) in spans {
+        if new_lines > 0 && (new_start..new_start + new_lines).contains(&line) {
+            return None;
+        }
+        // A pure deletion sits after `new_start`; everything else before it
+        let before = if new_lines == 0 {
+            new_start < line
+        } else {
+            new_start + new_lines <= line
+        };
+        if !before {
+            break;
+        }
+        shift += old_lines as isize - new_lines as isize;
+    }
+    usize::try_from(line as isize + shift).ok()
+}
+
+/// The region's lines as a patch adding all of them
+fn added_patch(repo: &Repository, tree: &Tree, region: &Region) -> Result<String> {
+    let lines = file_lines(repo, tree, &region.path)?;
+    let mut patch = format!(
+        "@@ -0,0 +{},{} @@\n",
+        region.start,
+        region.end + 1 - region.start
+    );
+    for line in lines.iter().take(region.end).skip(region.start - 1) {
+        patch.push('+');
+        patch.push_str(line);
+        patch.push('\n');
+    }
+    Ok(patch)
+}
+
+/// Record that `parent` should be traced for `region`, widening any range
+/// already recorded for it through another child
+fn merge_region(pending: &mut HashMap<Oid, Region>, parent: Oid, region: Region) {
+    pending
+        .entry(parent)
+        .and_modify(|existing| {
+            if existing.path == region.path {
+                existing.start = existing.start.min(region.start);
+                existing.end = existing.end.max(region.end);
+            }
+        })
+        .or_insert(region);
+}
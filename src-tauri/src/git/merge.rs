@@ -0,0 +1,185 @@
+//! Merging another branch into the current one.
+//!
+//! A branch HEAD already contains is left alone, and one that only moves
+//! HEAD forward is fast-forwarded. Anything else is merged through the index
+//! like `git merge`: a clean result is committed straight away once the
+//! `pre-merge-commit` hook allows it, while conflicts leave the working tree marked up and the repository in the
+//! merging state, so committing once they are resolved concludes the merge.
+//!
+//! A merge can also be previewed entirely in memory, to check a branch
+//! before merging it without touching HEAD, the index or the working tree.
+
+use git2::build::CheckoutBuilder;
+use git2::{AnnotatedCommit, Oid, Repository, RepositoryState};
+
+use super::checkout::changed_files;
+use super::commit::write_commit;
+use super::commit_template::comment_char;
+use super::hooks::run_required_hook;
+use super::types::{DifferConfig, HookPolicy, MergeOutcome, MergePreview, MergeResult};
+use super::{
+    commit_to_info, conflicted_paths, parse_diff, resolve_commit, review, sensitive, GitError,
+    Result,
+};
+
+/// Merge `name` (a branch, remote-tracking branch or any revision) into
+/// HEAD.
+///
+/// Refuses while another operation is in progress or tracked files have
+/// local changes, so the merge never mixes with unrelated work. A failing
+/// `pre-merge-commit` hook leaves the merge uncommitted, as git does.
+pub fn merge_branch(repo: &Repository, name: &str, hooks: &HookPolicy) -> Result<MergeResult> {
+    if repo.state() != RepositoryState::Clean {
+        return Err(GitError::MergeInProgress);
+    }
+    let theirs = annotated(repo, name)?;
+    let changed = changed_files(repo)?;
+    if !changed.is_empty() {
+        return Err(GitError::UncommittedChanges(changed));
+    }
+    merge_annotated(repo, &theirs, name, hooks)
+}
+
+/// Merge `theirs` into HEAD once the repository is known to be ready for
+/// it; `name` is how the merge refers to it
+pub(super) fn merge_annotated(
+    repo: &Repository,
+    theirs: &AnnotatedCommit,
+    name: &str,
+    hooks: &HookPolicy,
+) -> Result<MergeResult> {
+    let mut result = MergeResult {
+        outcome: MergeOutcome::UpToDate,
+        branch: name.to_string(),
+        sha: theirs.id().to_string(),
+        commit: None,
+        conflicts: Vec::new(),
+        message: None,
+    };
+    let (analysis, _) = repo.merge_analysis(&[theirs])?;
+    if analysis.is_up_to_date() {
+        result.sha = repo.head()?.peel_to_commit()?.id().to_string();
+        return Ok(result);
+    }
+    if analysis.is_fast_forward() || analysis.is_unborn() {
+        fast_forward(repo, theirs.id(), name)?;
+        result.outcome = MergeOutcome::FastForward;
+        return Ok(result);
+    }
+
+    repo.merge(&[theirs], None, None)?;
+    let comment = comment_char(&repo.config()?).bytes().next();
+    let message = git2::message_prettify(repo.message()?, comment)?;
+    result.message = Some(message.clone());
+
+    let mut index = repo.index()?;
+    result.conflicts = conflicted_paths(&index)?;
+    if !result.conflicts.is_empty() {
+        result.outcome = MergeOutcome::Conflicted;
+        result.sha = repo.head()?.peel_to_commit()?.id().to_string();
+        return Ok(result);
+    }
+
+    // The hook may stage fixes of its own, so the tree comes after it
+    run_required_hook(repo, hooks, "pre-merge-commit", &[], None)?;
+    index.read(true)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let other = repo.find_commit(theirs.id())?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = repo.signature().map_err(|_| GitError::IdentityMissing)?;
+    let oid = write_commit(
+        repo,
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&head, &other],
+        &format!("merge {}", name),
+    )?;
+    repo.cleanup_state()?;
+
+    result.outcome = MergeOutcome::Merged;
+    result.sha = oid.to_string();
+    result.commit = Some(commit_to_info(&repo.find_commit(oid)?, repo));
+    Ok(result)
+}
+
+/// What merging `theirs` into `ours` would do, worked out in memory: the
+/// outcome, the files that would conflict and the diff the merge would make
+/// to `ours`. Conflicted files show up in the diff with the changes merged
+/// cleanly around them.
+pub fn preview_merge(
+    repo: &Repository,
+    ours: &str,
+    theirs: &str,
+    config: &DifferConfig,
+) -> Result<MergePreview> {
+    let ours_commit = resolve_commit(repo, ours)?;
+    let theirs_commit = resolve_commit(repo, theirs)?;
+    let ours_tree = ours_commit.tree()?;
+
+    let mut conflicts = Vec::new();
+    let (outcome, diff) = if ours_commit.id() == theirs_commit.id()
+        || repo.graph_descendant_of(ours_commit.id(), theirs_commit.id())?
+    {
+        let diff = repo.diff_tree_to_tree(Some(&ours_tree), Some(&ours_tree), None)?;
+        (MergeOutcome::UpToDate, diff)
+    } else if repo.graph_descendant_of(theirs_commit.id(), ours_commit.id())? {
+        let theirs_tree = theirs_commit.tree()?;
+        let diff = repo.diff_tree_to_tree(Some(&ours_tree), Some(&theirs_tree), None)?;
+        (MergeOutcome::FastForward, diff)
+    } else {
+        let index = repo.merge_commits(&ours_commit, &theirs_commit, None)?;
+        conflicts = conflicted_paths(&index)?;
+        let outcome = if conflicts.is_empty() {
+            MergeOutcome::Merged
+        } else {
+            MergeOutcome::Conflicted
+        };
+        (
+            outcome,
+            repo.diff_tree_to_index(Some(&ours_tree), Some(&index), None)?,
+        )
+    };
+
+    let mut result = parse_diff(&diff, usize::MAX)?;
+    result.warnings = sensitive::check_sensitive_files(&result.files, &config.sensitive_patterns);
+    result.complexity = Some(review::review_complexity(
+        &result.files,
+        &config.review_thresholds,
+    ));
+    Ok(MergePreview {
+        ours: ours_commit.id().to_string(),
+        theirs: theirs_commit.id().to_string(),
+        outcome,
+        conflicts,
+        diff: result,
+    })
+}
+
+/// `name` as a commit to merge, remembering the ref it came from so the
+/// merge message can name the branch
+fn annotated<'r>(repo: &'r Repository, name: &str) -> Result<AnnotatedCommit<'r>> {
+    if let Ok(reference) = repo.resolve_reference_from_short_name(name) {
+        return Ok(repo.reference_to_annotated_commit(&reference)?);
+    }
+    let commit = repo
+        .revparse_single(name)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| GitError::BranchNotFound(name.to_string()))?;
+    Ok(repo.find_annotated_commit(commit.id())?)
+}
+
+/// Move the current branch (or detached HEAD) up to `target`
+pub(super) fn fast_forward(repo: &Repository, target: Oid, name: &str) -> Result<()> {
+    let commit = repo.find_commit(target)?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    let reflog = format!("merge {}: Fast-forward", name);
+    match repo.find_reference("HEAD")?.symbolic_target() {
+        Some(branch) => {
+            repo.reference(branch, target, true, &reflog)?;
+        }
+        None => repo.set_head_detached(target)?,
+    }
+    Ok(())
+}
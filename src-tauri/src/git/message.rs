@@ -0,0 +1,260 @@
+//! Commit messages as structured text.
+//!
+//! Messages follow loose conventions rather than a markup language: a
+//! subject, a blank line, then prose hard-wrapped at 72 columns, bulleted or
+//! numbered lists, and indented or fenced code. This recognizes those so
+//! the frontend can render a description without guessing at raw text.
+//! Wrapped lines are joined back into paragraphs, code keeps its layout,
+//! and URLs and issue references become links.
+
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+use super::types::{CommitMessage, GitProvider, MessageBlock, MessageSpan, RemoteInfo};
+
+/// A URL, or an issue reference optionally qualified by `owner/repo`
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?P<url>https?://[^\s<>]+)|(?:(?P<repo>[\w.-]+/[\w.-]+))?(?P<sigil>[#!])(?P<number>\d+)\b")
+        .expect("valid pattern")
+});
+
+/// Subject and body of a message. The subject is the first paragraph on one
+/// line, as `git log --format=%s` shows it.
+pub fn split_message(message: &str) -> (String, String) {
+    let message = message.trim_start_matches(['\n', '\r']);
+    let (subject, body) = match message.find("\n\n") {
+        Some(at) => (&message[..at], &message[at + 2..]),
+        None => (message, ""),
+    };
+    let subject = subject.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+    let body = body.trim_start_matches(['\n', '\r']).trim_end();
+    (subject, body.to_string())
+}
+
+/// Parse `message` for display. `remote` decides where issue references link.
+pub fn parse_commit_message(message: &str, remote: Option<&RemoteInfo>) -> CommitMessage {
+    let (subject, body) = split_message(message);
+    CommitMessage {
+        subject: spans(&subject, remote),
+        body: blocks(&body, remote),
+    }
+}
+
+fn blocks(body: &str, remote: Option<&RemoteInfo>) -> Vec<MessageBlock> {
+    let lines: Vec<&str> = body.lines().collect();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim().is_empty() {
+            i += 1;
+            continue;
+        }
+
+        if let Some(fence) = fence(line) {
+            let start = i + 1;
+            let end = (start..lines.len())
+                .find(|&j| lines[j].trim_start().starts_with(fence))
+                .unwrap_or(lines.len());
+            blocks.push(MessageBlock::Code {
+                text: lines[start..end].join("\n"),
+            });
+            i = end + 1;
+            continue;
+        }
+
+        if is_indented(line) {
+            let start = i;
+            while i < lines.len()
+                && (is_indented(lines[i])
+                    || lines[i].trim().is_empty()
+                        && lines.get(i + 1).is_some_and(|next| is_indented(next)))
+            {
+                i += 1;
+            }
+            let text: Vec<&str> = lines[start..i].iter().map(|l| unindent(l)).collect();
+            blocks.push(MessageBlock::Code {
+                text: text.join("\n"),
+            });
+            continue;
+        }
+
+        if let Some((ordered, first)) = list_item(line) {
+            let mut items = vec![first.to_string()];
+            i += 1;
+            while i < lines.len() {
+                let line = lines[i];
+                if line.trim().is_empty() {
+                    // A blank line between items of the same list keeps it going
+                    let next = lines.get(i + 1).and_then(|next| list_item(next));
+                    if next.is_some_and(|(o, _)| o == ordered) {
+                        i += 1;
+                        continue;
+                    }
+                    break;
+                }
+                match list_item(line) {
+                    Some((o, _)) if o != ordered => break,
+                    Some((_, item)) => items.push(item.to_string()),
+                    // Wrapped continuation of the last item
+                    None => {
+                        let last = items.last_mut().expect("list has an item");
+                        last.push(' ');
+                        last.push_str(line.trim());
+                    }
+                }
+                i += 1;
+            }
+            blocks.push(MessageBlock::List {
+                ordered,
+                items: items.iter().map(|item| spans(item, remote)).collect(),
+            });
+            continue;
+        }
+
+        let mut paragraph = vec![line.trim()];
+        i += 1;
+        while i < lines.len() {
+            let line = lines[i];
+            if line.trim().is_empty() || list_item(line).is_some() || fence(line).is_some() {
+                break;
+            }
+            paragraph.push(line.trim());
+            i += 1;
+        }
+        blocks.push(MessageBlock::Paragraph {
+            spans: spans(&paragraph.join(" "), remote),
+        });
+    }
+    blocks
+}
+
+/// The fence a line opens a fenced code block with
+fn fence(line: &str) -> Option<&'static str> {
+    let line = line.trim_start();
+    ["```", "~~~"]
+        .into_iter()
+        .find(|fence| line.starts_with(fence))
+}
+
+fn is_indented(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+fn unindent(line: &str) -> &str {
+    line.strip_prefix("    ")
+        .or_else(|| line.strip_prefix('\t'))
+        .unwrap_or(line)
+}
+
+/// Whether the line starts a list item: `- `, `* `, `+ `, or `1. ` / `1) `
+/// for ordered lists. Returns the kind and the item's text.
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    let line = line.trim_start();
+    if let Some(rest) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+    {
+        return Some((false, rest.trim()));
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits > 3 {
+        return None;
+    }
+    let rest = &line[digits..];
+    rest.strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))
+        .map(|item| (true, item.trim()))
+}
+
+/// Text with its URLs and issue references picked out
+fn spans(text: &str, remote: Option<&RemoteInfo>) -> Vec<MessageSpan> {
+    let mut spans = Vec::new();
+    let mut plain_from = 0;
+    for captures in REFERENCE.captures_iter(text) {
+        let whole = captures.get(0).expect("match");
+        let Some((span, len)) = reference(text, &captures, remote) else {
+            continue;
+        };
+        if whole.start() > plain_from {
+            spans.push(MessageSpan::Text {
+                text: text[plain_from..whole.start()].to_string(),
+            });
+        }
+        spans.push(span);
+        plain_from = whole.start() + len;
+    }
+    if plain_from < text.len() {
+        spans.push(MessageSpan::Text {
+            text: text[plain_from..].to_string(),
+        });
+    }
+    spans
+}
+
+/// The span for one match and how many bytes of it it covers, or `None`
+/// when the match isn't a reference after all
+fn reference(
+    text: &str,
+    captures: &Captures,
+    remote: Option<&RemoteInfo>,
+) -> Option<(MessageSpan, usize)> {
+    if let Some(url) = captures.name("url") {
+        let url = trim_url(url.as_str());
+        let span = MessageSpan::Link {
+            text: url.to_string(),
+            url: url.to_string(),
+        };
+        return Some((span, url.len()));
+    }
+
+    let whole = captures.get(0)?;
+    let repo = captures.name("repo").map(|m| m.as_str());
+    // `a#1`, `&#39;` and paths aren't references
+    if repo.is_none() {
+        let before = text[..whole.start()].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || matches!(c, '&' | '/' | '_')) {
+            return None;
+        }
+    }
+    let sigil = captures.name("sigil")?.as_str();
+    let provider = remote.map(|r| &r.provider);
+    if sigil == "!" && !matches!(provider, Some(GitProvider::Gitlab)) {
+        return None;
+    }
+    let number: u64 = captures.name("number")?.as_str().parse().ok()?;
+    let span = MessageSpan::Issue {
+        text: whole.as_str().to_string(),
+        number,
+        url: remote.and_then(|remote| issue_url(remote, repo, sigil, number)),
+    };
+    Some((span, whole.len()))
+}
+
+/// A URL without the punctuation that usually follows one in prose
+fn trim_url(url: &str) -> &str {
+    let mut url = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+    while url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+        url = &url[..url.len() - 1];
+    }
+    url
+}
+
+fn issue_url(remote: &RemoteInfo, repo: Option<&str>, sigil: &str, number: u64) -> Option<String> {
+    let base = match repo {
+        // Same host, another repository
+        Some(repo) => {
+            let host: Vec<&str> = remote.url.splitn(4, '/').take(3).collect();
+            format!("{}/{}", host.join("/"), repo)
+        }
+        None => remote.url.clone(),
+    };
+    match (&remote.provider, sigil) {
+        (GitProvider::Github | GitProvider::Bitbucket, "#") => {
+            Some(format!("{}/issues/{}", base, number))
+        }
+        (GitProvider::Gitlab, "#") => Some(format!("{}/-/issues/{}", base, number)),
+        (GitProvider::Gitlab, "!") => Some(format!("{}/-/merge_requests/{}", base, number)),
+        _ => None,
+    }
+}
@@ -0,0 +1,105 @@
+use git2::{ObjectType, Repository, Tree};
+use std::collections::BTreeMap;
+
+use super::types::{DiffWarning, WarningCategory};
+use super::Result;
+
+/// Check branch migrations against the base branch.
+///
+/// For every configured migrations directory, each migration that exists only
+/// on `head` is compared with `base`: sharing a number/timestamp with any base
+/// migration is a collision, and being older than a migration that exists only
+/// on `base` means it would run out of order once the branches are merged.
+pub fn check_migrations(
+    repo: &Repository,
+    base_tree: &Tree,
+    head_tree: &Tree,
+    migration_paths: &[String],
+) -> Result<Vec<DiffWarning>> {
+    let mut warnings = Vec::new();
+
+    for dir in migration_paths {
+        let dir = dir.trim_matches('/');
+        let base = list_migrations(repo, base_tree, dir)?;
+        let head = list_migrations(repo, head_tree, dir)?;
+
+        let base_only: Vec<(&String, u128)> = base
+            .iter()
+            .filter(|(name, _)| !head.contains_key(*name))
+            .map(|(name, id)| (name, *id))
+            .collect();
+
+        for (name, id) in head.iter().filter(|(name, _)| !base.contains_key(*name)) {
+            let path = format!("{}/{}", dir, name);
+
+            let collision = base.iter().find(|(_, other_id)| *other_id == id);
+            let newer = base_only
+                .iter()
+                .filter(|(_, other_id)| other_id > id)
+                .max_by_key(|(_, other_id)| *other_id);
+
+            if let Some((other, _)) = collision {
+                warnings.push(DiffWarning {
+                    category: WarningCategory::MigrationCollision,
+                    path,
+                    message: format!(
+                        "Migration {} has the same number as {}/{} on the base branch",
+                        name, dir, other
+                    ),
+                });
+            } else if let Some((newest, _)) = newer {
+                warnings.push(DiffWarning {
+                    category: WarningCategory::MigrationOrder,
+                    path,
+                    message: format!(
+                        "Migration {} is older than {}/{} on the base branch and would run out of order",
+                        name, dir, newest
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Map each migration (file or directory directly under `dir`) to its numeric id
+fn list_migrations(repo: &Repository, tree: &Tree, dir: &str) -> Result<BTreeMap<String, u128>> {
+    let mut migrations = BTreeMap::new();
+
+    let entry = match tree.get_path(std::path::Path::new(dir)) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(migrations),
+        Err(e) => return Err(e.into()),
+    };
+    if entry.kind() != Some(ObjectType::Tree) {
+        return Ok(migrations);
+    }
+
+    let dir_tree = repo.find_tree(entry.id())?;
+    for child in dir_tree.iter() {
+        let Some(name) = child.name() else { continue };
+        if let Some(id) = migration_id(name) {
+            migrations.insert(name.to_string(), id);
+        }
+    }
+
+    Ok(migrations)
+}
+
+/// Extract the ordering key from names like `0042_add_users.sql`,
+/// `20240101120000_init.rb`, `2024-01-01-120000_init` or `V3__seed.sql`
+fn migration_id(name: &str) -> Option<u128> {
+    let name = name.strip_prefix(['V', 'v']).unwrap_or(name);
+    let digits: String = name
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '-')
+        .filter(char::is_ascii_digit)
+        .collect();
+
+    if digits.is_empty() {
+        None
+    } else {
+        digits.parse().ok()
+    }
+}
@@ -0,0 +1,71 @@
+use git2::{DiffHunk, DiffLine};
+
+/// Builds a run-length encoded change map for one file's diff.
+///
+/// The encoding is a sequence of `<kind><count>` runs in unified-view order:
+/// `a` added, `d` deleted, `c` context, and `g` unchanged lines between hunks
+/// that are not part of the patch. For example `g12c3a4d1c3` means 12 skipped
+/// lines, 3 context, 4 added, 1 deleted, 3 context.
+#[derive(Default)]
+pub struct MinimapBuilder {
+    encoded: String,
+    kind: Option<char>,
+    count: usize,
+    /// Next old-file line expected; a hunk starting past it leaves a gap
+    old_cursor: u32,
+    hunk_old_start: Option<u32>,
+}
+
+impl MinimapBuilder {
+    pub fn push_line(&mut self, hunk: Option<&DiffHunk>, line: &DiffLine) {
+        if let Some(hunk) = hunk {
+            if self.hunk_old_start != Some(hunk.old_start()) {
+                self.hunk_old_start = Some(hunk.old_start());
+                // Pure insertions report the line they follow as their start
+                let first_line = if hunk.old_lines() == 0 {
+                    hunk.old_start() + 1
+                } else {
+                    hunk.old_start()
+                };
+                let cursor = self.old_cursor.max(1);
+                if first_line > cursor {
+                    self.push_run('g', (first_line - cursor) as usize);
+                }
+                self.old_cursor = first_line;
+            }
+        }
+
+        let kind = match line.origin() {
+            '+' => 'a',
+            '-' => 'd',
+            ' ' => 'c',
+            _ => return,
+        };
+        if kind != 'a' {
+            self.old_cursor += 1;
+        }
+        self.push_run(kind, 1);
+    }
+
+    fn push_run(&mut self, kind: char, count: usize) {
+        if self.kind == Some(kind) {
+            self.count += count;
+            return;
+        }
+        self.flush();
+        self.kind = Some(kind);
+        self.count = count;
+    }
+
+    fn flush(&mut self) {
+        if let Some(kind) = self.kind.take() {
+            self.encoded.push(kind);
+            self.encoded.push_str(&self.count.to_string());
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        self.flush();
+        self.encoded
+    }
+}
@@ -1,12 +1,110 @@
+pub mod archive;
+pub mod audit;
+pub mod blame;
+pub mod branch_delta;
+pub mod branch_matrix;
+pub mod branches;
+pub mod case_rename;
+pub mod checkout;
+pub mod cherry;
+pub mod commit;
+pub mod commit_template;
+pub mod conflicts;
+pub mod credentials;
+pub mod dependencies;
+pub mod diff_delta;
+pub mod discard;
+pub mod document;
+pub mod drift;
+pub mod extensions;
+pub mod file_history;
+pub mod git_config;
+pub mod hooks;
+pub mod hunks;
+pub mod large_repo;
+pub mod line_history;
+pub mod merge;
+pub mod message;
+pub mod migrations;
+pub mod minimap;
+pub mod muted;
+pub mod narrative;
+pub mod navigation;
+pub mod notebook;
+pub mod object_cache;
+pub mod paths;
+mod process;
+pub mod public_api;
+pub mod pull;
+pub mod push;
+pub mod refs;
+pub mod remote;
+pub mod repo_status;
+pub mod review;
+pub mod risk;
+pub mod sensitive;
+pub mod sequencer;
+pub mod signing;
+pub mod staging;
+pub mod stash;
+pub mod svg;
+pub mod tags;
 pub mod types;
+pub mod worktrees;
 
-use git2::{Commit, Delta, Diff, DiffOptions, Repository};
+use git2::{Commit, Delta, Diff, DiffLine, DiffOptions, Patch, Repository, Tree};
 use std::cell::RefCell;
 use thiserror::Error;
 
+pub use archive::diff_against_archive;
+pub use audit::{get_audit_log, record_operation, RefSnapshot};
+pub use blame::{get_blame, get_blame_at_parent};
+pub use branch_delta::get_branch_delta;
+pub use branch_matrix::compare_branch_matrix;
+pub use branches::{create_branch, delete_branch, rename_branch};
+pub use checkout::checkout_branch;
+pub use commit::{amend_commit, create_commit};
+pub use commit_template::get_commit_template;
+pub use conflicts::{get_conflicts, resolve_conflict};
+pub use credentials::{Credentials, PassphrasePrompt};
+pub use dependencies::analyze_dependency_changes;
+pub use diff_delta::DiffSnapshot;
+pub use discard::{discard_file, discard_hunk, restore_discarded};
+pub use document::get_document_diff;
+pub use drift::{check_drift, get_branch_drift, DriftTracker};
+pub use file_history::get_file_history;
+pub use git_config::get_git_config;
+pub use hooks::{run_hook, run_required_hook};
+pub use hunks::get_file_hunks;
+pub use large_repo::get_large_repo_status;
+pub use line_history::{get_line_history, RegionSelector};
+pub use merge::{merge_branch, preview_merge};
+pub use message::{parse_commit_message, split_message};
+pub use muted::{
+    all_muted, apply_muted, list_muted, mute_file, replace_muted, unmute_file, MutedComparison,
+    MutedStore,
+};
+pub use narrative::get_diff_narrative;
+pub use notebook::get_notebook_diff;
+pub use public_api::diff_public_api;
+pub use pull::pull;
+pub use push::push;
+pub use repo_status::{
+    get_repo_status, get_status_line, inspect_repo, is_read_only, read_repo_config,
+    write_repo_config,
+};
+pub use sequencer::{cherry_pick, revert_commit};
+pub use signing::verify_commit;
+pub use staging::{stage_file, stage_hunk, stage_lines, unstage_file, unstage_hunk, unstage_lines};
+pub use stash::{get_stash_diff, get_stashes, stash_apply, stash_drop, stash_save};
+pub use svg::get_svg_render_pair;
+pub use tags::{compare_tags, create_tag, get_release_delta, get_tags};
 pub use types::*;
+pub use worktrees::{add_worktree, get_worktrees, prune_worktrees, remove_worktree};
 
 const MAX_PATCH_SIZE: usize = 50000; // 50KB max per file for display
+/// Commits returned inline with a comparison; each one costs a diff for its stats
+pub const COMPARE_COMMIT_LIMIT: usize = 100;
 
 #[derive(Error, Debug)]
 pub enum GitError {
@@ -18,55 +116,257 @@ pub enum GitError {
     CommitNotFound(String),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Invalid notebook: {0}")]
+    InvalidNotebook(String),
+    #[error("Text conversion failed: {0}")]
+    Conversion(String),
+    #[error("Worktree error: {0}")]
+    Worktree(String),
+    #[error("The {} hook failed", .0.hook)]
+    HookFailed(Box<HookResult>),
+    #[error("Invalid line range: {0}")]
+    InvalidRange(String),
+    #[error("Tag not found: {0}")]
+    TagNotFound(String),
+    #[error("No such file in the working tree or index: {0}")]
+    PathNotFound(String),
+    #[error("Hunk not found, the file may have changed: {0}")]
+    HunkNotFound(String),
+    #[error("No discarded changes to restore: {0}")]
+    BackupNotFound(String),
+    #[error("Unreadable archive {0}")]
+    InvalidArchive(String),
+    #[error("Nothing is staged to commit")]
+    NothingToCommit,
+    #[error("Set user.name and user.email before committing")]
+    IdentityMissing,
+    #[error("The commit message is empty")]
+    EmptyMessage,
+    #[error("Finish or abort the merge in progress first")]
+    MergeInProgress,
+    #[error("The commit is already pushed to {0}")]
+    AlreadyPushed(String),
+    #[error("Signing the commit ({format}) failed: {detail}")]
+    SigningFailed { format: String, detail: String },
+    #[error("Branch not found: {0}")]
+    BranchNotFound(String),
+    #[error("Commit or stash the changes to {} files first", .0.len())]
+    UncommittedChanges(Vec<String>),
+    #[error("A branch named {0} already exists")]
+    BranchExists(String),
+    #[error("Not a valid branch name: {0}")]
+    InvalidBranchName(String),
+    #[error("Branch {0} is not fully merged")]
+    BranchNotMerged(String),
+    #[error("Branch {0} is checked out")]
+    BranchCheckedOut(String),
+    #[error("A tag named {0} already exists")]
+    TagExists(String),
+    #[error("Not a valid tag name: {0}")]
+    InvalidTagName(String),
+    #[error("No conflict to resolve in {0}")]
+    NotConflicted(String),
+    #[error("{0} has no upstream branch to pull from")]
+    NoUpstream(String),
+    #[error("Can't fast-forward to {0}, the branches have diverged")]
+    NotFastForward(String),
+    #[error("{0} changed on the remote since it was last fetched")]
+    StaleLease(String),
+    #[error("The remote rejected {name}: {reason}")]
+    PushRejected { name: String, reason: String },
+    #[error("Not a valid refspec: {0}")]
+    InvalidRefspec(String),
+    #[error("Couldn't authenticate to {0}")]
+    AuthFailed(String),
 }
 
 pub type Result<T> = std::result::Result<T, GitError>;
 
 /// Open a git repository at the given path
+///
+/// Verbatim Windows paths (`\\?\C:\...`, `\\?\UNC\...`) are accepted and
+/// opened through their plain form, which libgit2 handles itself even past
+/// `MAX_PATH`.
 pub fn open_repo(path: &str) -> Result<Repository> {
-    Repository::discover(path).map_err(|_| GitError::RepoNotFound(path.to_string()))
+    Repository::discover(paths::plain_path(std::path::Path::new(path)))
+        .map_err(|_| GitError::RepoNotFound(path.to_string()))
 }
 
-/// Get current diff (working directory vs HEAD)
-pub fn get_current_diff(repo: &Repository) -> Result<DiffResult> {
-    let head = repo.head()?.peel_to_tree()?;
+/// Resolve any revision (branch, tag, SHA, `HEAD~2`, ...) to a commit
+pub fn resolve_commit<'r>(repo: &'r Repository, rev: &str) -> Result<Commit<'r>> {
+    Ok(repo.revparse_single(rev)?.peel_to_commit()?)
+}
+
+/// Tree of `base_ref`, or of HEAD when not given
+fn base_tree<'r>(repo: &'r Repository, base_ref: Option<&str>) -> Result<Tree<'r>> {
+    match base_ref {
+        Some(rev) => Ok(resolve_commit(repo, rev)?.tree()?),
+        None => Ok(repo.head()?.peel_to_tree()?),
+    }
+}
+
+/// Get current diff (working directory vs HEAD, or vs `base_ref` such as
+/// `main`, a tag or `HEAD~3`)
+pub fn get_current_diff(
+    repo: &Repository,
+    base_ref: Option<&str>,
+    config: &DifferConfig,
+) -> Result<DiffResult> {
+    let base = base_tree(repo, base_ref)?;
+    let degraded = large_repo::degradations(repo, config);
 
     let mut diff_opts = DiffOptions::new();
     diff_opts.include_untracked(true);
-    diff_opts.recurse_untracked_dirs(true);
+    diff_opts.recurse_untracked_dirs(!degraded.contains(&Degradation::UntrackedRecursion));
+
+    // Diff base to workdir (includes staged + unstaged)
+    let diff = repo.diff_tree_to_workdir_with_index(Some(&base), Some(&mut diff_opts))?;
+    let mut result = local_diff_result(&diff, config)?;
+    result.degraded = untracked_degradation(&degraded);
+    muted::apply_muted(
+        repo,
+        &local_comparison("working", base_ref),
+        &mut result.files,
+    )?;
+    Ok(result)
+}
 
-    // Diff HEAD to workdir (includes staged + unstaged)
-    let diff = repo.diff_tree_to_workdir_with_index(Some(&head), Some(&mut diff_opts))?;
+/// Split local changes into what is staged (HEAD to index) and what is not
+/// (index to working tree, untracked files included), for a staging area view
+pub fn get_working_changes(repo: &Repository, config: &DifferConfig) -> Result<WorkingChanges> {
+    // An unborn HEAD stages everything against the empty tree
+    let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    let staged_diff = repo.diff_tree_to_index(head.as_ref(), None, None)?;
 
-    parse_diff(&diff, MAX_PATCH_SIZE)
+    let degraded = large_repo::degradations(repo, config);
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.include_untracked(true);
+    diff_opts.recurse_untracked_dirs(!degraded.contains(&Degradation::UntrackedRecursion));
+    let unstaged_diff = repo.diff_index_to_workdir(None, Some(&mut diff_opts))?;
+
+    let mut staged = local_diff_result(&staged_diff, config)?;
+    let mut unstaged = local_diff_result(&unstaged_diff, config)?;
+    unstaged.degraded = untracked_degradation(&degraded);
+    // Both sections are the same review, so they share its mutes
+    muted::apply_muted(repo, "working", &mut staged.files)?;
+    muted::apply_muted(repo, "working", &mut unstaged.files)?;
+    let partially_staged = staged
+        .files
+        .iter()
+        .filter(|f| unstaged.files.iter().any(|u| u.path == f.path))
+        .map(|f| f.path.clone())
+        .collect();
+
+    Ok(WorkingChanges {
+        staged,
+        unstaged,
+        partially_staged,
+    })
 }
 
-/// Get file patch on demand (for lazy loading large files)
-pub fn get_file_patch(repo: &Repository, file_path: &str) -> Result<String> {
-    let head = repo.head()?.peel_to_tree()?;
+/// Get staged diff (index vs HEAD, or vs `base_ref`), i.e. exactly what would
+/// land relative to that ref if the index were committed now
+pub fn get_staged_diff(
+    repo: &Repository,
+    base_ref: Option<&str>,
+    config: &DifferConfig,
+) -> Result<DiffResult> {
+    let base = base_tree(repo, base_ref)?;
+    let diff = repo.diff_tree_to_index(Some(&base), None, None)?;
+    let mut result = local_diff_result(&diff, config)?;
+    muted::apply_muted(
+        repo,
+        &local_comparison("staged", base_ref),
+        &mut result.files,
+    )?;
+    Ok(result)
+}
+
+/// Mute key for local changes, `working` or `<base>..working` when diffed
+/// against another ref
+fn local_comparison(target: &str, base_ref: Option<&str>) -> String {
+    match base_ref {
+        Some(base) => format!("{}..{}", base, target),
+        None => target.to_string(),
+    }
+}
+
+/// The degradation a working tree diff reports, if untracked directories
+/// were left unexpanded
+fn untracked_degradation(degraded: &[Degradation]) -> Vec<Degradation> {
+    degraded
+        .iter()
+        .copied()
+        .filter(|d| *d == Degradation::UntrackedRecursion)
+        .collect()
+}
+
+/// Parse a diff of local changes and attach warnings and review complexity
+fn local_diff_result(diff: &Diff, config: &DifferConfig) -> Result<DiffResult> {
+    let mut result = parse_diff(diff, MAX_PATCH_SIZE)?;
+    result.warnings = sensitive::check_sensitive_files(&result.files, &config.sensitive_patterns);
+    result.complexity = Some(review::review_complexity(
+        &result.files,
+        &config.review_thresholds,
+    ));
+    Ok(result)
+}
+
+/// Get file patch on demand (for lazy loading large files), against the same
+/// base as `get_current_diff`
+pub fn get_file_patch(
+    repo: &Repository,
+    file_path: &str,
+    base_ref: Option<&str>,
+) -> Result<String> {
+    let base = base_tree(repo, base_ref)?;
 
     let mut diff_opts = DiffOptions::new();
     diff_opts.pathspec(file_path);
 
-    let diff = repo.diff_tree_to_workdir_with_index(Some(&head), Some(&mut diff_opts))?;
+    let diff = repo.diff_tree_to_workdir_with_index(Some(&base), Some(&mut diff_opts))?;
 
     let mut patch = String::new();
     diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-        let origin = line.origin();
-        if origin == '+' || origin == '-' || origin == ' ' {
-            patch.push(origin);
-        }
-        if let Ok(content) = std::str::from_utf8(line.content()) {
-            patch.push_str(content);
-        }
+        push_patch_line(&mut patch, &line);
         true
     })?;
 
     Ok(patch)
 }
 
-/// Get commit history with pagination
-pub fn get_commit_history(repo: &Repository, limit: usize, offset: usize) -> Result<CommitHistory> {
+/// Append a printed diff line to a patch, prefixing content lines with their origin
+fn push_patch_line(patch: &mut String, line: &DiffLine) {
+    let origin = line.origin();
+    if origin == '+' || origin == '-' || origin == ' ' {
+        patch.push(origin);
+    }
+    if let Ok(content) = std::str::from_utf8(line.content()) {
+        patch.push_str(content);
+    }
+}
+
+/// Paths with conflict entries in `index`, each listed once
+pub(crate) fn conflicted_paths(index: &git2::Index) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            paths.push(String::from_utf8_lossy(&entry.path).to_string());
+        }
+    }
+    Ok(paths)
+}
+
+/// Get commit history with pagination; huge repositories skip per-commit stats
+pub fn get_commit_history(
+    repo: &Repository,
+    limit: usize,
+    offset: usize,
+    config: &DifferConfig,
+) -> Result<CommitHistory> {
+    let degraded = stats_degradation(repo, config);
+
     // First pass: count total commits
     let mut revwalk = repo.revwalk()?;
     revwalk.push_head()?;
@@ -84,14 +384,27 @@ pub fn get_commit_history(repo: &Repository, limit: usize, offset: usize) -> Res
         .take(limit)
         .filter_map(|oid| oid.ok())
         .filter_map(|oid| repo.find_commit(oid).ok())
-        .map(|commit| commit_to_info(&commit, repo))
+        .map(|commit| commit_info(&commit, repo, degraded.is_empty()))
         .collect();
 
-    Ok(CommitHistory { commits, total })
+    Ok(CommitHistory {
+        commits,
+        total,
+        degraded,
+        applied_in_truncated: false,
+    })
+}
+
+/// `[CommitStats]` when commit lists should skip diffing every commit
+fn stats_degradation(repo: &Repository, config: &DifferConfig) -> Vec<Degradation> {
+    large_repo::degradations(repo, config)
+        .into_iter()
+        .filter(|d| *d == Degradation::CommitStats)
+        .collect()
 }
 
 /// Get diff for a specific commit
-pub fn get_commit_diff(repo: &Repository, sha: &str) -> Result<CommitDiff> {
+pub fn get_commit_diff(repo: &Repository, sha: &str, config: &DifferConfig) -> Result<CommitDiff> {
     let oid = git2::Oid::from_str(sha)?;
     let commit = repo.find_commit(oid)?;
 
@@ -104,9 +417,13 @@ pub fn get_commit_diff(repo: &Repository, sha: &str) -> Result<CommitDiff> {
     let commit_tree = commit.tree()?;
 
     let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
-    let diff_result = parse_diff(&diff, usize::MAX)?;
+    let mut diff_result = parse_diff(&diff, usize::MAX)?;
+    muted::apply_muted(repo, sha, &mut diff_result.files)?;
 
     let commit_info = commit_to_info(&commit, repo);
+    let warnings = sensitive::check_sensitive_files(&diff_result.files, &config.sensitive_patterns);
+    let remote = get_remote_url(repo).ok().flatten();
+    let description = parse_commit_message(&commit_info.message, remote.as_ref());
 
     Ok(CommitDiff {
         commit: CommitInfo {
@@ -117,85 +434,369 @@ pub fn get_commit_diff(repo: &Repository, sha: &str) -> Result<CommitDiff> {
             },
             ..commit_info
         },
+        description,
         files: diff_result.files,
+        warnings,
+        navigation: diff_result.navigation,
     })
 }
 
 /// Compare two branches
-pub fn compare_branches(repo: &Repository, base: &str, head: &str) -> Result<CompareBranchesResult> {
+pub fn compare_branches(
+    repo: &Repository,
+    base: &str,
+    head: &str,
+    config: &DifferConfig,
+) -> Result<CompareBranchesResult> {
     let base_ref = repo.resolve_reference_from_short_name(base)?;
     let head_ref = repo.resolve_reference_from_short_name(head)?;
 
-    let base_commit = base_ref.peel_to_commit()?;
-    let head_commit = head_ref.peel_to_commit()?;
+    let mut result = compare_commits(
+        repo,
+        &base_ref.peel_to_commit()?,
+        &head_ref.peel_to_commit()?,
+        config,
+    )?;
+    muted::apply_muted(repo, &format!("{}..{}", base, head), &mut result.files)?;
+    Ok(result)
+}
+
+/// Compare any two revisions: tags, SHAs, `HEAD~3`, remote branches, ...
+pub fn compare_refs(
+    repo: &Repository,
+    base: &str,
+    head: &str,
+    config: &DifferConfig,
+) -> Result<CompareBranchesResult> {
+    let mut result = compare_commits(
+        repo,
+        &resolve_commit(repo, base)?,
+        &resolve_commit(repo, head)?,
+        config,
+    )?;
+    muted::apply_muted(repo, &format!("{}..{}", base, head), &mut result.files)?;
+    Ok(result)
+}
 
+fn compare_commits(
+    repo: &Repository,
+    base_commit: &Commit,
+    head_commit: &Commit,
+    config: &DifferConfig,
+) -> Result<CompareBranchesResult> {
     let base_tree = base_commit.tree()?;
     let head_tree = head_commit.tree()?;
 
-    // Count commits between branches
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push(head_commit.id())?;
-    revwalk.hide(base_commit.id())?;
-    let commit_count = revwalk.count();
+    let history = range_commits(
+        repo,
+        base_commit.id(),
+        head_commit.id(),
+        COMPARE_COMMIT_LIMIT,
+        0,
+        stats_degradation(repo, config),
+    )?;
 
     let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)?;
-    let diff_result = parse_diff(&diff, usize::MAX)?;
+    let mut diff_result = parse_diff(&diff, usize::MAX)?;
+    // Walks the same history the commit stats do, so it is left out with them
+    if history.degraded.is_empty() {
+        risk::score_file_risk(
+            repo,
+            head_commit.id(),
+            &mut diff_result.files,
+            &config.risk_paths,
+        )?;
+    } else {
+        for file in &mut diff_result.files {
+            file.risk_status = Some(RiskStatus::Skipped);
+        }
+    }
+
+    let mut warnings =
+        sensitive::check_sensitive_files(&diff_result.files, &config.sensitive_patterns);
+    warnings.extend(migrations::check_migrations(
+        repo,
+        &base_tree,
+        &head_tree,
+        &config.migration_paths,
+    )?);
+
+    let complexity = review::review_complexity(&diff_result.files, &config.review_thresholds);
+    let by_extension = extensions::stats_by_extension(&diff_result.files);
 
     Ok(CompareBranchesResult {
         files: diff_result.files,
         stats: diff_result.stats,
-        commit_count,
+        commit_count: history.total,
+        commits: history.commits,
+        applied_in_truncated: history.applied_in_truncated,
+        degraded: history.degraded,
+        warnings,
+        complexity,
+        by_extension,
+        navigation: diff_result.navigation,
+    })
+}
+
+/// One page of the commits in `base..head`, oldest first as on a pull request
+pub fn get_compare_commits(
+    repo: &Repository,
+    base: &str,
+    head: &str,
+    limit: usize,
+    offset: usize,
+    config: &DifferConfig,
+) -> Result<CommitHistory> {
+    range_commits(
+        repo,
+        resolve_commit(repo, base)?.id(),
+        resolve_commit(repo, head)?.id(),
+        limit,
+        offset,
+        stats_degradation(repo, config),
+    )
+}
+
+/// Commits in `base..head`, each marked when `base` already has the same
+/// change; `degraded` holding `CommitStats` skips both the stats and the marks
+fn range_commits(
+    repo: &Repository,
+    base: git2::Oid,
+    head: git2::Oid,
+    limit: usize,
+    offset: usize,
+    degraded: Vec<Degradation>,
+) -> Result<CommitHistory> {
+    let walk = || -> Result<git2::Revwalk> {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(head)?;
+        revwalk.hide(base)?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME | git2::Sort::REVERSE)?;
+        Ok(revwalk)
+    };
+
+    let total = walk()?.count();
+    let page: Vec<Commit> = walk()?
+        .skip(offset)
+        .take(limit)
+        .filter_map(|oid| oid.ok())
+        .filter_map(|oid| repo.find_commit(oid).ok())
+        .collect();
+    // Patch ids need a diff per commit, so they're skipped along with stats
+    let upstream = if page.is_empty() || !degraded.is_empty() {
+        None
+    } else {
+        Some(cherry::Upstream::new(
+            repo,
+            base,
+            head,
+            COMPARE_COMMIT_LIMIT,
+        )?)
+    };
+    let commits = page
+        .iter()
+        .map(|commit| CommitInfo {
+            applied_in: upstream
+                .as_ref()
+                .and_then(|upstream| upstream.equivalent(repo, commit))
+                .map(|oid| oid.to_string()),
+            ..commit_info(commit, repo, degraded.is_empty())
+        })
+        .collect();
+
+    Ok(CommitHistory {
+        commits,
+        total,
+        degraded,
+        applied_in_truncated: upstream.is_some_and(|upstream| upstream.truncated),
     })
 }
 
 /// Get branch list
 pub fn get_branches(repo: &Repository) -> Result<BranchList> {
-    let head = repo.head()?;
-    let current_branch = head
-        .shorthand()
-        .map(|s| s.to_string())
-        .unwrap_or_default();
+    get_branch_page(repo, &BranchQuery::default())
+}
+
+/// One page of local branches, sorted by name and optionally filtered.
+///
+/// Only the branches on the page are looked up: their tip commits and merge
+/// status are the expensive part on repositories with thousands of branches.
+pub fn get_branch_page(repo: &Repository, query: &BranchQuery) -> Result<BranchList> {
+    let context = BranchContext::new(repo)?;
+
+    let filter = query.filter.as_deref().map(str::to_lowercase);
+    let matching: Vec<(String, git2::Oid)> = refs::list_refs(repo, "refs/heads/")?
+        .into_iter()
+        .map(|(name, oid)| (name["refs/heads/".len()..].to_string(), oid))
+        .filter(|(name, _)| {
+            filter
+                .as_deref()
+                .is_none_or(|filter| name.to_lowercase().contains(filter))
+        })
+        .collect();
+    let total = matching.len();
+    let page = matching
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX));
 
     let mut branches = Vec::new();
+    for (name, oid) in page {
+        branches.push(context.branch_info(repo, name, oid)?);
+    }
 
-    for branch_result in repo.branches(Some(git2::BranchType::Local))? {
-        let (branch, _) = branch_result?;
-        let name = branch.name()?.unwrap_or("").to_string();
-        let commit = branch.get().peel_to_commit()?.id().to_string();
-        let is_current = branch.is_head();
+    Ok(BranchList {
+        branches,
+        current: context.current_branch,
+        default_branch: context.default_branch,
+        total,
+    })
+}
 
-        branches.push(BranchInfo {
+/// What each `BranchInfo` is compared against
+pub(crate) struct BranchContext {
+    current_branch: String,
+    head_is_branch: bool,
+    head_oid: git2::Oid,
+    default_branch: Option<String>,
+    default_oid: Option<git2::Oid>,
+}
+
+impl BranchContext {
+    pub(crate) fn new(repo: &Repository) -> Result<Self> {
+        let head = repo.head()?;
+        let default_branch = repo_status::detect_default_branch(repo).map(|(name, _)| name);
+        let default_oid = default_branch
+            .as_deref()
+            .and_then(|name| resolve_commit(repo, name).ok())
+            .map(|c| c.id());
+        Ok(Self {
+            current_branch: head.shorthand().map(|s| s.to_string()).unwrap_or_default(),
+            head_is_branch: head.is_branch(),
+            head_oid: head.peel_to_commit()?.id(),
+            default_branch,
+            default_oid,
+        })
+    }
+
+    /// Details of the local branch `name` pointing at `oid`
+    pub(crate) fn branch_info(
+        &self,
+        repo: &Repository,
+        name: String,
+        oid: git2::Oid,
+    ) -> Result<BranchInfo> {
+        let tip = repo.find_object(oid, None)?.peel_to_commit()?;
+        let is_current = self.head_is_branch && self.current_branch == name;
+
+        let merged_into_default = self
+            .default_oid
+            .map(|target| is_merged(repo, tip.id(), target));
+        let orphan = self
+            .default_oid
+            .map(|target| repo.merge_base(tip.id(), target).is_err())
+            .unwrap_or(false);
+        let author = tip.author().name().unwrap_or("").to_string();
+
+        Ok(BranchInfo {
             name,
             current: is_current,
-            commit: commit[..7].to_string(),
-        });
+            commit: tip.id().to_string()[..7].to_string(),
+            merged_into_default,
+            merged_into_head: is_merged(repo, tip.id(), self.head_oid),
+            orphan,
+            last_commit_date: format_commit_time(&tip),
+            last_commit_author: author,
+        })
     }
+}
 
-    Ok(BranchList {
-        branches,
-        current: current_branch,
-    })
+/// Whether `branch` is reachable from `target`, i.e. deleting it loses nothing
+pub(crate) fn is_merged(repo: &Repository, branch: git2::Oid, target: git2::Oid) -> bool {
+    branch == target || repo.graph_descendant_of(target, branch).unwrap_or(false)
 }
 
 /// Get file contents at a specific ref
 pub fn get_file_contents(repo: &Repository, file_path: &str, git_ref: Option<&str>) -> Result<String> {
     match git_ref {
         Some(r) => {
-            let obj = repo.revparse_single(&format!("{}:{}", r, file_path))?;
-            let blob = obj.peel_to_blob()?;
-            Ok(String::from_utf8_lossy(blob.content()).to_string())
+            let content = case_rename::blob_content(repo, r, file_path)?;
+            Ok(String::from_utf8_lossy(&content).to_string())
         }
         None => {
             // Read from working directory
             let workdir = repo.workdir().ok_or_else(|| {
                 GitError::Git(git2::Error::from_str("No working directory"))
             })?;
-            let full_path = workdir.join(file_path);
-            Ok(std::fs::read_to_string(full_path)?)
+            let full_path = case_rename::workdir_file(repo, workdir, file_path);
+            Ok(std::fs::read_to_string(paths::long_path(&full_path))?)
+        }
+    }
+}
+
+/// Get file contents at a specific ref, or `None` if the file does not exist there
+pub fn read_file_at(
+    repo: &Repository,
+    file_path: &str,
+    git_ref: Option<&str>,
+) -> Result<Option<String>> {
+    match get_file_contents(repo, file_path, git_ref) {
+        Ok(content) => Ok(Some(content)),
+        Err(GitError::Git(e)) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(GitError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get raw file bytes at a specific ref (working directory when `None`),
+/// or `None` if the file does not exist there
+pub fn read_bytes_at(
+    repo: &Repository,
+    file_path: &str,
+    git_ref: Option<&str>,
+) -> Result<Option<Vec<u8>>> {
+    match git_ref {
+        Some(r) => match case_rename::blob_content(repo, r, file_path) {
+            Ok(content) => Ok(Some(content.to_vec())),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        },
+        None => {
+            let workdir = repo
+                .workdir()
+                .ok_or_else(|| GitError::Git(git2::Error::from_str("No working directory")))?;
+            let full_path = case_rename::workdir_file(repo, workdir, file_path);
+            match std::fs::read(paths::long_path(&full_path)) {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
         }
     }
 }
 
+/// Line-diff two in-memory texts, returning the hunks plus addition/deletion counts
+fn diff_text(old: &str, new: &str) -> Result<(String, usize, usize)> {
+    let mut patch = Patch::from_buffers(old.as_bytes(), None, new.as_bytes(), None, None)?;
+
+    let mut text = String::new();
+    let mut additions = 0;
+    let mut deletions = 0;
+    patch.print(&mut |_delta, _hunk, line| {
+        match line.origin() {
+            '+' => additions += 1,
+            '-' => deletions += 1,
+            // The synthetic "a/file b/file" header carries no information here
+            'F' => return true,
+            _ => {}
+        }
+        push_patch_line(&mut text, &line);
+        true
+    })?;
+
+    Ok((text, additions, deletions))
+}
+
 /// Get remote URL info
 pub fn get_remote_url(repo: &Repository) -> Result<Option<RemoteInfo>> {
     let remote = match repo.find_remote("origin") {
@@ -211,48 +812,96 @@ pub fn get_remote_url(repo: &Repository) -> Result<Option<RemoteInfo>> {
     parse_remote_url(&url)
 }
 
-/// Parse remote URL to extract provider info
-fn parse_remote_url(url: &str) -> Result<Option<RemoteInfo>> {
-    // SSH format: git@github.com:owner/repo.git
-    if url.starts_with("git@") {
-        let parts: Vec<&str> = url.strip_prefix("git@").unwrap().split(':').collect();
-        if parts.len() != 2 {
-            return Ok(None);
-        }
-        let host = parts[0];
-        let path = parts[1].trim_end_matches(".git");
-        let path_parts: Vec<&str> = path.split('/').collect();
-        if path_parts.len() < 2 {
+/// Parse remote URL to extract provider info.
+///
+/// Accepts scp-style SSH (`git@host:owner/repo.git`), URL forms (`https://`,
+/// `ssh://` with ports, `git://`), `codecommit::region://repo`, and nested
+/// paths such as GitLab subgroups (the owner is everything before the repo).
+pub fn parse_remote_url(url: &str) -> Result<Option<RemoteInfo>> {
+    // git-remote-codecommit: codecommit::<region>://<repo> or codecommit://<repo>
+    let codecommit = url
+        .strip_prefix("codecommit::")
+        .and_then(|rest| rest.split_once("://"))
+        .or_else(|| url.strip_prefix("codecommit://").map(|repo| ("", repo)));
+    if let Some((region, repo)) = codecommit {
+        // An optional AWS profile is given as `profile@repo`
+        let repo = repo.rsplit('@').next().unwrap_or(repo);
+        if repo.is_empty() {
             return Ok(None);
         }
+        let host = if region.is_empty() {
+            "git-codecommit.amazonaws.com".to_string()
+        } else {
+            format!("git-codecommit.{}.amazonaws.com", region)
+        };
+        return Ok(remote_from_parts(&host, &format!("v1/repos/{}", repo)));
+    }
 
-        return Ok(Some(RemoteInfo {
-            url: format!("https://{}/{}", host, path),
-            provider: detect_provider(host),
-            owner: path_parts[0].to_string(),
-            repo: path_parts[1].to_string(),
-        }));
+    // SSH scp-like format: git@github.com:owner/repo.git
+    if !url.contains("://") {
+        let Some((user_host, path)) = url.split_once(':') else {
+            return Ok(None);
+        };
+        let host = user_host.rsplit('@').next().unwrap_or(user_host);
+        return Ok(remote_from_parts(host, path));
     }
 
-    // HTTPS format
+    // URL formats: https://, ssh:// (optionally with a port), git://
     if let Ok(parsed) = url::Url::parse(url) {
         let host = parsed.host_str().unwrap_or("");
-        let path = parsed.path().trim_start_matches('/').trim_end_matches(".git");
-        let path_parts: Vec<&str> = path.split('/').collect();
-
-        if path_parts.len() >= 2 {
-            return Ok(Some(RemoteInfo {
-                url: format!("https://{}/{}", host, path),
-                provider: detect_provider(host),
-                owner: path_parts[0].to_string(),
-                repo: path_parts[1].to_string(),
-            }));
-        }
+        return Ok(remote_from_parts(host, parsed.path()));
     }
 
     Ok(None)
 }
 
+/// Split a host and repository path into owner/repo, normalizing hosts whose
+/// SSH and web addresses differ
+fn remote_from_parts(host: &str, path: &str) -> Option<RemoteInfo> {
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if host.is_empty() {
+        return None;
+    }
+
+    // Azure DevOps SSH: ssh.dev.azure.com:v3/org/project/repo
+    let (host, path) = match (host, path.strip_prefix("v3/")) {
+        ("ssh.dev.azure.com", Some(rest)) => match rest.rsplit_once('/') {
+            Some((owner, repo)) => ("dev.azure.com", format!("{}/_git/{}", owner, repo)),
+            None => return None,
+        },
+        _ => (host, path.to_string()),
+    };
+
+    let (owner, repo) = if let Some((owner, repo)) = path.split_once("/_git/") {
+        // Azure DevOps HTTPS: dev.azure.com/org/project/_git/repo
+        (owner.to_string(), repo.to_string())
+    } else if let (true, Some(repo)) = (
+        host.starts_with("git-codecommit."),
+        path.strip_prefix("v1/repos/"),
+    ) {
+        // CodeCommit has no owner in the path; the region stands in for it
+        let region = host
+            .strip_prefix("git-codecommit.")
+            .and_then(|h| h.strip_suffix(".amazonaws.com"))
+            .unwrap_or("");
+        (region.to_string(), repo.to_string())
+    } else {
+        let (owner, repo) = path.rsplit_once('/')?;
+        (owner.to_string(), repo.to_string())
+    };
+
+    if repo.is_empty() || (owner.is_empty() && !host.starts_with("git-codecommit.")) {
+        return None;
+    }
+
+    Some(RemoteInfo {
+        url: format!("https://{}/{}", host, path),
+        provider: detect_provider(host),
+        owner,
+        repo,
+    })
+}
+
 fn detect_provider(host: &str) -> GitProvider {
     if host.contains("github") {
         GitProvider::Github
@@ -265,7 +914,12 @@ fn detect_provider(host: &str) -> GitProvider {
     }
 }
 
-fn commit_to_info(commit: &Commit, repo: &Repository) -> CommitInfo {
+pub(crate) fn commit_to_info(commit: &Commit, repo: &Repository) -> CommitInfo {
+    commit_info(commit, repo, true)
+}
+
+/// Commit details, with zeroed stats unless `stats` is set
+pub(crate) fn commit_info(commit: &Commit, repo: &Repository, stats: bool) -> CommitInfo {
     let sha = commit.id().to_string();
     let short_sha = sha[..7].to_string();
     let message = commit.message().unwrap_or("").to_string();
@@ -273,30 +927,48 @@ fn commit_to_info(commit: &Commit, repo: &Repository) -> CommitInfo {
     let author_name = author.name().unwrap_or("").to_string();
     let author_email = author.email().unwrap_or("").to_string();
 
-    // Format date as ISO 8601
-    let time = commit.time();
-    let datetime = chrono::DateTime::from_timestamp(time.seconds(), 0)
-        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-        .unwrap_or_default();
+    let datetime = format_commit_time(commit);
 
     // Calculate stats
-    let stats = calculate_commit_stats(commit, repo).unwrap_or(CommitStats {
+    let empty = CommitStats {
         additions: 0,
         deletions: 0,
         files: 0,
-    });
+    };
+    let stats = if stats {
+        calculate_commit_stats(commit, repo).unwrap_or(empty)
+    } else {
+        empty
+    };
 
+    let (subject, body) = split_message(&message);
     CommitInfo {
         sha,
         short_sha,
         message,
+        subject,
+        body,
         author: author_name,
         author_email,
         date: datetime,
         stats,
+        signed: repo.extract_signature(&commit.id(), None).is_ok(),
+        applied_in: None,
     }
 }
 
+/// Commit time as ISO 8601 in UTC
+pub(crate) fn format_commit_time(commit: &Commit) -> String {
+    format_git_time(commit.time())
+}
+
+/// A git timestamp as ISO 8601 in UTC
+pub(crate) fn format_git_time(time: git2::Time) -> String {
+    chrono::DateTime::from_timestamp(time.seconds(), 0)
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_default()
+}
+
 fn calculate_commit_stats(commit: &Commit, repo: &Repository) -> Result<CommitStats> {
     let parent_tree = if commit.parent_count() > 0 {
         Some(commit.parent(0)?.tree()?)
@@ -315,48 +987,63 @@ fn calculate_commit_stats(commit: &Commit, repo: &Repository) -> Result<CommitSt
     })
 }
 
-fn parse_diff(diff: &Diff, max_patch_size: usize) -> Result<DiffResult> {
+/// Collect file infos, patches and stats from a libgit2 diff.
+/// Patches over `max_patch_size` bytes are dropped and the file marked large.
+/// A file whose contents can't be read (permissions, a lock held by another
+/// program) gets an `error` instead of failing the whole diff.
+pub fn parse_diff(diff: &Diff, max_patch_size: usize) -> Result<DiffResult> {
+    let mut files = match collect_files(diff, max_patch_size) {
+        Ok(files) => files,
+        // One unreadable file aborts the single pass; redo it file by file so
+        // only that file is affected
+        Err(GitError::Git(e)) if e.class() == git2::ErrorClass::Os => {
+            collect_files_separately(diff, max_patch_size)
+        }
+        Err(e) => return Err(e),
+    };
+    case_rename::merge_case_renames(diff, &mut files, max_patch_size)?;
+    let navigation = navigation::change_navigation(&files);
+
+    // Calculate totals
+    let mut total_additions = 0;
+    let mut total_deletions = 0;
+    for file in &files {
+        total_additions += file.additions;
+        total_deletions += file.deletions;
+    }
+
+    let num_files = files.len();
+
+    Ok(DiffResult {
+        files,
+        stats: DiffStats {
+            additions: total_additions,
+            deletions: total_deletions,
+            files: num_files,
+        },
+        warnings: Vec::new(),
+        complexity: None,
+        degraded: Vec::new(),
+        navigation,
+    })
+}
+
+/// All files of `diff` in a single pass, failing if any file can't be read
+fn collect_files(diff: &Diff, max_patch_size: usize) -> Result<Vec<FileDiffInfo>> {
     // Use RefCell to allow interior mutability in closures
     let files: RefCell<Vec<FileDiffInfo>> = RefCell::new(Vec::new());
+    let builders: RefCell<Vec<(minimap::MinimapBuilder, navigation::AnchorBuilder)>> =
+        RefCell::new(Vec::new());
 
-    // First pass: collect file info
     diff.foreach(
         &mut |delta, _progress| {
-            let path = delta.new_file().path()
-                .or_else(|| delta.old_file().path())
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_default();
-
-            let old_path = if delta.status() == Delta::Renamed {
-                delta.old_file().path().map(|p| p.to_string_lossy().to_string())
-            } else {
-                None
-            };
-
-            let status = match delta.status() {
-                Delta::Added | Delta::Untracked => FileStatus::Added,
-                Delta::Deleted => FileStatus::Deleted,
-                Delta::Renamed => FileStatus::Renamed,
-                _ => FileStatus::Modified,
-            };
-
-            files.borrow_mut().push(FileDiffInfo {
-                path,
-                old_path,
-                status,
-                additions: 0,
-                deletions: 0,
-                old_content: None,
-                new_content: None,
-                patch: Some(String::new()),
-                is_large: Some(false),
-            });
-
+            files.borrow_mut().push(file_entry(&delta));
+            builders.borrow_mut().push(Default::default());
             true
         },
         None,
         None,
-        Some(&mut |delta, _hunk, line| {
+        Some(&mut |delta, hunk, line| {
             let mut files_mut = files.borrow_mut();
             if let Some(file) = files_mut.last_mut() {
                 // Check if this is for the current file
@@ -366,27 +1053,8 @@ fn parse_diff(diff: &Diff, max_patch_size: usize) -> Result<DiffResult> {
                     .unwrap_or_default();
 
                 if file.path == current_path {
-                    match line.origin() {
-                        '+' => file.additions += 1,
-                        '-' => file.deletions += 1,
-                        _ => {}
-                    }
-
-                    // Build patch
-                    if let Some(ref mut patch) = file.patch {
-                        let origin = line.origin();
-                        if origin == '+' || origin == '-' || origin == ' ' {
-                            patch.push(origin);
-                        }
-                        if let Ok(content) = std::str::from_utf8(line.content()) {
-                            patch.push_str(content);
-                        }
-
-                        // Check if patch is too large
-                        if patch.len() > max_patch_size {
-                            file.is_large = Some(true);
-                            file.patch = Some(String::new());
-                        }
+                    if let Some((builder, anchors)) = builders.borrow_mut().last_mut() {
+                        record_line(file, builder, anchors, hunk.as_ref(), &line, max_patch_size);
                     }
                 }
             }
@@ -394,24 +1062,133 @@ fn parse_diff(diff: &Diff, max_patch_size: usize) -> Result<DiffResult> {
         }),
     )?;
 
-    let files = files.into_inner();
+    let mut files = files.into_inner();
+    for (file, (builder, anchors)) in files.iter_mut().zip(builders.into_inner()) {
+        file.minimap = Some(builder.finish());
+        file.hunks = anchors.finish(&file.path);
+    }
+    Ok(files)
+}
 
-    // Calculate totals
-    let mut total_additions = 0;
-    let mut total_deletions = 0;
-    for file in &files {
-        total_additions += file.additions;
-        total_deletions += file.deletions;
+/// All files of `diff`, loading each separately so a file that can't be read
+/// only marks that file
+fn collect_files_separately(diff: &Diff, max_patch_size: usize) -> Vec<FileDiffInfo> {
+    let load = |idx: usize, file: &mut FileDiffInfo| -> std::result::Result<(), git2::Error> {
+        let Some(patch) = Patch::from_diff(diff, idx)? else {
+            return Ok(());
+        };
+        let mut builder = minimap::MinimapBuilder::default();
+        let mut anchors = navigation::AnchorBuilder::default();
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_idx)?;
+            for line_idx in 0..line_count {
+                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                record_line(
+                    file,
+                    &mut builder,
+                    &mut anchors,
+                    Some(&hunk),
+                    &line,
+                    max_patch_size,
+                );
+            }
+        }
+        file.minimap = Some(builder.finish());
+        file.hunks = anchors.finish(&file.path);
+        Ok(())
+    };
+
+    diff.deltas()
+        .enumerate()
+        .map(|(idx, delta)| {
+            let mut file = file_entry(&delta);
+            if let Err(e) = load(idx, &mut file) {
+                file.additions = 0;
+                file.deletions = 0;
+                file.patch = None;
+                file.error = Some(e.message().to_string());
+            }
+            file
+        })
+        .collect()
+}
+
+/// File info for a delta, before any of its lines are seen
+fn file_entry(delta: &git2::DiffDelta) -> FileDiffInfo {
+    let path = delta
+        .new_file()
+        .path()
+        .or_else(|| delta.old_file().path())
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let old_path = if delta.status() == Delta::Renamed {
+        delta
+            .old_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    let status = match delta.status() {
+        Delta::Added | Delta::Untracked => FileStatus::Added,
+        Delta::Deleted => FileStatus::Deleted,
+        Delta::Renamed => FileStatus::Renamed,
+        _ => FileStatus::Modified,
+    };
+
+    FileDiffInfo {
+        id: navigation::file_id(&path),
+        path,
+        old_path,
+        status,
+        additions: 0,
+        deletions: 0,
+        old_content: None,
+        new_content: None,
+        patch: Some(String::new()),
+        is_large: Some(false),
+        risk: None,
+        risk_status: None,
+        minimap: None,
+        case_rename: None,
+        error: None,
+        hunks: Vec::new(),
+        muted: None,
     }
+}
 
-    let num_files = files.len();
+/// Count one diff line and add it to the file's patch, minimap and anchors
+fn record_line(
+    file: &mut FileDiffInfo,
+    builder: &mut minimap::MinimapBuilder,
+    anchors: &mut navigation::AnchorBuilder,
+    hunk: Option<&git2::DiffHunk>,
+    line: &DiffLine,
+    max_patch_size: usize,
+) {
+    match line.origin() {
+        '+' => file.additions += 1,
+        '-' => file.deletions += 1,
+        _ => {}
+    }
 
-    Ok(DiffResult {
-        files,
-        stats: DiffStats {
-            additions: total_additions,
-            deletions: total_deletions,
-            files: num_files,
-        },
-    })
+    // The minimap and hunk anchors survive even when a large patch is dropped
+    builder.push_line(hunk, line);
+    anchors.push_line(hunk, line);
+
+    // Build patch, unless it was already dropped as too large
+    if file.is_large == Some(true) {
+        return;
+    }
+    if let Some(ref mut patch) = file.patch {
+        push_patch_line(patch, line);
+
+        // Check if patch is too large
+        if patch.len() > max_patch_size {
+            file.is_large = Some(true);
+            file.patch = Some(String::new());
+        }
+    }
 }
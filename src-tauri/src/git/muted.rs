@@ -0,0 +1,176 @@
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::types::FileDiffInfo;
+use super::Result;
+
+/// Stored inside the git dir so it is per-repository and never committed
+const MUTED_FILE: &str = "differ-muted.json";
+
+/// Mutes untouched for this long belong to a finished review
+const MUTE_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Comparisons kept at most; the least recently muted are dropped first
+const MAX_COMPARISONS: usize = 100;
+
+/// Files muted for one comparison
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MutedComparison {
+    /// Commit the comparison was based on when the files were muted: HEAD
+    /// for the working tree, the base of a range, or the commit itself.
+    /// Once it moves the review is a new one and the mutes no longer apply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Unix time of the last mute or unmute
+    pub muted_at: i64,
+    pub paths: BTreeSet<String>,
+}
+
+/// Comparison key -> muted paths
+pub type MutedStore = BTreeMap<String, MutedComparison>;
+
+fn store_path(repo: &Repository) -> PathBuf {
+    repo.path().join(MUTED_FILE)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Key for a comparison: `working`, `staged`, `<base>..<head>`, or a commit
+/// revision, matching the `target` accepted by `get_file_hunks`. Local
+/// changes diffed against a ref use `<base>..working` or `<base>..staged`
+fn comparison_key(comparison: &str) -> String {
+    if comparison.is_empty() {
+        "working".to_string()
+    } else {
+        comparison.to_string()
+    }
+}
+
+/// Commit the comparison currently starts from, see [`MutedComparison::base`]
+fn comparison_base(repo: &Repository, key: &str) -> Option<String> {
+    let rev = match key {
+        "working" | "staged" | "unstaged" => "HEAD",
+        _ => key.split_once("..").map_or(key, |(base, _)| base),
+    };
+    repo.revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .map(|commit| commit.id().to_string())
+        .ok()
+}
+
+/// Drop expired comparisons and keep only the most recent ones
+fn prune(store: &mut MutedStore, now: i64) {
+    store.retain(|_, entry| now - entry.muted_at < MUTE_TTL_SECS && !entry.paths.is_empty());
+    if store.len() > MAX_COMPARISONS {
+        let mut ages: Vec<i64> = store.values().map(|e| e.muted_at).collect();
+        ages.sort_unstable_by(|a, b| b.cmp(a));
+        let cutoff = ages[MAX_COMPARISONS - 1];
+        store.retain(|_, entry| entry.muted_at >= cutoff);
+    }
+}
+
+fn load(repo: &Repository) -> Result<MutedStore> {
+    let mut store = match std::fs::read(store_path(repo)) {
+        // A corrupt store only loses mutes, so start over rather than fail
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => MutedStore::new(),
+        Err(e) => return Err(e.into()),
+    };
+    prune(&mut store, now());
+    Ok(store)
+}
+
+fn save(repo: &Repository, store: &MutedStore) -> Result<()> {
+    let json = serde_json::to_vec_pretty(store).map_err(std::io::Error::from)?;
+    std::fs::write(store_path(repo), json)?;
+    Ok(())
+}
+
+/// Paths muted for a comparison that is still on the same base
+fn current_paths(repo: &Repository, store: &MutedStore, key: &str) -> BTreeSet<String> {
+    match store.get(key) {
+        Some(entry) if entry.base == comparison_base(repo, key) => entry.paths.clone(),
+        _ => BTreeSet::new(),
+    }
+}
+
+/// Files hidden for one comparison, sorted by path
+pub fn list_muted(repo: &Repository, comparison: &str) -> Result<Vec<String>> {
+    let store = load(repo)?;
+    let key = comparison_key(comparison);
+    Ok(current_paths(repo, &store, &key).into_iter().collect())
+}
+
+/// Hide a file for this comparison only; other comparisons still show it,
+/// and so does this one once its base commit moves
+pub fn mute_file(repo: &Repository, comparison: &str, path: &str) -> Result<Vec<String>> {
+    let mut store = load(repo)?;
+    let key = comparison_key(comparison);
+    let mut paths = current_paths(repo, &store, &key);
+    paths.insert(path.to_string());
+    let base = comparison_base(repo, &key);
+    store.insert(
+        key,
+        MutedComparison {
+            base,
+            muted_at: now(),
+            paths,
+        },
+    );
+    prune(&mut store, now());
+    save(repo, &store)?;
+    list_muted(repo, comparison)
+}
+
+pub fn unmute_file(repo: &Repository, comparison: &str, path: &str) -> Result<Vec<String>> {
+    let mut store = load(repo)?;
+    let key = comparison_key(comparison);
+    let mut paths = current_paths(repo, &store, &key);
+    paths.remove(path);
+    if paths.is_empty() {
+        store.remove(&key);
+    } else {
+        let base = comparison_base(repo, &key);
+        store.insert(
+            key,
+            MutedComparison {
+                base,
+                muted_at: now(),
+                paths,
+            },
+        );
+    }
+    save(repo, &store)?;
+    list_muted(repo, comparison)
+}
+
+/// Mark the files muted for `comparison` so the UI can collapse them
+pub fn apply_muted(repo: &Repository, comparison: &str, files: &mut [FileDiffInfo]) -> Result<()> {
+    let store = load(repo)?;
+    let muted = current_paths(repo, &store, &comparison_key(comparison));
+    for file in files {
+        file.muted = muted.contains(&file.path).then_some(true);
+    }
+    Ok(())
+}
+
+/// Every muted file, keyed by comparison, for exporting settings
+pub fn all_muted(repo: &Repository) -> Result<MutedStore> {
+    load(repo)
+}
+
+/// Replace all mutes at once, e.g. when importing settings
+pub fn replace_muted(repo: &Repository, store: &MutedStore) -> Result<()> {
+    let mut store = store.clone();
+    prune(&mut store, now());
+    save(repo, &store)
+}
@@ -0,0 +1,277 @@
+use git2::Repository;
+
+use super::hunks::file_patch;
+use super::types::{DiffNarrative, LineRange, NarrativeEntry, NarrativeKind};
+use super::Result;
+
+/// Keywords that introduce a named declaration, with the word used to describe it
+const DECLARATION_KEYWORDS: &[(&str, &str)] = &[
+    ("fn", "function"),
+    ("function", "function"),
+    ("def", "function"),
+    ("func", "function"),
+    ("class", "class"),
+    ("struct", "struct"),
+    ("enum", "enum"),
+    ("trait", "trait"),
+    ("interface", "interface"),
+    ("type", "type"),
+    ("mod", "module"),
+];
+
+/// One run of consecutive removed and/or added lines within a hunk
+#[derive(Default)]
+struct ChangeBlock {
+    removed: Vec<(u32, String)>,
+    added: Vec<(u32, String)>,
+    /// Last old-file line before the block, for describing pure insertions
+    after_old_line: u32,
+}
+
+/// Describe a file's changes in plain language for screen readers.
+///
+/// The file is diffed against `target` as in `hunks::file_patch`. Each run of
+/// changed lines becomes one entry, e.g. "Lines 10–14 removed" or "Function
+/// foo renamed to bar on line 3". Output is fully deterministic.
+pub fn get_diff_narrative(
+    repo: &Repository,
+    file_path: &str,
+    target: &str,
+) -> Result<DiffNarrative> {
+    let Some(patch) = file_patch(repo, file_path, target)? else {
+        return Ok(DiffNarrative {
+            path: file_path.to_string(),
+            summary: format!("No text changes in {}.", file_path),
+            entries: Vec::new(),
+        });
+    };
+
+    let mut entries = Vec::new();
+    for hunk_idx in 0..patch.num_hunks() {
+        let (_, line_count) = patch.hunk(hunk_idx)?;
+        let mut block = ChangeBlock::default();
+
+        for line_idx in 0..line_count {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            let content = String::from_utf8_lossy(line.content())
+                .trim_end()
+                .to_string();
+            match line.origin() {
+                '-' => block
+                    .removed
+                    .push((line.old_lineno().unwrap_or(0), content)),
+                '+' => block.added.push((line.new_lineno().unwrap_or(0), content)),
+                ' ' => {
+                    entries.extend(describe_block(hunk_idx, &block));
+                    block = ChangeBlock {
+                        after_old_line: line.old_lineno().unwrap_or(0),
+                        ..Default::default()
+                    };
+                }
+                _ => {}
+            }
+        }
+        entries.extend(describe_block(hunk_idx, &block));
+    }
+
+    let added: usize = entries.iter().map(|e| e.lines_added).sum();
+    let removed: usize = entries.iter().map(|e| e.lines_removed).sum();
+    let summary = format!(
+        "{} in {}: {} added, {} removed.",
+        plural(entries.len(), "change"),
+        file_path,
+        plural(added, "line"),
+        plural(removed, "line"),
+    );
+
+    Ok(DiffNarrative {
+        path: file_path.to_string(),
+        summary,
+        entries,
+    })
+}
+
+fn describe_block(hunk_index: usize, block: &ChangeBlock) -> Option<NarrativeEntry> {
+    let old_lines = line_range(&block.removed);
+    let new_lines = line_range(&block.added);
+
+    let (kind, description) = match (&old_lines, &new_lines) {
+        (None, None) => return None,
+        (Some(old), None) => {
+            let mut text = format!("{} removed", capitalize(&describe_range(old)));
+            if let Some(decls) = declarations_sentence(&block.removed, "Removes") {
+                text.push_str(". ");
+                text.push_str(&decls);
+            }
+            (NarrativeKind::Removed, text)
+        }
+        (None, Some(new)) => {
+            let position = if block.after_old_line == 0 {
+                "at the start of the file".to_string()
+            } else {
+                format!("after original line {}", block.after_old_line)
+            };
+            let mut text = format!(
+                "{} added {} (now {})",
+                capitalize(&plural(block.added.len(), "line")),
+                position,
+                describe_range(new)
+            );
+            if let Some(decls) = declarations_sentence(&block.added, "Adds") {
+                text.push_str(". ");
+                text.push_str(&decls);
+            }
+            (NarrativeKind::Added, text)
+        }
+        (Some(old), Some(new)) => {
+            if let Some((from, to)) = renamed_identifier(&block.removed, &block.added) {
+                let subject = match declaration_kind_of(&block.removed, &from) {
+                    Some(kind) => format!("{} {}", capitalize(kind), from),
+                    None => from.clone(),
+                };
+                (
+                    NarrativeKind::Renamed,
+                    format!("{} renamed to {} on {}", subject, to, describe_range(new)),
+                )
+            } else if same_ignoring_whitespace(&block.removed, &block.added) {
+                (
+                    NarrativeKind::Whitespace,
+                    format!("Whitespace changed on {}", describe_range(new)),
+                )
+            } else {
+                (
+                    NarrativeKind::Modified,
+                    format!(
+                        "{} replaced with {} (now {})",
+                        capitalize(&describe_range(old)),
+                        plural(block.added.len(), "line"),
+                        describe_range(new)
+                    ),
+                )
+            }
+        }
+    };
+
+    Some(NarrativeEntry {
+        hunk_index,
+        kind,
+        old_lines,
+        new_lines,
+        lines_added: block.added.len(),
+        lines_removed: block.removed.len(),
+        description,
+    })
+}
+
+fn line_range(lines: &[(u32, String)]) -> Option<LineRange> {
+    let start = lines.first()?.0;
+    let end = lines.last()?.0;
+    Some(LineRange { start, end })
+}
+
+fn describe_range(range: &LineRange) -> String {
+    if range.start == range.end {
+        format!("line {}", range.start)
+    } else {
+        format!("lines {}–{}", range.start, range.end)
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn tokens(line: &str) -> Vec<&str> {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Named declarations on a line, e.g. `pub fn foo(` gives `("function", "foo")`
+fn declarations(line: &str) -> Vec<(&'static str, &str)> {
+    let tokens = tokens(line);
+    tokens
+        .windows(2)
+        .filter_map(|pair| {
+            DECLARATION_KEYWORDS
+                .iter()
+                .find(|(keyword, _)| *keyword == pair[0])
+                .map(|(_, kind)| (*kind, pair[1]))
+        })
+        .collect()
+}
+
+fn declarations_sentence(lines: &[(u32, String)], verb: &str) -> Option<String> {
+    let names: Vec<String> = lines
+        .iter()
+        .flat_map(|(_, content)| declarations(content))
+        .map(|(kind, name)| format!("{} {}", kind, name))
+        .collect();
+    if names.is_empty() {
+        return None;
+    }
+    Some(format!("{} {}", verb, names.join(", ")))
+}
+
+fn declaration_kind_of(lines: &[(u32, String)], name: &str) -> Option<&'static str> {
+    lines
+        .iter()
+        .flat_map(|(_, content)| declarations(content))
+        .find(|(_, declared)| *declared == name)
+        .map(|(kind, _)| kind)
+}
+
+/// When every removed/added line pair differs only by one identifier being
+/// swapped for another, return that `(old, new)` identifier
+fn renamed_identifier(
+    removed: &[(u32, String)],
+    added: &[(u32, String)],
+) -> Option<(String, String)> {
+    if removed.len() != added.len() {
+        return None;
+    }
+
+    let mut rename: Option<(&str, &str)> = None;
+    for ((_, old), (_, new)) in removed.iter().zip(added) {
+        let old_tokens = tokens(old);
+        let new_tokens = tokens(new);
+        if old_tokens.len() != new_tokens.len() {
+            return None;
+        }
+        for (from, to) in old_tokens.iter().zip(&new_tokens) {
+            if from == to {
+                continue;
+            }
+            match rename {
+                None => rename = Some((from, to)),
+                Some(pair) if pair == (*from, *to) => {}
+                Some(_) => return None,
+            }
+        }
+    }
+
+    rename.map(|(from, to)| (from.to_string(), to.to_string()))
+}
+
+fn same_ignoring_whitespace(removed: &[(u32, String)], added: &[(u32, String)]) -> bool {
+    let squash = |lines: &[(u32, String)]| -> String {
+        lines
+            .iter()
+            .flat_map(|(_, content)| content.chars())
+            .filter(|c| !c.is_whitespace())
+            .collect()
+    };
+    squash(removed) == squash(added)
+}
@@ -0,0 +1,123 @@
+//! Next/previous change navigation and stable anchors.
+//!
+//! Reviewers step through a diff hunk by hunk with `n`/`p`, crossing from
+//! one file into the next. The stops are laid out once when the diff is
+//! parsed, so moving is an index step in the frontend rather than a scan of
+//! every file on each render.
+//!
+//! Files and hunks also carry ids that survive a refresh, so the view can
+//! find its place again after the watcher reloads the diff. A file's id is
+//! derived from its path. A hunk's comes from the path, the section heading
+//! of its header and its lines; the header's line numbers are left out, so
+//! a hunk keeps its id when an edit above it shifts the file.
+
+use git2::{DiffHunk, DiffLine};
+use std::collections::HashMap;
+
+use super::types::{ChangeAnchor, ChangeNavigation, FileDiffInfo, HunkAnchor};
+
+/// Navigation stops for `files`, in their order
+pub(crate) fn change_navigation(files: &[FileDiffInfo]) -> ChangeNavigation {
+    let mut changes = Vec::new();
+    let mut file_starts = Vec::with_capacity(files.len() + 1);
+    for (file, info) in files.iter().enumerate() {
+        file_starts.push(changes.len());
+        changes.extend((0..info.hunks.len()).map(|hunk| ChangeAnchor { file, hunk }));
+    }
+    file_starts.push(changes.len());
+    ChangeNavigation {
+        changes,
+        file_starts,
+    }
+}
+
+/// Stable id of the file at `path`
+pub(crate) fn file_id(path: &str) -> String {
+    short_hash(path.as_bytes())
+}
+
+/// Collects a file's hunk anchors as its diff lines go by, in the same way
+/// as `minimap::MinimapBuilder`
+#[derive(Default)]
+pub struct AnchorBuilder {
+    hunks: Vec<PendingHunk>,
+}
+
+struct PendingHunk {
+    old_start: u32,
+    new_start: u32,
+    /// Function context after the closing `@@` of the header
+    section: Vec<u8>,
+    /// Lines with their `+`/`-`/` ` prefixes
+    content: Vec<u8>,
+}
+
+impl AnchorBuilder {
+    pub fn push_line(&mut self, hunk: Option<&DiffHunk>, line: &DiffLine) {
+        let Some(hunk) = hunk else {
+            return;
+        };
+        let starts = (hunk.old_start(), hunk.new_start());
+        if self.hunks.last().map(|h| (h.old_start, h.new_start)) != Some(starts) {
+            self.hunks.push(PendingHunk {
+                old_start: hunk.old_start(),
+                new_start: hunk.new_start(),
+                section: section(hunk.header()).to_vec(),
+                content: Vec::new(),
+            });
+        }
+
+        let origin = line.origin();
+        if let (Some(current), '+' | '-' | ' ') = (self.hunks.last_mut(), origin) {
+            current.content.push(origin as u8);
+            current.content.extend_from_slice(line.content());
+        }
+    }
+
+    /// Anchors for the file at `path`. Identical hunks get a `-<n>` suffix by
+    /// order of appearance, as in `hunks::hunk_ids`.
+    pub fn finish(self, path: &str) -> Vec<HunkAnchor> {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        self.hunks
+            .into_iter()
+            .map(|hunk| {
+                let mut key =
+                    Vec::with_capacity(path.len() + hunk.section.len() + hunk.content.len() + 2);
+                key.extend_from_slice(path.as_bytes());
+                key.push(0);
+                key.extend_from_slice(&hunk.section);
+                key.push(0);
+                key.extend_from_slice(&hunk.content);
+
+                let hash = short_hash(&key);
+                let occurrence = seen.entry(hash.clone()).or_default();
+                let id = match *occurrence {
+                    0 => hash,
+                    n => format!("{}-{}", hash, n),
+                };
+                *occurrence += 1;
+                HunkAnchor {
+                    id,
+                    old_start: hunk.old_start,
+                    new_start: hunk.new_start,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The part of a `@@ -a,b +c,d @@ section` header after the ranges
+fn section(header: &[u8]) -> &[u8] {
+    header
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| *pair == b"@@")
+        .nth(1)
+        .map_or(&[][..], |(at, _)| header[at + 2..].trim_ascii())
+}
+
+fn short_hash(bytes: &[u8]) -> String {
+    git2::Oid::hash_object(git2::ObjectType::Blob, bytes)
+        .map(|oid| oid.to_string()[..12].to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,213 @@
+use git2::Repository;
+use serde_json::Value;
+
+use super::types::{CellStatus, NotebookCellDiff, NotebookDiff};
+use super::{diff_text, read_file_at, GitError, Result};
+
+/// A single cell extracted from an `.ipynb` document
+struct NotebookCell {
+    cell_type: String,
+    source: String,
+    outputs: Value,
+    execution_count: Value,
+}
+
+impl NotebookCell {
+    fn same_content(&self, other: &NotebookCell) -> bool {
+        self.cell_type == other.cell_type && self.source == other.source
+    }
+
+    fn outputs_differ(&self, other: &NotebookCell) -> bool {
+        self.outputs != other.outputs || self.execution_count != other.execution_count
+    }
+}
+
+/// Diff a notebook cell-by-cell between two refs.
+///
+/// `base`/`head` follow `get_file_contents`: `None` for `head` reads the working
+/// directory. A side where the file does not exist is treated as an empty notebook.
+/// With `strip_outputs`, output and execution-count changes are ignored.
+pub fn get_notebook_diff(
+    repo: &Repository,
+    file_path: &str,
+    base: Option<&str>,
+    head: Option<&str>,
+    strip_outputs: bool,
+) -> Result<NotebookDiff> {
+    let old_cells = read_cells(repo, file_path, base)?;
+    let new_cells = read_cells(repo, file_path, head)?;
+
+    let pairs = align_cells(&old_cells, &new_cells);
+
+    let mut cells = Vec::with_capacity(pairs.len());
+    let mut additions = 0;
+    let mut deletions = 0;
+
+    for (old_index, new_index) in pairs {
+        let old = old_index.map(|i| &old_cells[i]);
+        let new = new_index.map(|i| &new_cells[i]);
+
+        let outputs_changed = match (old, new) {
+            (Some(o), Some(n)) => !strip_outputs && o.outputs_differ(n),
+            _ => false,
+        };
+
+        let status = match (old, new) {
+            (Some(o), Some(n)) if o.source == n.source && o.cell_type == n.cell_type => {
+                if outputs_changed {
+                    CellStatus::Modified
+                } else {
+                    CellStatus::Unchanged
+                }
+            }
+            (Some(_), Some(_)) => CellStatus::Modified,
+            (None, Some(_)) => CellStatus::Added,
+            _ => CellStatus::Deleted,
+        };
+
+        let old_source = old.map(|c| c.source.as_str()).unwrap_or("");
+        let new_source = new.map(|c| c.source.as_str()).unwrap_or("");
+        let (patch, cell_additions, cell_deletions) = if old_source == new_source {
+            (None, 0, 0)
+        } else {
+            let (patch, a, d) = diff_text(old_source, new_source)?;
+            (Some(patch), a, d)
+        };
+
+        additions += cell_additions;
+        deletions += cell_deletions;
+
+        cells.push(NotebookCellDiff {
+            status,
+            cell_type: new.or(old).map(|c| c.cell_type.clone()).unwrap_or_default(),
+            old_index,
+            new_index,
+            old_source: old.map(|c| c.source.clone()),
+            new_source: new.map(|c| c.source.clone()),
+            patch,
+            additions: cell_additions,
+            deletions: cell_deletions,
+            outputs_changed,
+        });
+    }
+
+    Ok(NotebookDiff {
+        path: file_path.to_string(),
+        cells,
+        additions,
+        deletions,
+        outputs_stripped: strip_outputs,
+    })
+}
+
+fn read_cells(
+    repo: &Repository,
+    file_path: &str,
+    git_ref: Option<&str>,
+) -> Result<Vec<NotebookCell>> {
+    // Missing on this side (added or deleted notebook)
+    let Some(content) = read_file_at(repo, file_path, git_ref)? else {
+        return Ok(Vec::new());
+    };
+
+    parse_cells(&content).map_err(|e| GitError::InvalidNotebook(format!("{}: {}", file_path, e)))
+}
+
+fn parse_cells(content: &str) -> std::result::Result<Vec<NotebookCell>, String> {
+    let doc: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let cells = doc
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "missing \"cells\" array".to_string())?;
+
+    Ok(cells
+        .iter()
+        .map(|cell| NotebookCell {
+            cell_type: cell
+                .get("cell_type")
+                .and_then(Value::as_str)
+                .unwrap_or("code")
+                .to_string(),
+            source: join_source(cell.get("source")),
+            outputs: cell.get("outputs").cloned().unwrap_or(Value::Null),
+            execution_count: cell.get("execution_count").cloned().unwrap_or(Value::Null),
+        })
+        .collect())
+}
+
+/// Notebook sources are either a single string or a list of lines
+fn join_source(source: Option<&Value>) -> String {
+    match source {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Pair old and new cells: identical cells are matched via LCS, and the
+/// leftovers between matches are paired positionally when their types agree.
+fn align_cells(old: &[NotebookCell], new: &[NotebookCell]) -> Vec<(Option<usize>, Option<usize>)> {
+    let n = old.len();
+    let m = new.len();
+
+    // lcs[i][j] = length of LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i].same_content(&new[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut anchors = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i].same_content(&new[j]) {
+            anchors.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    anchors.push((n, m));
+
+    let mut pairs = Vec::new();
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for (anchor_old, anchor_new) in anchors {
+        let mut old_gap = old_pos..anchor_old;
+        let mut new_gap = new_pos..anchor_new;
+
+        loop {
+            match (old_gap.clone().next(), new_gap.clone().next()) {
+                (Some(o), Some(nw)) if old[o].cell_type == new[nw].cell_type => {
+                    pairs.push((Some(o), Some(nw)));
+                    old_gap.next();
+                    new_gap.next();
+                }
+                (Some(o), _) => {
+                    pairs.push((Some(o), None));
+                    old_gap.next();
+                }
+                (None, Some(nw)) => {
+                    pairs.push((None, Some(nw)));
+                    new_gap.next();
+                }
+                (None, None) => break,
+            }
+        }
+
+        if anchor_old < n && anchor_new < m {
+            pairs.push((Some(anchor_old), Some(anchor_new)));
+        }
+        old_pos = anchor_old + 1;
+        new_pos = anchor_new + 1;
+    }
+
+    pairs
+}
@@ -0,0 +1,146 @@
+//! Size-bounded, least-recently-used cache of blob contents and tree path
+//! lookups.
+//!
+//! Side-by-side views, context expansion and line history read the same
+//! blobs over and over while the user scrolls through a comparison. Objects
+//! are immutable and named by their content, so entries never go stale: a
+//! path is cached per tree id, and a new commit simply has a different tree.
+
+use git2::{Oid, Repository};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, LazyLock};
+
+/// Blob bytes kept by the shared cache
+const CAPACITY_BYTES: usize = 64 * 1024 * 1024;
+/// Path lookups kept before the lookup table is dropped and rebuilt
+const MAX_PATHS: usize = 65_536;
+
+static GLOBAL: LazyLock<ObjectCache> = LazyLock::new(|| ObjectCache::new(CAPACITY_BYTES));
+
+/// The cache shared by every command. Cleared when another repository is
+/// opened, since nothing in it is useful there.
+pub fn global() -> &'static ObjectCache {
+    &GLOBAL
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ObjectCacheStats {
+    pub blobs: usize,
+    pub bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+pub struct ObjectCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Content and the tick it was last used at
+    blobs: HashMap<Oid, (Arc<[u8]>, u64)>,
+    /// Blob ids by last use, oldest first
+    recency: BTreeMap<u64, Oid>,
+    /// Blob id of a path within a tree
+    paths: HashMap<(Oid, String), Oid>,
+    bytes: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Inner {
+    fn touch(&mut self, oid: Oid) -> Option<Arc<[u8]>> {
+        self.clock += 1;
+        let clock = self.clock;
+        let (content, last_used) = self.blobs.get_mut(&oid)?;
+        self.recency.remove(last_used);
+        *last_used = clock;
+        self.recency.insert(clock, oid);
+        Some(content.clone())
+    }
+}
+
+impl ObjectCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    /// Contents of blob `oid`, read from the object database on a miss.
+    /// Blobs bigger than an eighth of the capacity are read but not kept, so
+    /// one huge file can't flush everything else.
+    pub fn blob(&self, repo: &Repository, oid: Oid) -> Result<Arc<[u8]>, git2::Error> {
+        {
+            let mut inner = self.inner.lock();
+            if let Some(content) = inner.touch(oid) {
+                inner.hits += 1;
+                return Ok(content);
+            }
+            inner.misses += 1;
+        }
+
+        // Read without holding the lock; a racing reader may load it too
+        let content: Arc<[u8]> = Arc::from(repo.find_blob(oid)?.content());
+        if content.len() > self.capacity / 8 {
+            return Ok(content);
+        }
+        let mut inner = self.inner.lock();
+        if inner.touch(oid).is_none() {
+            inner.clock += 1;
+            let clock = inner.clock;
+            inner.bytes += content.len();
+            inner.blobs.insert(oid, (content.clone(), clock));
+            inner.recency.insert(clock, oid);
+            while inner.bytes > self.capacity {
+                let Some((_, oldest)) = inner.recency.pop_first() else {
+                    break;
+                };
+                if let Some((evicted, _)) = inner.blobs.remove(&oldest) {
+                    inner.bytes -= evicted.len();
+                }
+            }
+        }
+        Ok(content)
+    }
+
+    /// Id of the blob at `path` in tree `tree`, resolved with `lookup` on a
+    /// miss
+    pub fn tree_entry(
+        &self,
+        tree: Oid,
+        path: &str,
+        lookup: impl FnOnce() -> Result<Oid, git2::Error>,
+    ) -> Result<Oid, git2::Error> {
+        let key = (tree, path.to_string());
+        if let Some(oid) = self.inner.lock().paths.get(&key) {
+            return Ok(*oid);
+        }
+
+        let oid = lookup()?;
+        let mut inner = self.inner.lock();
+        if inner.paths.len() >= MAX_PATHS {
+            inner.paths.clear();
+        }
+        inner.paths.insert(key, oid);
+        Ok(oid)
+    }
+
+    pub fn stats(&self) -> ObjectCacheStats {
+        let inner = self.inner.lock();
+        ObjectCacheStats {
+            blobs: inner.blobs.len(),
+            bytes: inner.bytes,
+            hits: inner.hits,
+            misses: inner.misses,
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.inner.lock() = Inner::default();
+    }
+}
@@ -0,0 +1,138 @@
+//! Windows path forms.
+//!
+//! Win32 APIs cap ordinary paths at 260 characters (`MAX_PATH`). The
+//! extended-length ("verbatim") forms `\\?\C:\...` and `\\?\UNC\server\share\...`
+//! lift that limit, but git, editors and users expect the plain forms. Paths
+//! are kept plain everywhere and only turned verbatim right before a call
+//! that needs it. The string functions work on any platform; the `Path`
+//! wrappers only change anything on Windows.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+const VERBATIM: &str = r"\\?\";
+const VERBATIM_UNC: &str = r"\\?\UNC\";
+const DEVICE: &str = r"\\.\";
+
+/// The plain form of a verbatim path: `\\?\C:\repo` becomes `C:\repo` and
+/// `\\?\UNC\server\share` becomes `\\server\share`. Other paths are returned
+/// unchanged.
+pub fn strip_verbatim(path: &str) -> Cow<'_, str> {
+    if let Some(rest) = path.strip_prefix(VERBATIM_UNC) {
+        Cow::Owned(format!(r"\\{}", rest))
+    } else if let Some(rest) = path.strip_prefix(VERBATIM) {
+        // Only drive paths have a plain form; `\\?\Volume{...}` has none
+        if has_drive(rest) {
+            Cow::Borrowed(rest)
+        } else {
+            Cow::Borrowed(path)
+        }
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+/// The verbatim form of an absolute Windows path. Verbatim paths reach the
+/// filesystem as is, so slashes are turned into backslashes and `.`/`..`
+/// resolved first. Relative, drive-relative and already verbatim paths are
+/// returned unchanged.
+pub fn to_verbatim(path: &str) -> String {
+    if path.starts_with(VERBATIM) || path.starts_with(DEVICE) {
+        return path.to_string();
+    }
+    let path = path.replace('/', r"\");
+
+    if let Some(rest) = path.strip_prefix(r"\\") {
+        let mut parts = rest.split('\\').filter(|part| !part.is_empty());
+        let (Some(server), Some(share)) = (parts.next(), parts.next()) else {
+            return path;
+        };
+        let tail = normalize(parts);
+        return join_verbatim(&format!(r"{}{}\{}", VERBATIM_UNC, server, share), &tail);
+    }
+
+    if has_drive(&path) && path[2..].starts_with('\\') {
+        let tail = normalize(path[3..].split('\\'));
+        return join_verbatim(&format!(r"{}{}\", VERBATIM, &path[..2]), &tail);
+    }
+    path
+}
+
+/// Whether `path` is on a network share, written as `\\server\share`,
+/// `//server/share` or `\\?\UNC\server\share`
+pub fn is_unc(path: &str) -> bool {
+    if path.starts_with(VERBATIM_UNC) {
+        return true;
+    }
+    if path.starts_with(VERBATIM) || path.starts_with(DEVICE) {
+        return false;
+    }
+    let mut chars = path.chars();
+    matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some('\\' | '/'), Some('\\' | '/'), Some(c)) if c != '\\' && c != '/'
+    )
+}
+
+/// Join a repository-relative path (always `/`-separated, as git writes them)
+/// onto `base` with the platform's separator
+pub fn join_relative(base: &Path, relative: &str) -> PathBuf {
+    relative
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .fold(base.to_path_buf(), |path, part| path.join(part))
+}
+
+/// `path` as handed to Windows APIs that don't lift `MAX_PATH` themselves,
+/// such as directory watching. Unchanged on other platforms.
+pub fn long_path(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(to_verbatim(&path.to_string_lossy()))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// `path` in the form git, editors and users expect, e.g. after
+/// `canonicalize` returned a verbatim path. Unchanged on other platforms,
+/// where backslashes are ordinary file name characters.
+pub fn plain_path(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(strip_verbatim(&path.to_string_lossy()).into_owned())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Whether `path` starts with a drive letter, as in `C:`
+fn has_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// Resolve `.` and `..` components; `..` never climbs above the root
+fn normalize<'a>(parts: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut normalized = Vec::new();
+    for part in parts {
+        match part {
+            "" | "." => {}
+            ".." => {
+                normalized.pop();
+            }
+            _ => normalized.push(part),
+        }
+    }
+    normalized
+}
+
+fn join_verbatim(root: &str, tail: &[&str]) -> String {
+    let mut path = root.trim_end_matches('\\').to_string();
+    for part in tail {
+        path.push('\\');
+        path.push_str(part);
+    }
+    if tail.is_empty() && root.ends_with('\\') {
+        path.push('\\');
+    }
+    path
+}
@@ -0,0 +1,143 @@
+//! Running the external programs a repository configures (converters,
+//! hooks) without letting one hang the operation that started it.
+
+use parking_lot::Mutex;
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How long output still in the pipes is waited for once the program has
+/// exited past its deadline or been killed
+const DRAIN_GRACE: Duration = Duration::from_millis(100);
+
+/// What a program run by [`run_within`] left behind
+pub(crate) struct Run {
+    /// `None` when it was killed for running past its timeout
+    pub status: Option<ExitStatus>,
+    /// Output up to the program's exit, or up to the timeout when something
+    /// it started keeps the pipes open
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Run `command` to completion with `input` on its stdin, capturing its
+/// output. A program still running after `timeout` is killed and the call
+/// fails with `ErrorKind::TimedOut`.
+pub(crate) fn output_within(
+    command: &mut Command,
+    input: Option<&[u8]>,
+    timeout: Duration,
+) -> io::Result<Output> {
+    let run = run_within(command, input, timeout)?;
+    match run.status {
+        Some(status) => Ok(Output {
+            status,
+            stdout: run.stdout,
+            stderr: run.stderr,
+        }),
+        None => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("timed out after {}s", timeout.as_secs()),
+        )),
+    }
+}
+
+/// Like [`output_within`], but a program killed at the timeout still
+/// returns the output it wrote before then.
+///
+/// Input and output go through their own threads, so a program that writes
+/// a lot before reading (or never reads at all) can't deadlock against us
+/// on a full pipe. Nothing waits on those threads past the timeout, so a
+/// background process that inherits the pipes (`cmd &` in a hook) can't
+/// hold the call up either.
+pub(crate) fn run_within(
+    command: &mut Command,
+    input: Option<&[u8]>,
+    timeout: Duration,
+) -> io::Result<Run> {
+    let deadline = Instant::now() + timeout;
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let (Some(input), Some(mut pipe)) = (input, child.stdin.take()) {
+        let input = input.to_vec();
+        // A program may exit without reading its input; that is not an error
+        thread::spawn(move || {
+            let _ = pipe.write_all(&input);
+        });
+    }
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = wait_until(&mut child, deadline)?;
+    if status.is_none() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    let until = deadline.max(Instant::now() + DRAIN_GRACE);
+    Ok(Run {
+        status,
+        stdout: stdout.collect(until),
+        stderr: stderr.collect(until),
+    })
+}
+
+fn wait_until(child: &mut Child, deadline: Instant) -> io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// A pipe read on its own thread into a buffer that can be taken at any time
+struct Drain {
+    bytes: Arc<Mutex<Vec<u8>>>,
+    closed: Receiver<()>,
+}
+
+impl Drain {
+    /// What has been read, after waiting until `until` for the pipe to close
+    fn collect(self, until: Instant) -> Vec<u8> {
+        let _ = self
+            .closed
+            .recv_timeout(until.saturating_duration_since(Instant::now()));
+        std::mem::take(&mut *self.bytes.lock())
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> Drain {
+    let bytes = Arc::new(Mutex::new(Vec::new()));
+    let (close, closed) = channel();
+    let sink = bytes.clone();
+    thread::spawn(move || {
+        if let Some(mut pipe) = pipe {
+            let mut chunk = [0; 8192];
+            loop {
+                match pipe.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => sink.lock().extend_from_slice(&chunk[..n]),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                }
+            }
+        }
+        let _ = close.send(());
+    });
+    Drain { bytes, closed }
+}
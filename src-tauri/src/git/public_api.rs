@@ -0,0 +1,355 @@
+use git2::Repository;
+use quote::ToTokens;
+use std::collections::BTreeMap;
+use syn::{Fields, ImplItem, Item, TraitItem, Visibility};
+
+use super::types::{ApiChangeKind, PublicApiChange, PublicApiDiff, SemverImpact};
+use super::{read_file_at, resolve_commit, Result};
+
+/// A public item keyed by its path, e.g. `crate::git::open_repo`
+struct ApiItem {
+    kind: &'static str,
+    signature: String,
+}
+
+/// Walks a crate's module tree at one commit
+struct ApiCollector<'a> {
+    repo: &'a Repository,
+    sha: String,
+    items: BTreeMap<String, ApiItem>,
+    errors: Vec<String>,
+}
+
+/// Diff the public API of the Rust crate at `crate_path` between two refs.
+///
+/// Modules are followed from `src/lib.rs` through `pub mod` declarations, so
+/// items in private modules only count when re-exported with `pub use`.
+/// Attributes and doc comments are ignored; function bodies never matter.
+pub fn diff_public_api(
+    repo: &Repository,
+    base: &str,
+    head: &str,
+    crate_path: &str,
+) -> Result<PublicApiDiff> {
+    let (old_items, mut errors) = collect_public_api(repo, base, crate_path)?;
+    let (new_items, new_errors) = collect_public_api(repo, head, crate_path)?;
+    errors.extend(new_errors);
+
+    let mut changes = Vec::new();
+    for (path, old) in &old_items {
+        match new_items.get(path) {
+            None => changes.push(PublicApiChange {
+                path: path.clone(),
+                kind: old.kind.to_string(),
+                change: ApiChangeKind::Removed,
+                old_signature: Some(old.signature.clone()),
+                new_signature: None,
+            }),
+            Some(new) if new.signature != old.signature => changes.push(PublicApiChange {
+                path: path.clone(),
+                kind: new.kind.to_string(),
+                change: ApiChangeKind::Changed,
+                old_signature: Some(old.signature.clone()),
+                new_signature: Some(new.signature.clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (path, new) in &new_items {
+        if !old_items.contains_key(path) {
+            changes.push(PublicApiChange {
+                path: path.clone(),
+                kind: new.kind.to_string(),
+                change: ApiChangeKind::Added,
+                old_signature: None,
+                new_signature: Some(new.signature.clone()),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let semver_impact = if changes
+        .iter()
+        .any(|c| !matches!(c.change, ApiChangeKind::Added))
+    {
+        SemverImpact::Major
+    } else if !changes.is_empty() {
+        SemverImpact::Minor
+    } else {
+        SemverImpact::None
+    };
+
+    Ok(PublicApiDiff {
+        crate_path: crate_path.to_string(),
+        base: base.to_string(),
+        head: head.to_string(),
+        changes,
+        semver_impact,
+        errors,
+    })
+}
+
+fn collect_public_api(
+    repo: &Repository,
+    rev: &str,
+    crate_path: &str,
+) -> Result<(BTreeMap<String, ApiItem>, Vec<String>)> {
+    let sha = resolve_commit(repo, rev)?.id().to_string();
+    let src_dir = join(crate_path.trim_matches('/'), "src");
+
+    let mut collector = ApiCollector {
+        repo,
+        sha,
+        items: BTreeMap::new(),
+        errors: Vec::new(),
+    };
+    collector.load_module(&join(&src_dir, "lib.rs"), &src_dir, "crate")?;
+
+    Ok((collector.items, collector.errors))
+}
+
+impl ApiCollector<'_> {
+    /// Parse a module file; `child_dir` is where its own `mod foo;` files live
+    fn load_module(&mut self, file: &str, child_dir: &str, module: &str) -> Result<()> {
+        let Some(content) = read_file_at(self.repo, file, Some(&self.sha))? else {
+            return Ok(());
+        };
+        match syn::parse_file(&content) {
+            Ok(parsed) => self.collect(&parsed.items, child_dir, module),
+            Err(e) => {
+                self.errors.push(format!("{}: {}", file, e));
+                Ok(())
+            }
+        }
+    }
+
+    fn collect(&mut self, items: &[Item], child_dir: &str, module: &str) -> Result<()> {
+        for item in items {
+            match item {
+                Item::Fn(f) if is_pub(&f.vis) => {
+                    self.add(module, &f.sig.ident, "fn", tokens(&f.sig));
+                }
+                Item::Struct(s) if is_pub(&s.vis) => {
+                    let mut s = s.clone();
+                    s.attrs.clear();
+                    strip_fields(&mut s.fields, true);
+                    self.add(module, &s.ident, "struct", tokens(&s));
+                }
+                Item::Enum(e) if is_pub(&e.vis) => {
+                    let mut e = e.clone();
+                    e.attrs.clear();
+                    for variant in &mut e.variants {
+                        variant.attrs.clear();
+                        strip_fields(&mut variant.fields, false);
+                    }
+                    self.add(module, &e.ident, "enum", tokens(&e));
+                }
+                Item::Union(u) if is_pub(&u.vis) => {
+                    let mut u = u.clone();
+                    u.attrs.clear();
+                    self.add(module, &u.ident, "union", tokens(&u));
+                }
+                Item::Trait(t) if is_pub(&t.vis) => {
+                    let mut t = t.clone();
+                    t.attrs.clear();
+                    for trait_item in &mut t.items {
+                        match trait_item {
+                            TraitItem::Fn(f) => {
+                                f.attrs.clear();
+                                f.default = None;
+                            }
+                            TraitItem::Const(c) => {
+                                c.attrs.clear();
+                                c.default = None;
+                            }
+                            TraitItem::Type(ty) => ty.attrs.clear(),
+                            _ => {}
+                        }
+                    }
+                    self.add(module, &t.ident, "trait", tokens(&t));
+                }
+                Item::Type(t) if is_pub(&t.vis) => {
+                    let mut t = t.clone();
+                    t.attrs.clear();
+                    self.add(module, &t.ident, "type", tokens(&t));
+                }
+                Item::Const(c) if is_pub(&c.vis) => {
+                    let ty = &c.ty;
+                    self.add(module, &c.ident, "const", tokens(ty));
+                }
+                Item::Static(s) if is_pub(&s.vis) => {
+                    let ty = &s.ty;
+                    let mutability = match s.mutability {
+                        syn::StaticMutability::Mut(_) => "mut ",
+                        _ => "",
+                    };
+                    self.add(
+                        module,
+                        &s.ident,
+                        "static",
+                        format!("{}{}", mutability, tokens(ty)),
+                    );
+                }
+                Item::Use(u) if is_pub(&u.vis) => {
+                    let tree = tokens(&u.tree);
+                    self.items.insert(
+                        format!("{}::{{{}}}", module, tree),
+                        ApiItem {
+                            kind: "use",
+                            signature: tree,
+                        },
+                    );
+                }
+                Item::Macro(m) if m.attrs.iter().any(|a| a.path().is_ident("macro_export")) => {
+                    if let Some(ident) = &m.ident {
+                        // Exported macros always live at the crate root
+                        self.add("crate", ident, "macro", format!("{}!", ident));
+                    }
+                }
+                Item::Mod(m) if is_pub(&m.vis) => {
+                    let name = m.ident.to_string();
+                    let path = format!("{}::{}", module, name);
+                    let dir = join(child_dir, &name);
+                    match &m.content {
+                        Some((_, inline)) => self.collect(inline, &dir, &path)?,
+                        None => {
+                            let flat = join(child_dir, &format!("{}.rs", name));
+                            if read_file_at(self.repo, &flat, Some(&self.sha))?.is_some() {
+                                self.load_module(&flat, &dir, &path)?;
+                            } else {
+                                self.load_module(&join(&dir, "mod.rs"), &dir, &path)?;
+                            }
+                        }
+                    }
+                }
+                Item::Impl(i) => self.collect_impl(i, module),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_impl(&mut self, imp: &syn::ItemImpl, module: &str) {
+        let self_ty = tokens(&imp.self_ty);
+
+        match &imp.trait_ {
+            // Trait impls are public wherever the type is; key them by header alone
+            Some((negative, trait_path, _)) => {
+                let bang = if negative.is_some() { "!" } else { "" };
+                let header = format!(
+                    "impl{} {}{} for {}{}",
+                    tokens(&imp.generics),
+                    bang,
+                    tokens(trait_path),
+                    self_ty,
+                    imp.generics
+                        .where_clause
+                        .as_ref()
+                        .map(|w| format!(" {}", tokens(w)))
+                        .unwrap_or_default()
+                );
+                self.items.insert(
+                    header.clone(),
+                    ApiItem {
+                        kind: "impl",
+                        signature: header,
+                    },
+                );
+            }
+            None => {
+                for impl_item in &imp.items {
+                    match impl_item {
+                        ImplItem::Fn(f) if is_pub(&f.vis) => {
+                            let path = format!("{}::{}::{}", module, self_ty, f.sig.ident);
+                            self.items.insert(
+                                path,
+                                ApiItem {
+                                    kind: "method",
+                                    signature: tokens(&f.sig),
+                                },
+                            );
+                        }
+                        ImplItem::Const(c) if is_pub(&c.vis) => {
+                            let path = format!("{}::{}::{}", module, self_ty, c.ident);
+                            self.items.insert(
+                                path,
+                                ApiItem {
+                                    kind: "const",
+                                    signature: tokens(&c.ty),
+                                },
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn add(&mut self, module: &str, ident: &syn::Ident, kind: &'static str, signature: String) {
+        self.items.insert(
+            format!("{}::{}", module, ident),
+            ApiItem { kind, signature },
+        );
+    }
+}
+
+fn is_pub(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+/// Drop attributes from fields, and private fields from structs (they are not API)
+fn strip_fields(fields: &mut Fields, drop_private: bool) {
+    match fields {
+        Fields::Named(named) => {
+            named.named = std::mem::take(&mut named.named)
+                .into_iter()
+                .filter(|f| !drop_private || is_pub(&f.vis))
+                .map(|mut f| {
+                    f.attrs.clear();
+                    f
+                })
+                .collect();
+        }
+        Fields::Unnamed(unnamed) => {
+            for field in unnamed.unnamed.iter_mut() {
+                field.attrs.clear();
+            }
+        }
+        Fields::Unit => {}
+    }
+}
+
+/// Render tokens compactly, e.g. `Vec<String>` instead of `Vec < String >`
+fn tokens<T: ToTokens>(node: &T) -> String {
+    let raw = node.to_token_stream().to_string();
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ' ' {
+            let mut tail = out.chars().rev();
+            let prev = tail.next();
+            let prev2 = tail.next();
+            let next = chars.peek().copied();
+
+            let word_before = prev.is_some_and(|p| p.is_alphanumeric() || p == '_');
+            let tight_after = matches!(prev, Some('<' | '(' | '[' | '&' | '#'))
+                || (prev == Some(':') && prev2 == Some(':'));
+            let tight_before = matches!(next, Some(',' | ';' | ')' | ']' | '>' | '?' | '.' | ':'))
+                || (word_before && matches!(next, Some('(' | '[' | '<')));
+            if tight_after || tight_before {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
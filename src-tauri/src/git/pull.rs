@@ -0,0 +1,181 @@
+//! Pulling the current branch's upstream.
+//!
+//! A pull fetches the upstream's remote, then brings the remote-tracking
+//! branch in like `git pull`: merging it, replaying local commits on top of
+//! it, or only ever fast-forwarding. Without a strategy given, the one git
+//! would use comes from `branch.<name>.rebase`, `pull.rebase` and `pull.ff`.
+//!
+//! A merge that conflicts is left in progress, the same as `merge_branch`.
+//! A rebase that conflicts is aborted instead, since concluding one takes a
+//! commit per replayed change; the result names the commit it stopped at and
+//! the files that conflicted, and the branch is left as it was.
+
+use git2::{AnnotatedCommit, Config, ErrorCode, RebaseOptions, Repository, RepositoryState};
+
+use super::checkout::changed_files;
+use super::credentials::Credentials;
+use super::merge::{fast_forward, merge_annotated};
+use super::remote;
+use super::types::{HookPolicy, MergeOutcome, PullOutcome, PullResult, PullStrategy};
+use super::{conflicted_paths, GitError, Result};
+
+/// Fetch and integrate the current branch's upstream with `strategy`, or
+/// the configured one when not given.
+///
+/// Refuses while another operation is in progress or tracked files have
+/// local changes, and on a detached HEAD or a branch without an upstream.
+pub fn pull(
+    repo: &Repository,
+    strategy: Option<PullStrategy>,
+    credentials: &Credentials,
+    hooks: &HookPolicy,
+) -> Result<PullResult> {
+    if repo.state() != RepositoryState::Clean {
+        return Err(GitError::MergeInProgress);
+    }
+    let Some(refname) = repo
+        .find_reference("HEAD")?
+        .symbolic_target()
+        .map(String::from)
+    else {
+        return Err(GitError::NoUpstream("HEAD".to_string()));
+    };
+    let branch = refname
+        .strip_prefix("refs/heads/")
+        .unwrap_or(&refname)
+        .to_string();
+    let (remote_name, upstream_name) = match (
+        repo.branch_upstream_remote(&refname),
+        repo.branch_upstream_name(&refname),
+    ) {
+        (Ok(remote), Ok(upstream)) => (
+            remote.as_str().unwrap_or(".").to_string(),
+            upstream.as_str().unwrap_or("").to_string(),
+        ),
+        _ => return Err(GitError::NoUpstream(branch)),
+    };
+    let changed = changed_files(repo)?;
+    if !changed.is_empty() {
+        return Err(GitError::UncommittedChanges(changed));
+    }
+    let strategy = match strategy {
+        Some(strategy) => strategy,
+        None => configured_strategy(&repo.config()?.snapshot()?, &branch),
+    };
+
+    // A local upstream (`remote = .`) has nothing to fetch
+    if remote_name != "." {
+        remote::fetch(repo, &remote_name, credentials)?;
+    }
+    let upstream_ref = repo
+        .find_reference(&upstream_name)
+        .map_err(|_| GitError::NoUpstream(branch.clone()))?;
+    let upstream = upstream_ref
+        .shorthand()
+        .unwrap_or(&upstream_name)
+        .to_string();
+    let theirs = repo.reference_to_annotated_commit(&upstream_ref)?;
+
+    let mut result = PullResult {
+        strategy,
+        outcome: PullOutcome::UpToDate,
+        branch,
+        upstream,
+        sha: theirs.id().to_string(),
+        commit: None,
+        rebased: 0,
+        conflicts: Vec::new(),
+        stopped_at: None,
+    };
+    let (analysis, _) = repo.merge_analysis(&[&theirs])?;
+    if analysis.is_up_to_date() {
+        result.sha = repo.head()?.peel_to_commit()?.id().to_string();
+        return Ok(result);
+    }
+    if analysis.is_fast_forward() || analysis.is_unborn() {
+        fast_forward(repo, theirs.id(), &result.upstream)?;
+        result.outcome = PullOutcome::FastForward;
+        return Ok(result);
+    }
+
+    match strategy {
+        PullStrategy::FfOnly => return Err(GitError::NotFastForward(result.upstream)),
+        PullStrategy::Merge => {
+            let merged = merge_annotated(repo, &theirs, &result.upstream, hooks)?;
+            result.outcome = match merged.outcome {
+                MergeOutcome::Conflicted => PullOutcome::Conflicted,
+                _ => PullOutcome::Merged,
+            };
+            result.sha = merged.sha;
+            result.commit = merged.commit;
+            result.conflicts = merged.conflicts;
+        }
+        PullStrategy::Rebase => rebase(repo, &theirs, &mut result)?,
+    }
+    Ok(result)
+}
+
+/// The strategy `git pull` would pick for `branch`. `branch.<name>.rebase`
+/// overrides `pull.rebase`, and values like `merges` still mean rebasing.
+fn configured_strategy(config: &Config, branch: &str) -> PullStrategy {
+    let rebase = [
+        format!("branch.{}.rebase", branch),
+        "pull.rebase".to_string(),
+    ]
+    .iter()
+    .find_map(|key| {
+        config
+            .get_string(key)
+            .ok()
+            .map(|_| config.get_bool(key).unwrap_or(true))
+    })
+    .unwrap_or(false);
+    if rebase {
+        PullStrategy::Rebase
+    } else if config.get_string("pull.ff").is_ok_and(|ff| ff == "only") {
+        PullStrategy::FfOnly
+    } else {
+        PullStrategy::Merge
+    }
+}
+
+/// Replay HEAD's commits since it diverged onto `upstream`, keeping their
+/// authors. Commits the upstream already has are dropped. Replayed commits
+/// aren't signed.
+fn rebase(repo: &Repository, upstream: &AnnotatedCommit, result: &mut PullResult) -> Result<()> {
+    let committer = repo.signature().map_err(|_| GitError::IdentityMissing)?;
+    let head = repo.reference_to_annotated_commit(&repo.head()?)?;
+    let mut rebase = repo.rebase(
+        Some(&head),
+        Some(upstream),
+        None,
+        Some(&mut RebaseOptions::new()),
+    )?;
+
+    while let Some(operation) = rebase.next() {
+        let id = operation?.id();
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            result.outcome = PullOutcome::Conflicted;
+            result.conflicts = conflicted_paths(&index)?;
+            result.stopped_at = Some(id.to_string());
+            rebase.abort()?;
+            result.sha = repo.head()?.peel_to_commit()?.id().to_string();
+            return Ok(());
+        }
+        match rebase.commit(None, &committer, None) {
+            Ok(_) => result.rebased += 1,
+            // The upstream already made this change
+            Err(e) if e.code() == ErrorCode::Applied => {}
+            Err(e) => {
+                let _ = rebase.abort();
+                return Err(e.into());
+            }
+        }
+    }
+    rebase.finish(Some(&committer))?;
+
+    result.outcome = PullOutcome::Rebased;
+    result.sha = repo.head()?.peel_to_commit()?.id().to_string();
+    Ok(())
+}
@@ -0,0 +1,310 @@
+//! Pushing to a remote.
+//!
+//! A push sends one refspec the way git takes it: `src:dst`, with a leading
+//! `+` to force and an empty source to delete the remote ref. Without one,
+//! the current branch goes to its upstream when that is on the same remote,
+//! or to a branch of the same name.
+//!
+//! Forcing with a lease only overwrites the remote ref while it still points
+//! where our remote-tracking ref says, so commits someone else pushed since
+//! the last fetch aren't lost. The check runs once the remote has said where
+//! its refs are and before anything is sent, which is also when git runs the
+//! `pre-push` hook.
+
+use git2::{Direction, ErrorCode, Oid, PackBuilderStage, PushOptions, Remote, Repository};
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+use super::credentials::Credentials;
+use super::hooks::run_hook;
+use super::remote;
+use super::types::{HookPolicy, HookResult, PushProgress, PushResult, PushStage, RefUpdate};
+use super::{GitError, Result};
+
+/// Least time between progress reports, so a large push doesn't flood the UI
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The ref a push updates and what with
+struct Target {
+    /// Local ref or revision to send; `None` deletes `dst`
+    src: Option<String>,
+    /// Full name of the remote ref
+    dst: String,
+    force: bool,
+}
+
+/// Push `refspec` (the current branch by default) to `remote` (the current
+/// branch's upstream remote, else `origin`), reporting progress as it goes.
+///
+/// A rejected push fails with the remote's reason, and one that would drop
+/// remote commits fails unless forced. The `pre-push` hook runs when the
+/// policy allows it and can stop the push.
+pub fn push(
+    repo: &Repository,
+    remote: Option<&str>,
+    refspec: Option<&str>,
+    force_with_lease: bool,
+    hooks: &HookPolicy,
+    credentials: &Credentials,
+    on_progress: impl FnMut(PushProgress),
+) -> Result<PushResult> {
+    let remote_name = remote::push_remote(repo, remote);
+    let mut remote = repo.find_remote(&remote_name)?;
+    let url = remote.url().unwrap_or("").to_string();
+    let target = target(repo, &remote_name, refspec)?;
+    let lease = force_with_lease.then(|| tracking_oid(repo, &remote, &target.dst));
+    let forced = target.force || force_with_lease;
+
+    let hook: RefCell<Option<HookResult>> = RefCell::new(None);
+    let stale = Cell::new(false);
+    let updates: RefCell<Vec<RefUpdate>> = RefCell::new(Vec::new());
+    let rejected: RefCell<Option<(String, String)>> = RefCell::new(None);
+    let progress = RefCell::new(Progress {
+        report: on_progress,
+        last: None,
+    });
+
+    let mut callbacks = remote::callbacks(repo, credentials)?;
+    callbacks.push_negotiation(|pending| {
+        if let Some(expected) = lease {
+            let moved = pending.iter().any(|update| {
+                update.dst_refname() == Some(target.dst.as_str())
+                    && update.src() != expected.unwrap_or_else(Oid::zero)
+            });
+            if moved {
+                stale.set(true);
+                return Err(git2::Error::from_str("stale lease"));
+            }
+        }
+
+        // `<local ref> <local sha> <remote ref> <remote sha>` per ref
+        let lines: String = pending
+            .iter()
+            .map(|update| {
+                let local = match update.src_refname() {
+                    Some(name) if !name.is_empty() => name,
+                    _ => "(delete)",
+                };
+                format!(
+                    "{} {} {} {}\n",
+                    local,
+                    update.dst(),
+                    update.dst_refname().unwrap_or(""),
+                    update.src()
+                )
+            })
+            .collect();
+        let result = run_hook(repo, hooks, "pre-push", &[&remote_name, &url], Some(&lines))
+            .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+        let failed = result.as_ref().is_some_and(|result| !result.success);
+        *hook.borrow_mut() = result;
+        if failed {
+            return Err(git2::Error::from_str("pre-push hook failed"));
+        }
+
+        updates
+            .borrow_mut()
+            .extend(pending.iter().map(|update| RefUpdate {
+                name: update.dst_refname().unwrap_or("").to_string(),
+                old: non_zero(update.src()),
+                new: non_zero(update.dst()),
+            }));
+        Ok(())
+    });
+    callbacks.push_update_reference(|name, status| {
+        if let Some(reason) = status {
+            rejected
+                .borrow_mut()
+                .get_or_insert_with(|| (name.to_string(), reason.to_string()));
+        }
+        Ok(())
+    });
+    callbacks.pack_progress(|_: PackBuilderStage, current, total| {
+        progress
+            .borrow_mut()
+            .update(PushStage::Packing, current, total, 0)
+    });
+    callbacks.push_transfer_progress(|current, total, bytes| {
+        progress
+            .borrow_mut()
+            .update(PushStage::Sending, current, total, bytes)
+    });
+
+    let spec = format!(
+        "{}{}:{}",
+        if forced { "+" } else { "" },
+        target.src.as_deref().unwrap_or(""),
+        target.dst
+    );
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    let pushed = remote.push(&[spec.as_str()], Some(&mut options));
+    drop(options);
+
+    let hook = hook.into_inner();
+    if let Err(e) = pushed {
+        let name = short_name(&target.dst);
+        if stale.get() {
+            return Err(GitError::StaleLease(name.to_string()));
+        }
+        if let Some(result) = hook.filter(|result| !result.success) {
+            return Err(GitError::HookFailed(Box::new(result)));
+        }
+        if e.code() == ErrorCode::Auth {
+            return Err(GitError::AuthFailed(url));
+        }
+        if e.code() == ErrorCode::NotFastForward {
+            return Err(GitError::NotFastForward(format!(
+                "{}/{}",
+                remote_name, name
+            )));
+        }
+        return Err(e.into());
+    }
+    if let Some((name, reason)) = rejected.into_inner() {
+        return Err(GitError::PushRejected { name, reason });
+    }
+
+    Ok(PushResult {
+        remote: remote_name,
+        refs: updates.into_inner(),
+        forced,
+        hook,
+    })
+}
+
+/// Reports progress at most every `PROGRESS_INTERVAL`, plus whenever a
+/// stage starts or finishes
+struct Progress<F> {
+    report: F,
+    last: Option<(PushStage, Instant)>,
+}
+
+impl<F: FnMut(PushProgress)> Progress<F> {
+    fn update(&mut self, stage: PushStage, current: usize, total: usize, bytes: usize) {
+        let due = match self.last {
+            Some((last_stage, at)) => {
+                last_stage != stage || current == total || at.elapsed() >= PROGRESS_INTERVAL
+            }
+            None => true,
+        };
+        if due {
+            self.last = Some((stage, Instant::now()));
+            (self.report)(PushProgress {
+                stage,
+                current,
+                total,
+                bytes,
+            });
+        }
+    }
+}
+
+/// What `refspec` pushes, or the current branch when not given
+fn target(repo: &Repository, remote_name: &str, refspec: Option<&str>) -> Result<Target> {
+    let Some(refspec) = refspec else {
+        let Some(branch) = repo
+            .find_reference("HEAD")?
+            .symbolic_target()
+            .map(String::from)
+        else {
+            return Err(GitError::BranchNotFound("HEAD".to_string()));
+        };
+        let short = branch.strip_prefix("refs/heads/").unwrap_or(&branch);
+        let upstream = match repo.branch_upstream_remote(&branch) {
+            Ok(remote) if remote.as_str() == Some(remote_name) => repo
+                .config()?
+                .snapshot()?
+                .get_string(&format!("branch.{}.merge", short))
+                .ok(),
+            _ => None,
+        };
+        return Ok(Target {
+            dst: upstream.unwrap_or_else(|| branch.clone()),
+            src: Some(branch),
+            force: false,
+        });
+    };
+
+    let invalid = || GitError::InvalidRefspec(refspec.to_string());
+    let (force, spec) = match refspec.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, refspec),
+    };
+    let (src, dst) = match spec.split_once(':') {
+        Some((src, dst)) => (src, Some(dst).filter(|dst| !dst.is_empty())),
+        None => (spec, None),
+    };
+    if src.is_empty() {
+        let dst = dst.ok_or_else(invalid)?;
+        return Ok(Target {
+            src: None,
+            dst: qualify(dst, "refs/heads/"),
+            force,
+        });
+    }
+
+    // `HEAD` stands for the branch it is on
+    let src = match repo.resolve_reference_from_short_name(src) {
+        Ok(reference) => reference
+            .resolve()
+            .ok()
+            .and_then(|reference| reference.name().map(String::from))
+            .unwrap_or_else(|| src.to_string()),
+        Err(_) => {
+            repo.revparse_single(src)
+                .map_err(|_| GitError::BranchNotFound(src.to_string()))?;
+            src.to_string()
+        }
+    };
+    let dst = match dst {
+        Some(dst) => qualify(dst, namespace(&src)),
+        None if src.starts_with("refs/") => src.clone(),
+        None => return Err(invalid()),
+    };
+    Ok(Target {
+        src: Some(src),
+        dst,
+        force,
+    })
+}
+
+/// Where our remote-tracking ref for the remote's `dst` points, if there
+/// is one
+fn tracking_oid(repo: &Repository, remote: &Remote, dst: &str) -> Option<Oid> {
+    let tracking = remote
+        .refspecs()
+        .filter(|spec| spec.direction() == Direction::Fetch)
+        .find(|spec| spec.src_matches(dst))?
+        .transform(dst)
+        .ok()?;
+    repo.refname_to_id(tracking.as_str()?).ok()
+}
+
+/// `name` as a full ref, taken from `namespace` when it is short
+fn qualify(name: &str, namespace: &str) -> String {
+    if name.starts_with("refs/") {
+        name.to_string()
+    } else {
+        format!("{}{}", namespace, name)
+    }
+}
+
+fn namespace(refname: &str) -> &'static str {
+    if refname.starts_with("refs/tags/") {
+        "refs/tags/"
+    } else {
+        "refs/heads/"
+    }
+}
+
+fn short_name(refname: &str) -> &str {
+    refname
+        .strip_prefix("refs/heads/")
+        .or_else(|| refname.strip_prefix("refs/tags/"))
+        .unwrap_or(refname)
+}
+
+fn non_zero(oid: Oid) -> Option<String> {
+    (!oid.is_zero()).then(|| oid.to_string())
+}
@@ -0,0 +1,78 @@
+use git2::{Oid, Repository};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::git_config::common_dir;
+use super::Result;
+
+/// Every ref under `prefix` (e.g. `refs/heads/`) with the object it points
+/// at, sorted by name.
+///
+/// Reads `packed-refs` and the loose ref files directly instead of building a
+/// `Reference` per ref, which matters on mirrors with tens of thousands of
+/// refs. Loose refs override packed ones, as in git. Symbolic refs such as
+/// `refs/remotes/origin/HEAD` are skipped.
+pub(crate) fn list_refs(repo: &Repository, prefix: &str) -> Result<Vec<(String, Oid)>> {
+    let git_dir = common_dir(repo);
+    // Ref storage this parser doesn't understand; let libgit2 read it
+    if git_dir.join("reftable").is_dir() {
+        return list_refs_slow(repo, prefix);
+    }
+
+    let mut refs = BTreeMap::new();
+    if let Ok(packed) = std::fs::read_to_string(git_dir.join("packed-refs")) {
+        for line in packed.lines() {
+            // Header and peeled-tag lines
+            if line.starts_with('#') || line.starts_with('^') {
+                continue;
+            }
+            let Some((oid, name)) = line.split_once(' ') else {
+                continue;
+            };
+            if let (true, Ok(oid)) = (name.starts_with(prefix), Oid::from_str(oid)) {
+                refs.insert(name.to_string(), oid);
+            }
+        }
+    }
+
+    read_loose_refs(&git_dir, &git_dir.join(prefix), &mut refs)?;
+    Ok(refs.into_iter().collect())
+}
+
+fn read_loose_refs(git_dir: &Path, dir: &Path, refs: &mut BTreeMap<String, Oid>) -> Result<()> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_loose_refs(git_dir, &path, refs)?;
+            continue;
+        }
+        // Lock files left by an interrupted update are not refs
+        if path.extension().is_some_and(|ext| ext == "lock") {
+            continue;
+        }
+        let Ok(name) = path.strip_prefix(git_dir) else {
+            continue;
+        };
+        let name = name.to_string_lossy().replace('\\', "/");
+        let contents = std::fs::read_to_string(&path)?;
+        if let Ok(oid) = Oid::from_str(contents.trim()) {
+            refs.insert(name, oid);
+        }
+    }
+    Ok(())
+}
+
+fn list_refs_slow(repo: &Repository, prefix: &str) -> Result<Vec<(String, Oid)>> {
+    let mut refs = Vec::new();
+    for reference in repo.references_glob(&format!("{}*", prefix))? {
+        let reference = reference?;
+        if let (Some(name), Some(oid)) = (reference.name(), reference.target()) {
+            refs.push((name.to_string(), oid));
+        }
+    }
+    refs.sort();
+    Ok(refs)
+}
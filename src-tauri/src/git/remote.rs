@@ -0,0 +1,50 @@
+//! Talking to remotes.
+//!
+//! Fetches and pushes authenticate the way the git command line would: SSH
+//! remotes with the agent or key files, HTTPS ones through the configured
+//! credential helper; see `credentials`.
+
+use git2::{ErrorCode, FetchOptions, RemoteCallbacks, Repository};
+
+use super::credentials::Credentials;
+use super::{GitError, Result};
+
+/// Fetch `remote` using its configured refspecs, updating its
+/// remote-tracking branches
+pub(crate) fn fetch(repo: &Repository, remote: &str, credentials: &Credentials) -> Result<()> {
+    let mut remote = repo.find_remote(remote)?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks(repo, credentials)?);
+    match remote.fetch::<&str>(&[], Some(&mut options), None) {
+        Err(e) if e.code() == ErrorCode::Auth => {
+            Err(GitError::AuthFailed(remote.url().unwrap_or("").to_string()))
+        }
+        fetched => Ok(fetched?),
+    }
+}
+
+/// Callbacks answering credential requests with `credentials`
+pub(crate) fn callbacks<'a>(
+    repo: &Repository,
+    credentials: &'a Credentials,
+) -> Result<RemoteCallbacks<'a>> {
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(credentials.callback(repo)?);
+    Ok(callbacks)
+}
+
+/// `remote`, else the current branch's upstream remote, else `origin`
+pub fn push_remote(repo: &Repository, remote: Option<&str>) -> String {
+    match remote {
+        Some(name) => name.to_string(),
+        None => upstream_remote(repo).unwrap_or_else(|| "origin".to_string()),
+    }
+}
+
+/// The current branch's upstream remote; `.` for a local upstream
+pub fn upstream_remote(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    let branch = head.symbolic_target()?;
+    let remote = repo.branch_upstream_remote(branch).ok()?;
+    remote.as_str().map(String::from)
+}
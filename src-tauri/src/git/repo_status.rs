@@ -0,0 +1,251 @@
+use git2::{BranchType, Repository, RepositoryOpenFlags, Status, StatusOptions};
+use std::path::{Path, PathBuf};
+
+use super::paths::plain_path;
+use super::types::{DefaultBranchSource, RepoInspection, RepoStatus, StatusLine};
+use super::{GitError, Result};
+
+/// Repo-local config shared with the web server; `defaultBranch` overrides
+/// detection and `readOnly` refuses changes to the repository
+const REPO_CONFIG_FILE: &str = ".diffyrc.json";
+
+/// Branch names tried, in order, when nothing better is known
+const DEFAULT_BRANCH_CANDIDATES: &[&str] = &["main", "master", "trunk", "develop"];
+
+/// Summarize HEAD and the repository's default branch
+pub fn get_repo_status(repo: &Repository) -> Result<RepoStatus> {
+    let is_detached = repo.head_detached().unwrap_or(false);
+    let (current_branch, head_commit) = match repo.head() {
+        Ok(head) => {
+            let branch = if is_detached {
+                None
+            } else {
+                head.shorthand().map(String::from)
+            };
+            let commit = head
+                .peel_to_commit()
+                .ok()
+                .map(|c| c.id().to_string()[..7].to_string());
+            (branch, commit)
+        }
+        // Unborn branch in a fresh repository
+        Err(_) => (None, None),
+    };
+
+    let (default_branch, default_branch_source) = match detect_default_branch(repo) {
+        Some((name, source)) => (Some(name), Some(source)),
+        None => (None, None),
+    };
+
+    Ok(RepoStatus {
+        current_branch,
+        head_commit,
+        is_detached,
+        default_branch,
+        default_branch_source,
+    })
+}
+
+/// Status of the repository at `path` for a one-off look, such as scanning a
+/// workspace for repositories.
+///
+/// Unlike opening a repository for review, this keeps no state and starts no
+/// watchers. Only `path` itself is tried, not its parents, so a directory
+/// inside another repository isn't mistaken for it. Bare repositories have no
+/// working tree to count changes in.
+pub fn inspect_repo(path: &str) -> Result<RepoInspection> {
+    let root = plain_path(Path::new(path));
+    let repo = Repository::open_ext(&root, RepositoryOpenFlags::NO_SEARCH, [] as [&str; 0])
+        .map_err(|_| GitError::RepoNotFound(path.to_string()))?;
+    let bare = repo.is_bare();
+    // libgit2 hands the directories back with a trailing separator
+    let root: PathBuf = repo
+        .workdir()
+        .unwrap_or_else(|| repo.path())
+        .components()
+        .collect();
+    Ok(RepoInspection {
+        path: root.to_string_lossy().into_owned(),
+        bare,
+        status: get_repo_status(&repo)?,
+        line: if bare {
+            None
+        } else {
+            Some(get_status_line(&repo)?)
+        },
+    })
+}
+
+/// Branch, change counts and upstream divergence for a status line.
+///
+/// Kept cheap enough to poll every second or so: untracked directories count
+/// once instead of being walked, and submodules are skipped.
+pub fn get_status_line(repo: &Repository) -> Result<StatusLine> {
+    let is_detached = repo.head_detached().unwrap_or(false);
+    let head = repo.head().ok();
+    let branch = head.as_ref().and_then(|head| {
+        if is_detached {
+            head.target().map(|oid| oid.to_string()[..7].to_string())
+        } else {
+            head.shorthand().map(String::from)
+        }
+    });
+
+    let mut status_opts = StatusOptions::new();
+    status_opts
+        .include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .exclude_submodules(true);
+    let (mut staged, mut unstaged, mut untracked, mut conflicted) = (0, 0, 0, 0);
+    for entry in repo.statuses(Some(&mut status_opts))?.iter() {
+        let status = entry.status();
+        if status.is_conflicted() {
+            conflicted += 1;
+            continue;
+        }
+        if status.is_wt_new() {
+            untracked += 1;
+        }
+        if status.intersects(
+            Status::INDEX_NEW
+                | Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_RENAMED
+                | Status::INDEX_TYPECHANGE,
+        ) {
+            staged += 1;
+        }
+        if status.intersects(
+            Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
+        ) {
+            unstaged += 1;
+        }
+    }
+
+    let mut upstream = None;
+    let (mut ahead, mut behind) = (0, 0);
+    if let Some(name) = head
+        .as_ref()
+        .filter(|_| !is_detached)
+        .and_then(|h| h.shorthand())
+    {
+        if let Ok(tracking) = repo
+            .find_branch(name, BranchType::Local)
+            .and_then(|branch| branch.upstream())
+        {
+            if let (Some(local), Some(remote)) = (
+                head.as_ref().and_then(|h| h.target()),
+                tracking.get().target(),
+            ) {
+                (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+            }
+            upstream = tracking.name().ok().flatten().map(String::from);
+        }
+    }
+
+    Ok(StatusLine {
+        branch,
+        is_detached,
+        staged,
+        unstaged,
+        untracked,
+        conflicted,
+        upstream,
+        ahead,
+        behind,
+    })
+}
+
+/// Find the branch that changes are usually compared against.
+///
+/// Order: `defaultBranch` in `.diffyrc.json`, the `origin/HEAD` symref, then
+/// `init.defaultBranch` and common names (`main`, `master`, `trunk`, ...) that
+/// exist locally or on `origin`. Local branch names are preferred over
+/// remote-tracking ones so the result can be used directly as a compare base.
+pub fn detect_default_branch(repo: &Repository) -> Option<(String, DefaultBranchSource)> {
+    if let Some(name) = configured_default_branch(repo) {
+        return Some((name, DefaultBranchSource::Config));
+    }
+
+    if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
+        if let Some(target) = reference.symbolic_target() {
+            if let Some(remote_branch) = target.strip_prefix("refs/remotes/") {
+                let local = remote_branch
+                    .strip_prefix("origin/")
+                    .unwrap_or(remote_branch);
+                let name = if has_branch(repo, local, BranchType::Local) {
+                    local
+                } else {
+                    remote_branch
+                };
+                return Some((name.to_string(), DefaultBranchSource::Remote));
+            }
+        }
+    }
+
+    let init_default = repo
+        .config()
+        .ok()
+        .and_then(|c| c.get_string("init.defaultBranch").ok());
+    let candidates = init_default
+        .iter()
+        .map(String::as_str)
+        .chain(DEFAULT_BRANCH_CANDIDATES.iter().copied());
+
+    for name in candidates {
+        if has_branch(repo, name, BranchType::Local) {
+            return Some((name.to_string(), DefaultBranchSource::Heuristic));
+        }
+        let remote = format!("origin/{}", name);
+        if has_branch(repo, &remote, BranchType::Remote) {
+            return Some((remote, DefaultBranchSource::Heuristic));
+        }
+    }
+
+    None
+}
+
+fn has_branch(repo: &Repository, name: &str, kind: BranchType) -> bool {
+    repo.find_branch(name, kind).is_ok()
+}
+
+fn configured_default_branch(repo: &Repository) -> Option<String> {
+    read_repo_config(repo)?
+        .get("defaultBranch")?
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+/// Whether the repo's `.diffyrc.json` sets `readOnly`
+pub fn is_read_only(repo: &Repository) -> bool {
+    read_repo_config(repo)
+        .and_then(|config| config.get("readOnly")?.as_bool())
+        .unwrap_or(false)
+}
+
+/// The repo's `.diffyrc.json`, or `None` when it is missing or not JSON
+pub fn read_repo_config(repo: &Repository) -> Option<serde_json::Value> {
+    let path = repo.workdir()?.join(REPO_CONFIG_FILE);
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Overwrite the repo's `.diffyrc.json`, or remove it for `None`; bare
+/// repositories have nowhere to put it
+pub fn write_repo_config(repo: &Repository, config: Option<&serde_json::Value>) -> Result<()> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(());
+    };
+    let path = workdir.join(REPO_CONFIG_FILE);
+    let Some(config) = config else {
+        return match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    };
+    let mut json = serde_json::to_string_pretty(config).map_err(std::io::Error::from)?;
+    json.push('\n');
+    std::fs::write(path, json)?;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use super::types::{FileDiffInfo, ReviewComplexity, ReviewSize, ReviewThresholds};
+
+/// Summarize how hard a change set is to review.
+///
+/// The size class comes from total changed lines; a change set is flagged for
+/// splitting when it is extra large or touches more files than `max_files`.
+pub fn review_complexity(
+    files: &[FileDiffInfo],
+    thresholds: &ReviewThresholds,
+) -> ReviewComplexity {
+    let total_changed_lines: usize = files.iter().map(|f| f.additions + f.deletions).sum();
+
+    let large_files: Vec<String> = files
+        .iter()
+        .filter(|f| f.additions + f.deletions > thresholds.large_file_lines)
+        .map(|f| f.path.clone())
+        .collect();
+
+    let size = if total_changed_lines <= thresholds.small_lines {
+        ReviewSize::Small
+    } else if total_changed_lines <= thresholds.medium_lines {
+        ReviewSize::Medium
+    } else if total_changed_lines <= thresholds.large_lines {
+        ReviewSize::Large
+    } else {
+        ReviewSize::XLarge
+    };
+
+    let suggest_split = matches!(size, ReviewSize::XLarge) || files.len() > thresholds.max_files;
+
+    ReviewComplexity {
+        total_changed_lines,
+        files_changed: files.len(),
+        large_files,
+        size,
+        suggest_split,
+    }
+}
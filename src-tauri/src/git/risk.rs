@@ -0,0 +1,186 @@
+use git2::{BlameOptions, Oid, Repository};
+use glob::Pattern;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::types::{FileDiffInfo, FileRisk, FileStatus, RiskLevel, RiskStatus};
+use super::Result;
+
+/// How many commits of history are scanned for churn
+const HISTORY_WINDOW: usize = 500;
+/// Blame is the expensive part; skip ownership beyond this many files
+const MAX_BLAME_FILES: usize = 100;
+
+#[derive(Default)]
+struct PathHistory {
+    commits: usize,
+    with_tests: usize,
+    authors: HashSet<String>,
+}
+
+/// Score each changed file's review risk and store it on `FileDiffInfo::risk`,
+/// marking it `RiskStatus::Scored`.
+///
+/// Signals, all measured as of `head`: how many commits and distinct authors
+/// touched the file recently, how fragmented line ownership is (from blame),
+/// whether the path is listed in `risk_paths`, and how rarely its changes come
+/// with test changes.
+pub fn score_file_risk(
+    repo: &Repository,
+    head: Oid,
+    files: &mut [FileDiffInfo],
+    risk_paths: &[String],
+) -> Result<()> {
+    let wanted: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let history = scan_history(repo, head, &wanted)?;
+    let patterns: Vec<Pattern> = risk_paths
+        .iter()
+        .filter_map(|p| Pattern::new(p).ok())
+        .collect();
+
+    for (i, file) in files.iter_mut().enumerate() {
+        let stats = history.get(&file.path);
+        let recent_commits = stats.map(|s| s.commits).unwrap_or(0);
+        let recent_authors = stats.map(|s| s.authors.len()).unwrap_or(0);
+        let test_correlation = stats
+            .filter(|s| s.commits > 0)
+            .map(|s| s.with_tests as f32 / s.commits as f32);
+
+        let top_owner_share = if i < MAX_BLAME_FILES && !matches!(file.status, FileStatus::Added) {
+            top_owner_share(repo, head, &file.path)
+        } else {
+            None
+        };
+        let bug_prone = patterns.iter().any(|p| p.matches(&file.path));
+
+        let mut score = 0.0;
+        let mut reasons = Vec::new();
+
+        score += recent_authors.min(5) as f32 / 5.0 * 30.0;
+        if recent_authors >= 3 {
+            reasons.push(format!("{} authors changed it recently", recent_authors));
+        }
+        score += recent_commits.min(20) as f32 / 20.0 * 20.0;
+        if recent_commits >= 10 {
+            reasons.push(format!("Changed in {} recent commits", recent_commits));
+        }
+        if let Some(share) = top_owner_share {
+            score += (1.0 - share) * 20.0;
+            if share < 0.5 {
+                reasons.push("No clear owner in blame".to_string());
+            }
+        }
+        if bug_prone {
+            score += 20.0;
+            reasons.push("Path is marked as bug-prone".to_string());
+        }
+        if let Some(ratio) = test_correlation {
+            if !is_test_path(&file.path) {
+                score += (1.0 - ratio) * 10.0;
+                if ratio < 0.2 && recent_commits >= 3 {
+                    reasons.push("Rarely changed together with tests".to_string());
+                }
+            }
+        }
+
+        let score = score.round().min(100.0) as u8;
+        let level = match score {
+            0..=34 => RiskLevel::Low,
+            35..=64 => RiskLevel::Medium,
+            _ => RiskLevel::High,
+        };
+
+        file.risk = Some(FileRisk {
+            score,
+            level,
+            recent_commits,
+            recent_authors,
+            top_owner_share,
+            bug_prone,
+            test_correlation,
+            reasons,
+        });
+        file.risk_status = Some(RiskStatus::Scored);
+    }
+
+    Ok(())
+}
+
+/// Walk recent history once, tallying commits/authors for the wanted paths
+fn scan_history(
+    repo: &Repository,
+    head: Oid,
+    wanted: &HashSet<&str>,
+) -> Result<HashMap<String, PathHistory>> {
+    let mut history: HashMap<String, PathHistory> = HashMap::new();
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head)?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    for oid in revwalk.take(HISTORY_WINDOW) {
+        let commit = repo.find_commit(oid?)?;
+        let parent_tree = if commit.parent_count() > 0 {
+            Some(commit.parent(0)?.tree()?)
+        } else {
+            None
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+
+        let mut touched = Vec::new();
+        let mut touches_tests = false;
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().or_else(|| delta.old_file().path()) else {
+                continue;
+            };
+            let path = path.to_string_lossy().to_string();
+            touches_tests |= is_test_path(&path);
+            if wanted.contains(path.as_str()) {
+                touched.push(path);
+            }
+        }
+
+        let author = commit.author().email().unwrap_or("").to_string();
+        for path in touched {
+            let entry = history.entry(path).or_default();
+            entry.commits += 1;
+            entry.authors.insert(author.clone());
+            if touches_tests {
+                entry.with_tests += 1;
+            }
+        }
+    }
+
+    Ok(history)
+}
+
+/// Share of the file's lines last touched by its most prolific author
+fn top_owner_share(repo: &Repository, head: Oid, path: &str) -> Option<f32> {
+    let mut opts = BlameOptions::new();
+    opts.newest_commit(head);
+    let blame = repo.blame_file(Path::new(path), Some(&mut opts)).ok()?;
+
+    let mut lines_by_author: HashMap<String, usize> = HashMap::new();
+    let mut total = 0;
+    for hunk in blame.iter() {
+        let author = hunk.final_signature().email().unwrap_or("").to_string();
+        *lines_by_author.entry(author).or_default() += hunk.lines_in_hunk();
+        total += hunk.lines_in_hunk();
+    }
+
+    let top = lines_by_author.values().max().copied()?;
+    (total > 0).then(|| top as f32 / total as f32)
+}
+
+fn is_test_path(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or(&lower);
+    lower.contains("test/")
+        || lower.contains("tests/")
+        || lower.contains("__tests__/")
+        || lower.contains("spec/")
+        || name.starts_with("test_")
+        || name.contains("_test.")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+}
@@ -0,0 +1,95 @@
+use glob::Pattern;
+
+use super::types::{DiffWarning, FileDiffInfo, FileStatus, WarningCategory};
+
+/// File name patterns that usually hold credentials or keys.
+/// Patterns containing `/` are matched against the path instead of the file name.
+const DEFAULT_SENSITIVE_PATTERNS: &[&str] = &[
+    ".env",
+    ".env.*",
+    "*.env",
+    "*.pem",
+    "*.key",
+    "*.p12",
+    "*.pfx",
+    "*.keystore",
+    "*.jks",
+    "id_rsa*",
+    "id_dsa*",
+    "id_ecdsa*",
+    "id_ed25519*",
+    ".npmrc",
+    ".pypirc",
+    ".netrc",
+    ".htpasswd",
+    "credentials.json",
+    "kubeconfig",
+    "*.kubeconfig",
+    ".kube/config",
+    ".aws/credentials",
+    ".docker/config.json",
+];
+
+/// Checked-in templates such as `.env.example` are meant to be shared
+const TEMPLATE_SUFFIXES: &[&str] = &[".example", ".sample", ".template", ".dist"];
+
+/// Flag changed files whose names match sensitive patterns.
+///
+/// This looks only at paths, not contents; `extra_patterns` come from config
+/// and extend the built-in list.
+pub fn check_sensitive_files(
+    files: &[FileDiffInfo],
+    extra_patterns: &[String],
+) -> Vec<DiffWarning> {
+    let patterns: Vec<(String, Pattern)> = DEFAULT_SENSITIVE_PATTERNS
+        .iter()
+        .map(|p| p.to_string())
+        .chain(extra_patterns.iter().cloned())
+        .filter_map(|p| Pattern::new(&p).ok().map(|compiled| (p, compiled)))
+        .collect();
+
+    let mut warnings = Vec::new();
+    for file in files {
+        if TEMPLATE_SUFFIXES.iter().any(|s| file.path.ends_with(s)) {
+            continue;
+        }
+
+        let Some(pattern) = patterns
+            .iter()
+            .find(|(raw, pattern)| matches_path(raw, pattern, &file.path))
+            .map(|(raw, _)| raw)
+        else {
+            continue;
+        };
+
+        let action = match file.status {
+            FileStatus::Added => "added",
+            FileStatus::Deleted => "deleted",
+            FileStatus::Renamed => "renamed",
+            FileStatus::Modified => "modified",
+        };
+        warnings.push(DiffWarning {
+            category: WarningCategory::SensitiveFile,
+            path: file.path.clone(),
+            message: format!(
+                "Sensitive file {} {} (matches {})",
+                file.path, action, pattern
+            ),
+        });
+    }
+
+    warnings
+}
+
+fn matches_path(raw: &str, pattern: &Pattern, path: &str) -> bool {
+    if !raw.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return pattern.matches(name);
+    }
+
+    // Anchor path patterns at any directory boundary, e.g. `.kube/config`
+    // matches both `.kube/config` and `home/me/.kube/config`
+    std::iter::once(path)
+        .chain(path.match_indices('/').map(|(i, _)| &path[i + 1..]))
+        .any(|suffix| pattern.matches(suffix))
+}
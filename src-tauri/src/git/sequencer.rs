@@ -0,0 +1,110 @@
+//! Reverting and cherry-picking single commits.
+//!
+//! Both replay one commit's change onto HEAD through the index. A clean
+//! result is committed straight away; conflicts leave the working tree
+//! marked up and the repository in the revert or cherry-pick state, the
+//! same place `git revert` stops, and committing once they are resolved
+//! concludes the operation.
+
+use git2::{CherrypickOptions, Commit, Repository, RepositoryState, RevertOptions};
+
+use super::checkout::changed_files;
+use super::commit::write_commit;
+use super::commit_template::comment_char;
+use super::types::{SequencerOperation, SequencerResult};
+use super::{commit_to_info, conflicted_paths, resolve_commit, GitError, Result};
+
+/// Revert `rev` on top of HEAD, like `git revert`. A merge commit is
+/// reverted against its first parent, undoing what it brought in.
+///
+/// Refuses while another operation is in progress or tracked files have
+/// local changes, so the revert never mixes with unrelated work.
+pub fn revert_commit(repo: &Repository, rev: &str) -> Result<SequencerResult> {
+    let commit = replayable(repo, rev)?;
+    let mut options = RevertOptions::new();
+    if commit.parent_count() > 1 {
+        options.mainline(1);
+    }
+    repo.revert(&commit, Some(&mut options))?;
+
+    conclude(repo, &commit, SequencerOperation::Revert, "revert")
+}
+
+/// Apply the change made by `rev` on top of HEAD, like `git cherry-pick`,
+/// keeping its author and message. A merge commit is picked relative to its
+/// first parent. Refuses in the same situations as [`revert_commit`].
+pub fn cherry_pick(repo: &Repository, rev: &str) -> Result<SequencerResult> {
+    let commit = replayable(repo, rev)?;
+    let mut options = CherrypickOptions::new();
+    if commit.parent_count() > 1 {
+        options.mainline(1);
+    }
+    repo.cherrypick(&commit, Some(&mut options))?;
+
+    conclude(repo, &commit, SequencerOperation::CherryPick, "cherry-pick")
+}
+
+/// The commit `rev` names, once the repository is ready to replay it
+fn replayable<'r>(repo: &'r Repository, rev: &str) -> Result<Commit<'r>> {
+    if repo.state() != RepositoryState::Clean {
+        return Err(GitError::MergeInProgress);
+    }
+    let commit = resolve_commit(repo, rev)?;
+    let changed = changed_files(repo)?;
+    if !changed.is_empty() {
+        return Err(GitError::UncommittedChanges(changed));
+    }
+    Ok(commit)
+}
+
+/// Commit the index the revert or pick left behind, or report its conflicts
+/// and leave the operation in progress
+fn conclude(
+    repo: &Repository,
+    source: &Commit,
+    operation: SequencerOperation,
+    reflog_kind: &str,
+) -> Result<SequencerResult> {
+    let comment = comment_char(&repo.config()?).bytes().next();
+    let message = git2::message_prettify(repo.message()?, comment)?;
+    let mut result = SequencerResult {
+        operation,
+        source: source.id().to_string(),
+        commit: None,
+        conflicts: Vec::new(),
+        message,
+    };
+
+    let mut index = repo.index()?;
+    result.conflicts = conflicted_paths(&index)?;
+    if !result.conflicts.is_empty() {
+        return Ok(result);
+    }
+
+    let head = repo.head()?.peel_to_commit()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    if tree.id() == head.tree_id() {
+        // The change is already there (or already undone); nothing to record
+        repo.cleanup_state()?;
+        return Err(GitError::NothingToCommit);
+    }
+
+    let committer = repo.signature().map_err(|_| GitError::IdentityMissing)?;
+    let author = match operation {
+        SequencerOperation::Revert => committer.clone(),
+        SequencerOperation::CherryPick => source.author().to_owned(),
+    };
+    let oid = write_commit(
+        repo,
+        &author,
+        &committer,
+        &result.message,
+        &tree,
+        &[&head],
+        reflog_kind,
+    )?;
+    repo.cleanup_state()?;
+
+    result.commit = Some(commit_to_info(&repo.find_commit(oid)?, repo));
+    Ok(result)
+}
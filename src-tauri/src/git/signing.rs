@@ -0,0 +1,474 @@
+use git2::{Config, ErrorCode, Repository, Signature};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use super::git_config::config_path;
+use super::types::{CommitSignature, SignatureStatus};
+use super::{GitError, Result};
+
+/// Signature formats git supports through `gpg.format`
+enum SigningFormat {
+    OpenPgp,
+    X509,
+    Ssh,
+}
+
+impl SigningFormat {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::OpenPgp => "openpgp",
+            Self::X509 => "x509",
+            Self::Ssh => "ssh",
+        }
+    }
+
+    /// Format of an armored signature, from its header line
+    fn detect(signature: &str) -> Option<Self> {
+        let signature = signature.trim_start();
+        if signature.starts_with("-----BEGIN PGP SIGNATURE-----") {
+            Some(Self::OpenPgp)
+        } else if signature.starts_with("-----BEGIN SIGNED MESSAGE-----") {
+            Some(Self::X509)
+        } else if signature.starts_with("-----BEGIN SSH SIGNATURE-----") {
+            Some(Self::Ssh)
+        } else {
+            None
+        }
+    }
+}
+
+/// Sign a commit buffer the way `git commit` does when `commit.gpgsign` is
+/// set, returning `None` when signing is off
+pub(crate) fn sign_commit(
+    repo: &Repository,
+    buffer: &str,
+    committer: &Signature,
+) -> Result<Option<String>> {
+    if !repo.config()?.get_bool("commit.gpgsign").unwrap_or(false) {
+        return Ok(None);
+    }
+    sign_buffer(repo, buffer, committer).map(Some)
+}
+
+/// Detached signature of a commit or tag object buffer.
+///
+/// Honors `gpg.format`, `user.signingkey` and the per-format program
+/// overrides. OpenPGP and X.509 signatures default to the signer's identity
+/// as the key; SSH signing needs `user.signingkey`.
+pub(crate) fn sign_buffer(repo: &Repository, buffer: &str, signer: &Signature) -> Result<String> {
+    let config = repo.config()?;
+    let format = match config.get_string("gpg.format").as_deref() {
+        Ok("ssh") => SigningFormat::Ssh,
+        Ok("x509") => SigningFormat::X509,
+        Ok("openpgp") | Err(_) => SigningFormat::OpenPgp,
+        Ok(other) => {
+            return Err(GitError::SigningFailed {
+                format: other.to_string(),
+                detail: "unsupported gpg.format".to_string(),
+            })
+        }
+    };
+    let failed = |detail: String| GitError::SigningFailed {
+        format: format.name().to_string(),
+        detail,
+    };
+
+    let signature = match format {
+        SigningFormat::OpenPgp | SigningFormat::X509 => {
+            let key = config.get_string("user.signingkey").unwrap_or_else(|_| {
+                format!(
+                    "{} <{}>",
+                    signer.name().unwrap_or(""),
+                    signer.email().unwrap_or("")
+                )
+            });
+            sign_with_gpg(&program(&config, &format), &key, buffer).map_err(failed)?
+        }
+        SigningFormat::Ssh => {
+            let key = config
+                .get_string("user.signingkey")
+                .map_err(|_| failed("user.signingkey is not set".to_string()))?;
+            let key_file = if is_literal_ssh_key(&key) {
+                None
+            } else {
+                config_path(repo, "user.signingkey")
+            };
+            sign_with_ssh(&program(&config, &format), &key, key_file, buffer).map_err(failed)?
+        }
+    };
+    Ok(signature)
+}
+
+/// Program for a format, from `gpg.<format>.program` (or `gpg.program` for
+/// OpenPGP) like git
+fn program(config: &Config, format: &SigningFormat) -> String {
+    let (key, default) = match format {
+        SigningFormat::OpenPgp => ("gpg.openpgp.program", "gpg"),
+        SigningFormat::X509 => ("gpg.x509.program", "gpgsm"),
+        SigningFormat::Ssh => ("gpg.ssh.program", "ssh-keygen"),
+    };
+    config
+        .get_string(key)
+        .ok()
+        .or_else(|| match format {
+            SigningFormat::OpenPgp => config.get_string("gpg.program").ok(),
+            _ => None,
+        })
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// Detached, armored signature from gpg or gpgsm. Like git, success is judged
+/// by the `SIG_CREATED` status line rather than the exit code alone.
+fn sign_with_gpg(program: &str, key: &str, buffer: &str) -> std::result::Result<String, String> {
+    let mut command = Command::new(program);
+    command.args(["--status-fd=2", "-bsau", key]);
+    let output = run_with_input(&mut command, program, buffer.as_bytes())?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !stderr.contains("[GNUPG:] SIG_CREATED ") {
+        return Err(stderr_detail(&stderr)
+            .unwrap_or_else(|| format!("{} did not create a signature", program)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// SSH signature from `ssh-keygen -Y sign`. A literal public key (as
+/// `key::ssh-ed25519 ...` or plain `ssh-ed25519 ...`) signs through the SSH
+/// agent; anything else is a path to the private key.
+fn sign_with_ssh(
+    program: &str,
+    key: &str,
+    key_file: Option<std::path::PathBuf>,
+    buffer: &str,
+) -> std::result::Result<String, String> {
+    let scratch = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let buffer_path = scratch.path().join("commit");
+    std::fs::write(&buffer_path, buffer).map_err(|e| e.to_string())?;
+
+    let mut command = Command::new(program);
+    command.args(["-Y", "sign", "-n", "git", "-f"]);
+    match key_file {
+        Some(path) => {
+            command.arg(path);
+        }
+        None => {
+            let public_key = scratch.path().join("key.pub");
+            let literal = key.strip_prefix("key::").unwrap_or(key);
+            std::fs::write(&public_key, literal).map_err(|e| e.to_string())?;
+            command.arg(public_key).arg("-U");
+        }
+    }
+    let output = command
+        .arg(&buffer_path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("could not run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(stderr_detail(&String::from_utf8_lossy(&output.stderr))
+            .unwrap_or_else(|| format!("{} did not create a signature", program)));
+    }
+
+    std::fs::read_to_string(buffer_path.with_extension("sig"))
+        .map_err(|e| format!("{} wrote no signature: {}", program, e))
+}
+
+/// Whether `user.signingkey` holds a public key rather than a key file path
+fn is_literal_ssh_key(key: &str) -> bool {
+    key.starts_with("key::")
+        || key.starts_with("ssh-")
+        || key.starts_with("ecdsa-")
+        || key.starts_with("sk-")
+}
+
+/// Check a commit's signature the way `git verify-commit` does, using the
+/// program configured for the signature's format. SSH signatures are checked
+/// against `gpg.ssh.allowedSignersFile`; without a matching principal they
+/// can only be shown to be intact, which is reported as an unknown key.
+///
+/// Failing to run the verifier is reported in the result rather than as an
+/// error, so history can still show that the commit is signed.
+pub fn verify_commit(repo: &Repository, rev: &str) -> Result<CommitSignature> {
+    let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+    let mut result = CommitSignature {
+        sha: commit.id().to_string(),
+        status: SignatureStatus::Unsigned,
+        format: None,
+        signer: None,
+        key: None,
+        trust: None,
+        detail: None,
+    };
+    let (signature, payload) = match repo.extract_signature(&commit.id(), None) {
+        Ok(extracted) => extracted,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(result),
+        Err(e) => return Err(e.into()),
+    };
+    verify(
+        repo,
+        &String::from_utf8_lossy(&signature),
+        &payload,
+        &mut result,
+    )?;
+    Ok(result)
+}
+
+/// Check an annotated tag's signature the way `git verify-tag` does, with
+/// the same programs and settings as [`verify_commit`]. `None` when the tag
+/// object isn't signed.
+pub(crate) fn verify_tag(repo: &Repository, tag: git2::Oid) -> Result<Option<CommitSignature>> {
+    let odb = repo.odb()?;
+    let object = odb.read(tag)?;
+    let buffer = String::from_utf8_lossy(object.data());
+    let Some(at) = signature_start(&buffer) else {
+        return Ok(None);
+    };
+    let mut result = CommitSignature {
+        sha: tag.to_string(),
+        status: SignatureStatus::Unsigned,
+        format: None,
+        signer: None,
+        key: None,
+        trust: None,
+        detail: None,
+    };
+    verify(repo, &buffer[at..], buffer[..at].as_bytes(), &mut result)?;
+    Ok(Some(result))
+}
+
+/// Where the signature git appends to a signed tag (or a tag message) starts
+pub(crate) fn signature_start(buffer: &str) -> Option<usize> {
+    const HEADERS: [&str; 3] = [
+        "-----BEGIN PGP SIGNATURE-----",
+        "-----BEGIN SIGNED MESSAGE-----",
+        "-----BEGIN SSH SIGNATURE-----",
+    ];
+    HEADERS
+        .iter()
+        .filter_map(|header| {
+            buffer
+                .match_indices(header)
+                .map(|(at, _)| at)
+                .find(|&at| at == 0 || buffer[..at].ends_with('\n'))
+        })
+        .min()
+}
+
+/// Run the verifier for `signature`'s format over `payload`, recording the
+/// verdict in `result`
+fn verify(
+    repo: &Repository,
+    signature: &str,
+    payload: &[u8],
+    result: &mut CommitSignature,
+) -> Result<()> {
+    let Some(format) = SigningFormat::detect(signature) else {
+        result.status = SignatureStatus::Error;
+        result.detail = Some("unrecognized signature format".to_string());
+        return Ok(());
+    };
+    result.format = Some(format.name().to_string());
+
+    let config = repo.config()?;
+    let program = program(&config, &format);
+    let checked = match format {
+        SigningFormat::OpenPgp | SigningFormat::X509 => {
+            verify_with_gpg(&program, signature, payload, result)
+        }
+        SigningFormat::Ssh => {
+            let allowed_signers = config_path(repo, "gpg.ssh.allowedSignersFile");
+            let revocations = config_path(repo, "gpg.ssh.revocationFile");
+            verify_with_ssh(
+                &program,
+                allowed_signers.as_deref(),
+                revocations.as_deref(),
+                signature,
+                payload,
+                result,
+            )
+        }
+    };
+    if let Err(detail) = checked {
+        result.status = SignatureStatus::Error;
+        result.detail = Some(detail);
+    }
+    Ok(())
+}
+
+/// Verify with gpg or gpgsm, reading the verdict from `--status-fd` lines
+fn verify_with_gpg(
+    program: &str,
+    signature: &str,
+    payload: &[u8],
+    result: &mut CommitSignature,
+) -> std::result::Result<(), String> {
+    let scratch = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let signature_path = scratch.path().join("commit.sig");
+    std::fs::write(&signature_path, signature).map_err(|e| e.to_string())?;
+
+    let mut command = Command::new(program);
+    command
+        .args(["--keyid-format=long", "--status-fd=1", "--verify"])
+        .arg(&signature_path)
+        .arg("-");
+    let output = run_with_input(&mut command, program, payload)?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut verdict = None;
+    for line in stdout.lines() {
+        let Some(status) = line.strip_prefix("[GNUPG:] ") else {
+            continue;
+        };
+        let (keyword, rest) = status.split_once(' ').unwrap_or((status, ""));
+        let (key_id, user_id) = rest.split_once(' ').unwrap_or((rest, ""));
+        let status = match keyword {
+            "GOODSIG" => SignatureStatus::Good,
+            "BADSIG" => SignatureStatus::Bad,
+            "EXPSIG" | "EXPKEYSIG" => SignatureStatus::Expired,
+            "REVKEYSIG" => SignatureStatus::Revoked,
+            "ERRSIG" => {
+                result.key.get_or_insert_with(|| key_id.to_string());
+                continue;
+            }
+            "NO_PUBKEY" => {
+                verdict.get_or_insert(SignatureStatus::UnknownKey);
+                continue;
+            }
+            "VALIDSIG" => {
+                // The fingerprint says more than the long key ID
+                result.key = Some(key_id.to_string());
+                continue;
+            }
+            _ => {
+                if let Some(trust) = keyword.strip_prefix("TRUST_") {
+                    result.trust = Some(trust.to_lowercase());
+                }
+                continue;
+            }
+        };
+        verdict = Some(status);
+        if result.key.is_none() {
+            result.key = Some(key_id.to_string());
+        }
+        if !user_id.is_empty() {
+            result.signer = Some(user_id.to_string());
+        }
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match verdict {
+        Some(status) => {
+            result.status = status;
+            if status != SignatureStatus::Good {
+                result.detail = stderr_detail(&stderr);
+            }
+            Ok(())
+        }
+        None => Err(stderr_detail(&stderr)
+            .unwrap_or_else(|| format!("{} gave no verdict on the signature", program))),
+    }
+}
+
+/// Verify with `ssh-keygen -Y verify` for the principal the allowed signers
+/// file lists for the key, or `-Y check-novalidate` when it lists none
+fn verify_with_ssh(
+    program: &str,
+    allowed_signers: Option<&Path>,
+    revocations: Option<&Path>,
+    signature: &str,
+    payload: &[u8],
+    result: &mut CommitSignature,
+) -> std::result::Result<(), String> {
+    let scratch = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let signature_path = scratch.path().join("commit.sig");
+    std::fs::write(&signature_path, signature).map_err(|e| e.to_string())?;
+
+    let principal = match allowed_signers {
+        Some(allowed_signers) => {
+            let output = Command::new(program)
+                .args(["-Y", "find-principals", "-f"])
+                .arg(allowed_signers)
+                .arg("-s")
+                .arg(&signature_path)
+                .stdin(Stdio::null())
+                .output()
+                .map_err(|e| format!("could not run {}: {}", program, e))?;
+            // Fails when no principal matches the key
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .filter(|_| output.status.success())
+                .map(str::to_string)
+        }
+        None => None,
+    };
+
+    let mut command = Command::new(program);
+    match (&principal, allowed_signers) {
+        (Some(principal), Some(allowed_signers)) => {
+            command
+                .args(["-Y", "verify", "-n", "git", "-f"])
+                .arg(allowed_signers)
+                .args(["-I", principal]);
+            if let Some(revocations) = revocations {
+                command.arg("-r").arg(revocations);
+            }
+        }
+        _ => {
+            command.args(["-Y", "check-novalidate", "-n", "git"]);
+        }
+    }
+    command.arg("-s").arg(&signature_path);
+    let output = run_with_input(&mut command, program, payload)?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() {
+        // Good "git" signature [for <principal>] with ED25519 key SHA256:...
+        result.key = stdout
+            .lines()
+            .find(|line| line.starts_with("Good "))
+            .and_then(|line| line.rsplit_once(" key "))
+            .map(|(_, key)| key.trim().to_string());
+        result.status = if principal.is_some() {
+            SignatureStatus::Good
+        } else {
+            SignatureStatus::UnknownKey
+        };
+        result.signer = principal;
+    } else {
+        result.status = SignatureStatus::Bad;
+        result.signer = principal;
+        result.detail = stderr_detail(&stderr).or_else(|| stderr_detail(&stdout));
+    }
+    Ok(())
+}
+
+/// Run `command` with `input` on stdin, capturing its output
+fn run_with_input(
+    command: &mut Command,
+    program: &str,
+    input: &[u8],
+) -> std::result::Result<Output, String> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run {}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input).map_err(|e| e.to_string())?;
+    }
+    child.wait_with_output().map_err(|e| e.to_string())
+}
+
+/// The useful part of a signer's or verifier's stderr, without gpg's status
+/// lines
+fn stderr_detail(stderr: &str) -> Option<String> {
+    let detail: Vec<&str> = stderr
+        .lines()
+        .filter(|line| !line.starts_with("[GNUPG:]") && !line.trim().is_empty())
+        .collect();
+    (!detail.is_empty()).then(|| detail.join("\n"))
+}
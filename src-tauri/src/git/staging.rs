@@ -0,0 +1,305 @@
+use git2::{
+    ApplyLocation, ApplyOptions, Delta, Diff, DiffLine, DiffOptions, IndexEntry, IndexTime, Patch,
+    Repository,
+};
+use std::path::Path;
+
+use super::hunks::{hunk_ids, target_diff};
+use super::types::LineSelection;
+use super::{GitError, Result};
+
+/// Stage one file the way `git add` would: new and modified files are added
+/// to the index, files deleted from the working tree are removed from it
+pub fn stage_file(repo: &Repository, path: &str) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::RepoNotFound(repo.path().display().to_string()))?;
+    let mut index = repo.index()?;
+    let relative = Path::new(path);
+
+    // symlink_metadata so a dangling symlink still counts as present
+    if workdir.join(relative).symlink_metadata().is_ok() {
+        index.add_path(relative)?;
+    } else if index.get_path(relative, 0).is_some() {
+        index.remove_path(relative)?;
+    } else {
+        return Err(GitError::PathNotFound(path.to_string()));
+    }
+
+    index.write()?;
+    Ok(())
+}
+
+/// Unstage one file the way `git restore --staged` would: its index entry is
+/// reset to HEAD, so staged additions become untracked again and staged
+/// deletions are restored to the index. The working tree is left alone.
+pub fn unstage_file(repo: &Repository, path: &str) -> Result<()> {
+    match repo.head().ok().and_then(|h| h.peel_to_commit().ok()) {
+        Some(head) => repo.reset_default(Some(head.as_object()), [path])?,
+        // Nothing is committed yet, so unstaging means forgetting the entry
+        None => {
+            let mut index = repo.index()?;
+            index.remove_path(Path::new(path))?;
+            index.write()?;
+        }
+    }
+    Ok(())
+}
+
+/// Stage one hunk of a file's unstaged changes, like picking it in
+/// `git add -p`. `hunk_id` comes from `get_file_hunks` with the `"unstaged"`
+/// target.
+pub fn stage_hunk(repo: &Repository, path: &str, hunk_id: &str) -> Result<()> {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.pathspec(path);
+    let diff = target_diff(repo, "unstaged", &mut diff_opts)?;
+    let position = hunk_position(&diff, path, hunk_id)?;
+
+    // An untracked file is a single hunk that creates the whole file, which
+    // applying to the index can't express
+    if diff.deltas().any(|d| d.status() == Delta::Untracked) {
+        return stage_file(repo, path);
+    }
+    apply_hunk(repo, &diff, position, ApplyLocation::Index)
+}
+
+/// Unstage one hunk of a file's staged changes, leaving the working tree
+/// alone. `hunk_id` comes from `get_file_hunks` with the `"staged"` target.
+pub fn unstage_hunk(repo: &Repository, path: &str, hunk_id: &str) -> Result<()> {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.pathspec(path);
+    let diff = target_diff(repo, "staged", &mut diff_opts)?;
+    let position = hunk_position(&diff, path, hunk_id)?;
+
+    // Applying the reversed index diff takes the hunk back out of the index;
+    // reversing keeps the hunks in the same order
+    let mut reverse_opts = DiffOptions::new();
+    reverse_opts.pathspec(path).reverse(true);
+    let reversed = target_diff(repo, "staged", &mut reverse_opts)?;
+    apply_hunk(repo, &reversed, position, ApplyLocation::Index)
+}
+
+/// Stage only the selected lines of one unstaged hunk. Unselected removals
+/// stay in the index and unselected additions stay in the working tree only.
+pub fn stage_lines(
+    repo: &Repository,
+    path: &str,
+    hunk_id: &str,
+    selection: &LineSelection,
+) -> Result<()> {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.pathspec(path);
+    let diff = target_diff(repo, "unstaged", &mut diff_opts)?;
+    let position = hunk_position(&diff, path, hunk_id)?;
+
+    // Give an untracked file an empty index entry so the selected lines can
+    // be applied on top of it; the hunk body, and so its id, stays the same
+    let untracked = diff.deltas().any(|d| d.status() == Delta::Untracked);
+    let diff = if untracked {
+        add_empty_entry(repo, path)?;
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.pathspec(path);
+        target_diff(repo, "unstaged", &mut diff_opts)?
+    } else {
+        diff
+    };
+
+    let applied = Patch::from_diff(&diff, 0)?
+        .ok_or_else(|| GitError::HunkNotFound(format!("{} ({})", hunk_id, path)))
+        .and_then(|patch| {
+            partial_patch(path, &patch, position, |line| match line.origin() {
+                '-' => line
+                    .old_lineno()
+                    .is_some_and(|n| selection.deletions.contains(&n)),
+                _ => line
+                    .new_lineno()
+                    .is_some_and(|n| selection.additions.contains(&n)),
+            })
+        })
+        .and_then(|partial| apply_to_index(repo, &partial));
+
+    if applied.is_err() && untracked {
+        let mut index = repo.index()?;
+        index.remove_path(Path::new(path))?;
+        index.write()?;
+    }
+    applied
+}
+
+/// Unstage only the selected lines of one staged hunk, leaving the working
+/// tree alone. Line numbers refer to the staged diff: old-side numbers for
+/// removals, new-side (index) numbers for additions.
+pub fn unstage_lines(
+    repo: &Repository,
+    path: &str,
+    hunk_id: &str,
+    selection: &LineSelection,
+) -> Result<()> {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.pathspec(path);
+    let diff = target_diff(repo, "staged", &mut diff_opts)?;
+    let position = hunk_position(&diff, path, hunk_id)?;
+
+    // In the reversed diff staged additions are removals from the index side
+    // and staged removals are additions, so the line numbers swap roles too
+    let mut reverse_opts = DiffOptions::new();
+    reverse_opts.pathspec(path).reverse(true);
+    let reversed = target_diff(repo, "staged", &mut reverse_opts)?;
+    let patch = Patch::from_diff(&reversed, 0)?
+        .ok_or_else(|| GitError::HunkNotFound(format!("{} ({})", hunk_id, path)))?;
+    let partial = partial_patch(path, &patch, position, |line| match line.origin() {
+        '-' => line
+            .old_lineno()
+            .is_some_and(|n| selection.additions.contains(&n)),
+        _ => line
+            .new_lineno()
+            .is_some_and(|n| selection.deletions.contains(&n)),
+    })?;
+    apply_to_index(repo, &partial)
+}
+
+/// Build a one-hunk patch from hunk `position` of `patch` that keeps only
+/// the changed lines `selected` accepts. A removal that isn't selected
+/// becomes context, an addition that isn't selected is dropped, and the
+/// hunk header is recounted to match.
+fn partial_patch(
+    path: &str,
+    patch: &Patch,
+    position: usize,
+    selected: impl Fn(&DiffLine) -> bool,
+) -> Result<Vec<u8>> {
+    let (hunk, line_count) = patch.hunk(position)?;
+    let mut body = Vec::new();
+    let (mut old_lines, mut new_lines) = (0, 0);
+    let mut any_selected = false;
+    // Whether the previous line made it into the patch, for the
+    // "\ No newline at end of file" marker that follows it
+    let mut kept = false;
+
+    for idx in 0..line_count {
+        let line = patch.line_in_hunk(position, idx)?;
+        match line.origin() {
+            ' ' => {
+                body.push(b' ');
+                old_lines += 1;
+                new_lines += 1;
+                kept = true;
+            }
+            '-' if selected(&line) => {
+                body.push(b'-');
+                old_lines += 1;
+                any_selected = true;
+                kept = true;
+            }
+            '-' => {
+                body.push(b' ');
+                old_lines += 1;
+                new_lines += 1;
+                kept = true;
+            }
+            '+' if selected(&line) => {
+                body.push(b'+');
+                new_lines += 1;
+                any_selected = true;
+                kept = true;
+            }
+            '+' => {
+                kept = false;
+                continue;
+            }
+            // End-of-file newline markers
+            _ => {
+                if kept {
+                    body.extend_from_slice(b"\n\\ No newline at end of file\n");
+                }
+                continue;
+            }
+        }
+        body.extend_from_slice(line.content());
+    }
+
+    if !any_selected {
+        return Err(GitError::InvalidRange(format!(
+            "no changed lines of {} are selected",
+            path
+        )));
+    }
+
+    let old_start = hunk.old_start();
+    let new_start = if new_lines == 0 {
+        old_start.saturating_sub(1)
+    } else if old_lines == 0 {
+        old_start + 1
+    } else {
+        old_start
+    };
+    let mut partial = format!(
+        "diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n@@ -{},{} +{},{} @@\n",
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        path = path
+    )
+    .into_bytes();
+    partial.extend_from_slice(&body);
+    Ok(partial)
+}
+
+fn apply_to_index(repo: &Repository, patch: &[u8]) -> Result<()> {
+    let diff = Diff::from_buffer(patch)?;
+    repo.apply(&diff, ApplyLocation::Index, None)?;
+    Ok(())
+}
+
+/// Track `path` in the index with empty contents
+fn add_empty_entry(repo: &Repository, path: &str) -> Result<()> {
+    let entry = IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: 0o100644,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: git2::Oid::zero(),
+        flags: 0,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    };
+    let mut index = repo.index()?;
+    index.add_frombuffer(&entry, b"")?;
+    index.write()?;
+    Ok(())
+}
+
+/// Position within `diff` of the hunk with the given id
+pub(crate) fn hunk_position(diff: &Diff, path: &str, hunk_id: &str) -> Result<usize> {
+    let not_found = || GitError::HunkNotFound(format!("{} ({})", hunk_id, path));
+    if diff.deltas().len() == 0 {
+        return Err(not_found());
+    }
+    let patch = Patch::from_diff(diff, 0)?.ok_or_else(not_found)?;
+    hunk_ids(&patch, patch.num_hunks())?
+        .iter()
+        .position(|id| id == hunk_id)
+        .ok_or_else(not_found)
+}
+
+/// Apply only the hunk at `position` of a single-file diff
+pub(crate) fn apply_hunk(
+    repo: &Repository,
+    diff: &Diff,
+    position: usize,
+    location: ApplyLocation,
+) -> Result<()> {
+    let mut seen = 0;
+    let mut apply_opts = ApplyOptions::new();
+    apply_opts.hunk_callback(|_| {
+        seen += 1;
+        seen == position + 1
+    });
+    repo.apply(diff, location, Some(&mut apply_opts))?;
+    Ok(())
+}
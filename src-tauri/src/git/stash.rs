@@ -0,0 +1,121 @@
+use git2::Repository;
+
+use super::types::{DiffResult, DifferConfig, StashApplyResult, StashInfo, StashList};
+use super::{conflicted_paths, format_commit_time, local_diff_result, GitError, Result};
+
+/// List stash entries, newest (`stash@{0}`) first.
+///
+/// `git2` needs a mutable repository to walk the stash reflog.
+pub fn get_stashes(repo: &mut Repository) -> Result<StashList> {
+    let mut entries = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        entries.push((index, message.to_string(), *oid));
+        true
+    })?;
+
+    let mut stashes = Vec::new();
+    for (index, message, oid) in entries {
+        let commit = repo.find_commit(oid)?;
+        let sha = oid.to_string();
+        let (branch, description) = parse_stash_message(&message);
+        stashes.push(StashInfo {
+            index,
+            reference: format!("stash@{{{}}}", index),
+            short_sha: sha[..7].to_string(),
+            sha,
+            branch,
+            message: description,
+            date: format_commit_time(&commit),
+        });
+    }
+
+    Ok(StashList { stashes })
+}
+
+/// What a stash entry would bring back: its tracked changes against the
+/// commit it was made on, plus any untracked files it saved
+pub fn get_stash_diff(
+    repo: &Repository,
+    index: usize,
+    config: &DifferConfig,
+) -> Result<DiffResult> {
+    let reference = format!("stash@{{{}}}", index);
+    let stash = repo
+        .revparse_single(&reference)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| GitError::CommitNotFound(reference))?;
+
+    // Stash commits have the base commit, the index commit and, with
+    // `--include-untracked`, a commit holding only the untracked files
+    let base_tree = stash.parent(0)?.tree()?;
+    let mut diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&stash.tree()?), None)?;
+    if stash.parent_count() > 2 {
+        let untracked_tree = stash.parent(2)?.tree()?;
+        let untracked = repo.diff_tree_to_tree(None, Some(&untracked_tree), None)?;
+        diff.merge(&untracked)?;
+    }
+
+    local_diff_result(&diff, config)
+}
+
+/// Stash local changes, like `git stash push [-u] [-m message]`.
+/// Returns the new entry, which becomes `stash@{0}`.
+pub fn stash_save(
+    repo: &mut Repository,
+    message: Option<&str>,
+    include_untracked: bool,
+) -> Result<StashInfo> {
+    let signature = repo.signature()?;
+    let flags = if include_untracked {
+        git2::StashFlags::INCLUDE_UNTRACKED
+    } else {
+        git2::StashFlags::DEFAULT
+    };
+    repo.stash_save2(&signature, message.filter(|m| !m.is_empty()), Some(flags))?;
+
+    get_stashes(repo)?
+        .stashes
+        .into_iter()
+        .next()
+        .ok_or_else(|| GitError::CommitNotFound("stash@{0}".to_string()))
+}
+
+/// Apply `stash@{index}` to the working tree. With `pop`, the entry is
+/// dropped afterwards unless applying it left conflicts, as `git stash pop`
+/// does. Local changes that would be overwritten abort before anything is
+/// touched.
+pub fn stash_apply(repo: &mut Repository, index: usize, pop: bool) -> Result<StashApplyResult> {
+    repo.stash_apply(index, None)?;
+
+    let conflicts = conflicted_paths(&repo.index()?)?;
+
+    let dropped = pop && conflicts.is_empty();
+    if dropped {
+        repo.stash_drop(index)?;
+    }
+
+    Ok(StashApplyResult {
+        index,
+        dropped,
+        conflicts,
+    })
+}
+
+/// Delete `stash@{index}` and return the remaining entries
+pub fn stash_drop(repo: &mut Repository, index: usize) -> Result<StashList> {
+    repo.stash_drop(index)?;
+    get_stashes(repo)
+}
+
+/// Split `WIP on main: 1a2b3c4 subject` or `On main: my message` into the
+/// branch and the part after it. Detached stashes say `(no branch)`.
+fn parse_stash_message(message: &str) -> (Option<String>, String) {
+    let rest = message
+        .strip_prefix("WIP on ")
+        .or_else(|| message.strip_prefix("On "));
+    let Some((branch, description)) = rest.and_then(|r| r.split_once(": ")) else {
+        return (None, message.to_string());
+    };
+    let branch = (branch != "(no branch)").then(|| branch.to_string());
+    (branch, description.to_string())
+}
@@ -0,0 +1,167 @@
+use git2::Repository;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer, XmlVersion};
+
+use super::types::SvgRenderPair;
+use super::{diff_text, read_file_at, Result};
+
+/// Elements that can execute script, embed foreign documents or pull in
+/// resources through CSS `@import` and `url()`
+const BLOCKED_ELEMENTS: &[&str] = &[
+    "script",
+    "style",
+    "foreignobject",
+    "iframe",
+    "object",
+    "embed",
+    "handler",
+    "listener",
+];
+
+/// Elements that can set another attribute, and so rewrite a link after it
+/// was checked
+const ANIMATION_ELEMENTS: &[&str] = &["set", "animate", "animatemotion", "animatetransform"];
+
+/// Raster formats allowed as inline `data:` images. An SVG one would be a
+/// second document that never went through the sanitizer.
+const INLINE_IMAGE_TYPES: &[&str] = &["png", "jpeg", "jpg", "gif", "webp", "bmp"];
+
+/// Get old/new SVG markup for a visual preview alongside the text diff.
+///
+/// Markup is sanitized for inline rendering: scripts, style sheets, event
+/// handlers, foreign content and external references are removed. A side is
+/// `None` when the file does not exist there or cannot be parsed as XML.
+pub fn get_svg_render_pair(
+    repo: &Repository,
+    file_path: &str,
+    base: Option<&str>,
+    head: Option<&str>,
+) -> Result<SvgRenderPair> {
+    let old = read_file_at(repo, file_path, base)?;
+    let new = read_file_at(repo, file_path, head)?;
+
+    let (patch, additions, deletions) =
+        diff_text(old.as_deref().unwrap_or(""), new.as_deref().unwrap_or(""))?;
+
+    Ok(SvgRenderPair {
+        path: file_path.to_string(),
+        old_markup: old.as_deref().and_then(sanitize_svg),
+        new_markup: new.as_deref().and_then(sanitize_svg),
+        patch,
+        additions,
+        deletions,
+    })
+}
+
+/// Re-serialize SVG markup, dropping anything that could run code or fetch
+/// external resources. Returns `None` for malformed documents.
+pub fn sanitize_svg(markup: &str) -> Option<String> {
+    let mut reader = Reader::from_str(markup);
+    let mut writer = Writer::new(Vec::new());
+    let mut skip_depth = 0usize;
+
+    loop {
+        let event = reader.read_event().ok()?;
+
+        if skip_depth > 0 {
+            match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                Event::Eof => return None,
+                _ => {}
+            }
+            continue;
+        }
+
+        let out = match event {
+            Event::Start(e) if is_blocked(&e) => {
+                skip_depth = 1;
+                continue;
+            }
+            Event::Empty(e) if is_blocked(&e) => continue,
+            Event::Start(e) => Event::Start(clean_element(&e)),
+            Event::Empty(e) => Event::Empty(clean_element(&e)),
+            // Entity declarations and processing instructions have no place in a preview
+            Event::DocType(_) | Event::PI(_) => continue,
+            Event::Eof => break,
+            other => other,
+        };
+
+        writer.write_event(out).ok()?;
+    }
+
+    String::from_utf8(writer.into_inner()).ok()
+}
+
+fn is_blocked(element: &BytesStart) -> bool {
+    let name = element.local_name().as_ref().to_lowercase();
+    if BLOCKED_ELEMENTS.contains(&name.as_str()) {
+        return true;
+    }
+    ANIMATION_ELEMENTS.contains(&name.as_str())
+        && element.attributes().flatten().any(|attr| {
+            attr.key
+                .local_name()
+                .as_ref()
+                .eq_ignore_ascii_case("attributename")
+                && attr.value.trim().to_lowercase().ends_with("href")
+        })
+}
+
+fn clean_element(element: &BytesStart) -> BytesStart<'static> {
+    let name = element.name().as_ref().to_string();
+    let mut cleaned = BytesStart::new(name);
+
+    for attr in element.attributes().flatten() {
+        let key = attr.key.local_name().as_ref().to_lowercase();
+        // Checked the way the browser reads it: with entities decoded and
+        // without the whitespace and control characters URLs may contain
+        let Ok(value) = attr.normalized_value(XmlVersion::Implicit1_0) else {
+            continue;
+        };
+        let value: String = value
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect::<String>()
+            .to_lowercase();
+
+        if key.starts_with("on") || value.contains("javascript:") || value.contains("vbscript:") {
+            continue;
+        }
+        if key == "href" && !is_local_reference(&value) {
+            continue;
+        }
+        if !only_fragment_urls(&value) {
+            continue;
+        }
+        // A CSS escape can spell `url(` without those letters
+        if key == "style" && value.contains('\\') {
+            continue;
+        }
+
+        cleaned.push_attribute(attr);
+    }
+
+    cleaned.into_owned()
+}
+
+/// Fragment links and inline raster images are the only references safe to
+/// render
+fn is_local_reference(value: &str) -> bool {
+    value.starts_with('#')
+        || value.strip_prefix("data:image/").is_some_and(|rest| {
+            INLINE_IMAGE_TYPES.iter().any(|kind| {
+                rest.strip_prefix(kind)
+                    .is_some_and(|rest| rest.starts_with([';', ',']))
+            })
+        })
+}
+
+/// Whether every `url(...)` in a value points inside the document
+fn only_fragment_urls(value: &str) -> bool {
+    value.match_indices("url(").all(|(at, _)| {
+        value[at + "url(".len()..]
+            .trim_start_matches(['"', '\''])
+            .starts_with('#')
+    })
+}
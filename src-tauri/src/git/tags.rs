@@ -0,0 +1,303 @@
+use git2::{ErrorCode, ObjectType, Reference, Repository, Signature};
+
+use super::commit_template::comment_char;
+use super::signing::{sign_buffer, signature_start, verify_tag};
+use super::types::{
+    ChangelogEntry, ChangelogSection, DifferConfig, ReleaseDelta, TagComparison, TagInfo, TagList,
+};
+use super::{
+    compare_commits, format_commit_time, format_git_time, resolve_commit, GitError, Result,
+};
+
+/// List lightweight and annotated tags, newest first.
+///
+/// Annotated tags carry their tagger, message and tagging date; lightweight
+/// tags fall back to the date of the commit they point at. Tags of trees or
+/// blobs are left out.
+pub fn get_tags(repo: &Repository) -> Result<TagList> {
+    let mut tags = Vec::new();
+
+    repo.tag_foreach(|oid, name| {
+        let name = String::from_utf8_lossy(name);
+        let name = name.strip_prefix("refs/tags/").unwrap_or(&name).to_string();
+        if let Some(tag) = tag_info(repo, oid, name) {
+            tags.push(tag);
+        }
+        true
+    })?;
+
+    tags.sort_by(|(a_time, a), (b_time, b)| b_time.cmp(a_time).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(TagList {
+        tags: tags.into_iter().map(|(_, tag)| tag).collect(),
+    })
+}
+
+/// What shipped between two releases: the diff from tag `from` to tag `to`
+/// plus the full list of commits in between
+pub fn compare_tags(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+    config: &DifferConfig,
+) -> Result<TagComparison> {
+    let from_tag = find_tag(repo, from)?;
+    let to_tag = find_tag(repo, to)?;
+    let from_commit = repo.find_commit(git2::Oid::from_str(&from_tag.sha)?)?;
+    let to_commit = repo.find_commit(git2::Oid::from_str(&to_tag.sha)?)?;
+
+    let comparison = compare_commits(repo, &from_commit, &to_commit, config)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(to_commit.id())?;
+    revwalk.hide(from_commit.id())?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME | git2::Sort::REVERSE)?;
+    let mut changelog = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let sha = commit.id().to_string();
+        changelog.push(ChangelogEntry {
+            short_sha: sha[..7].to_string(),
+            sha,
+            summary: commit.summary().unwrap_or("").to_string(),
+            author: commit.author().name().unwrap_or("").to_string(),
+            date: format_commit_time(&commit),
+        });
+    }
+
+    Ok(TagComparison {
+        from: from_tag,
+        to: to_tag,
+        comparison,
+        changelog,
+    })
+}
+
+/// The release delta between tags `a` and `b`, given in either order: the
+/// commits in between, their combined diff and a changelog grouped by
+/// conventional-commit type.
+///
+/// The older release is the one the other descends from. Tags on diverged
+/// lines fall back to the earlier tagging date.
+pub fn get_release_delta(
+    repo: &Repository,
+    a: &str,
+    b: &str,
+    config: &DifferConfig,
+) -> Result<ReleaseDelta> {
+    let a_tag = find_tag(repo, a)?;
+    let b_tag = find_tag(repo, b)?;
+    let a_oid = git2::Oid::from_str(&a_tag.sha)?;
+    let b_oid = git2::Oid::from_str(&b_tag.sha)?;
+    let swapped = if repo.graph_descendant_of(a_oid, b_oid)? {
+        true
+    } else if repo.graph_descendant_of(b_oid, a_oid)? {
+        false
+    } else {
+        tag_time(repo, &a_tag.name) > tag_time(repo, &b_tag.name)
+    };
+    let (from, to) = if swapped { (b, a) } else { (a, b) };
+
+    let release = compare_tags(repo, from, to, config)?;
+    let sections = changelog_sections(&release.changelog);
+    Ok(ReleaseDelta {
+        from: release.from,
+        to: release.to,
+        swapped,
+        comparison: release.comparison,
+        changelog: release.changelog,
+        sections,
+    })
+}
+
+/// When tag `name` was made, for ordering releases
+fn tag_time(repo: &Repository, name: &str) -> i64 {
+    repo.find_reference(&format!("refs/tags/{}", name))
+        .ok()
+        .and_then(|reference| reference.target())
+        .and_then(|oid| tag_info(repo, oid, name.to_string()))
+        .map_or(0, |(time, _)| time)
+}
+
+/// Group `changelog` by the `type` of `type(scope)!: subject` summaries.
+/// Features and fixes lead, other types follow alphabetically, and commits
+/// without a type come last as `other`.
+fn changelog_sections(changelog: &[ChangelogEntry]) -> Vec<ChangelogSection> {
+    let mut sections: Vec<ChangelogSection> = Vec::new();
+    for entry in changelog {
+        let kind = commit_type(&entry.summary).unwrap_or("other");
+        match sections.iter_mut().find(|section| section.kind == kind) {
+            Some(section) => section.entries.push(entry.clone()),
+            None => sections.push(ChangelogSection {
+                kind: kind.to_string(),
+                entries: vec![entry.clone()],
+            }),
+        }
+    }
+    let rank = |kind: &str| match kind {
+        "feat" => 0,
+        "fix" => 1,
+        "other" => 3,
+        _ => 2,
+    };
+    sections.sort_by(|a, b| {
+        rank(&a.kind)
+            .cmp(&rank(&b.kind))
+            .then_with(|| a.kind.cmp(&b.kind))
+    });
+    sections
+}
+
+/// The type of a conventional-commit summary, which git convention keeps
+/// lowercase
+fn commit_type(summary: &str) -> Option<&str> {
+    let (prefix, _) = summary.split_once(": ")?;
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let kind = match prefix.split_once('(') {
+        Some((kind, scope)) if scope.ends_with(')') => kind,
+        Some(_) => return None,
+        None => prefix,
+    };
+    let valid = !kind.is_empty() && kind.chars().all(|c| c.is_ascii_lowercase());
+    valid.then_some(kind)
+}
+
+/// Tag `target_ref` (any revision resolving to a commit) as `name`.
+///
+/// Without a message the tag is lightweight. A message makes it annotated,
+/// cleaned up like `git tag -m` and tagged by the configured identity.
+/// Annotated tags are signed when `sign` is set or, when it is `None`, when
+/// `tag.gpgSign` is; signing follows the same `gpg.*` settings as commits.
+pub fn create_tag(
+    repo: &Repository,
+    name: &str,
+    target_ref: &str,
+    message: Option<&str>,
+    sign: Option<bool>,
+) -> Result<TagInfo> {
+    let refname = format!("refs/tags/{}", name);
+    if !Reference::is_valid_name(&refname) {
+        return Err(GitError::InvalidTagName(name.to_string()));
+    }
+    if repo.find_reference(&refname).is_ok() {
+        return Err(GitError::TagExists(name.to_string()));
+    }
+    let target = resolve_commit(repo, target_ref)?;
+    let config = repo.config()?;
+    let sign = sign.unwrap_or_else(|| config.get_bool("tag.gpgSign").unwrap_or(false));
+
+    let oid = match message {
+        None if sign => return Err(GitError::EmptyMessage),
+        None => repo.tag_lightweight(name, target.as_object(), false),
+        Some(message) => {
+            let comment = comment_char(&config).bytes().next();
+            let message = git2::message_prettify(message, comment)?;
+            if message.trim().is_empty() {
+                return Err(GitError::EmptyMessage);
+            }
+            let tagger = repo.signature().map_err(|_| GitError::IdentityMissing)?;
+            if sign {
+                let buffer = tag_buffer(target.id(), name, &tagger, &message);
+                let signature = sign_buffer(repo, &buffer, &tagger)?;
+                let tag = repo.odb()?.write(
+                    ObjectType::Tag,
+                    format!("{}{}", buffer, signature).as_bytes(),
+                )?;
+                repo.reference(&refname, tag, false, &format!("tag: {}", name))
+                    .map(|reference| reference.target().unwrap_or(tag))
+            } else {
+                repo.tag(name, target.as_object(), &tagger, &message, false)
+            }
+        }
+    }
+    .map_err(|e| match e.code() {
+        ErrorCode::Exists => GitError::TagExists(name.to_string()),
+        _ => e.into(),
+    })?;
+
+    tag_info(repo, oid, name.to_string())
+        .map(|(_, tag)| tag)
+        .ok_or_else(|| GitError::TagNotFound(name.to_string()))
+}
+
+/// A tag object as git writes it, ready to have a signature appended
+fn tag_buffer(target: git2::Oid, name: &str, tagger: &Signature, message: &str) -> String {
+    let when = tagger.when();
+    let offset = when.offset_minutes().abs();
+    format!(
+        "object {}\ntype commit\ntag {}\ntagger {} <{}> {} {}{:02}{:02}\n\n{}",
+        target,
+        name,
+        tagger.name().unwrap_or(""),
+        tagger.email().unwrap_or(""),
+        when.seconds(),
+        when.sign(),
+        offset / 60,
+        offset % 60,
+        message
+    )
+}
+
+fn find_tag(repo: &Repository, name: &str) -> Result<TagInfo> {
+    let short = name.strip_prefix("refs/tags/").unwrap_or(name);
+    repo.find_reference(&format!("refs/tags/{}", short))
+        .ok()
+        .and_then(|reference| reference.target())
+        .and_then(|oid| tag_info(repo, oid, short.to_string()))
+        .map(|(_, tag)| tag)
+        .ok_or_else(|| GitError::TagNotFound(name.to_string()))
+}
+
+/// A signed tag's message with the signature that git appends to it removed
+fn strip_signature(message: &str) -> &str {
+    signature_start(message).map_or(message, |at| &message[..at])
+}
+
+/// Tag details plus its timestamp for sorting
+fn tag_info(repo: &Repository, oid: git2::Oid, name: String) -> Option<(i64, TagInfo)> {
+    let object = repo.find_object(oid, None).ok()?;
+    let commit = object.peel_to_commit().ok()?;
+    let sha = commit.id().to_string();
+    let short_sha = sha[..7].to_string();
+
+    let info = match object.as_tag() {
+        Some(tag) => {
+            let tagger = tag.tagger();
+            let time = tagger
+                .as_ref()
+                .map(|t| t.when())
+                .unwrap_or_else(|| commit.time());
+            (
+                time.seconds(),
+                TagInfo {
+                    name,
+                    sha,
+                    short_sha,
+                    annotated: true,
+                    tagger: tagger.as_ref().and_then(|t| t.name().map(str::to_string)),
+                    tagger_email: tagger.as_ref().and_then(|t| t.email().map(str::to_string)),
+                    message: tag
+                        .message()
+                        .map(|m| strip_signature(m).trim_end().to_string()),
+                    date: format_git_time(time),
+                    signature: verify_tag(repo, oid).ok().flatten(),
+                },
+            )
+        }
+        None => (
+            commit.time().seconds(),
+            TagInfo {
+                name,
+                sha,
+                short_sha,
+                annotated: false,
+                tagger: None,
+                tagger_email: None,
+                message: None,
+                date: format_git_time(commit.time()),
+                signature: None,
+            },
+        ),
+    };
+    Some(info)
+}
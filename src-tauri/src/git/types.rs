@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,87 +18,1188 @@ pub struct FileDiffInfo {
     pub patch: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_large: Option<bool>,
+    /// Review risk, on comparisons only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub risk: Option<FileRisk>,
+    /// Set on comparisons, saying whether `risk` was scored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_status: Option<RiskStatus>,
+    /// Run-length encoded change map, see `minimap::MinimapBuilder`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimap: Option<String>,
+    /// A rename that only changes letter case, e.g. `Foo.ts` to `foo.ts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub case_rename: Option<bool>,
+    /// Why the file's contents couldn't be read, e.g. a permission error; the
+    /// rest of the diff is still complete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Identifies the file across refreshes, see `navigation::file_id`
+    #[serde(default)]
+    pub id: String,
+    /// Where each hunk starts, in patch order; kept when a large patch is
+    /// dropped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hunks: Vec<HunkAnchor>,
+    /// Muted for this comparison, see `muted::mute_file`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub muted: Option<bool>,
+}
+
+/// Start of one hunk of a file's diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkAnchor {
+    /// Identifies the hunk across refreshes, see `navigation::AnchorBuilder`
+    pub id: String,
+    pub old_start: u32,
+    pub new_start: u32,
+}
+
+/// One stop when stepping through changes: hunk `hunk` of `files[file]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeAnchor {
+    pub file: usize,
+    pub hunk: usize,
+}
+
+/// Every hunk of a diff in review order, so next/previous change is an index
+/// step rather than a search
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChangeNavigation {
+    /// All hunks, file by file in the order of `files`
+    pub changes: Vec<ChangeAnchor>,
+    /// Index into `changes` of each file's first hunk, plus `changes.len()`
+    /// at the end: file `i` owns `changes[file_starts[i]..file_starts[i + 1]]`
+    pub file_starts: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRisk {
+    /// 0-100, higher means the change deserves a closer look
+    pub score: u8,
+    pub level: RiskLevel,
+    pub recent_commits: usize,
+    pub recent_authors: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_owner_share: Option<f32>,
+    pub bug_prone: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_correlation: Option<f32>,
+    pub reasons: Vec<String>,
+}
+
+/// Whether a compared file's `risk` was scored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskStatus {
+    Scored,
+    /// The repository is too large to walk its history, see
+    /// `Degradation::CommitStats`; `risk` is left empty
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffStats {
+    pub additions: usize,
+    pub deletions: usize,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffResult {
+    pub files: Vec<FileDiffInfo>,
+    pub stats: DiffStats,
+    pub warnings: Vec<DiffWarning>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complexity: Option<ReviewComplexity>,
+    /// Features left out because the repository is huge, see `large_repo`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub degraded: Vec<Degradation>,
+    #[serde(default)]
+    pub navigation: ChangeNavigation,
+}
+
+/// Files of a diff that changed since it was last loaded, by file id
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffDelta {
+    /// Nothing earlier to compare with; every file is in `added`
+    pub full: bool,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+    /// Files the frontend can keep as they are
+    pub unchanged: usize,
+}
+
+/// Payload of the `diff-updated` event, sent whenever a diff is loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffUpdatedEvent {
+    /// Which diff was loaded: `current` or `staged`
+    pub view: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_ref: Option<String>,
+    pub delta: DiffDelta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingChanges {
+    /// HEAD to index
+    pub staged: DiffResult,
+    /// Index to working tree, including untracked files
+    pub unstaged: DiffResult,
+    /// Paths that appear in both sections
+    pub partially_staged: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareBranchesResult {
+    pub files: Vec<FileDiffInfo>,
+    pub stats: DiffStats,
+    pub commit_count: usize,
+    /// The first `COMPARE_COMMIT_LIMIT` commits of `base..head`, oldest first;
+    /// fetch the rest with `get_compare_commits`
+    pub commits: Vec<CommitInfo>,
+    /// Only the newest `COMPARE_COMMIT_LIMIT` commits of `head..base` were
+    /// checked for `applied_in`, so older equivalents aren't marked
+    #[serde(default)]
+    pub applied_in_truncated: bool,
+    pub warnings: Vec<DiffWarning>,
+    pub complexity: ReviewComplexity,
+    pub by_extension: Vec<ExtensionStats>,
+    /// Features left out because the repository is huge, see `large_repo`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub degraded: Vec<Degradation>,
+    #[serde(default)]
+    pub navigation: ChangeNavigation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionStats {
+    pub extension: String,
+    pub language: String,
+    pub files: usize,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewSize {
+    Small,
+    Medium,
+    Large,
+    XLarge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewComplexity {
+    pub total_changed_lines: usize,
+    pub files_changed: usize,
+    pub large_files: Vec<String>,
+    pub size: ReviewSize,
+    pub suggest_split: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningCategory {
+    SensitiveFile,
+    MigrationCollision,
+    MigrationOrder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffWarning {
+    pub category: WarningCategory,
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitStats {
+    pub additions: usize,
+    pub deletions: usize,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitInfo {
+    pub sha: String,
+    pub short_sha: String,
+    pub message: String,
+    pub author: String,
+    pub author_email: String,
+    pub date: String,
+    pub stats: CommitStats,
+    /// First paragraph of `message`, on one line
+    #[serde(default)]
+    pub subject: String,
+    /// Everything after the subject
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body: String,
+    /// Whether the commit carries a signature; `verify_commit` checks it
+    #[serde(default)]
+    pub signed: bool,
+    /// In a comparison, the base commit that already makes the same change,
+    /// e.g. after a cherry-pick; see `cherry`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_in: Option<String>,
+}
+
+/// A commit message split into parts for display, see `message`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitMessage {
+    pub subject: Vec<MessageSpan>,
+    pub body: Vec<MessageBlock>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MessageBlock {
+    /// Prose, with hard-wrapped lines joined back together
+    Paragraph { spans: Vec<MessageSpan> },
+    List {
+        ordered: bool,
+        items: Vec<Vec<MessageSpan>>,
+    },
+    /// Indented or fenced text, kept verbatim
+    Code { text: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum MessageSpan {
+    Text {
+        text: String,
+    },
+    Link {
+        text: String,
+        url: String,
+    },
+    /// `#12`, `owner/repo#12` or, on GitLab, `!12`; linked when the
+    /// provider of `origin` is known
+    Issue {
+        text: String,
+        number: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameLine {
+    /// 1-based line number in the blamed revision of the file
+    pub line: usize,
+    pub sha: String,
+    pub short_sha: String,
+    pub author: String,
+    pub author_email: String,
+    pub date: String,
+    /// First line of the commit message
+    pub summary: String,
+    /// Line number within the attributed commit's version of the file
+    pub orig_line: usize,
+    /// Path in the attributed commit, when the file has since been renamed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_path: Option<String>,
+    /// Attribution was moved past a revision listed in the ignore-revs file
+    pub adjusted: bool,
+    /// The ignored revision that last touched the line, when `adjusted`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignored_sha: Option<String>,
+}
+
+/// The same logical line one step further back in history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviousBlame {
+    /// Commit the line was blamed on
+    pub commit: String,
+    /// Its first parent; `None` for a root commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// The file's path in the parent, following a rename
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The line in the parent, `None` when `commit` introduced it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blame: Option<BlameLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileBlame {
+    pub path: String,
+    /// Revision that was blamed, `HEAD` when none was given
+    pub git_ref: String,
+    pub lines: Vec<BlameLine>,
+    /// Ignore-revs file that was honored, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_revs_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHunk {
+    /// Position of the hunk within the file's patch
+    pub index: usize,
+    /// Identifies the hunk across refreshes, see `hunks::hunk_ids`
+    pub id: String,
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    /// Hunk body with `+`/`-`/` ` prefixed lines, header excluded
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHunkPage {
+    pub path: String,
+    pub hunks: Vec<FileHunk>,
+    pub offset: usize,
+    /// Total hunk count for the file, independent of the page
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NarrativeKind {
+    Added,
+    Removed,
+    Modified,
+    Renamed,
+    Whitespace,
+}
+
+/// Inclusive 1-based line range
+/// Changed lines picked out of one hunk for line-level staging
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LineSelection {
+    /// Old-side line numbers of removed lines
+    pub deletions: Vec<u32>,
+    /// New-side line numbers of added lines
+    pub additions: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NarrativeEntry {
+    pub hunk_index: usize,
+    pub kind: NarrativeKind,
+    /// Affected lines in the old file, absent for pure additions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_lines: Option<LineRange>,
+    /// Affected lines in the new file, absent for pure removals
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_lines: Option<LineRange>,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffNarrative {
+    pub path: String,
+    pub summary: String,
+    pub entries: Vec<NarrativeEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultBranchSource {
+    /// `defaultBranch` in the repo's `.diffyrc.json`
+    Config,
+    /// The `origin/HEAD` symref
+    Remote,
+    /// A conventional name such as `main` that exists in the repo
+    Heuristic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head_commit: Option<String>,
+    pub is_detached: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_branch_source: Option<DefaultBranchSource>,
+}
+
+/// A repository looked at once without opening it, see
+/// `repo_status::inspect_repo`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoInspection {
+    /// Working tree, or the git directory of a bare repository
+    pub path: String,
+    pub bare: bool,
+    pub status: RepoStatus,
+    /// Change counts and upstream divergence; `None` for bare repositories
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<StatusLine>,
+}
+
+/// Differences between a source archive and the local checkout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveDiff {
+    pub archive: String,
+    /// `"working"` or the ref the archive was compared with
+    pub target: String,
+    /// Top-level directory of the archive that was treated as the root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    pub diff: DiffResult,
+}
+
+/// Handle for undoing a discard, see `discard::restore_discarded`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscardBackup {
+    /// Name under `refs/diffy/discarded/`
+    pub id: String,
+    pub path: String,
+    /// Backup commit holding the discarded contents
+    pub sha: String,
+    /// False when the discarded change was deleting the file
+    pub existed: bool,
+    pub date: String,
+}
+
+/// Git config level a value was read from, lowest priority first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GitConfigScope {
+    ProgramData,
+    System,
+    Xdg,
+    Global,
+    Local,
+    Worktree,
+    App,
+}
+
+/// One value of a git config key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConfigEntry {
+    pub value: String,
+    pub scope: GitConfigScope,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Set in a file pulled in by `include.path` or `includeIf` from `file`
+    pub included: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConfigValue {
+    pub key: String,
+    /// The value git would use, from the highest-priority entry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub entries: Vec<GitConfigEntry>,
+}
+
+/// Outcome of checking a commit's signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureStatus {
+    Unsigned,
+    Good,
+    Bad,
+    /// Made with a key that isn't in the keyring or allowed signers file
+    UnknownKey,
+    /// Good signature, but it or its key has expired
+    Expired,
+    /// Good signature, but the key has been revoked
+    Revoked,
+    /// The verifier could not be run or gave no verdict
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSignature {
+    pub sha: String,
+    pub status: SignatureStatus,
+    /// `openpgp`, `x509` or `ssh`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// User ID for gpg, principal for ssh
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    /// Key fingerprint, or the key ID when gpg can't tell more
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// gpg's trust level for the key, e.g. `ultimate` or `undefined`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust: Option<String>,
+    /// The verifier's own message when the signature isn't good
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Compact repository summary for shell prompts and status bars
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusLine {
+    /// Branch name, or the short HEAD sha when detached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub is_detached: bool,
+    pub staged: usize,
+    pub unstaged: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+    /// Upstream of the current branch, e.g. `origin/main`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+/// Outcome of running one git hook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookResult {
+    pub hook: String,
+    pub success: bool,
+    /// `None` when the hook was killed by a signal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreparedMessageSource {
+    /// `MERGE_MSG`, left by a merge, revert or cherry-pick that stopped
+    Merge,
+    /// `SQUASH_MSG`, left by `git merge --squash`
+    Squash,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitTemplate {
+    /// Contents of the file named by `commit.template`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template_path: Option<String>,
+    /// Message git prepared for the next commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prepared_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prepared_source: Option<PreparedMessageSource>,
+    /// What `git commit` would open the editor with: the prepared message if
+    /// any, else the template, else empty
+    pub message: String,
+    /// Lines starting with this are stripped from the final message
+    pub comment_char: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchMatrixCell {
+    pub base: String,
+    pub head: String,
+    /// Commits on `head` that are not on `base`
+    pub ahead: usize,
+    /// Commits on `base` that are not on `head`
+    pub behind: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchMatrix {
+    /// Requested branches with glob patterns expanded
+    pub branches: Vec<String>,
+    pub cells: Vec<BranchMatrixCell>,
+}
+
+/// Divergence between a monitored pair of refs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    pub base: String,
+    pub head: String,
+    /// Commits on `head` that are not on `base`
+    pub ahead: usize,
+    /// Commits on `base` that are not on `head`
+    pub behind: usize,
+    /// Files that differ between the two tips
+    pub changed_files: usize,
+    /// Over the monitor's commit or file threshold
+    pub exceeded: bool,
+    /// The monitor asks for a system notification when exceeded
+    pub notify: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchDeltaFile {
+    pub path: String,
+    pub status: FileStatus,
+    /// Also modified by commits on this branch since it left the base
+    pub changed_on_branch: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchDelta {
+    pub base_ref: String,
+    pub merge_base: String,
+    /// Files changed by the branch's own commits since the merge base
+    pub branch_files: Vec<String>,
+    /// Uncommitted changes, flagged by whether the branch already touched them
+    pub files: Vec<BranchDeltaFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitHistory {
+    pub commits: Vec<CommitInfo>,
+    pub total: usize,
+    /// Features left out because the repository is huge, see `large_repo`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub degraded: Vec<Degradation>,
+    /// In a comparison, only the newest `COMPARE_COMMIT_LIMIT` commits of
+    /// `head..base` were checked for `applied_in`
+    #[serde(default)]
+    pub applied_in_truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub commit: CommitInfo,
+    /// The file's path as of this commit
+    pub path: String,
+    /// Previous path when this commit renamed the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    pub status: FileStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistory {
+    pub path: String,
+    pub entries: Vec<FileHistoryEntry>,
+    pub total: usize,
+    /// Features left out because the repository is huge, see `large_repo`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub degraded: Vec<Degradation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineHistoryEntry {
+    pub commit: CommitInfo,
+    /// The file's path as of this commit
+    pub path: String,
+    /// The tracked region's lines in this commit
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Hunks of the commit's diff that touch the region, without context
+    pub patch: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineHistory {
+    pub path: String,
+    /// The region at HEAD, after resolving a function name
+    pub start_line: usize,
+    pub end_line: usize,
+    pub entries: Vec<LineHistoryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitDiff {
+    pub commit: CommitInfo,
+    pub description: CommitMessage,
+    pub files: Vec<FileDiffInfo>,
+    pub warnings: Vec<DiffWarning>,
+    #[serde(default)]
+    pub navigation: ChangeNavigation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchInfo {
+    pub name: String,
+    pub current: bool,
+    pub commit: String,
+    /// `None` when no default branch could be detected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_into_default: Option<bool>,
+    pub merged_into_head: bool,
+    /// Shares no history with the default branch
+    pub orphan: bool,
+    pub last_commit_date: String,
+    pub last_commit_author: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchList {
+    pub branches: Vec<BranchInfo>,
+    pub current: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    /// Branches matching the query, across all pages
+    #[serde(default)]
+    pub total: usize,
+}
+
+/// Which local branches to list
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BranchQuery {
+    /// Case-insensitive substring the branch name must contain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    pub offset: usize,
+    /// Page size; every matching branch when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagInfo {
+    pub name: String,
+    /// Commit the tag points at
+    pub sha: String,
+    pub short_sha: String,
+    pub annotated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tagger: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tagger_email: Option<String>,
+    /// Tag message; `None` for lightweight tags
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// When the tag was made, or the commit date for lightweight tags
+    pub date: String,
+    /// Verification of a signed annotated tag; its `sha` is the tag object's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<CommitSignature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagList {
+    /// Newest first
+    pub tags: Vec<TagInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashInfo {
+    /// Position in the stash list, 0 being the most recent
+    pub index: usize,
+    /// `stash@{n}`
+    pub reference: String,
+    pub sha: String,
+    pub short_sha: String,
+    /// Branch the stash was made on; `None` when HEAD was detached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// The stash message without its `On <branch>:` prefix
+    pub message: String,
+    pub date: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashList {
+    pub stashes: Vec<StashInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashApplyResult {
+    pub index: usize,
+    /// Whether the entry was removed from the stash list
+    pub dropped: bool,
+    /// Files left with conflict markers; empty when the stash applied cleanly
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SequencerOperation {
+    Revert,
+    CherryPick,
+}
+
+/// Outcome of replaying one commit onto HEAD, see `sequencer`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequencerResult {
+    pub operation: SequencerOperation,
+    /// The commit that was replayed
+    pub source: String,
+    /// The commit made; absent when conflicts stopped the operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<CommitInfo>,
+    /// Files left with conflict markers. Committing once they are resolved
+    /// and staged concludes the operation.
+    pub conflicts: Vec<String>,
+    /// Message of the commit made, or prepared for concluding it
+    pub message: String,
+}
+
+/// How the two sides of a conflicted file disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictKind {
+    BothModified,
+    /// No common ancestor: each side added its own file
+    BothAdded,
+    /// We deleted the file, they changed it
+    DeletedByUs,
+    /// They deleted the file, we changed it
+    DeletedByThem,
+}
+
+/// One conflicted file, see `conflicts`. Versions a side doesn't have are
+/// absent, as are all of them for binary files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictFile {
+    pub path: String,
+    pub kind: ConflictKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ancestor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ours: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theirs: Option<String>,
+    /// The working tree file, with conflict markers until it is resolved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working: Option<String>,
+    pub binary: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum FileStatus {
-    Added,
-    Deleted,
-    Modified,
-    Renamed,
+#[serde(rename_all = "camelCase")]
+pub struct ConflictList {
+    pub files: Vec<ConflictFile>,
 }
 
+/// How to resolve a conflicted file, see `conflicts::resolve_conflict`
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    /// Content merged by hand
+    Content {
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct DiffStats {
-    pub additions: usize,
-    pub deletions: usize,
-    pub files: usize,
+pub enum MergeOutcome {
+    /// HEAD already contains the branch
+    UpToDate,
+    FastForward,
+    /// A merge commit was made
+    Merged,
+    /// Conflicts stopped the merge, leaving the repository merging
+    Conflicted,
 }
 
+/// Outcome of merging a branch into HEAD, see `merge`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct DiffResult {
-    pub files: Vec<FileDiffInfo>,
-    pub stats: DiffStats,
+pub struct MergeResult {
+    pub outcome: MergeOutcome,
+    /// The branch or revision merged, as given
+    pub branch: String,
+    /// HEAD after the merge
+    pub sha: String,
+    /// The merge commit; absent for fast-forwards and stopped merges
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<CommitInfo>,
+    /// Files left with conflict markers. Committing once they are resolved
+    /// and staged concludes the merge.
+    pub conflicts: Vec<String>,
+    /// Message of the merge commit, or prepared for concluding it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
+/// A merge worked out without touching the repository, see `merge`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CompareBranchesResult {
-    pub files: Vec<FileDiffInfo>,
-    pub stats: DiffStats,
-    pub commit_count: usize,
+pub struct MergePreview {
+    /// The commit merged into
+    pub ours: String,
+    /// The commit merged
+    pub theirs: String,
+    /// What merging would do; `conflicted` means it wouldn't be clean
+    pub outcome: MergeOutcome,
+    /// Files that would be left with conflicts
+    pub conflicts: Vec<String>,
+    /// Changes the merge would make to `ours`
+    pub diff: DiffResult,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PushStage {
+    /// Counting and compressing the objects to send
+    Packing,
+    /// Uploading them to the remote
+    Sending,
+}
+
+/// Progress of a push, sent as `push-progress` events
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CommitStats {
-    pub additions: usize,
-    pub deletions: usize,
-    pub files: usize,
+pub struct PushProgress {
+    pub stage: PushStage,
+    pub current: usize,
+    pub total: usize,
+    /// Bytes uploaded so far; 0 while packing
+    pub bytes: usize,
 }
 
+/// Outcome of a push, see `push`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CommitInfo {
+pub struct PushResult {
+    pub remote: String,
+    /// Remote refs updated, with where they pointed before and after
+    pub refs: Vec<RefUpdate>,
+    /// Whether the push was allowed to rewrite history
+    pub forced: bool,
+    /// The `pre-push` hook's output, when it ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hook: Option<HookResult>,
+}
+
+/// A key passphrase a fetch, pull or push needs to go on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassphraseRequest {
+    /// Path of the private key
+    pub key: String,
+    /// The remote it is for
+    pub url: String,
+}
+
+/// A ref a write moved, created (`old` absent) or deleted (`new` absent)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefUpdate {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<String>,
+}
+
+/// One write made through the app, see `audit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// Command that made it, e.g. `stageFile` or `commit`
+    pub operation: String,
+    /// Milliseconds since the epoch
+    pub timestamp: i64,
+    /// `Name <email>` from git config, when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Refs that changed, `HEAD` first when it moved
+    #[serde(default)]
+    pub refs: Vec<RefUpdate>,
+    /// Files the operation was given or that changed between the old and
+    /// new HEAD
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Paths left out to keep the entry small
+    #[serde(default)]
+    pub omitted_paths: usize,
+    /// Why the operation failed; refs and paths still show anything it
+    /// changed before failing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// How a pull brings in the upstream's commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PullStrategy {
+    /// Merge them, fast-forwarding when possible
+    Merge,
+    /// Replay local commits on top of them
+    Rebase,
+    /// Only fast-forward, refusing when the branches have diverged
+    FfOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PullOutcome {
+    /// The branch already had everything upstream
+    UpToDate,
+    FastForward,
+    /// A merge commit was made
+    Merged,
+    /// Local commits were replayed on top of the upstream
+    Rebased,
+    /// Conflicts stopped the pull, see `PullResult::conflicts`
+    Conflicted,
+}
+
+/// Outcome of pulling the current branch's upstream, see `pull`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullResult {
+    /// The strategy used, given or from git config
+    pub strategy: PullStrategy,
+    pub outcome: PullOutcome,
+    pub branch: String,
+    /// Remote-tracking branch pulled, e.g. `origin/main`
+    pub upstream: String,
+    /// HEAD after the pull
     pub sha: String,
-    pub short_sha: String,
-    pub message: String,
-    pub author: String,
-    pub author_email: String,
-    pub date: String,
-    pub stats: CommitStats,
+    /// The merge commit, when one was made
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<CommitInfo>,
+    /// Local commits replayed by a rebase
+    pub rebased: usize,
+    /// Files that conflicted. A merge is left in progress to resolve and
+    /// commit; a rebase is aborted, leaving the branch as it was.
+    pub conflicts: Vec<String>,
+    /// The local commit a rebase stopped at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CommitHistory {
-    pub commits: Vec<CommitInfo>,
-    pub total: usize,
+pub struct CheckoutResult {
+    pub branch: String,
+    pub sha: String,
+    /// Branch HEAD was on before, or its short sha when detached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+    /// Local changes stashed for the switch and reapplied on the new branch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_stash: Option<StashApplyResult>,
 }
 
+/// One line of a release changelog
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CommitDiff {
-    pub commit: CommitInfo,
-    pub files: Vec<FileDiffInfo>,
+pub struct ChangelogEntry {
+    pub sha: String,
+    pub short_sha: String,
+    /// First line of the commit message
+    pub summary: String,
+    pub author: String,
+    pub date: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct BranchInfo {
-    pub name: String,
-    pub current: bool,
-    pub commit: String,
+pub struct TagComparison {
+    pub from: TagInfo,
+    pub to: TagInfo,
+    pub comparison: CompareBranchesResult,
+    /// Every commit in `from..to`, oldest first; unlike `comparison.commits`
+    /// this is never truncated
+    pub changelog: Vec<ChangelogEntry>,
 }
 
+/// Commits of a release sharing a conventional-commit type
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct BranchList {
-    pub branches: Vec<BranchInfo>,
-    pub current: String,
+pub struct ChangelogSection {
+    /// `feat`, `fix` and so on, or `other` for messages without a type
+    pub kind: String,
+    /// Oldest first
+    pub entries: Vec<ChangelogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseDelta {
+    /// The older release
+    pub from: TagInfo,
+    /// The newer release
+    pub to: TagInfo,
+    /// Whether the tags were asked for newest first and swapped
+    pub swapped: bool,
+    pub comparison: CompareBranchesResult,
+    /// Every commit in `from..to`, oldest first
+    pub changelog: Vec<ChangelogEntry>,
+    /// The changelog grouped by type, features and fixes first
+    pub sections: Vec<ChangelogSection>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,6 +1242,10 @@ pub struct RemoteInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DifferConfig {
+    /// Bumped on every successful write; writes made against an older
+    /// revision are rejected so concurrent windows cannot clobber each other
+    #[serde(default)]
+    pub revision: u64,
     #[serde(default = "default_editor")]
     pub editor: String,
     #[serde(default = "default_diff_style")]
@@ -150,6 +1256,264 @@ pub struct DifferConfig {
     pub auto_open: bool,
     #[serde(default = "default_large_file_threshold")]
     pub large_file_threshold: usize,
+    /// Source archives unpacking to more bytes than this aren't diffed
+    #[serde(default = "default_max_archive_bytes")]
+    pub max_archive_bytes: u64,
+    /// Locale for backend messages, e.g. `en`, `de`, `fr-CA`
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// External text converters keyed by file extension, e.g. `"pdf": "pdftotext %f -"`
+    #[serde(default)]
+    pub textconv: HashMap<String, String>,
+    /// Directories checked for migration collisions when comparing branches
+    #[serde(default = "default_migration_paths")]
+    pub migration_paths: Vec<String>,
+    /// Extra file patterns (beyond `.env*`, keys, kubeconfigs, ...) flagged as sensitive
+    #[serde(default)]
+    pub sensitive_patterns: Vec<String>,
+    #[serde(default)]
+    pub review_thresholds: ReviewThresholds,
+    /// Glob patterns for historically bug-prone paths, raising their risk score
+    #[serde(default)]
+    pub risk_paths: Vec<String>,
+    #[serde(default)]
+    pub ipc_compression: IpcCompression,
+    #[serde(default)]
+    pub ipc_handoff: IpcHandoff,
+    /// Ref pairs whose divergence is rechecked after every fetch
+    #[serde(default)]
+    pub drift_monitors: Vec<DriftMonitor>,
+    #[serde(default)]
+    pub hooks: HookPolicy,
+    #[serde(default)]
+    pub watcher: WatcherSettings,
+    #[serde(default)]
+    pub editor_sync: EditorSyncSettings,
+    #[serde(default)]
+    pub large_repo: LargeRepoSettings,
+    /// Refuse every command that changes a repository. A repository can
+    /// also turn this on for itself with `readOnly` in `.diffyrc.json`.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Private keys offered to SSH remotes after the agent's, `~/` for the
+    /// home directory; empty means `~/.ssh/id_ed25519`, `id_ecdsa`, `id_rsa`
+    #[serde(default)]
+    pub ssh_keys: Vec<String>,
+}
+
+/// Changed-line limits used to classify review size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReviewThresholds {
+    pub small_lines: usize,
+    pub medium_lines: usize,
+    pub large_lines: usize,
+    /// A single file changing more lines than this counts as a large file
+    pub large_file_lines: usize,
+    pub max_files: usize,
+}
+
+impl Default for ReviewThresholds {
+    fn default() -> Self {
+        Self {
+            small_lines: 100,
+            medium_lines: 400,
+            large_lines: 1000,
+            large_file_lines: 300,
+            max_files: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionEncoding {
+    Gzip,
+    /// Smaller and faster, but the frontend's `DecompressionStream` only
+    /// understands it on webviews that ship zstd support
+    Zstd,
+}
+
+/// Compression of large diff responses sent over IPC; off by default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IpcCompression {
+    pub enabled: bool,
+    pub encoding: CompressionEncoding,
+    /// Serialized responses at or below this many bytes are sent as plain JSON
+    pub threshold_bytes: usize,
+}
+
+impl Default for IpcCompression {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            encoding: CompressionEncoding::Gzip,
+            threshold_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Writing very large diff responses to a file in the app cache instead of
+/// sending them over IPC; takes precedence over compression when both apply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IpcHandoff {
+    pub enabled: bool,
+    /// Serialized responses over this many bytes are handed off as a file
+    pub threshold_bytes: usize,
+}
+
+impl Default for IpcHandoff {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// File watching. Polling is also used automatically on network
+/// filesystems and when native watching cannot be set up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatcherSettings {
+    /// Always poll instead of using OS change notifications
+    pub polling: bool,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for WatcherSettings {
+    fn default() -> Self {
+        Self {
+            polling: false,
+            poll_interval_ms: 2000,
+        }
+    }
+}
+
+/// Local server for editor companion plugins, see `editor_sync::protocol_info`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EditorSyncSettings {
+    pub enabled: bool,
+    /// Port on 127.0.0.1 to listen on
+    pub port: u16,
+}
+
+impl Default for EditorSyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47600,
+        }
+    }
+}
+
+/// Expensive default left out in huge-repository mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Degradation {
+    /// Untracked directories are listed as one entry instead of file by file
+    UntrackedRecursion,
+    /// Commit lists carry zeroed stats instead of diffing every commit
+    CommitStats,
+    /// File history stops at a rename instead of following it
+    RenameDetection,
+}
+
+impl Degradation {
+    pub const ALL: [Degradation; 3] = [
+        Degradation::UntrackedRecursion,
+        Degradation::CommitStats,
+        Degradation::RenameDetection,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LargeRepoMode {
+    /// Degrade when the repository crosses either threshold
+    Auto,
+    /// Always degrade
+    On,
+    /// Never degrade
+    Off,
+}
+
+/// When to treat a repository as huge and which features to keep anyway
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LargeRepoSettings {
+    pub mode: LargeRepoMode,
+    /// Files in the index above which a repository counts as huge
+    pub file_threshold: usize,
+    /// Objects in the object database above which a repository counts as huge
+    pub object_threshold: usize,
+    /// Features kept at full fidelity even in huge-repository mode
+    pub keep: Vec<Degradation>,
+}
+
+impl Default for LargeRepoSettings {
+    fn default() -> Self {
+        Self {
+            mode: LargeRepoMode::Auto,
+            file_threshold: 100_000,
+            object_threshold: 5_000_000,
+            keep: Vec::new(),
+        }
+    }
+}
+
+/// How big a repository is and what is degraded because of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LargeRepoStatus {
+    pub huge: bool,
+    /// Entries in the index
+    pub files: usize,
+    /// Packed objects plus an estimate of loose ones, like `git count-objects`
+    pub objects: usize,
+    pub degraded: Vec<Degradation>,
+}
+
+/// Which git hooks write operations run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HookPolicy {
+    pub enabled: bool,
+    /// Hook names to skip even when hooks are enabled, e.g. `pre-push`
+    pub skip: Vec<String>,
+    /// A hook still running after this many seconds is killed and fails
+    pub timeout_secs: u64,
+}
+
+impl Default for HookPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            skip: Vec::new(),
+            timeout_secs: 600,
+        }
+    }
+}
+
+/// A pair of refs to watch, e.g. `release/1.2` against `main`.
+///
+/// Drift counts commits on either side that the other lacks, and files that
+/// differ between the two tips. A threshold left unset is never exceeded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftMonitor {
+    pub base: String,
+    pub head: String,
+    #[serde(default)]
+    pub max_commits: Option<usize>,
+    #[serde(default)]
+    pub max_files: Option<usize>,
+    /// Ask the frontend to raise a system notification when exceeded
+    #[serde(default)]
+    pub notify: bool,
 }
 
 fn default_editor() -> String {
@@ -172,14 +1536,181 @@ fn default_large_file_threshold() -> usize {
     50000
 }
 
+fn default_max_archive_bytes() -> u64 {
+    2 << 30
+}
+
+fn default_locale() -> String {
+    "en".to_string()
+}
+
+fn default_migration_paths() -> Vec<String> {
+    vec![
+        "migrations".to_string(),
+        "db/migrations".to_string(),
+        "db/migrate".to_string(),
+    ]
+}
+
 impl Default for DifferConfig {
     fn default() -> Self {
         Self {
+            revision: 0,
             editor: default_editor(),
             diff_style: default_diff_style(),
             port: default_port(),
             auto_open: default_auto_open(),
             large_file_threshold: default_large_file_threshold(),
+            max_archive_bytes: default_max_archive_bytes(),
+            locale: default_locale(),
+            textconv: HashMap::new(),
+            migration_paths: default_migration_paths(),
+            sensitive_patterns: Vec::new(),
+            review_thresholds: ReviewThresholds::default(),
+            risk_paths: Vec::new(),
+            ipc_compression: IpcCompression::default(),
+            ipc_handoff: IpcHandoff::default(),
+            drift_monitors: Vec::new(),
+            hooks: HookPolicy::default(),
+            watcher: WatcherSettings::default(),
+            editor_sync: EditorSyncSettings::default(),
+            large_repo: LargeRepoSettings::default(),
+            read_only: false,
+            ssh_keys: Vec::new(),
         }
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellStatus {
+    Added,
+    Deleted,
+    Modified,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCellDiff {
+    pub status: CellStatus,
+    pub cell_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+    pub additions: usize,
+    pub deletions: usize,
+    pub outputs_changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookDiff {
+    pub path: String,
+    pub cells: Vec<NotebookCellDiff>,
+    pub additions: usize,
+    pub deletions: usize,
+    pub outputs_stripped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SvgRenderPair {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_markup: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_markup: Option<String>,
+    pub patch: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiff {
+    pub path: String,
+    pub converter: String,
+    pub patch: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyChangeKind {
+    Added,
+    Removed,
+    Upgraded,
+    Downgraded,
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyChange {
+    pub name: String,
+    pub ecosystem: String,
+    pub manifest: String,
+    pub kind: DependencyChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_version: Option<String>,
+    pub direct: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyAnalysis {
+    pub base: String,
+    pub head: String,
+    pub manifests: Vec<String>,
+    pub changes: Vec<DependencyChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SemverImpact {
+    Major,
+    Minor,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicApiChange {
+    pub path: String,
+    pub kind: String,
+    pub change: ApiChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicApiDiff {
+    pub crate_path: String,
+    pub base: String,
+    pub head: String,
+    pub changes: Vec<PublicApiChange>,
+    pub semver_impact: SemverImpact,
+    pub errors: Vec<String>,
+}
@@ -0,0 +1,230 @@
+use git2::{
+    BranchType, Oid, Repository, StatusOptions, Worktree, WorktreeAddOptions, WorktreeLockStatus,
+    WorktreePruneOptions,
+};
+use std::path::{Path, PathBuf};
+
+use super::types::{WorktreeInfo, WorktreeList};
+use super::{format_commit_time, repo_status, resolve_commit, GitError, Result};
+
+/// List the main worktree and every linked worktree of a repository.
+///
+/// Each entry reports its checked-out branch (`(detached)` for a detached
+/// HEAD), how far it is ahead of and behind the default branch, and the time
+/// of its HEAD commit. Worktrees whose directory is gone are skipped, as is
+/// the main worktree of a bare repository. Most recently active come first.
+pub fn get_worktrees(repo: &Repository) -> Result<WorktreeList> {
+    let current = repo.workdir().map(normalize).unwrap_or_default();
+
+    let default_oid = repo_status::detect_default_branch(repo)
+        .and_then(|(name, _)| resolve_commit(repo, &name).ok())
+        .map(|c| c.id());
+
+    let mut paths = Vec::new();
+    if let Some(main) = main_worktree_path(repo) {
+        paths.push(main);
+    }
+    for name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(name)?;
+        if worktree.validate().is_ok() {
+            paths.push(worktree.path().to_path_buf());
+        }
+    }
+
+    let mut worktrees = Vec::new();
+    for path in paths {
+        let Ok(worktree_repo) = Repository::open(&path) else {
+            continue;
+        };
+        let path = normalize(&path);
+        worktrees.push(describe_worktree(
+            &worktree_repo,
+            path.clone(),
+            path == current,
+            default_oid,
+        )?);
+    }
+
+    // ISO 8601 timestamps sort chronologically as strings
+    worktrees.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+
+    Ok(WorktreeList { worktrees, current })
+}
+
+/// Create a linked worktree at `path` with `branch` checked out.
+///
+/// A branch that does not exist yet is created from `base`, or from HEAD when
+/// no base is given. The worktree is named after the last component of
+/// `path`. Returns the updated worktree list.
+pub fn add_worktree(
+    repo: &Repository,
+    path: &str,
+    branch: &str,
+    base: Option<&str>,
+) -> Result<WorktreeList> {
+    let target = Path::new(path);
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| GitError::Worktree(format!("Not a directory path: {}", path)))?;
+    if target.exists() && target.read_dir()?.next().is_some() {
+        return Err(GitError::Worktree(format!(
+            "{} already exists and is not empty",
+            path
+        )));
+    }
+
+    let branch_ref = match repo.find_branch(branch, BranchType::Local) {
+        Ok(existing) => existing,
+        Err(_) => {
+            let start = match base {
+                Some(rev) => resolve_commit(repo, rev)?,
+                None => repo.head()?.peel_to_commit()?,
+            };
+            repo.branch(branch, &start, false)?
+        }
+    };
+
+    let mut opts = WorktreeAddOptions::new();
+    opts.reference(Some(branch_ref.get()));
+    repo.worktree(&name, target, Some(&opts))?;
+
+    get_worktrees(repo)
+}
+
+/// Delete a linked worktree's directory and its administrative entry.
+///
+/// Refuses worktrees with uncommitted changes or a lock unless `force` is
+/// set. The main worktree cannot be removed. Returns the updated worktree
+/// list.
+pub fn remove_worktree(repo: &Repository, path: &str, force: bool) -> Result<WorktreeList> {
+    let worktree = find_linked_worktree(repo, Path::new(path))?;
+
+    if let WorktreeLockStatus::Locked(reason) = worktree.is_locked()? {
+        if !force {
+            let reason = reason.map(|r| format!(": {}", r)).unwrap_or_default();
+            return Err(GitError::Worktree(format!("{} is locked{}", path, reason)));
+        }
+    }
+
+    if !force && has_local_changes(&worktree)? {
+        return Err(GitError::Worktree(format!(
+            "{} has uncommitted changes",
+            path
+        )));
+    }
+
+    worktree.prune(Some(
+        WorktreePruneOptions::new()
+            .valid(true)
+            .locked(force)
+            .working_tree(true),
+    ))?;
+
+    get_worktrees(repo)
+}
+
+/// Drop administrative entries for worktrees whose directory no longer
+/// exists. Locked entries are kept. Returns the names that were pruned.
+pub fn prune_worktrees(repo: &Repository) -> Result<Vec<String>> {
+    let mut pruned = Vec::new();
+    for name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(name)?;
+        if worktree.is_prunable(None)? {
+            worktree.prune(None)?;
+            pruned.push(name.to_string());
+        }
+    }
+    Ok(pruned)
+}
+
+fn find_linked_worktree(repo: &Repository, path: &Path) -> Result<Worktree> {
+    let wanted = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    for name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(name)?;
+        let candidate = worktree.path();
+        let candidate = candidate
+            .canonicalize()
+            .unwrap_or_else(|_| candidate.to_path_buf());
+        if normalize(&candidate) == normalize(&wanted) {
+            return Ok(worktree);
+        }
+    }
+    Err(GitError::Worktree(format!(
+        "No linked worktree at {}",
+        path.display()
+    )))
+}
+
+fn has_local_changes(worktree: &Worktree) -> Result<bool> {
+    let repo = Repository::open_from_worktree(worktree)?;
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut opts))?;
+    Ok(!statuses.is_empty())
+}
+
+/// Working directory of the main worktree, whether `repo` was opened from it
+/// or from one of its linked worktrees
+fn main_worktree_path(repo: &Repository) -> Option<PathBuf> {
+    if repo.is_worktree() {
+        // A linked worktree's git dir records the main `.git` directory,
+        // usually relative to itself, in its `commondir` file
+        let recorded = std::fs::read_to_string(repo.path().join("commondir")).ok()?;
+        let main = Repository::open(repo.path().join(recorded.trim())).ok()?;
+        main.workdir().map(Path::to_path_buf)
+    } else {
+        repo.workdir().map(Path::to_path_buf)
+    }
+}
+
+fn describe_worktree(
+    repo: &Repository,
+    path: String,
+    is_current: bool,
+    default_oid: Option<Oid>,
+) -> Result<WorktreeInfo> {
+    let branch = if repo.head_detached()? {
+        "(detached)".to_string()
+    } else {
+        // An unborn branch has no HEAD commit yet but still has a name
+        repo.find_reference("HEAD")?
+            .symbolic_target()
+            .map(|target| target.trim_start_matches("refs/heads/").to_string())
+            .unwrap_or_default()
+    };
+
+    let Ok(head) = repo.head().and_then(|h| h.peel_to_commit()) else {
+        return Ok(WorktreeInfo {
+            path,
+            branch,
+            commit: String::new(),
+            is_current,
+            behind_main: 0,
+            ahead_of_main: 0,
+            last_activity: String::new(),
+        });
+    };
+
+    let (ahead_of_main, behind_main) = match default_oid {
+        Some(oid) => repo.graph_ahead_behind(head.id(), oid)?,
+        None => (0, 0),
+    };
+
+    Ok(WorktreeInfo {
+        path,
+        branch,
+        commit: head.id().to_string()[..7].to_string(),
+        is_current,
+        behind_main,
+        ahead_of_main,
+        last_activity: format_commit_time(&head),
+    })
+}
+
+/// Display form of a worktree directory, without the trailing separator
+/// libgit2 appends
+fn normalize(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    path.trim_end_matches(['/', '\\']).to_string()
+}
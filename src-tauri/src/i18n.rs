@@ -0,0 +1,190 @@
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::git::{GitError, HookResult};
+
+const DEFAULT_LOCALE: &str = "en";
+
+/// Built-in catalogs; keys missing from a translation fall back to English
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("es", include_str!("../locales/es.json")),
+    ("fr", include_str!("../locales/fr.json")),
+];
+
+static CURRENT_LOCALE: RwLock<String> = RwLock::new(String::new());
+
+fn catalogs() -> &'static HashMap<&'static str, HashMap<String, String>> {
+    static PARSED: OnceLock<HashMap<&'static str, HashMap<String, String>>> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(locale, json)| {
+                let messages = serde_json::from_str(json).unwrap_or_default();
+                (*locale, messages)
+            })
+            .collect()
+    })
+}
+
+/// Set the locale used for backend messages, e.g. `de` or `fr-CA`
+pub fn set_locale(locale: &str) {
+    *CURRENT_LOCALE.write() = locale.to_string();
+}
+
+fn current_locale() -> String {
+    CURRENT_LOCALE.read().clone()
+}
+
+/// Catalogs to consult for a locale, most specific first: `fr-CA` tries
+/// `fr-CA`, then `fr`, then English
+fn fallback_chain(locale: &str) -> Vec<&'static HashMap<String, String>> {
+    let all = catalogs();
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+    [locale, language, DEFAULT_LOCALE]
+        .iter()
+        .filter_map(|l| all.get(*l))
+        .collect()
+}
+
+/// All messages for the current locale, for the UI to render backend keys
+pub fn message_catalog() -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    // Least specific first so more specific catalogs overwrite
+    for catalog in fallback_chain(&current_locale()).into_iter().rev() {
+        messages.extend(catalog.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    messages
+}
+
+/// Look up `key` in the current locale and fill `{name}` placeholders
+pub fn translate(key: &str, args: &[(&str, &str)]) -> String {
+    let template = fallback_chain(&current_locale())
+        .into_iter()
+        .find_map(|catalog| catalog.get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_string());
+
+    args.iter().fold(template, |message, (name, value)| {
+        message.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+/// Error returned from commands: a stable message key plus its text in the
+/// current locale, so the UI can show it as-is or localize the key itself
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandError {
+    pub key: String,
+    pub message: String,
+    /// Output of the hook that rejected a write operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hook: Option<Box<HookResult>>,
+}
+
+impl CommandError {
+    pub fn new(key: &str, args: &[(&str, &str)]) -> Self {
+        Self {
+            key: key.to_string(),
+            message: translate(key, args),
+            hook: None,
+        }
+    }
+}
+
+impl From<GitError> for CommandError {
+    fn from(error: GitError) -> Self {
+        match &error {
+            GitError::Git(e) => Self::new("error.git", &[("detail", e.message())]),
+            GitError::RepoNotFound(path) => Self::new("error.repoNotFound", &[("path", path)]),
+            GitError::CommitNotFound(sha) => Self::new("error.commitNotFound", &[("sha", sha)]),
+            GitError::Io(e) => Self::new("error.io", &[("detail", &e.to_string())]),
+            GitError::InvalidNotebook(detail) => {
+                Self::new("error.invalidNotebook", &[("detail", detail)])
+            }
+            GitError::Conversion(detail) => Self::new("error.conversion", &[("detail", detail)]),
+            GitError::Worktree(detail) => Self::new("error.worktree", &[("detail", detail)]),
+            GitError::InvalidRange(detail) => {
+                Self::new("error.invalidRange", &[("detail", detail)])
+            }
+            GitError::TagNotFound(name) => Self::new("error.tagNotFound", &[("name", name)]),
+            GitError::PathNotFound(path) => Self::new("error.pathNotFound", &[("path", path)]),
+            GitError::HunkNotFound(hunk) => Self::new("error.hunkNotFound", &[("hunk", hunk)]),
+            GitError::BackupNotFound(id) => Self::new("error.backupNotFound", &[("id", id)]),
+            GitError::InvalidArchive(detail) => {
+                Self::new("error.invalidArchive", &[("detail", detail)])
+            }
+            GitError::NothingToCommit => Self::new("error.nothingToCommit", &[]),
+            GitError::IdentityMissing => Self::new("error.identityMissing", &[]),
+            GitError::EmptyMessage => Self::new("error.emptyMessage", &[]),
+            GitError::SigningFailed { format, detail } => Self::new(
+                "error.signingFailed",
+                &[("format", format), ("detail", detail)],
+            ),
+            GitError::MergeInProgress => Self::new("error.mergeInProgress", &[]),
+            GitError::AlreadyPushed(remote) => {
+                Self::new("error.alreadyPushed", &[("remote", remote)])
+            }
+            GitError::BranchNotFound(name) => Self::new("error.branchNotFound", &[("name", name)]),
+            GitError::BranchExists(name) => Self::new("error.branchExists", &[("name", name)]),
+            GitError::InvalidBranchName(name) => {
+                Self::new("error.invalidBranchName", &[("name", name)])
+            }
+            GitError::BranchNotMerged(name) => {
+                Self::new("error.branchNotMerged", &[("name", name)])
+            }
+            GitError::BranchCheckedOut(name) => {
+                Self::new("error.branchCheckedOut", &[("name", name)])
+            }
+            GitError::TagExists(name) => Self::new("error.tagExists", &[("name", name)]),
+            GitError::InvalidTagName(name) => Self::new("error.invalidTagName", &[("name", name)]),
+            GitError::NotConflicted(path) => Self::new("error.notConflicted", &[("path", path)]),
+            GitError::NoUpstream(branch) => Self::new("error.noUpstream", &[("branch", branch)]),
+            GitError::NotFastForward(upstream) => {
+                Self::new("error.notFastForward", &[("upstream", upstream)])
+            }
+            GitError::StaleLease(name) => Self::new("error.staleLease", &[("name", name)]),
+            GitError::PushRejected { name, reason } => {
+                Self::new("error.pushRejected", &[("name", name), ("reason", reason)])
+            }
+            GitError::InvalidRefspec(spec) => Self::new("error.invalidRefspec", &[("spec", spec)]),
+            GitError::AuthFailed(url) => Self::new("error.authFailed", &[("url", url)]),
+            GitError::UncommittedChanges(files) => Self::new(
+                "error.uncommittedChanges",
+                &[
+                    ("count", &files.len().to_string()),
+                    ("files", &files.join(", ")),
+                ],
+            ),
+            GitError::HookFailed(result) => {
+                let output = if result.stderr.trim().is_empty() {
+                    &result.stdout
+                } else {
+                    &result.stderr
+                };
+                Self {
+                    hook: Some(result.clone()),
+                    ..Self::new(
+                        "error.hookFailed",
+                        &[("hook", &result.hook), ("detail", output.trim())],
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        Self::new("error.io", &[("detail", &error.to_string())])
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::new("error.serialization", &[("detail", &error.to_string())])
+    }
+}
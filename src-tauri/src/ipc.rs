@@ -0,0 +1,173 @@
+use base64::Engine;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::git::{CompressionEncoding, DifferConfig, IpcCompression, IpcHandoff};
+use crate::i18n::CommandError;
+
+/// A command response that is either the value itself or, when large, a
+/// compressed copy of its JSON or a handle to a file holding it.
+///
+/// `Plain` serializes exactly like the wrapped value, so callers that never
+/// enable compression or handoff see no difference. `Compressed` is an
+/// envelope marked with `compressed: true`; the frontend base64-decodes
+/// `data`, decompresses it with `encoding` and parses the result as JSON.
+/// `File` is marked with `handoff: true`; the frontend reads the JSON from
+/// `path` and then calls `cmd_release_payload`.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum IpcResponse<T> {
+    Plain(T),
+    Compressed(CompressedPayload),
+    File(FileHandoff),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressedPayload {
+    pub compressed: bool,
+    pub encoding: CompressionEncoding,
+    /// Size of the uncompressed JSON in bytes
+    pub original_size: usize,
+    pub data: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHandoff {
+    pub handoff: bool,
+    pub path: String,
+    /// Size of the JSON file in bytes
+    pub size: usize,
+}
+
+/// Wrap a response, handing it off or compressing it per the IPC settings
+pub fn encode_response<T: Serialize>(
+    value: T,
+    config: &DifferConfig,
+) -> Result<IpcResponse<T>, CommandError> {
+    let IpcHandoff {
+        enabled: handoff_enabled,
+        threshold_bytes: handoff_threshold,
+    } = config.ipc_handoff;
+    let IpcCompression {
+        enabled: compression_enabled,
+        encoding,
+        threshold_bytes: compression_threshold,
+    } = config.ipc_compression;
+
+    if !handoff_enabled && !compression_enabled {
+        return Ok(IpcResponse::Plain(value));
+    }
+
+    let json = serde_json::to_vec(&value)?;
+
+    if let (true, Some(dir)) = (
+        handoff_enabled && json.len() > handoff_threshold,
+        payload_dir(),
+    ) {
+        let path = write_payload(dir, &json)?;
+        return Ok(IpcResponse::File(FileHandoff {
+            handoff: true,
+            path: path.to_string_lossy().to_string(),
+            size: json.len(),
+        }));
+    }
+
+    if !compression_enabled || json.len() <= compression_threshold {
+        return Ok(IpcResponse::Plain(value));
+    }
+
+    let compressed = compress(&json, encoding)?;
+    Ok(IpcResponse::Compressed(CompressedPayload {
+        compressed: true,
+        encoding,
+        original_size: json.len(),
+        data: base64::engine::general_purpose::STANDARD.encode(compressed),
+    }))
+}
+
+fn compress(bytes: &[u8], encoding: CompressionEncoding) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        CompressionEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+        // Level 3 is zstd's default and still much faster than gzip
+        CompressionEncoding::Zstd => zstd::encode_all(bytes, 3),
+    }
+}
+
+/// This process's payload directory, set up once the app knows its cache dir
+static PAYLOAD_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Create this process's payload directory under `cache_dir`, readable only
+/// by the current user. Until this runs, large responses are compressed or
+/// sent plain instead of handed off.
+pub fn init_payload_dir(cache_dir: &Path) -> std::io::Result<&'static Path> {
+    if let Some(dir) = PAYLOAD_DIR.get() {
+        return Ok(dir);
+    }
+    let dir = cache_dir.join(format!("payloads-{}", std::process::id()));
+    // Left behind by an earlier process that crashed with the same id
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(cache_dir)?;
+    create_private_dir(&dir)?;
+    Ok(PAYLOAD_DIR.get_or_init(|| dir))
+}
+
+/// Directory holding this process's handed-off payloads, once initialized
+pub fn payload_dir() -> Option<&'static Path> {
+    PAYLOAD_DIR.get().map(PathBuf::as_path)
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new().mode(0o700).create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir(dir)
+}
+
+fn write_payload(dir: &Path, json: &[u8]) -> std::io::Result<PathBuf> {
+    let mut file = tempfile::Builder::new()
+        .prefix("payload-")
+        .suffix(".json")
+        .tempfile_in(dir)?;
+    file.write_all(json)?;
+    let (_, path) = file.keep().map_err(|e| e.error)?;
+    Ok(path)
+}
+
+/// Delete a handed-off payload once the frontend has read it.
+/// Only files inside this process's `payload_dir()` may be removed.
+pub fn release_payload(path: &str) -> std::io::Result<()> {
+    let not_a_payload = || {
+        std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "Not a handed-off payload",
+        )
+    };
+    let dir = payload_dir().ok_or_else(not_a_payload)?.canonicalize()?;
+    let path = Path::new(path).canonicalize()?;
+    if path.parent() != Some(dir.as_path()) {
+        return Err(not_a_payload());
+    }
+    std::fs::remove_file(path)
+}
+
+/// Remove this process's payloads when the app exits
+pub fn clear_payloads() {
+    if let Some(dir) = payload_dir() {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,21 +1,83 @@
-mod git;
-mod watcher;
+mod cli;
+mod config;
+mod drift;
+mod editor_sync;
+mod events;
+mod fonts;
+pub mod git;
+pub mod i18n;
+pub mod ipc;
+pub mod operations;
+mod preferences;
+pub mod prompts;
+pub mod settings;
+mod themes;
+mod tools;
+pub mod watcher;
 
+use config::{patch_config, replace_config};
+use drift::DriftWatcher;
+use editor_sync::{protocol_info, session_token, EditorSyncServer, ProtocolInfo};
+use events::{events_since, EventsSince};
+use fonts::{list_monospace_fonts, MonospaceFont};
 use git::{
-    compare_branches, get_branches, get_commit_diff, get_commit_history, get_current_diff,
-    get_file_contents, get_file_patch, get_remote_url, open_repo, BranchList, CompareBranchesResult,
-    CommitDiff, CommitHistory, DiffResult, DifferConfig, RemoteInfo,
+    add_worktree, amend_commit, analyze_dependency_changes, checkout_branch, cherry_pick,
+    compare_branch_matrix, compare_branches, compare_refs, compare_tags, create_branch,
+    create_commit, create_tag, delete_branch, diff_against_archive, diff_public_api, discard_file,
+    discard_hunk, get_audit_log, get_blame, get_blame_at_parent, get_branch_delta,
+    get_branch_drift, get_branch_page, get_commit_diff, get_commit_history, get_commit_template,
+    get_compare_commits, get_conflicts, get_current_diff, get_diff_narrative, get_document_diff,
+    get_file_contents, get_file_history, get_file_hunks, get_file_patch, get_git_config,
+    get_large_repo_status, get_line_history, get_notebook_diff, get_release_delta, get_remote_url,
+    get_repo_status, get_staged_diff, get_stash_diff, get_stashes, get_svg_render_pair, get_tags,
+    get_working_changes, get_worktrees, inspect_repo, is_read_only, list_muted, merge_branch,
+    mute_file, open_repo, preview_merge, prune_worktrees, pull, push, record_operation,
+    remove_worktree, rename_branch, resolve_conflict, restore_discarded, revert_commit, stage_file,
+    stage_hunk, stage_lines, stash_apply, stash_drop, stash_save, unmute_file, unstage_file,
+    unstage_hunk, unstage_lines, verify_commit, ArchiveDiff, AuditEntry, BranchDelta, BranchInfo,
+    BranchList, BranchMatrix, BranchQuery, CheckoutResult, CommitDiff, CommitHistory, CommitInfo,
+    CommitSignature, CommitTemplate, CompareBranchesResult, ConflictList, ConflictResolution,
+    Credentials, DependencyAnalysis, DiffNarrative, DiffResult, DiffSnapshot, DiffUpdatedEvent,
+    DifferConfig, DiscardBackup, DocumentDiff, DriftReport, FileBlame, FileHistory, FileHunkPage,
+    GitConfigValue, LargeRepoStatus, LineHistory, LineSelection, MergePreview, MergeResult,
+    NotebookDiff, PassphraseRequest, PreviousBlame, PublicApiDiff, PullResult, PullStrategy,
+    PushResult, RefSnapshot, RegionSelector, ReleaseDelta, RemoteInfo, RepoInspection, RepoStatus,
+    SequencerResult, StashApplyResult, StashInfo, StashList, SvgRenderPair, TagComparison, TagInfo,
+    TagList, WorkingChanges, WorktreeList, COMPARE_COMMIT_LIMIT,
 };
-use std::path::PathBuf;
-use std::sync::Mutex;
-use tauri::{AppHandle, State};
-use watcher::FileWatcher;
+use git2::{Direction, Repository};
+use i18n::{message_catalog, set_locale, CommandError};
+use ipc::{clear_payloads, encode_response, init_payload_dir, release_payload, IpcResponse};
+use operations::{OperationGuard, OperationStatus, Operations};
+use parking_lot::Mutex;
+use preferences::{check_preferences, Preferences};
+use prompts::{PromptEvent, Prompts};
+use settings::{RepoSettings, SettingsBundle};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use themes::{list_themes, user_theme_dir, Theme, ThemeSummary};
+use tools::{detect_tools, open_in_editor, DetectedTools, EditorLaunch};
+use watcher::{FileChangeEvent, FileWatcher, WatcherStatus};
 
-// Application state
+// Application state. The mutexes are parking_lot's, which aren't poisoned by
+// a command panicking while it holds one, so one failure can't lock every
+// later command out.
 pub struct AppState {
     pub repo_path: Mutex<Option<PathBuf>>,
     pub watcher: Mutex<Option<FileWatcher>>,
+    pub drift: Mutex<Option<DriftWatcher>>,
+    pub editor_sync: Mutex<Option<EditorSyncServer>>,
+    /// Editor plugins present this to read diffs; kept across server restarts
+    pub editor_sync_token: String,
     pub config: Mutex<DifferConfig>,
+    pub preferences: Mutex<Preferences>,
+    /// Last loaded diff per view and base, for `diff-updated` deltas
+    pub diff_snapshots: Mutex<HashMap<String, DiffSnapshot>>,
+    /// Orders writes to each repository against reads, see `operations`
+    pub operations: Operations,
+    /// Questions commands are waiting on the user for, see `prompts`
+    pub prompts: Prompts,
 }
 
 impl Default for AppState {
@@ -23,148 +85,1587 @@ impl Default for AppState {
         Self {
             repo_path: Mutex::new(None),
             watcher: Mutex::new(None),
+            drift: Mutex::new(None),
+            editor_sync: Mutex::new(None),
+            editor_sync_token: session_token(),
             config: Mutex::new(DifferConfig::default()),
+            preferences: Mutex::new(Preferences::load(&Preferences::default_path())),
+            diff_snapshots: Mutex::new(HashMap::new()),
+            operations: Operations::default(),
+            prompts: Prompts::default(),
         }
     }
 }
 
+impl AppState {
+    /// Close the repository, stop its watchers and the editor sync server,
+    /// drop cached diffs and objects and reload preferences from
+    /// `preferences_path`. The config is kept.
+    pub fn reset(&self, preferences_path: &Path) {
+        *self.watcher.lock() = None;
+        *self.drift.lock() = None;
+        *self.editor_sync.lock() = None;
+        *self.repo_path.lock() = None;
+        self.diff_snapshots.lock().clear();
+        git::object_cache::global().clear();
+        *self.preferences.lock() = Preferences::load(preferences_path);
+    }
+}
+
 // Helper to get repo path
-fn get_repo_path(state: &State<AppState>) -> Result<PathBuf, String> {
+fn get_repo_path(state: &State<AppState>) -> Result<PathBuf, CommandError> {
     state
         .repo_path
         .lock()
-        .map_err(|_| "Failed to lock state".to_string())?
         .clone()
-        .ok_or_else(|| "No repository selected".to_string())
+        .ok_or_else(|| CommandError::new("error.noRepository", &[]))
+}
+
+/// Queue the write `name` on the repository at `path`, refusing it when
+/// the app or the repository is read-only. `paths` are the files it was
+/// asked to change, for the audit log. Commands that take an operation are
+/// async, so waiting for the repository doesn't block the main thread.
+fn write_operation(
+    state: &State<AppState>,
+    path: &Path,
+    name: &'static str,
+    paths: &[&str],
+) -> Result<AuditedWrite, CommandError> {
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    check_writable(state, &repo, name)?;
+    let guard = state.operations.write(path, name);
+    // Taken once the write has its turn, so earlier writes aren't counted
+    let before = RefSnapshot::new(&repo).map_err(CommandError::from)?;
+    Ok(AuditedWrite {
+        _guard: guard,
+        path: path.to_path_buf(),
+        name,
+        paths: paths.iter().map(|p| p.to_string()).collect(),
+        before,
+    })
+}
+
+/// Refuse the write `name` when the app or `repo` is read-only
+fn check_writable(
+    state: &State<AppState>,
+    repo: &Repository,
+    name: &'static str,
+) -> Result<(), CommandError> {
+    if state.config.lock().read_only {
+        return Err(CommandError::new("error.readOnly", &[("operation", name)]));
+    }
+    if is_read_only(repo) {
+        return Err(CommandError::new(
+            "error.repoReadOnly",
+            &[("operation", name)],
+        ));
+    }
+    Ok(())
+}
+
+/// A write holding its repository's queue; `finish` logs how it went
+struct AuditedWrite {
+    _guard: OperationGuard,
+    path: PathBuf,
+    name: &'static str,
+    paths: Vec<String>,
+    before: RefSnapshot,
+}
+
+impl AuditedWrite {
+    /// Record the write's outcome in the audit log and pass it through. A
+    /// log that can't be written doesn't fail a write that already happened.
+    fn finish<T>(&self, result: git::Result<T>) -> git::Result<T> {
+        let error = result.as_ref().err().map(ToString::to_string);
+        let logged = open_repo(self.path.to_str().unwrap_or(""))
+            .and_then(|repo| record_operation(&repo, self.name, &self.before, &self.paths, error));
+        if let Err(e) = logged {
+            eprintln!("Failed to audit {}: {}", self.name, e);
+        }
+        result
+    }
 }
 
 // Commands
 
 #[tauri::command]
-fn cmd_set_repo_path(path: String, state: State<AppState>, app: AppHandle) -> Result<(), String> {
-    let path = PathBuf::from(&path);
+fn cmd_set_repo_path(
+    path: String,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<(), CommandError> {
+    // Kept in plain form; `canonicalize` and some dialogs hand back verbatim
+    // `\\?\` paths on Windows
+    let path = git::paths::plain_path(Path::new(&path));
 
     // Verify it's a valid git repo
-    open_repo(path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
+    open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
 
     // Update repo path
-    *state.repo_path.lock().map_err(|_| "Failed to lock state".to_string())? = Some(path.clone());
+    let previous = state.repo_path.lock().replace(path.clone());
+    if previous.as_ref() != Some(&path) {
+        git::object_cache::global().clear();
+        state.diff_snapshots.lock().clear();
+    }
+
+    restart_file_watcher(&state, app.clone())?;
+    restart_drift_watcher(&state, app.clone())?;
+    restart_editor_sync(&state, app)
+}
+
+/// Start the session over without restarting the app: stop watching and
+/// close the repository, forget cached diffs and objects, and reload
+/// preferences from disk. The config is kept; the editor sync server is
+/// restarted with it.
+#[tauri::command]
+fn cmd_reset_state(state: State<AppState>, app: AppHandle) -> Result<(), CommandError> {
+    state.reset(&Preferences::default_path());
+    restart_editor_sync(&state, app)
+}
+
+/// (Re)start the file watcher for the open repository with the current config
+fn restart_file_watcher(state: &State<AppState>, app: AppHandle) -> Result<(), CommandError> {
+    let settings = state.config.lock().watcher.clone();
+    let Some(path) = state.repo_path.lock().clone() else {
+        return Ok(());
+    };
 
-    // Set up file watcher
-    let watcher = FileWatcher::new(&path, app).map_err(|e| e.to_string())?;
-    *state.watcher.lock().map_err(|_| "Failed to lock state".to_string())? = Some(watcher);
+    let mut current = state.watcher.lock();
+    // Drop the old watcher first so a polling one stops scanning
+    *current = None;
+    let watcher = FileWatcher::new(&path, app, &settings)
+        .map_err(|e| CommandError::new("error.watcher", &[("detail", &e.to_string())]))?;
+    *current = Some(watcher);
+    Ok(())
+}
 
+/// (Re)start drift monitoring for the open repository with the current config
+fn restart_drift_watcher(state: &State<AppState>, app: AppHandle) -> Result<(), CommandError> {
+    let monitors = state.config.lock().drift_monitors.clone();
+    let repo_path = state.repo_path.lock().clone();
+
+    let watcher = match repo_path {
+        Some(path) if !monitors.is_empty() => Some(DriftWatcher::new(path, monitors, app)),
+        _ => None,
+    };
+    *state.drift.lock() = watcher;
     Ok(())
 }
 
+/// (Re)start or stop the editor sync server to match the current config.
+/// It serves diffs with a snapshot of the config, so it restarts whenever
+/// the config changes.
+fn restart_editor_sync(state: &State<AppState>, app: AppHandle) -> Result<(), CommandError> {
+    let config = state.config.lock().clone();
+    let repo_path = state.repo_path.lock().clone();
+
+    let mut current = state.editor_sync.lock();
+    // Stop the old server first so its port is free to bind again
+    *current = None;
+    if config.editor_sync.enabled {
+        let port = config.editor_sync.port;
+        let server = EditorSyncServer::new(config, repo_path, state.editor_sync_token.clone(), app)
+            .map_err(|e| {
+                CommandError::new(
+                    "error.editorSync",
+                    &[("port", &port.to_string()), ("detail", &e.to_string())],
+                )
+            })?;
+        *current = Some(server);
+    }
+    Ok(())
+}
+
+/// Version, base URL, endpoints and session token of the editor companion
+/// protocol
 #[tauri::command]
-fn cmd_get_diff_current(state: State<AppState>) -> Result<DiffResult, String> {
+fn cmd_get_protocol_info(state: State<AppState>) -> Result<ProtocolInfo, CommandError> {
+    let config = state.config.lock();
+    Ok(protocol_info(
+        &config.editor_sync,
+        Some(&state.editor_sync_token),
+    ))
+}
+
+/// Announce which files of a freshly loaded diff changed since the last load
+/// of the same view, so the frontend can patch rather than re-mount
+fn publish_diff_delta(
+    state: &State<AppState>,
+    app: &AppHandle,
+    view: &str,
+    base_ref: Option<String>,
+    diff: &DiffResult,
+) -> Result<(), CommandError> {
+    let snapshot = DiffSnapshot::new(diff);
+    let key = format!("{}:{}", view, base_ref.as_deref().unwrap_or(""));
+    let delta = {
+        let mut snapshots = state.diff_snapshots.lock();
+        let delta = snapshot.delta(snapshots.get(&key));
+        snapshots.insert(key, snapshot);
+        delta
+    };
+    events::emit(
+        app,
+        "diff-updated",
+        DiffUpdatedEvent {
+            view: view.to_string(),
+            base_ref,
+            delta,
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command(async)]
+fn cmd_get_diff_current(
+    base_ref: Option<String>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<IpcResponse<DiffResult>, CommandError> {
     let path = get_repo_path(&state)?;
-    let repo = open_repo(path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
-    get_current_diff(&repo).map_err(|e| e.to_string())
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let diff = get_current_diff(&repo, base_ref.as_deref(), &config).map_err(CommandError::from)?;
+    publish_diff_delta(&state, &app, "current", base_ref, &diff)?;
+    encode_response(diff, &config)
 }
 
-#[tauri::command]
-fn cmd_get_diff_file(path: String, state: State<AppState>) -> Result<String, String> {
+#[tauri::command(async)]
+fn cmd_get_diff_staged(
+    base_ref: Option<String>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<IpcResponse<DiffResult>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let diff = get_staged_diff(&repo, base_ref.as_deref(), &config).map_err(CommandError::from)?;
+    publish_diff_delta(&state, &app, "staged", base_ref, &diff)?;
+    encode_response(diff, &config)
+}
+
+/// Staged and unstaged changes as separate sections
+#[tauri::command(async)]
+fn cmd_get_working_changes(
+    state: State<AppState>,
+) -> Result<IpcResponse<WorkingChanges>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let changes = get_working_changes(&repo, &config).map_err(CommandError::from)?;
+    encode_response(changes, &config)
+}
+
+/// Stage a file and return the refreshed staged/unstaged sections
+#[tauri::command(async)]
+fn cmd_stage_file(
+    path: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<WorkingChanges>, CommandError> {
     let repo_path = get_repo_path(&state)?;
-    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
-    get_file_patch(&repo, &path).map_err(|e| e.to_string())
+    let op = write_operation(&state, &repo_path, "stageFile", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    op.finish(stage_file(&repo, &path))
+        .map_err(CommandError::from)?;
+    let changes = get_working_changes(&repo, &config).map_err(CommandError::from)?;
+    encode_response(changes, &config)
 }
 
-#[tauri::command]
+/// Unstage a file and return the refreshed staged/unstaged sections
+#[tauri::command(async)]
+fn cmd_unstage_file(
+    path: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<WorkingChanges>, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "unstageFile", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    op.finish(unstage_file(&repo, &path))
+        .map_err(CommandError::from)?;
+    let changes = get_working_changes(&repo, &config).map_err(CommandError::from)?;
+    encode_response(changes, &config)
+}
+
+/// Stage one hunk of a file and return the refreshed sections
+#[tauri::command(async)]
+fn cmd_stage_hunk(
+    path: String,
+    hunk_id: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<WorkingChanges>, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "stageHunk", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    op.finish(stage_hunk(&repo, &path, &hunk_id))
+        .map_err(CommandError::from)?;
+    let changes = get_working_changes(&repo, &config).map_err(CommandError::from)?;
+    encode_response(changes, &config)
+}
+
+/// Unstage one hunk of a file and return the refreshed sections
+#[tauri::command(async)]
+fn cmd_unstage_hunk(
+    path: String,
+    hunk_id: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<WorkingChanges>, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "unstageHunk", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    op.finish(unstage_hunk(&repo, &path, &hunk_id))
+        .map_err(CommandError::from)?;
+    let changes = get_working_changes(&repo, &config).map_err(CommandError::from)?;
+    encode_response(changes, &config)
+}
+
+/// Stage selected lines of one hunk and return the refreshed sections
+#[tauri::command(async)]
+fn cmd_stage_lines(
+    path: String,
+    hunk_id: String,
+    selection: LineSelection,
+    state: State<AppState>,
+) -> Result<IpcResponse<WorkingChanges>, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "stageLines", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    op.finish(stage_lines(&repo, &path, &hunk_id, &selection))
+        .map_err(CommandError::from)?;
+    let changes = get_working_changes(&repo, &config).map_err(CommandError::from)?;
+    encode_response(changes, &config)
+}
+
+/// Unstage selected lines of one hunk and return the refreshed sections
+#[tauri::command(async)]
+fn cmd_unstage_lines(
+    path: String,
+    hunk_id: String,
+    selection: LineSelection,
+    state: State<AppState>,
+) -> Result<IpcResponse<WorkingChanges>, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "unstageLines", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    op.finish(unstage_lines(&repo, &path, &hunk_id, &selection))
+        .map_err(CommandError::from)?;
+    let changes = get_working_changes(&repo, &config).map_err(CommandError::from)?;
+    encode_response(changes, &config)
+}
+
+/// Discard a file's unstaged changes, returning a handle to undo it
+#[tauri::command(async)]
+fn cmd_discard_file(path: String, state: State<AppState>) -> Result<DiscardBackup, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "discardFile", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(discard_file(&repo, &path))
+        .map_err(CommandError::from)
+}
+
+/// Revert one unstaged hunk in the working tree, returning a handle to undo it
+#[tauri::command(async)]
+fn cmd_discard_hunk(
+    path: String,
+    hunk_id: String,
+    state: State<AppState>,
+) -> Result<DiscardBackup, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "discardHunk", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(discard_hunk(&repo, &path, &hunk_id))
+        .map_err(CommandError::from)
+}
+
+/// Undo a discard using the handle `cmd_discard_file` or `cmd_discard_hunk`
+/// returned
+#[tauri::command(async)]
+fn cmd_restore_discarded(id: String, state: State<AppState>) -> Result<(), CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "restoreDiscarded", &[])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(restore_discarded(&repo, &id))
+        .map_err(CommandError::from)
+}
+
+/// Effective value of a git config key and the files it is set in
+#[tauri::command(async)]
+fn cmd_get_git_config(key: String, state: State<AppState>) -> Result<GitConfigValue, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_git_config(&repo, &key).map_err(CommandError::from)
+}
+
+/// Check a commit's signature with gpg, gpgsm or ssh-keygen
+#[tauri::command(async)]
+fn cmd_verify_commit(sha: String, state: State<AppState>) -> Result<CommitSignature, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    verify_commit(&repo, &sha).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_diff_file(
+    path: String,
+    base_ref: Option<String>,
+    state: State<AppState>,
+) -> Result<String, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_file_patch(&repo, &path, base_ref.as_deref()).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_file_hunks(
+    path: String,
+    target: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<FileHunkPage, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_file_hunks(
+        &repo,
+        &path,
+        target.as_deref().unwrap_or("working"),
+        offset.unwrap_or(0),
+        limit.unwrap_or(50),
+    )
+    .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_diff_narrative(
+    path: String,
+    target: Option<String>,
+    state: State<AppState>,
+) -> Result<DiffNarrative, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_diff_narrative(&repo, &path, target.as_deref().unwrap_or("working"))
+        .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
 fn cmd_get_commits(
     page: Option<usize>,
     limit: Option<usize>,
     state: State<AppState>,
-) -> Result<CommitHistory, String> {
+) -> Result<CommitHistory, CommandError> {
     let path = get_repo_path(&state)?;
-    let repo = open_repo(path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
 
     let page = page.unwrap_or(1);
     let limit = limit.unwrap_or(20);
     let offset = (page - 1) * limit;
 
-    get_commit_history(&repo, limit, offset).map_err(|e| e.to_string())
+    get_commit_history(&repo, limit, offset, &config).map_err(CommandError::from)
 }
 
-#[tauri::command]
-fn cmd_get_commit(sha: String, state: State<AppState>) -> Result<CommitDiff, String> {
+#[tauri::command(async)]
+fn cmd_get_commit(
+    sha: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<CommitDiff>, CommandError> {
     let path = get_repo_path(&state)?;
-    let repo = open_repo(path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
-    get_commit_diff(&repo, &sha).map_err(|e| e.to_string())
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let diff = get_commit_diff(&repo, &sha, &config).map_err(CommandError::from)?;
+    encode_response(diff, &config)
 }
 
 #[tauri::command]
-fn cmd_get_branch_list(state: State<AppState>) -> Result<BranchList, String> {
+fn cmd_release_payload(path: String) -> Result<(), CommandError> {
+    release_payload(&path).map_err(CommandError::from)
+}
+
+/// Local branches; pass `query` to filter and page through large lists
+#[tauri::command(async)]
+fn cmd_get_branch_list(
+    query: Option<BranchQuery>,
+    state: State<AppState>,
+) -> Result<BranchList, CommandError> {
     let path = get_repo_path(&state)?;
-    let repo = open_repo(path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
-    get_branches(&repo).map_err(|e| e.to_string())
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_branch_page(&repo, &query.unwrap_or_default()).map_err(CommandError::from)
 }
 
-#[tauri::command]
+/// Create a branch at `start_point` (any revision, HEAD by default)
+#[tauri::command(async)]
+fn cmd_create_branch(
+    name: String,
+    start_point: Option<String>,
+    state: State<AppState>,
+) -> Result<BranchInfo, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "createBranch", &[])?;
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(create_branch(
+        &repo,
+        &name,
+        start_point.as_deref().unwrap_or("HEAD"),
+    ))
+    .map_err(CommandError::from)
+}
+
+/// Delete a branch, returning the sha it pointed at; unmerged branches need
+/// `force`
+#[tauri::command(async)]
+fn cmd_delete_branch(
+    name: String,
+    force: Option<bool>,
+    state: State<AppState>,
+) -> Result<String, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "deleteBranch", &[])?;
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(delete_branch(&repo, &name, force.unwrap_or(false)))
+        .map_err(CommandError::from)
+}
+
+/// Rename a branch; `force` replaces an existing branch with the new name
+#[tauri::command(async)]
+fn cmd_rename_branch(
+    old_name: String,
+    new_name: String,
+    force: Option<bool>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<BranchInfo, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "renameBranch", &[])?;
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let info = op
+        .finish(rename_branch(
+            &repo,
+            &old_name,
+            &new_name,
+            force.unwrap_or(false),
+        ))
+        .map_err(CommandError::from)?;
+    if info.current {
+        events::emit(&app, "file-change", FileChangeEvent::head());
+    }
+    Ok(info)
+}
+
+#[tauri::command(async)]
+fn cmd_get_tags(state: State<AppState>) -> Result<TagList, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_tags(&repo).map_err(CommandError::from)
+}
+
+/// Tag a revision; a message makes the tag annotated, `sign` signs it
+#[tauri::command(async)]
+fn cmd_create_tag(
+    name: String,
+    target_ref: String,
+    message: Option<String>,
+    sign: Option<bool>,
+    state: State<AppState>,
+) -> Result<TagInfo, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "createTag", &[])?;
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(create_tag(
+        &repo,
+        &name,
+        &target_ref,
+        message.as_deref(),
+        sign,
+    ))
+    .map_err(CommandError::from)
+}
+
+/// Diff and changelog between two release tags
+#[tauri::command(async)]
+fn cmd_compare_tags(
+    from: String,
+    to: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<TagComparison>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let result = compare_tags(&repo, &from, &to, &config).map_err(CommandError::from)?;
+    encode_response(result, &config)
+}
+
+/// Commits, combined diff and grouped changelog between two releases, in
+/// either order
+#[tauri::command(async)]
+fn cmd_get_release_delta(
+    tag_a: String,
+    tag_b: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<ReleaseDelta>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let result = get_release_delta(&repo, &tag_a, &tag_b, &config).map_err(CommandError::from)?;
+    encode_response(result, &config)
+}
+
+#[tauri::command(async)]
+fn cmd_get_stashes(state: State<AppState>) -> Result<StashList, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let mut repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_stashes(&mut repo).map_err(CommandError::from)
+}
+
+/// Changes held in `stash@{index}`, including its untracked files
+#[tauri::command(async)]
+fn cmd_get_stash_diff(
+    index: usize,
+    state: State<AppState>,
+) -> Result<IpcResponse<DiffResult>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let result = get_stash_diff(&repo, index, &config).map_err(CommandError::from)?;
+    encode_response(result, &config)
+}
+
+#[tauri::command(async)]
+fn cmd_stash_save(
+    message: Option<String>,
+    include_untracked: Option<bool>,
+    state: State<AppState>,
+) -> Result<StashInfo, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "stashSave", &[])?;
+    let mut repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(stash_save(
+        &mut repo,
+        message.as_deref(),
+        include_untracked.unwrap_or(false),
+    ))
+    .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_stash_apply(index: usize, state: State<AppState>) -> Result<StashApplyResult, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "stashApply", &[])?;
+    let mut repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(stash_apply(&mut repo, index, false))
+        .map_err(CommandError::from)
+}
+
+/// Apply and drop a stash; it is kept when applying leaves conflicts
+#[tauri::command(async)]
+fn cmd_stash_pop(index: usize, state: State<AppState>) -> Result<StashApplyResult, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "stashPop", &[])?;
+    let mut repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(stash_apply(&mut repo, index, true))
+        .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_stash_drop(index: usize, state: State<AppState>) -> Result<StashList, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "stashDrop", &[])?;
+    let mut repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(stash_drop(&mut repo, index))
+        .map_err(CommandError::from)
+}
+
+/// Diff a CI source archive against the working tree or `base_ref`
+#[tauri::command(async)]
+fn cmd_diff_against_archive(
+    archive_path: String,
+    base_ref: Option<String>,
+    state: State<AppState>,
+) -> Result<IpcResponse<ArchiveDiff>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let result = diff_against_archive(
+        &repo,
+        Path::new(&archive_path),
+        base_ref.as_deref(),
+        &config,
+    )
+    .map_err(CommandError::from)?;
+    encode_response(result, &config)
+}
+
+#[tauri::command(async)]
 fn cmd_compare_branch(
     base: String,
     head: String,
     state: State<AppState>,
-) -> Result<CompareBranchesResult, String> {
+) -> Result<IpcResponse<CompareBranchesResult>, CommandError> {
     let path = get_repo_path(&state)?;
-    let repo = open_repo(path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
-    compare_branches(&repo, &base, &head).map_err(|e| e.to_string())
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let result = compare_branches(&repo, &base, &head, &config).map_err(CommandError::from)?;
+    encode_response(result, &config)
 }
 
-#[tauri::command]
+#[tauri::command(async)]
+fn cmd_compare_refs(
+    base: String,
+    head: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<CompareBranchesResult>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let result = compare_refs(&repo, &base, &head, &config).map_err(CommandError::from)?;
+    encode_response(result, &config)
+}
+
+/// Page through the commits of a comparison beyond those returned inline
+#[tauri::command(async)]
+fn cmd_get_compare_commits(
+    base: String,
+    head: String,
+    page: Option<usize>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<CommitHistory, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+
+    let page = page.unwrap_or(1).max(1);
+    let limit = limit.unwrap_or(COMPARE_COMMIT_LIMIT);
+    let offset = (page - 1) * limit;
+
+    get_compare_commits(&repo, &base, &head, limit, offset, &config).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_compare_branch_matrix(
+    branches: Vec<String>,
+    state: State<AppState>,
+) -> Result<BranchMatrix, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    compare_branch_matrix(&repo, &branches).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_worktree_add(
+    path: String,
+    branch: String,
+    base: Option<String>,
+    state: State<AppState>,
+) -> Result<WorktreeList, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "worktreeAdd", &[])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(add_worktree(&repo, &path, &branch, base.as_deref()))
+        .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_worktree_remove(
+    path: String,
+    force: Option<bool>,
+    state: State<AppState>,
+) -> Result<WorktreeList, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "worktreeRemove", &[])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(remove_worktree(&repo, &path, force.unwrap_or(false)))
+        .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_worktree_prune(state: State<AppState>) -> Result<Vec<String>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "worktreePrune", &[])?;
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(prune_worktrees(&repo))
+        .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_branch_drift(state: State<AppState>) -> Result<Vec<DriftReport>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let monitors = state.config.lock().drift_monitors.clone();
+    get_branch_drift(&repo, &monitors).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_blame_at_parent(
+    path: String,
+    line: usize,
+    commit: String,
+    state: State<AppState>,
+) -> Result<PreviousBlame, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_blame_at_parent(&repo, &path, line, &commit).map_err(CommandError::from)
+}
+
+/// Commit the staged changes on the current branch
+#[tauri::command(async)]
+fn cmd_commit(message: String, state: State<AppState>) -> Result<CommitInfo, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "commit", &[])?;
+    let config = state.config.lock().clone();
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(create_commit(&repo, &message, &config.hooks))
+        .map_err(CommandError::from)
+}
+
+/// Switch to a local branch. With `auto_stash`, local changes are stashed
+/// and reapplied instead of refusing the switch.
+#[tauri::command(async)]
+fn cmd_checkout_branch(
+    name: String,
+    auto_stash: Option<bool>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<CheckoutResult, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "checkoutBranch", &[])?;
+    let mut repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let result = op
+        .finish(checkout_branch(
+            &mut repo,
+            &name,
+            auto_stash.unwrap_or(false),
+        ))
+        .map_err(CommandError::from)?;
+    // The watcher may be off or slow to notice, so say so directly
+    events::emit(&app, "file-change", FileChangeEvent::head());
+    Ok(result)
+}
+
+/// Revert a commit on top of HEAD. Conflicts are reported and left in the
+/// working tree to resolve and commit.
+#[tauri::command(async)]
+fn cmd_revert_commit(
+    sha: String,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<SequencerResult, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "revertCommit", &[])?;
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let result = op
+        .finish(revert_commit(&repo, &sha))
+        .map_err(CommandError::from)?;
+    events::emit(&app, "file-change", FileChangeEvent::head());
+    Ok(result)
+}
+
+/// Apply a commit's change onto the current branch. Conflicts are reported
+/// and left in the working tree to resolve and commit.
+#[tauri::command(async)]
+fn cmd_cherry_pick(
+    sha: String,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<SequencerResult, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "cherryPick", &[])?;
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let result = op
+        .finish(cherry_pick(&repo, &sha))
+        .map_err(CommandError::from)?;
+    events::emit(&app, "file-change", FileChangeEvent::head());
+    Ok(result)
+}
+
+/// Merge a branch into the current one, fast-forwarding when possible.
+/// Conflicts are reported and left in the working tree to resolve and
+/// commit.
+#[tauri::command(async)]
+fn cmd_merge_branch(
+    name: String,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<MergeResult, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "mergeBranch", &[])?;
+    let hooks = state.config.lock().hooks.clone();
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let result = op
+        .finish(merge_branch(&repo, &name, &hooks))
+        .map_err(CommandError::from)?;
+    events::emit(&app, "file-change", FileChangeEvent::head());
+    Ok(result)
+}
+
+/// Asks the frontend for a key's passphrase with a `passphrase-request`
+/// event and waits for `cmd_answer_prompt`
+fn passphrase_prompt<'a>(
+    state: &'a State<AppState>,
+    app: &'a AppHandle,
+) -> impl Fn(&PassphraseRequest) -> Option<String> + 'a {
+    move |request| {
+        state.prompts.ask(|id| {
+            events::emit(
+                app,
+                "passphrase-request",
+                PromptEvent {
+                    id,
+                    request: request.clone(),
+                },
+            )
+        })
+    }
+}
+
+/// Fetch and integrate the current branch's upstream by merging, rebasing
+/// or fast-forwarding only; without a strategy, the one `pull.rebase` and
+/// `pull.ff` configure. Passphrases are asked for before the pull takes the
+/// repository, so other commands don't wait on the user.
+#[tauri::command(async)]
+fn cmd_pull(
+    strategy: Option<PullStrategy>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<PullResult, CommandError> {
+    let path = get_repo_path(&state)?;
+    let config = state.config.lock().clone();
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    check_writable(&state, &repo, "pull")?;
+    let prompt = passphrase_prompt(&state, &app);
+    let mut credentials = Credentials::new(&config.ssh_keys).with_prompt(&prompt);
+    if let Some(remote) = git::remote::upstream_remote(&repo) {
+        credentials.unlock(&repo, &remote, Direction::Fetch);
+    }
+    let op = write_operation(&state, &path, "pull", &[])?;
+    let result = op
+        .finish(pull(&repo, strategy, &credentials, &config.hooks))
+        .map_err(CommandError::from)?;
+    events::emit(&app, "file-change", FileChangeEvent::head());
+    Ok(result)
+}
+
+/// Push `refspec` (the current branch by default) to `remote`, sending
+/// `push-progress` events as it goes. `force_with_lease` overwrites the
+/// remote ref only if it is where it was when last fetched. Asks for
+/// passphrases up front like `cmd_pull`.
+#[tauri::command(async)]
+fn cmd_push(
+    remote: Option<String>,
+    refspec: Option<String>,
+    force_with_lease: Option<bool>,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<PushResult, CommandError> {
+    let path = get_repo_path(&state)?;
+    let config = state.config.lock().clone();
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    check_writable(&state, &repo, "push")?;
+    let prompt = passphrase_prompt(&state, &app);
+    let mut credentials = Credentials::new(&config.ssh_keys).with_prompt(&prompt);
+    let target = git::remote::push_remote(&repo, remote.as_deref());
+    credentials.unlock(&repo, &target, Direction::Push);
+    let op = write_operation(&state, &path, "push", &[])?;
+    let result = op
+        .finish(push(
+            &repo,
+            remote.as_deref(),
+            refspec.as_deref(),
+            force_with_lease.unwrap_or(false),
+            &config.hooks,
+            &credentials,
+            |progress| events::emit(&app, "push-progress", progress),
+        ))
+        .map_err(CommandError::from)?;
+    // Remote-tracking refs moved
+    events::emit(&app, "file-change", FileChangeEvent::head());
+    Ok(result)
+}
+
+/// Conflicted files with their ancestor, ours, theirs and working tree
+/// contents
+#[tauri::command(async)]
+fn cmd_get_conflicts(state: State<AppState>) -> Result<IpcResponse<ConflictList>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let result = get_conflicts(&repo).map_err(CommandError::from)?;
+    encode_response(result, &config)
+}
+
+/// Resolve a conflicted file with ours, theirs or merged content and stage
+/// it; returns the files still conflicted
+#[tauri::command(async)]
+fn cmd_resolve_conflict(
+    path: String,
+    resolution: ConflictResolution,
+    state: State<AppState>,
+) -> Result<Vec<String>, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let op = write_operation(&state, &repo_path, "resolveConflict", &[&path])?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(resolve_conflict(&repo, &path, &resolution))
+        .map_err(CommandError::from)
+}
+
+/// Work out merging `theirs` into `ours` without touching the repository
+#[tauri::command(async)]
+fn cmd_preview_merge(
+    ours: String,
+    theirs: String,
+    state: State<AppState>,
+) -> Result<IpcResponse<MergePreview>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    let result = preview_merge(&repo, &ours, &theirs, &config).map_err(CommandError::from)?;
+    encode_response(result, &config)
+}
+
+/// Amend HEAD with the staged changes; `force` allows amending a commit that
+/// is already on a remote
+#[tauri::command(async)]
+fn cmd_commit_amend(
+    message: Option<String>,
+    force: Option<bool>,
+    state: State<AppState>,
+) -> Result<CommitInfo, CommandError> {
+    let path = get_repo_path(&state)?;
+    let op = write_operation(&state, &path, "commitAmend", &[])?;
+    let config = state.config.lock().clone();
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    op.finish(amend_commit(
+        &repo,
+        message.as_deref(),
+        force.unwrap_or(false),
+        &config.hooks,
+    ))
+    .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_commit_template(state: State<AppState>) -> Result<CommitTemplate, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_commit_template(&repo).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_file_history(
+    path: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    state: State<AppState>,
+) -> Result<FileHistory, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let config = state.config.lock().clone();
+    get_file_history(
+        &repo,
+        &path,
+        limit.unwrap_or(20),
+        offset.unwrap_or(0),
+        &config,
+    )
+    .map_err(CommandError::from)
+}
+
+/// Repository size and the features huge-repository mode turns off
+#[tauri::command(async)]
+fn cmd_get_large_repo_status(state: State<AppState>) -> Result<LargeRepoStatus, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let config = state.config.lock().clone();
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    Ok(get_large_repo_status(&repo, &config.large_repo))
+}
+
+#[tauri::command(async)]
+fn cmd_get_line_history(
+    path: String,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+    funcname: Option<String>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<LineHistory, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let selector = match funcname.as_deref() {
+        Some(pattern) => RegionSelector::Function(pattern),
+        None => {
+            let start = start_line.unwrap_or(1);
+            RegionSelector::Lines {
+                start,
+                end: end_line.unwrap_or(start),
+            }
+        }
+    };
+    get_line_history(&repo, &path, selector, limit.unwrap_or(50)).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_blame(
+    path: String,
+    git_ref: Option<String>,
+    state: State<AppState>,
+) -> Result<FileBlame, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_blame(&repo, &path, git_ref.as_deref()).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_worktrees(state: State<AppState>) -> Result<WorktreeList, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_worktrees(&repo).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_branch_delta(
+    base_ref: Option<String>,
+    state: State<AppState>,
+) -> Result<BranchDelta, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_branch_delta(&repo, base_ref.as_deref()).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_analyze_dependency_changes(
+    base: String,
+    head: String,
+    state: State<AppState>,
+) -> Result<DependencyAnalysis, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    analyze_dependency_changes(&repo, &base, &head).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_diff_public_api(
+    base: String,
+    head: String,
+    crate_path: Option<String>,
+    state: State<AppState>,
+) -> Result<PublicApiDiff, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    diff_public_api(&repo, &base, &head, crate_path.as_deref().unwrap_or(""))
+        .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
 fn cmd_get_file(
     path: String,
     git_ref: Option<String>,
     state: State<AppState>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     let repo_path = get_repo_path(&state)?;
-    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
-    get_file_contents(&repo, &path, git_ref.as_deref()).map_err(|e| e.to_string())
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_file_contents(&repo, &path, git_ref.as_deref()).map_err(CommandError::from)
 }
 
+#[tauri::command(async)]
+fn cmd_get_notebook_diff(
+    path: String,
+    base: Option<String>,
+    head: Option<String>,
+    strip_outputs: Option<bool>,
+    state: State<AppState>,
+) -> Result<NotebookDiff, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let base = base.unwrap_or_else(|| "HEAD".to_string());
+    get_notebook_diff(
+        &repo,
+        &path,
+        Some(&base),
+        head.as_deref(),
+        strip_outputs.unwrap_or(true),
+    )
+    .map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_svg_render_pair(
+    path: String,
+    base: Option<String>,
+    head: Option<String>,
+    state: State<AppState>,
+) -> Result<SvgRenderPair, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let base = base.unwrap_or_else(|| "HEAD".to_string());
+    get_svg_render_pair(&repo, &path, Some(&base), head.as_deref()).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_document_diff(
+    path: String,
+    base: Option<String>,
+    head: Option<String>,
+    state: State<AppState>,
+) -> Result<DocumentDiff, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let _op = state.operations.read(&repo_path);
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    let converters = state.config.lock().textconv.clone();
+    let base = base.unwrap_or_else(|| "HEAD".to_string());
+    get_document_diff(&repo, &path, Some(&base), head.as_deref(), &converters)
+        .map_err(CommandError::from)
+}
+
+/// Hide `path` in `comparison`. The muted list lives in the git dir, so
+/// this counts as a write: it is refused in read-only mode and queued
+/// behind other writes, though it isn't audited since no ref or file in the
+/// working tree changes.
+#[tauri::command(async)]
+fn cmd_mute_file(
+    path: String,
+    comparison: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<String>, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    check_writable(&state, &repo, "muteFile")?;
+    let _op = state.operations.write(&repo_path, "muteFile");
+    mute_file(&repo, comparison.as_deref().unwrap_or(""), &path).map_err(CommandError::from)
+}
+
+/// Show `path` in `comparison` again; a write like `cmd_mute_file`
+#[tauri::command(async)]
+fn cmd_unmute_file(
+    path: String,
+    comparison: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<String>, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    check_writable(&state, &repo, "unmuteFile")?;
+    let _op = state.operations.write(&repo_path, "unmuteFile");
+    unmute_file(&repo, comparison.as_deref().unwrap_or(""), &path).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_list_muted(
+    comparison: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<String>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    list_muted(&repo, comparison.as_deref().unwrap_or("")).map_err(CommandError::from)
+}
+
+#[tauri::command(async)]
+fn cmd_get_repo_status(state: State<AppState>) -> Result<RepoStatus, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_repo_status(&repo).map_err(CommandError::from)
+}
+
+/// Status of any repository without opening it for review or watching it,
+/// for scanning many repositories at once
+#[tauri::command(async)]
+fn cmd_inspect_repo(path: String) -> Result<RepoInspection, CommandError> {
+    inspect_repo(&path).map_err(CommandError::from)
+}
+
+/// What the open repository's operation queue is running and has waiting
+#[tauri::command(async)]
+fn cmd_get_operation_status(state: State<AppState>) -> Result<OperationStatus, CommandError> {
+    let path = get_repo_path(&state)?;
+    Ok(state.operations.status(&path))
+}
+
+/// Answer the prompt `id` from a `passphrase-request` event; `None`
+/// declines it
+#[tauri::command(async)]
+fn cmd_answer_prompt(
+    id: u64,
+    answer: Option<String>,
+    state: State<AppState>,
+) -> Result<(), CommandError> {
+    if state.prompts.answer(id, answer) {
+        Ok(())
+    } else {
+        Err(CommandError::new("error.promptExpired", &[]))
+    }
+}
+
+/// Writes the app made to the open repository, newest first
+#[tauri::command(async)]
+fn cmd_get_audit_log(
+    limit: Option<usize>,
+    offset: Option<usize>,
+    state: State<AppState>,
+) -> Result<Vec<AuditEntry>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_audit_log(&repo, limit.unwrap_or(100), offset.unwrap_or(0)).map_err(CommandError::from)
+}
+
+/// Whether commands that change the open repository are refused, by the
+/// app's `readOnly` setting or the repository's own
 #[tauri::command]
-fn cmd_get_remote(state: State<AppState>) -> Result<Option<RemoteInfo>, String> {
+fn cmd_is_read_only(state: State<AppState>) -> Result<bool, CommandError> {
+    if state.config.lock().read_only {
+        return Ok(true);
+    }
     let path = get_repo_path(&state)?;
-    let repo = open_repo(path.to_str().unwrap_or("")).map_err(|e| e.to_string())?;
-    get_remote_url(&repo).map_err(|e| e.to_string())
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    Ok(is_read_only(&repo))
+}
+
+#[tauri::command(async)]
+fn cmd_get_remote(state: State<AppState>) -> Result<Option<RemoteInfo>, CommandError> {
+    let path = get_repo_path(&state)?;
+    let _op = state.operations.read(&path);
+    let repo = open_repo(path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+    get_remote_url(&repo).map_err(CommandError::from)
 }
 
 #[tauri::command]
-fn cmd_get_config(state: State<AppState>) -> Result<DifferConfig, String> {
-    let config = state.config.lock().map_err(|_| "Failed to lock state".to_string())?;
+fn cmd_get_config(state: State<AppState>) -> Result<DifferConfig, CommandError> {
+    let config = state.config.lock();
     Ok(config.clone())
 }
 
+/// Replace the config. `config.revision` must match the current revision;
+/// returns the stored config with its new revision.
 #[tauri::command]
-fn cmd_set_config(config: DifferConfig, state: State<AppState>) -> Result<(), String> {
-    *state.config.lock().map_err(|_| "Failed to lock state".to_string())? = config;
-    Ok(())
+fn cmd_set_config(
+    config: DifferConfig,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<DifferConfig, CommandError> {
+    let (previous, updated) = {
+        let mut current = state.config.lock();
+        let updated = replace_config(&current, config)?;
+        (std::mem::replace(&mut *current, updated.clone()), updated)
+    };
+    apply_config(&state, app, &previous, &updated)?;
+    Ok(updated)
 }
 
+/// Update individual config fields with a JSON merge patch
 #[tauri::command]
-fn cmd_open_in_editor(file_path: String, editor: String, state: State<AppState>) -> Result<(), String> {
-    let repo_path = get_repo_path(&state)?;
-    let full_path = repo_path.join(&file_path);
+fn cmd_patch_config(
+    partial: serde_json::Value,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<DifferConfig, CommandError> {
+    let (previous, updated) = {
+        let mut current = state.config.lock();
+        let updated = patch_config(&current, &partial)?;
+        (std::mem::replace(&mut *current, updated.clone()), updated)
+    };
+    apply_config(&state, app, &previous, &updated)?;
+    Ok(updated)
+}
 
-    let editor_cmd = match editor.as_str() {
-        "vscode" => "code",
-        "cursor" => "cursor",
-        "zed" => "zed",
-        "sublime" => "subl",
-        "webstorm" => "webstorm",
-        "idea" => "idea",
-        _ => "code", // Default to VS Code
+/// Bring locale and background watchers in line with a newly stored config
+fn apply_config(
+    state: &State<AppState>,
+    app: AppHandle,
+    previous: &DifferConfig,
+    updated: &DifferConfig,
+) -> Result<(), CommandError> {
+    set_locale(&updated.locale);
+    if previous.watcher != updated.watcher {
+        restart_file_watcher(state, app.clone())?;
+    }
+    restart_editor_sync(state, app.clone())?;
+    restart_drift_watcher(state, app)
+}
+
+/// Write config, preferences and the open repository's overrides to one
+/// portable JSON file
+#[tauri::command]
+fn cmd_export_settings(path: String, state: State<AppState>) -> Result<(), CommandError> {
+    let config = state.config.lock().clone();
+    let preferences = state.preferences.lock().all().clone();
+    let repository = match get_repo_path(&state) {
+        Ok(repo_path) => {
+            let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+            Some(RepoSettings::read(&repo).map_err(CommandError::from)?)
+        }
+        Err(_) => None,
     };
 
-    std::process::Command::new(editor_cmd)
-        .arg(full_path)
-        .spawn()
-        .map_err(|e| format!("Failed to open editor: {}", e))?;
+    SettingsBundle::new(config, preferences, repository).write(&PathBuf::from(path))
+}
 
-    Ok(())
+/// Load a file written by `cmd_export_settings`. Repository overrides are
+/// applied to the currently open repository, if any, as a write to it.
+/// Returns the new config.
+///
+/// The whole bundle is checked before anything changes, and when saving
+/// one part fails the parts saved before it are put back.
+#[tauri::command(async)]
+fn cmd_import_settings(
+    path: String,
+    state: State<AppState>,
+    app: AppHandle,
+) -> Result<DifferConfig, CommandError> {
+    let mut bundle = SettingsBundle::read(&PathBuf::from(path))?;
+    check_preferences(&bundle.preferences)?;
+
+    let repository = match (&bundle.repository, get_repo_path(&state)) {
+        (Some(settings), Ok(repo_path)) => {
+            let op = write_operation(&state, &repo_path, "importSettings", &[".diffyrc.json"])?;
+            let repo = open_repo(repo_path.to_str().unwrap_or("")).map_err(CommandError::from)?;
+            let previous = op
+                .finish(settings.apply(&repo))
+                .map_err(CommandError::from)?;
+            Some((op, repo, previous))
+        }
+        _ => None,
+    };
+
+    let preferences = std::mem::take(&mut bundle.preferences);
+    if let Err(e) = state.preferences.lock().replace_all(preferences) {
+        if let Some((_op, repo, previous)) = &repository {
+            let _ = previous.restore(repo);
+        }
+        return Err(e);
+    }
+    drop(repository);
+
+    let (previous, updated) = {
+        let mut current = state.config.lock();
+        let updated = bundle.imported_config(&current);
+        (std::mem::replace(&mut *current, updated.clone()), updated)
+    };
+    apply_config(&state, app, &previous, &updated)?;
+    Ok(updated)
+}
+
+/// All stored UI preferences, keyed by namespace
+#[tauri::command]
+fn cmd_get_preferences(
+    state: State<AppState>,
+) -> Result<serde_json::Map<String, serde_json::Value>, CommandError> {
+    let preferences = state.preferences.lock();
+    Ok(preferences.all().clone())
 }
 
+/// Replace one preference namespace (`keybindings`, `layout`, `theme` or
+/// `ui`) and save it to disk; `null` clears the namespace
+#[tauri::command]
+fn cmd_set_preferences(
+    namespace: String,
+    json: serde_json::Value,
+    state: State<AppState>,
+) -> Result<serde_json::Value, CommandError> {
+    let mut preferences = state.preferences.lock();
+    preferences.set(&namespace, json)
+}
+
+/// Installed fixed-pitch fonts, for the font picker in settings
+#[tauri::command]
+fn cmd_list_monospace_fonts() -> Vec<MonospaceFont> {
+    list_monospace_fonts()
+}
+
+/// Built-in and user-provided syntax/diff themes
+#[tauri::command]
+fn cmd_list_themes() -> Vec<ThemeSummary> {
+    list_themes(&user_theme_dir())
+}
+
+/// Resolved colors and token styles for one theme
+#[tauri::command]
+fn cmd_get_theme(name: String) -> Result<Theme, CommandError> {
+    themes::get_theme(&user_theme_dir(), &name)
+}
+
+#[tauri::command]
+fn cmd_get_watcher_status(state: State<AppState>) -> Result<WatcherStatus, CommandError> {
+    let watcher = state.watcher.lock();
+    Ok(watcher
+        .as_ref()
+        .map(FileWatcher::status)
+        .unwrap_or_else(WatcherStatus::inactive))
+}
+
+#[tauri::command]
+fn cmd_get_events_since(cursor: Option<u64>) -> EventsSince {
+    events_since(cursor)
+}
+
+#[tauri::command]
+fn cmd_get_messages() -> HashMap<String, String> {
+    message_catalog()
+}
+
+/// Installed editors and terminals, so settings only offer ones that will launch
+#[tauri::command]
+fn cmd_detect_tools() -> DetectedTools {
+    detect_tools()
+}
+
+/// Open a repository file in the chosen editor, reporting how it was launched.
+/// `line` is a new-file line number from the diff, so it matches the file on disk.
+#[tauri::command]
+fn cmd_open_in_editor(
+    file_path: String,
+    editor: String,
+    line: Option<u32>,
+    column: Option<u32>,
+    state: State<AppState>,
+) -> Result<EditorLaunch, CommandError> {
+    let repo_path = get_repo_path(&state)?;
+    let file = git::paths::join_relative(&repo_path, &file_path);
+    open_in_editor(&editor, &git::paths::plain_path(&file), line, column)
+}
+
+pub use cli::{run_json, run_json_to};
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -173,20 +1674,113 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
         .manage(AppState::default())
+        .setup(|app| {
+            init_payload_dir(&app.path().app_cache_dir()?)?;
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             cmd_set_repo_path,
+            cmd_reset_state,
             cmd_get_diff_current,
+            cmd_get_diff_staged,
+            cmd_get_working_changes,
+            cmd_get_protocol_info,
+            cmd_get_git_config,
+            cmd_verify_commit,
+            cmd_stage_file,
+            cmd_unstage_file,
+            cmd_stage_hunk,
+            cmd_unstage_hunk,
+            cmd_stage_lines,
+            cmd_unstage_lines,
+            cmd_discard_file,
+            cmd_discard_hunk,
+            cmd_restore_discarded,
             cmd_get_diff_file,
+            cmd_get_file_hunks,
+            cmd_get_diff_narrative,
             cmd_get_commits,
             cmd_get_commit,
+            cmd_release_payload,
             cmd_get_branch_list,
+            cmd_create_branch,
+            cmd_delete_branch,
+            cmd_rename_branch,
+            cmd_get_tags,
+            cmd_create_tag,
+            cmd_compare_tags,
+            cmd_get_release_delta,
+            cmd_get_stashes,
+            cmd_get_stash_diff,
+            cmd_stash_save,
+            cmd_stash_apply,
+            cmd_stash_pop,
+            cmd_stash_drop,
             cmd_compare_branch,
+            cmd_diff_against_archive,
+            cmd_compare_refs,
+            cmd_get_compare_commits,
+            cmd_compare_branch_matrix,
+            cmd_get_branch_delta,
+            cmd_get_worktrees,
+            cmd_worktree_add,
+            cmd_worktree_remove,
+            cmd_worktree_prune,
+            cmd_get_branch_drift,
+            cmd_analyze_dependency_changes,
+            cmd_diff_public_api,
             cmd_get_file,
+            cmd_get_file_history,
+            cmd_get_large_repo_status,
+            cmd_get_line_history,
+            cmd_get_blame,
+            cmd_get_blame_at_parent,
+            cmd_get_commit_template,
+            cmd_commit,
+            cmd_commit_amend,
+            cmd_checkout_branch,
+            cmd_revert_commit,
+            cmd_cherry_pick,
+            cmd_merge_branch,
+            cmd_pull,
+            cmd_push,
+            cmd_preview_merge,
+            cmd_get_conflicts,
+            cmd_resolve_conflict,
+            cmd_get_notebook_diff,
+            cmd_get_svg_render_pair,
+            cmd_get_document_diff,
+            cmd_mute_file,
+            cmd_unmute_file,
+            cmd_list_muted,
+            cmd_get_repo_status,
+            cmd_inspect_repo,
+            cmd_get_operation_status,
+            cmd_answer_prompt,
+            cmd_is_read_only,
+            cmd_get_audit_log,
             cmd_get_remote,
             cmd_get_config,
             cmd_set_config,
+            cmd_patch_config,
+            cmd_get_preferences,
+            cmd_set_preferences,
+            cmd_export_settings,
+            cmd_import_settings,
+            cmd_list_themes,
+            cmd_get_theme,
+            cmd_list_monospace_fonts,
+            cmd_get_messages,
+            cmd_get_watcher_status,
+            cmd_get_events_since,
+            cmd_detect_tools,
             cmd_open_in_editor,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_, event| {
+            if let tauri::RunEvent::Exit = event {
+                clear_payloads();
+            }
+        });
 }
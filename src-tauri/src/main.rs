@@ -2,5 +2,29 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // `diffy json <command>` (or `--json`) runs headless for scripts and CI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if matches!(args.first().map(String::as_str), Some("json" | "--json")) {
+        attach_parent_console();
+        std::process::exit(diffy_lib::run_json(&args[1..]));
+    }
+
     diffy_lib::run()
 }
+
+/// Release builds on Windows have no console of their own, so output meant
+/// for a terminal would be lost; borrow the one of the shell that started
+/// us. cmd.exe doesn't wait for GUI programs, so the output can land after
+/// its next prompt: use `start /wait diffy json ...`, or redirect the
+/// output, which works without a console.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // Fails harmlessly when the parent has no console, e.g. output is piped
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
@@ -0,0 +1,130 @@
+//! Serializing repository writes against reads.
+//!
+//! Staging, committing or checking out rewrites the index and the working
+//! tree. A diff read halfway through sees a mix of before and after, and two
+//! writes at once can lose one's index update. Each repository gets a
+//! readers-writer lock: reads share it, while a write waits for the reads in
+//! flight, runs alone, and holds new reads back until it is done. The lock
+//! is fair, so a stream of reads can't starve a waiting write.
+//!
+//! Writes waiting their turn are listed by name so the UI can show what is
+//! queued behind a slow hook or checkout.
+
+use parking_lot::lock_api::{ArcRwLockReadGuard, ArcRwLockWriteGuard};
+use parking_lot::{Mutex, RawRwLock, RwLock};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// What one repository's queue is doing
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationStatus {
+    /// The write holding the repository
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub running: Option<String>,
+    /// Writes waiting for it, in arrival order
+    pub queued: Vec<String>,
+    /// Reads in progress
+    pub readers: usize,
+}
+
+/// Operation queues by repository path
+#[derive(Default)]
+pub struct Operations {
+    queues: Mutex<HashMap<PathBuf, Arc<Queue>>>,
+}
+
+#[derive(Default)]
+struct Queue {
+    lock: Arc<RwLock<()>>,
+    status: Mutex<Waiting>,
+}
+
+#[derive(Default)]
+struct Waiting {
+    next_ticket: u64,
+    status: OperationStatus,
+    /// Tickets of the queued writes, matching `status.queued`
+    tickets: Vec<u64>,
+}
+
+/// Held for the duration of an operation; dropping it lets the next one in
+pub struct OperationGuard {
+    queue: Arc<Queue>,
+    lock: Held,
+}
+
+enum Held {
+    Read(#[allow(dead_code)] ArcRwLockReadGuard<RawRwLock, ()>),
+    Write(#[allow(dead_code)] ArcRwLockWriteGuard<RawRwLock, ()>),
+}
+
+impl Operations {
+    /// Wait until no write is running or queued on `repo`, then read
+    pub fn read(&self, repo: &Path) -> OperationGuard {
+        let queue = self.queue(repo);
+        let guard = queue.lock.read_arc();
+        queue.status.lock().status.readers += 1;
+        OperationGuard {
+            queue,
+            lock: Held::Read(guard),
+        }
+    }
+
+    /// Queue the write `name` on `repo` and wait for its turn
+    pub fn write(&self, repo: &Path, name: &str) -> OperationGuard {
+        let queue = self.queue(repo);
+        let ticket = {
+            let mut waiting = queue.status.lock();
+            let ticket = waiting.next_ticket;
+            waiting.next_ticket += 1;
+            waiting.tickets.push(ticket);
+            waiting.status.queued.push(name.to_string());
+            ticket
+        };
+
+        let guard = queue.lock.write_arc();
+        {
+            let mut waiting = queue.status.lock();
+            if let Some(at) = waiting.tickets.iter().position(|&t| t == ticket) {
+                waiting.tickets.remove(at);
+                waiting.status.queued.remove(at);
+            }
+            waiting.status.running = Some(name.to_string());
+        }
+        OperationGuard {
+            queue,
+            lock: Held::Write(guard),
+        }
+    }
+
+    /// What `repo`'s queue is doing; idle for a repository never used
+    pub fn status(&self, repo: &Path) -> OperationStatus {
+        match self.queues.lock().get(repo) {
+            Some(queue) => queue.status.lock().status.clone(),
+            None => OperationStatus::default(),
+        }
+    }
+
+    fn queue(&self, repo: &Path) -> Arc<Queue> {
+        self.queues
+            .lock()
+            .entry(repo.to_path_buf())
+            .or_default()
+            .clone()
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        // Runs before the lock is released, so the status never shows a
+        // finished write as running while the next one goes
+        let mut waiting = self.queue.status.lock();
+        match self.lock {
+            Held::Read(_) => waiting.status.readers -= 1,
+            Held::Write(_) => waiting.status.running = None,
+        }
+    }
+}
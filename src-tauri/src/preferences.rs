@@ -0,0 +1,176 @@
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::CommandError;
+
+/// Shape a preference value must have
+enum Schema {
+    Bool,
+    Number,
+    String,
+    /// Object with arbitrary keys whose values all match the inner schema
+    Map(Box<Schema>),
+    /// Object with known, optional fields; unknown fields are rejected
+    Record(Vec<(&'static str, Schema)>),
+    /// Any one of the listed schemas
+    OneOf(Vec<Schema>),
+}
+
+/// Schema for each preference namespace; values outside these are rejected
+fn namespace_schema(namespace: &str) -> Option<Schema> {
+    match namespace {
+        // Command id -> shortcut, e.g. `"diff.nextFile": "Mod+J"`
+        "keybindings" => Some(Schema::Map(Box::new(Schema::String))),
+        // Panel id -> its size, visibility and placement
+        "layout" => Some(Schema::Map(Box::new(Schema::Record(vec![
+            ("visible", Schema::Bool),
+            ("size", Schema::Number),
+            ("position", Schema::String),
+        ])))),
+        "theme" => Some(Schema::Record(vec![
+            ("name", Schema::String),
+            ("mode", Schema::String),
+            ("accent", Schema::String),
+            ("fontSize", Schema::Number),
+            ("overrides", Schema::Map(Box::new(Schema::String))),
+        ])),
+        // Small UI state that has no dedicated namespace yet
+        "ui" => Some(Schema::Map(Box::new(Schema::OneOf(vec![
+            Schema::Bool,
+            Schema::Number,
+            Schema::String,
+        ])))),
+        _ => None,
+    }
+}
+
+/// Check `value` against `schema`, returning the path of the first mismatch
+fn validate(schema: &Schema, value: &Value, path: &str) -> Result<(), String> {
+    let ok = match (schema, value) {
+        (Schema::Bool, Value::Bool(_))
+        | (Schema::Number, Value::Number(_))
+        | (Schema::String, Value::String(_)) => true,
+        (Schema::Map(inner), Value::Object(fields)) => {
+            for (key, field) in fields {
+                validate(inner, field, &format!("{}.{}", path, key))?;
+            }
+            true
+        }
+        (Schema::Record(known), Value::Object(fields)) => {
+            for (key, field) in fields {
+                let Some((_, inner)) = known.iter().find(|(name, _)| name == key) else {
+                    return Err(format!("{}.{} is not a known field", path, key));
+                };
+                validate(inner, field, &format!("{}.{}", path, key))?;
+            }
+            true
+        }
+        (Schema::OneOf(options), _) => options
+            .iter()
+            .any(|option| validate(option, value, path).is_ok()),
+        _ => false,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("{} has the wrong type", path))
+    }
+}
+
+/// Per-user UI preferences (keybindings, panel layout, theme), kept apart
+/// from `DifferConfig` and saved to a JSON file in the user's config dir
+pub struct Preferences {
+    path: PathBuf,
+    values: Map<String, Value>,
+}
+
+impl Preferences {
+    /// Default location: `<config dir>/diffy/preferences.json`
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("diffy")
+            .join("preferences.json")
+    }
+
+    /// Load preferences from `path`. A missing or unreadable file, or
+    /// namespaces that no longer validate, fall back to empty preferences.
+    pub fn load(path: &Path) -> Self {
+        let mut values = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Map<String, Value>>(&json).ok())
+            .unwrap_or_default();
+        values.retain(|namespace, value| {
+            namespace_schema(namespace)
+                .is_some_and(|schema| validate(&schema, value, namespace).is_ok())
+        });
+        Self {
+            path: path.to_path_buf(),
+            values,
+        }
+    }
+
+    /// All stored namespaces
+    pub fn all(&self) -> &Map<String, Value> {
+        &self.values
+    }
+
+    /// Replace one namespace after validating it; `null` clears it
+    pub fn set(&mut self, namespace: &str, value: Value) -> Result<Value, CommandError> {
+        let mut next = self.values.clone();
+        if value.is_null() {
+            check_namespace(namespace, None)?;
+            next.remove(namespace);
+        } else {
+            check_namespace(namespace, Some(&value))?;
+            next.insert(namespace.to_string(), value.clone());
+        }
+
+        self.save(&next)?;
+        self.values = next;
+        Ok(value)
+    }
+
+    /// Replace every namespace at once; nothing changes unless all are valid
+    pub fn replace_all(&mut self, values: Map<String, Value>) -> Result<(), CommandError> {
+        check_preferences(&values)?;
+        self.save(&values)?;
+        self.values = values;
+        Ok(())
+    }
+
+    /// Write atomically so a crash never leaves a truncated file behind
+    fn save(&self, values: &Map<String, Value>) -> std::io::Result<()> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(dir)?;
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        file.write_all(&serde_json::to_vec_pretty(values)?)?;
+        file.persist(&self.path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+/// Check every namespace of a full set of preferences without saving them
+pub fn check_preferences(values: &Map<String, Value>) -> Result<(), CommandError> {
+    for (namespace, value) in values {
+        check_namespace(namespace, Some(value))?;
+    }
+    Ok(())
+}
+
+/// Check that `namespace` exists and, when given, that `value` fits its schema
+fn check_namespace(namespace: &str, value: Option<&Value>) -> Result<(), CommandError> {
+    let schema = namespace_schema(namespace).ok_or_else(|| {
+        CommandError::new("error.unknownPreferences", &[("namespace", namespace)])
+    })?;
+    if let Some(value) = value {
+        validate(&schema, value, namespace).map_err(|detail| {
+            CommandError::new(
+                "error.invalidPreferences",
+                &[("namespace", namespace), ("detail", &detail)],
+            )
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,59 @@
+//! Questions a command asks the user while it runs.
+//!
+//! A fetch, pull or push that reaches an SSH key with a passphrase has to
+//! stop and ask for it. The command sends an event carrying a prompt id and
+//! blocks; the frontend shows a dialog and answers with `cmd_answer_prompt`,
+//! which wakes it. Commands that can prompt run off the main thread so the
+//! answer can arrive while they wait.
+//!
+//! Passphrases are asked for before the command takes its repository, so
+//! other commands carry on while the dialog is open. An unanswered prompt
+//! gives up after `PROMPT_TIMEOUT`, the same as declining, so a closed
+//! window can't leave the command waiting for good.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::time::Duration;
+
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Event payload for a prompt, answered by passing `id` back
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptEvent<T> {
+    pub id: u64,
+    #[serde(flatten)]
+    pub request: T,
+}
+
+/// Prompts waiting for an answer
+#[derive(Default)]
+pub struct Prompts {
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, Sender<Option<String>>>>,
+}
+
+impl Prompts {
+    /// Register a prompt, let `send` show it under its id, and wait for the
+    /// answer; `None` when declined or not answered in time
+    pub fn ask(&self, send: impl FnOnce(u64)) -> Option<String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = channel();
+        self.pending.lock().insert(id, sender);
+        send(id);
+        let answer = receiver.recv_timeout(PROMPT_TIMEOUT).ok().flatten();
+        self.pending.lock().remove(&id);
+        answer
+    }
+
+    /// Answer prompt `id`; false when nothing is waiting for it any more
+    pub fn answer(&self, id: u64, answer: Option<String>) -> bool {
+        match self.pending.lock().remove(&id) {
+            Some(sender) => sender.send(answer).is_ok(),
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::git::{
+    self, all_muted, read_repo_config, replace_muted, write_repo_config, DifferConfig, MutedStore,
+};
+use crate::i18n::CommandError;
+
+/// Bumped when the bundle layout changes incompatibly
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Everything needed to reproduce this app's setup on another machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBundle {
+    pub version: u32,
+    pub exported_at: String,
+    pub config: DifferConfig,
+    #[serde(default)]
+    pub preferences: Map<String, Value>,
+    /// Settings of the repository that was open when exporting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<RepoSettings>,
+}
+
+/// Per-repository overrides
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RepoSettings {
+    /// Contents of the repo's `.diffyrc.json`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<Value>,
+    pub muted: MutedStore,
+}
+
+impl SettingsBundle {
+    pub fn new(
+        config: DifferConfig,
+        preferences: Map<String, Value>,
+        repository: Option<RepoSettings>,
+    ) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            exported_at: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            config,
+            preferences,
+            repository,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), CommandError> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Read a bundle, rejecting ones written by a newer, incompatible version
+    pub fn read(path: &Path) -> Result<Self, CommandError> {
+        let invalid =
+            |detail: &str| CommandError::new("error.invalidSettings", &[("detail", detail)]);
+        let json = std::fs::read_to_string(path)?;
+        let bundle: Self = serde_json::from_str(&json).map_err(|e| invalid(&e.to_string()))?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(invalid(&format!(
+                "exported by a newer version (format {})",
+                bundle.version
+            )));
+        }
+        let overrides = bundle
+            .repository
+            .as_ref()
+            .and_then(|r| r.overrides.as_ref());
+        if overrides.is_some_and(|overrides| !overrides.is_object()) {
+            return Err(invalid("repository overrides are not an object"));
+        }
+        Ok(bundle)
+    }
+
+    /// The config to store when importing this bundle over `current`.
+    ///
+    /// A bundle can come from anywhere, so it never brings commands to run
+    /// or lifts read-only mode: the current text converters are kept, and
+    /// `readOnly` can only be turned on.
+    pub fn imported_config(&self, current: &DifferConfig) -> DifferConfig {
+        DifferConfig {
+            revision: current.revision + 1,
+            textconv: current.textconv.clone(),
+            read_only: current.read_only || self.config.read_only,
+            ..self.config.clone()
+        }
+    }
+}
+
+impl RepoSettings {
+    /// The settings `repo` has now
+    pub fn read(repo: &Repository) -> git::Result<Self> {
+        Ok(Self {
+            overrides: read_repo_config(repo),
+            muted: all_muted(repo)?,
+        })
+    }
+
+    /// Write these settings to `repo`, leaving its `.diffyrc.json` alone when
+    /// there are no overrides. Returns the settings they replaced; if writing
+    /// fails part way, those are put back before the error is returned.
+    pub fn apply(&self, repo: &Repository) -> git::Result<Self> {
+        let previous = Self::read(repo)?;
+        let applied = self
+            .overrides
+            .as_ref()
+            .map_or(Ok(()), |overrides| write_repo_config(repo, Some(overrides)))
+            .and_then(|()| replace_muted(repo, &self.muted));
+        if let Err(e) = applied {
+            let _ = previous.restore(repo);
+            return Err(e);
+        }
+        Ok(previous)
+    }
+
+    /// Put back settings returned by [`RepoSettings::apply`] exactly,
+    /// removing a `.diffyrc.json` the repository didn't have
+    pub fn restore(&self, repo: &Repository) -> git::Result<()> {
+        write_repo_config(repo, self.overrides.as_ref())?;
+        replace_muted(repo, &self.muted)
+    }
+}
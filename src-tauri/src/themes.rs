@@ -0,0 +1,512 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::CommandError;
+
+/// Built-in themes, already in resolved form
+const BUILTIN_THEMES: &[&str] = &[
+    include_str!("../themes/diffy-dark.json"),
+    include_str!("../themes/diffy-light.json"),
+    include_str!("../themes/high-contrast.json"),
+];
+
+/// Color keys every resolved theme provides
+const REQUIRED_COLORS: &[&str] = &[
+    "background",
+    "foreground",
+    "lineNumber",
+    "lineHighlight",
+    "selection",
+    "hunkHeader",
+    "addedForeground",
+    "addedBackground",
+    "removedForeground",
+    "removedBackground",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeKind {
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeSource {
+    #[default]
+    Builtin,
+    User,
+}
+
+/// Style for one or more TextMate scopes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenStyle {
+    pub scope: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    /// Space-separated `bold`, `italic`, `underline`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_style: Option<String>,
+}
+
+/// A theme with variables resolved and every key in `REQUIRED_COLORS` set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Theme {
+    pub name: String,
+    pub kind: ThemeKind,
+    #[serde(default)]
+    pub source: ThemeSource,
+    /// File a user theme was loaded from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub colors: BTreeMap<String, String>,
+    pub tokens: Vec<TokenStyle>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeSummary {
+    pub name: String,
+    pub kind: ThemeKind,
+    pub source: ThemeSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Directory scanned for user themes: `<config dir>/diffy/themes`
+pub fn user_theme_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("diffy")
+        .join("themes")
+}
+
+fn builtin_themes() -> Vec<Theme> {
+    BUILTIN_THEMES
+        .iter()
+        .filter_map(|json| serde_json::from_str(json).ok())
+        .collect()
+}
+
+/// `.tmTheme` and `.sublime-color-scheme` files in `dir`, sorted by name
+fn user_theme_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("tmTheme" | "sublime-color-scheme")
+            )
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// All themes: built-ins first, then user themes. A user theme with the same
+/// name as a built-in replaces it; files that fail to parse are left out.
+pub fn list_themes(user_dir: &Path) -> Vec<ThemeSummary> {
+    let mut themes = builtin_themes();
+    for file in user_theme_files(user_dir) {
+        let Ok(theme) = load_user_theme(&file) else {
+            continue;
+        };
+        match themes.iter_mut().find(|t| t.name == theme.name) {
+            Some(existing) => *existing = theme,
+            None => themes.push(theme),
+        }
+    }
+    themes
+        .into_iter()
+        .map(|theme| ThemeSummary {
+            name: theme.name,
+            kind: theme.kind,
+            source: theme.source,
+            path: theme.path,
+        })
+        .collect()
+}
+
+/// Resolve a theme by name, preferring user themes over built-ins
+pub fn get_theme(user_dir: &Path, name: &str) -> Result<Theme, CommandError> {
+    for file in user_theme_files(user_dir) {
+        let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        match load_user_theme(&file) {
+            Ok(theme) if theme.name == name => return Ok(theme),
+            // Report a broken file when it is the one being asked for
+            Err(e) if stem == name => return Err(e),
+            _ => {}
+        }
+    }
+    builtin_themes()
+        .into_iter()
+        .find(|theme| theme.name == name)
+        .ok_or_else(|| CommandError::new("error.themeNotFound", &[("name", name)]))
+}
+
+fn load_user_theme(path: &Path) -> Result<Theme, CommandError> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let invalid = |detail: String| {
+        CommandError::new(
+            "error.invalidTheme",
+            &[("name", &stem), ("detail", &detail)],
+        )
+    };
+
+    let text = std::fs::read_to_string(path)?;
+    let (name, globals, tokens) = match path.extension().and_then(|e| e.to_str()) {
+        Some("tmTheme") => parse_tm_theme(&text).map_err(invalid)?,
+        _ => parse_sublime_scheme(&text).map_err(invalid)?,
+    };
+
+    Ok(resolve(
+        name.unwrap_or(stem),
+        globals,
+        tokens,
+        Some(path.to_string_lossy().to_string()),
+    ))
+}
+
+/// Map editor globals onto theme colors, derive diff colors from the
+/// `markup.inserted` / `markup.deleted` rules and fill anything still missing
+/// from the built-in theme of the same kind
+fn resolve(
+    name: String,
+    globals: BTreeMap<String, String>,
+    tokens: Vec<TokenStyle>,
+    path: Option<String>,
+) -> Theme {
+    let mut colors = BTreeMap::new();
+    for (key, value) in globals {
+        let mapped = match key.as_str() {
+            "background" => "background",
+            "foreground" => "foreground",
+            "selection" => "selection",
+            "lineHighlight" | "line_highlight" => "lineHighlight",
+            "gutterForeground" | "gutter_foreground" => "lineNumber",
+            _ => continue,
+        };
+        colors.insert(mapped.to_string(), value);
+    }
+
+    for (scope, prefix) in [("markup.inserted", "added"), ("markup.deleted", "removed")] {
+        let rule = tokens
+            .iter()
+            .find(|t| t.scope.iter().any(|s| s == scope) && t.foreground.is_some());
+        if let Some(foreground) = rule.and_then(|r| r.foreground.clone()) {
+            if let Some(tint) = translucent(&foreground) {
+                colors.insert(format!("{}Background", prefix), tint);
+            }
+            colors.insert(format!("{}Foreground", prefix), foreground);
+        }
+    }
+
+    let kind = match colors.get("background").and_then(|bg| luminance(bg)) {
+        Some(l) if l > 0.5 => ThemeKind::Light,
+        _ => ThemeKind::Dark,
+    };
+    let fallback_name = match kind {
+        ThemeKind::Light => "diffy-light",
+        ThemeKind::Dark => "diffy-dark",
+    };
+    if let Some(fallback) = builtin_themes()
+        .into_iter()
+        .find(|t| t.name == fallback_name)
+    {
+        for key in REQUIRED_COLORS {
+            if let Some(value) = fallback.colors.get(*key) {
+                colors
+                    .entry(key.to_string())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+    }
+
+    Theme {
+        name,
+        kind,
+        source: ThemeSource::User,
+        path,
+        colors,
+        tokens,
+    }
+}
+
+/// Split a comma-separated scope selector into its scopes
+fn split_scopes(scope: &str) -> Vec<String> {
+    scope
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+type ParsedTheme = (Option<String>, BTreeMap<String, String>, Vec<TokenStyle>);
+
+/// Parse a Sublime Text `.sublime-color-scheme` (JSON with `var()` references)
+fn parse_sublime_scheme(text: &str) -> Result<ParsedTheme, String> {
+    let scheme: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let variables: BTreeMap<String, String> = scheme
+        .get("variables")
+        .and_then(Value::as_object)
+        .map(|vars| {
+            vars.iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let color = |value: &Value| value.as_str().map(|v| resolve_variables(v, &variables));
+
+    let globals = scheme
+        .get("globals")
+        .and_then(Value::as_object)
+        .map(|globals| {
+            globals
+                .iter()
+                .filter_map(|(k, v)| Some((k.clone(), color(v)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let tokens = scheme
+        .get("rules")
+        .and_then(Value::as_array)
+        .map(|rules| {
+            rules
+                .iter()
+                .filter_map(|rule| {
+                    Some(TokenStyle {
+                        scope: split_scopes(rule.get("scope")?.as_str()?),
+                        foreground: rule.get("foreground").and_then(color),
+                        background: rule.get("background").and_then(color),
+                        font_style: rule
+                            .get("font_style")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let name = scheme
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    Ok((name, globals, tokens))
+}
+
+/// Replace `var(name)` with the variable's value; variables may refer to
+/// other variables, up to a small depth to stop cycles
+fn resolve_variables(value: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut resolved = value.to_string();
+    for _ in 0..8 {
+        let Some(start) = resolved.find("var(") else {
+            break;
+        };
+        let Some(len) = resolved[start..].find(')') else {
+            break;
+        };
+        let name = resolved[start + 4..start + len].trim().to_string();
+        let replacement = variables.get(&name).cloned().unwrap_or_default();
+        resolved.replace_range(start..=start + len, &replacement);
+    }
+    resolved
+}
+
+/// A property-list value, as used by `.tmTheme` files
+enum Plist {
+    Dict(Vec<(String, Plist)>),
+    Array(Vec<Plist>),
+    String(String),
+    Other,
+}
+
+impl Plist {
+    fn get(&self, key: &str) -> Option<&Plist> {
+        match self {
+            Plist::Dict(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Plist::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a TextMate `.tmTheme` property list
+fn parse_tm_theme(text: &str) -> Result<ParsedTheme, String> {
+    let mut reader = Reader::from_str(text);
+    let root = loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(e) if e.local_name().as_ref() == "dict" => {
+                break read_dict(&mut reader)?;
+            }
+            Event::Eof => return Err("No theme dictionary found".to_string()),
+            _ => {}
+        }
+    };
+
+    let mut globals = BTreeMap::new();
+    let mut tokens = Vec::new();
+    let Some(Plist::Array(items)) = root.get("settings") else {
+        return Err("Missing settings array".to_string());
+    };
+    for item in items {
+        let Some(Plist::Dict(settings)) = item.get("settings") else {
+            continue;
+        };
+        let setting = |key: &str| {
+            settings
+                .iter()
+                .find(|(k, _)| k == key)
+                .and_then(|(_, v)| v.as_str())
+                .map(str::to_string)
+        };
+        match item.get("scope").and_then(Plist::as_str) {
+            // The scope-less entry holds the editor-wide colors
+            None => {
+                for (key, value) in settings {
+                    if let Some(value) = value.as_str() {
+                        globals.insert(key.clone(), value.to_string());
+                    }
+                }
+            }
+            Some(scope) => tokens.push(TokenStyle {
+                scope: split_scopes(scope),
+                foreground: setting("foreground"),
+                background: setting("background"),
+                font_style: setting("fontStyle").filter(|s| !s.is_empty()),
+            }),
+        }
+    }
+
+    let name = root.get("name").and_then(Plist::as_str).map(str::to_string);
+    Ok((name, globals, tokens))
+}
+
+/// Read the body of a `<dict>` whose start tag was just consumed
+fn read_dict(reader: &mut Reader<&[u8]>) -> Result<Plist, String> {
+    let mut entries = Vec::new();
+    let mut key: Option<String> = None;
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(e) if e.local_name().as_ref() == "key" => {
+                key = Some(read_text(reader)?);
+            }
+            Event::Start(e) => {
+                let value = read_value(reader, e.local_name().as_ref())?;
+                if let Some(key) = key.take() {
+                    entries.push((key, value));
+                }
+            }
+            Event::Empty(_) => {
+                if let Some(key) = key.take() {
+                    entries.push((key, Plist::Other));
+                }
+            }
+            Event::End(_) => return Ok(Plist::Dict(entries)),
+            Event::Eof => return Err("Unterminated dict".to_string()),
+            _ => {}
+        }
+    }
+}
+
+fn read_array(reader: &mut Reader<&[u8]>) -> Result<Plist, String> {
+    let mut items = Vec::new();
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(e) => items.push(read_value(reader, e.local_name().as_ref())?),
+            Event::Empty(_) => items.push(Plist::Other),
+            Event::End(_) => return Ok(Plist::Array(items)),
+            Event::Eof => return Err("Unterminated array".to_string()),
+            _ => {}
+        }
+    }
+}
+
+/// Read a value whose start tag `tag` was just consumed
+fn read_value(reader: &mut Reader<&[u8]>, tag: &str) -> Result<Plist, String> {
+    match tag {
+        "dict" => read_dict(reader),
+        "array" => read_array(reader),
+        "string" => read_text(reader).map(Plist::String),
+        _ => read_text(reader).map(|_| Plist::Other),
+    }
+}
+
+/// Character data up to the end tag of the element just opened
+fn read_text(reader: &mut Reader<&[u8]>) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Text(t) => text.push_str(&t.xml10_content()),
+            Event::CData(c) => text.push_str(&c.into_inner()),
+            Event::GeneralRef(r) => {
+                if let Ok(Some(c)) = r.resolve_char_ref() {
+                    text.push(c);
+                } else {
+                    text.push_str(match &*r {
+                        "amp" => "&",
+                        "lt" => "<",
+                        "gt" => ">",
+                        "quot" => "\"",
+                        "apos" => "'",
+                        _ => "",
+                    });
+                }
+            }
+            Event::End(_) => return Ok(text.trim().to_string()),
+            Event::Eof => return Err("Unterminated element".to_string()),
+            _ => {}
+        }
+    }
+}
+
+/// Parse `#rgb`, `#rrggbb` or `#rrggbbaa` into its RGB components
+fn parse_hex(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    let channel = |i: usize, width: usize| {
+        let value = u8::from_str_radix(hex.get(i * width..(i + 1) * width)?, 16).ok()?;
+        Some(if width == 1 { value * 17 } else { value })
+    };
+    let width = match hex.len() {
+        3 | 4 => 1,
+        6 | 8 => 2,
+        _ => return None,
+    };
+    Some((channel(0, width)?, channel(1, width)?, channel(2, width)?))
+}
+
+/// Relative luminance in 0..=1
+fn luminance(color: &str) -> Option<f64> {
+    let (r, g, b) = parse_hex(color)?;
+    Some((0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64) / 255.0)
+}
+
+/// A faint background tint of `color`, for added/removed line backgrounds
+fn translucent(color: &str) -> Option<String> {
+    let (r, g, b) = parse_hex(color)?;
+    Some(format!("#{:02x}{:02x}{:02x}26", r, g, b))
+}
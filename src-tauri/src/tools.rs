@@ -0,0 +1,447 @@
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::i18n::CommandError;
+
+/// How long to watch a freshly spawned launcher for an immediate failure
+const LAUNCH_GRACE: Duration = Duration::from_millis(500);
+
+/// How an editor's command line asks for a cursor position
+#[derive(Clone, Copy)]
+pub enum GotoSyntax {
+    /// `code -g file:line:column`
+    GotoFlag,
+    /// `subl file:line:column`
+    PathSuffix,
+    /// `idea --line N --column C file`
+    LineFlags,
+    /// No way to pass a position
+    None,
+}
+
+/// An editor or terminal the app knows how to launch
+pub struct ToolSpec {
+    /// Stable id used in settings, e.g. `vscode`
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Executable names looked up on PATH, preferred first
+    pub binaries: &'static [&'static str],
+    /// macOS app bundle, looked for in `/Applications` and `~/Applications`
+    pub mac_app: Option<&'static str>,
+    /// Locations relative to `%LOCALAPPDATA%` or `%ProgramFiles%` on Windows
+    pub windows_paths: &'static [&'static str],
+    /// How to get the command-line launcher onto PATH
+    pub install_hint: &'static str,
+    pub goto: GotoSyntax,
+}
+
+pub const EDITORS: &[ToolSpec] = &[
+    ToolSpec {
+        id: "vscode",
+        name: "Visual Studio Code",
+        binaries: &["code"],
+        mac_app: Some("Visual Studio Code.app/Contents/Resources/app/bin/code"),
+        windows_paths: &[
+            "Programs/Microsoft VS Code/bin/code.cmd",
+            "Microsoft VS Code/bin/code.cmd",
+        ],
+        install_hint: "Run \"Shell Command: Install 'code' command in PATH\" from the VS Code command palette",
+        goto: GotoSyntax::GotoFlag,
+    },
+    ToolSpec {
+        id: "cursor",
+        name: "Cursor",
+        binaries: &["cursor"],
+        mac_app: Some("Cursor.app/Contents/Resources/app/bin/cursor"),
+        windows_paths: &["Programs/cursor/resources/app/bin/cursor.cmd"],
+        install_hint: "Run \"Shell Command: Install 'cursor' command in PATH\" from the Cursor command palette",
+        goto: GotoSyntax::GotoFlag,
+    },
+    ToolSpec {
+        id: "zed",
+        name: "Zed",
+        binaries: &["zed", "zeditor"],
+        mac_app: Some("Zed.app/Contents/MacOS/cli"),
+        windows_paths: &[],
+        install_hint: "Run \"zed: install cli\" from the Zed command palette",
+        goto: GotoSyntax::PathSuffix,
+    },
+    ToolSpec {
+        id: "sublime",
+        name: "Sublime Text",
+        binaries: &["subl"],
+        mac_app: Some("Sublime Text.app/Contents/SharedSupport/bin/subl"),
+        windows_paths: &["Sublime Text/subl.exe", "Sublime Text 3/subl.exe"],
+        install_hint: "Add Sublime Text's bin directory (containing subl) to PATH",
+        goto: GotoSyntax::PathSuffix,
+    },
+    ToolSpec {
+        id: "webstorm",
+        name: "WebStorm",
+        binaries: &["webstorm", "webstorm.sh"],
+        mac_app: Some("WebStorm.app/Contents/MacOS/webstorm"),
+        windows_paths: &[],
+        install_hint: "Enable the shell script in JetBrains Toolbox (Settings > Tools > Shell scripts)",
+        goto: GotoSyntax::LineFlags,
+    },
+    ToolSpec {
+        id: "idea",
+        name: "IntelliJ IDEA",
+        binaries: &["idea", "idea.sh"],
+        mac_app: Some("IntelliJ IDEA.app/Contents/MacOS/idea"),
+        windows_paths: &[],
+        install_hint: "Enable the shell script in JetBrains Toolbox (Settings > Tools > Shell scripts)",
+        goto: GotoSyntax::LineFlags,
+    },
+];
+
+pub const TERMINALS: &[ToolSpec] = &[
+    ToolSpec {
+        id: "terminal",
+        name: "Terminal",
+        binaries: &[],
+        mac_app: Some("Utilities/Terminal.app/Contents/MacOS/Terminal"),
+        windows_paths: &[],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "iterm",
+        name: "iTerm2",
+        binaries: &[],
+        mac_app: Some("iTerm.app/Contents/MacOS/iTerm2"),
+        windows_paths: &[],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "windows-terminal",
+        name: "Windows Terminal",
+        binaries: &["wt"],
+        mac_app: None,
+        windows_paths: &["Microsoft/WindowsApps/wt.exe"],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "ghostty",
+        name: "Ghostty",
+        binaries: &["ghostty"],
+        mac_app: Some("Ghostty.app/Contents/MacOS/ghostty"),
+        windows_paths: &[],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "wezterm",
+        name: "WezTerm",
+        binaries: &["wezterm"],
+        mac_app: Some("WezTerm.app/Contents/MacOS/wezterm"),
+        windows_paths: &["WezTerm/wezterm.exe"],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "alacritty",
+        name: "Alacritty",
+        binaries: &["alacritty"],
+        mac_app: Some("Alacritty.app/Contents/MacOS/alacritty"),
+        windows_paths: &["Alacritty/alacritty.exe"],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "kitty",
+        name: "kitty",
+        binaries: &["kitty"],
+        mac_app: Some("kitty.app/Contents/MacOS/kitty"),
+        windows_paths: &[],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "gnome-terminal",
+        name: "GNOME Terminal",
+        binaries: &["gnome-terminal"],
+        mac_app: None,
+        windows_paths: &[],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "konsole",
+        name: "Konsole",
+        binaries: &["konsole"],
+        mac_app: None,
+        windows_paths: &[],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+    ToolSpec {
+        id: "xterm",
+        name: "xterm",
+        binaries: &["xterm"],
+        mac_app: None,
+        windows_paths: &[],
+        install_hint: "",
+        goto: GotoSyntax::None,
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedTool {
+    pub id: String,
+    pub name: String,
+    /// Where the executable was found
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedTools {
+    pub editors: Vec<DetectedTool>,
+    pub terminals: Vec<DetectedTool>,
+}
+
+/// Editors and terminals that are actually installed, in preference order
+pub fn detect_tools() -> DetectedTools {
+    let detect = |specs: &[ToolSpec]| -> Vec<DetectedTool> {
+        specs
+            .iter()
+            .filter_map(|spec| {
+                Some(DetectedTool {
+                    id: spec.id.to_string(),
+                    name: spec.name.to_string(),
+                    path: locate(spec)?.to_string_lossy().to_string(),
+                })
+            })
+            .collect()
+    };
+    DetectedTools {
+        editors: detect(EDITORS),
+        terminals: detect(TERMINALS),
+    }
+}
+
+/// How an editor launch was resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LaunchMethod {
+    /// The launcher was found on PATH
+    Path,
+    /// Found in a standard install location but not on PATH
+    InstallLocation,
+    /// Not found at all; handed to macOS `open -a` with the app name
+    MacOpen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorLaunch {
+    pub editor: String,
+    pub method: LaunchMethod,
+    /// The executable or app that was started
+    pub command: String,
+}
+
+/// Open `file` in the editor with id `editor` (unknown ids use VS Code),
+/// placing the cursor at `line`/`column` (1-based) when given.
+///
+/// The launcher is located before spawning and watched briefly afterwards,
+/// so a missing binary or one that exits with an error is reported instead of
+/// silently doing nothing.
+pub fn open_in_editor(
+    editor: &str,
+    file: &Path,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> Result<EditorLaunch, CommandError> {
+    let spec = EDITORS
+        .iter()
+        .find(|spec| spec.id == editor)
+        .unwrap_or(&EDITORS[0]);
+
+    let (method, program, args) =
+        if let Some(path) = spec.binaries.iter().find_map(|binary| find_on_path(binary)) {
+            (
+                LaunchMethod::Path,
+                path,
+                goto_args(spec.goto, file, line, column),
+            )
+        } else if let Some(path) = install_locations(spec).into_iter().find(|p| p.is_file()) {
+            (
+                LaunchMethod::InstallLocation,
+                path,
+                goto_args(spec.goto, file, line, column),
+            )
+        } else if let Some(app) = mac_app_name(spec) {
+            // `open` cannot forward a position, so the file opens at the top
+            (
+                LaunchMethod::MacOpen,
+                PathBuf::from("open"),
+                vec!["-a".into(), app.into(), file.as_os_str().to_owned()],
+            )
+        } else {
+            return Err(CommandError::new(
+                "error.editorNotFound",
+                &[("editor", spec.name), ("hint", spec.install_hint)],
+            ));
+        };
+
+    let launch_failed = |detail: &str| {
+        CommandError::new(
+            "error.openEditor",
+            &[("detail", &format!("{} ({})", detail, spec.install_hint))],
+        )
+    };
+
+    let mut child = Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| launch_failed(&e.to_string()))?;
+
+    // CLI launchers hand off to the running app and exit quickly; GUI binaries
+    // keep running. Either is fine, an early non-zero exit is not.
+    let started = Instant::now();
+    while started.elapsed() < LAUNCH_GRACE {
+        match child.try_wait() {
+            Ok(Some(status)) if !status.success() => {
+                if method == LaunchMethod::MacOpen {
+                    return Err(CommandError::new(
+                        "error.editorNotFound",
+                        &[("editor", spec.name), ("hint", spec.install_hint)],
+                    ));
+                }
+                return Err(launch_failed(&format!(
+                    "{} exited with {}",
+                    program.display(),
+                    status
+                )));
+            }
+            Ok(Some(_)) => break,
+            Ok(None) => std::thread::sleep(Duration::from_millis(25)),
+            Err(e) => return Err(launch_failed(&e.to_string())),
+        }
+    }
+
+    Ok(EditorLaunch {
+        editor: spec.id.to_string(),
+        method,
+        command: program.to_string_lossy().to_string(),
+    })
+}
+
+/// Arguments that open `file` at a position in the editor's own syntax
+fn goto_args(
+    syntax: GotoSyntax,
+    file: &Path,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> Vec<OsString> {
+    let Some(line) = line else {
+        return vec![file.as_os_str().to_owned()];
+    };
+    let with_position = || {
+        let mut target = file.as_os_str().to_owned();
+        target.push(format!(":{}", line));
+        if let Some(column) = column {
+            target.push(format!(":{}", column));
+        }
+        target
+    };
+
+    match syntax {
+        GotoSyntax::GotoFlag => vec!["-g".into(), with_position()],
+        GotoSyntax::PathSuffix => vec![with_position()],
+        GotoSyntax::LineFlags => {
+            let mut args: Vec<OsString> = vec!["--line".into(), line.to_string().into()];
+            if let Some(column) = column {
+                args.extend(["--column".into(), column.to_string().into()]);
+            }
+            args.push(file.as_os_str().to_owned());
+            args
+        }
+        GotoSyntax::None => vec![file.as_os_str().to_owned()],
+    }
+}
+
+/// App name for `open -a`, e.g. `Visual Studio Code`; macOS only
+fn mac_app_name(spec: &ToolSpec) -> Option<&'static str> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    let bundle = spec.mac_app?.split(".app/").next()?;
+    Some(bundle.rsplit('/').next().unwrap_or(bundle))
+}
+
+/// Find a tool's executable: PATH first, then the platform's install dirs
+pub fn locate(spec: &ToolSpec) -> Option<PathBuf> {
+    spec.binaries
+        .iter()
+        .find_map(|binary| find_on_path(binary))
+        .or_else(|| install_locations(spec).into_iter().find(|p| p.is_file()))
+}
+
+fn find_on_path(binary: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    // Windows resolves `code` to `code.cmd` and friends via PATHEXT
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(|ext| ext.to_lowercase())
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    std::env::split_paths(&path).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", binary, ext)))
+            .find(|candidate| is_executable(candidate))
+    })
+}
+
+fn install_locations(spec: &ToolSpec) -> Vec<PathBuf> {
+    let mut locations = Vec::new();
+    if cfg!(target_os = "macos") {
+        if let Some(app) = spec.mac_app {
+            locations.push(Path::new("/Applications").join(app));
+            locations.push(Path::new("/System/Applications").join(app));
+            if let Some(home) = dirs::home_dir() {
+                locations.push(home.join("Applications").join(app));
+            }
+        }
+    }
+    if cfg!(windows) {
+        let roots = ["LOCALAPPDATA", "ProgramFiles", "ProgramFiles(x86)"]
+            .iter()
+            .filter_map(std::env::var_os)
+            .map(PathBuf::from);
+        for root in roots {
+            locations.extend(spec.windows_paths.iter().map(|p| root.join(p)));
+        }
+    }
+    locations
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -1,12 +1,36 @@
-use notify::RecursiveMode;
-use notify_debouncer_mini::{new_debouncer, DebouncedEvent, Debouncer, notify::RecommendedWatcher};
-use std::path::Path;
-use std::sync::mpsc::{channel, Receiver};
+use notify::{PollWatcher, RecursiveMode, Watcher};
+use notify_debouncer_mini::{
+    new_debouncer_opt, notify::RecommendedWatcher, Config, DebounceEventResult, Debouncer,
+};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::JoinHandle;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
+
+use crate::events;
+use crate::git::paths::{long_path, plain_path};
+use crate::git::WatcherSettings;
 
 const DEBOUNCE_MS: u64 = 300;
 
+/// Filesystem types where native change notifications are unreliable:
+/// network shares and the VM file sharing used by Docker Desktop and WSL
+#[cfg(target_os = "linux")]
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "afs",
+    "9p",
+    "virtiofs",
+    "fuse.sshfs",
+    "fuse.grpcfuse",
+    "fakeowner",
+];
+
 #[derive(Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FileChangeEvent {
@@ -15,41 +39,357 @@ pub struct FileChangeEvent {
     pub timestamp: i64,
 }
 
+impl FileChangeEvent {
+    /// HEAD moved: the frontend reloads the diff and history
+    pub fn head() -> Self {
+        Self {
+            event_type: "head".to_string(),
+            file: "HEAD".to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatcherMode {
+    /// OS change notifications (inotify, FSEvents, ReadDirectoryChangesW)
+    Native,
+    /// Rescanning the tree on an interval
+    Polling,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatus {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<WatcherMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll_interval_ms: Option<u64>,
+    /// Why polling is used instead of native notifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_reason: Option<String>,
+    /// Directories, relative to the repository, that couldn't be watched
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_dirs: Vec<String>,
+}
+
+/// Payload of the `watch-warning` event
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchWarning {
+    /// Directories, relative to the repository, whose changes go unnoticed
+    pub skipped_dirs: Vec<String>,
+}
+
+impl WatcherStatus {
+    pub fn inactive() -> Self {
+        Self {
+            active: false,
+            mode: None,
+            poll_interval_ms: None,
+            fallback_reason: None,
+            skipped_dirs: Vec::new(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+enum Backend {
+    Native(Debouncer<RecommendedWatcher>),
+    Polling(Debouncer<PollWatcher>),
+}
+
+/// What the event thread receives: batches from the debouncer, or the
+/// signal to stop
+enum WatchMessage {
+    Events(DebounceEventResult),
+    Stop,
+}
+
+/// Watches a repository and reports changes from a thread of its own.
+/// Dropping the watcher stops watching and waits for that thread to exit, so
+/// switching repositories never leaves the old one's thread behind.
 pub struct FileWatcher {
-    #[allow(dead_code)]
-    debouncer: Debouncer<RecommendedWatcher>,
+    /// Taken on drop so watching stops before the thread is joined
+    debouncer: Option<Backend>,
+    stop: Sender<WatchMessage>,
+    thread: Option<JoinHandle<()>>,
+    poll_interval_ms: u64,
+    fallback_reason: Option<String>,
+    skipped_dirs: Vec<String>,
 }
 
 impl FileWatcher {
+    /// Watch `path` with native notifications, or by polling when the
+    /// settings ask for it, the path is on a network filesystem, or native
+    /// watching cannot be set up.
+    ///
+    /// Subdirectories the user can't read are left out rather than failing
+    /// the whole watch, and reported with a `watch-warning` event.
     pub fn new<P: AsRef<Path>>(
         path: P,
         app_handle: AppHandle,
+        settings: &WatcherSettings,
     ) -> Result<Self, Box<dyn std::error::Error>> {
+        let app = app_handle.clone();
+        let watcher = Self::with_handler(path, settings, move |event| {
+            events::emit(&app, "file-change", event)
+        })?;
+        if !watcher.skipped_dirs.is_empty() {
+            let warning = WatchWarning {
+                skipped_dirs: watcher.skipped_dirs.clone(),
+            };
+            events::emit(&app_handle, "watch-warning", warning);
+        }
+        Ok(watcher)
+    }
+
+    /// Watch `path` like [`FileWatcher::new`], passing each change to
+    /// `on_change` on the watcher's thread instead of emitting it
+    pub fn with_handler<P, F>(
+        path: P,
+        settings: &WatcherSettings,
+        on_change: F,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        P: AsRef<Path>,
+        F: Fn(FileChangeEvent) + Send + 'static,
+    {
+        let path = path.as_ref();
         let (tx, rx) = channel();
 
-        let mut debouncer = new_debouncer(Duration::from_millis(DEBOUNCE_MS), tx)?;
+        // A linked worktree keeps HEAD under the main repo's
+        // `.git/worktrees/<name>`, outside the working directory
+        let git_dir = git2::Repository::open(path)
+            .ok()
+            .map(|repo| repo.path().to_path_buf());
+        let extra_dir = git_dir.clone().filter(|dir| !dir.starts_with(path));
+
+        let forced = if settings.polling {
+            Some("Polling enabled in settings".to_string())
+        } else {
+            network_filesystem(path).map(|fs| format!("{} filesystem", fs))
+        };
+        let interval = Duration::from_millis(settings.poll_interval_ms);
+
+        let (debouncer, skipped, fallback_reason) = match forced {
+            Some(reason) => {
+                let (debouncer, skipped) =
+                    start::<PollWatcher>(path, &extra_dir, interval, tx.clone())?;
+                (Backend::Polling(debouncer), skipped, Some(reason))
+            }
+            None => match start::<RecommendedWatcher>(path, &extra_dir, interval, tx.clone()) {
+                Ok((debouncer, skipped)) => (Backend::Native(debouncer), skipped, None),
+                Err(e) => {
+                    let (debouncer, skipped) =
+                        start::<PollWatcher>(path, &extra_dir, interval, tx.clone())?;
+                    (
+                        Backend::Polling(debouncer),
+                        skipped,
+                        Some(format!("Native watching failed: {}", e)),
+                    )
+                }
+            },
+        };
 
-        debouncer.watcher().watch(path.as_ref(), RecursiveMode::Recursive)?;
+        let skipped_dirs: Vec<String> = skipped
+            .iter()
+            .map(|dir| {
+                let dir = dir.strip_prefix(path).unwrap_or(dir);
+                dir.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
 
         // Spawn a thread to handle file change events
-        let path_str = path.as_ref().to_string_lossy().to_string();
-        std::thread::spawn(move || {
-            handle_events(rx, app_handle, &path_str);
+        let path_str = path.to_string_lossy().to_string();
+        let thread = std::thread::spawn(move || {
+            handle_events(rx, on_change, &path_str, git_dir.as_deref());
         });
 
-        Ok(Self { debouncer })
+        Ok(Self {
+            debouncer: Some(debouncer),
+            stop: tx,
+            thread: Some(thread),
+            poll_interval_ms: settings.poll_interval_ms,
+            fallback_reason,
+            skipped_dirs,
+        })
+    }
+
+    pub fn status(&self) -> WatcherStatus {
+        let polling = matches!(self.debouncer, Some(Backend::Polling(_)));
+        WatcherStatus {
+            active: true,
+            mode: Some(if polling {
+                WatcherMode::Polling
+            } else {
+                WatcherMode::Native
+            }),
+            poll_interval_ms: polling.then_some(self.poll_interval_ms),
+            fallback_reason: self.fallback_reason.clone(),
+            skipped_dirs: self.skipped_dirs.clone(),
+        }
     }
 }
 
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        // Stop the notifications first so nothing arrives after the stop
+        // signal, then wait for the thread to finish what it has
+        self.debouncer = None;
+        let _ = self.stop.send(WatchMessage::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Start watching, returning the directories that had to be skipped
+fn start<W: Watcher>(
+    path: &Path,
+    extra_dir: &Option<PathBuf>,
+    poll_interval: Duration,
+    tx: Sender<WatchMessage>,
+) -> Result<(Debouncer<W>, Vec<PathBuf>), notify::Error> {
+    let config = || {
+        Config::default()
+            .with_timeout(Duration::from_millis(DEBOUNCE_MS))
+            .with_notify_config(notify::Config::default().with_poll_interval(poll_interval))
+    };
+    let handler = |tx: Sender<WatchMessage>| {
+        move |result: DebounceEventResult| {
+            let _ = tx.send(WatchMessage::Events(result));
+        }
+    };
+    let mut debouncer = new_debouncer_opt::<_, W>(config(), handler(tx.clone()))?;
+
+    // Deep monorepo checkouts pass MAX_PATH, which directory watching on
+    // Windows only accepts in the verbatim form
+    let mut skipped = Vec::new();
+    match debouncer
+        .watcher()
+        .watch(&long_path(path), RecursiveMode::Recursive)
+    {
+        Ok(()) => {}
+        // One unreadable subdirectory fails a recursive watch; start over
+        // and watch what can be watched one directory at a time
+        Err(e) if is_permission_denied(&e) => {
+            drop(debouncer);
+            debouncer = new_debouncer_opt::<_, W>(config(), handler(tx))?;
+            skipped = watch_each_dir(debouncer.watcher(), path);
+        }
+        Err(e) => return Err(e),
+    }
+    if let Some(dir) = extra_dir {
+        debouncer
+            .watcher()
+            .watch(&long_path(dir), RecursiveMode::NonRecursive)?;
+    }
+    Ok((debouncer, skipped))
+}
+
+fn is_permission_denied(error: &notify::Error) -> bool {
+    matches!(
+        &error.kind,
+        notify::ErrorKind::Io(e) if e.kind() == std::io::ErrorKind::PermissionDenied
+    )
+}
+
+/// Watch `root` and every directory below it individually, returning the
+/// ones that couldn't be watched or listed. Directories created later are
+/// not picked up, unlike with a recursive watch. Only the top of `.git` is
+/// watched, since that is where HEAD lives.
+fn watch_each_dir(watcher: &mut dyn Watcher, root: &Path) -> Vec<PathBuf> {
+    let mut skipped = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let listing = match watcher.watch(&long_path(&dir), RecursiveMode::NonRecursive) {
+            Ok(()) => std::fs::read_dir(&dir),
+            Err(_) => {
+                skipped.push(dir);
+                continue;
+            }
+        };
+        let Ok(entries) = listing else {
+            skipped.push(dir);
+            continue;
+        };
+        for entry in entries.flatten() {
+            if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            if entry.file_name() == ".git" {
+                if watcher
+                    .watch(&long_path(&entry.path()), RecursiveMode::NonRecursive)
+                    .is_err()
+                {
+                    skipped.push(entry.path());
+                }
+                continue;
+            }
+            pending.push(entry.path());
+        }
+    }
+    skipped.sort();
+    skipped
+}
+
+/// Type of the network filesystem `path` lives on, if any
+#[cfg(target_os = "linux")]
+fn network_filesystem(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+
+    // The mount covering `path` is the one with the longest matching prefix
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            path.starts_with(&mount_point)
+                .then(|| (mount_point.len(), fs_type.to_string()))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, fs_type)| fs_type)
+        .filter(|fs_type| NETWORK_FILESYSTEMS.contains(&fs_type.as_str()))
+}
+
+/// Windows reaches network shares through UNC paths, where change
+/// notifications are unreliable
+#[cfg(windows)]
+fn network_filesystem(path: &Path) -> Option<String> {
+    crate::git::paths::is_unc(&path.to_string_lossy()).then(|| "UNC network".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn network_filesystem(_path: &Path) -> Option<String> {
+    None
+}
+
 fn handle_events(
-    rx: Receiver<Result<Vec<DebouncedEvent>, notify::Error>>,
-    app_handle: AppHandle,
+    rx: Receiver<WatchMessage>,
+    on_change: impl Fn(FileChangeEvent),
     base_path: &str,
+    git_dir: Option<&Path>,
 ) {
     loop {
         match rx.recv() {
-            Ok(Ok(events)) => {
-                for event in events {
+            Ok(WatchMessage::Events(Ok(events))) => {
+                for mut event in events {
+                    // Watched through `long_path`, so events carry the
+                    // verbatim prefix the repo path doesn't have
+                    event.path = plain_path(&event.path);
+
+                    // HEAD moving means a checkout or commit, possibly made
+                    // from a terminal in a linked worktree
+                    if git_dir.is_some_and(|dir| event.path == dir.join("HEAD")) {
+                        on_change(FileChangeEvent::head());
+                        continue;
+                    }
+
                     // Skip .git directory changes
                     let path_str = event.path.to_string_lossy();
                     if path_str.contains(".git") {
@@ -70,19 +410,13 @@ fn handle_events(
                         timestamp: chrono::Utc::now().timestamp_millis(),
                     };
 
-                    // Emit event to all windows
-                    if let Err(e) = app_handle.emit("file-change", change_event) {
-                        eprintln!("Failed to emit file change event: {}", e);
-                    }
+                    on_change(change_event);
                 }
             }
-            Ok(Err(e)) => {
+            Ok(WatchMessage::Events(Err(e))) => {
                 eprintln!("File watcher error: {}", e);
             }
-            Err(_) => {
-                // Channel closed, exit loop
-                break;
-            }
+            Ok(WatchMessage::Stop) | Err(_) => break,
         }
     }
 }
@@ -0,0 +1,34 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::{git, AppState};
+
+#[test]
+fn reset_closes_the_repository_and_reloads_preferences() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    let blob = repo.repo.revparse_single("HEAD:a.txt").unwrap().id();
+
+    let state = AppState::default();
+    *state.repo_path.lock() = Some(repo.workdir().to_path_buf());
+    git::object_cache::global().blob(&repo.repo, blob).unwrap();
+    assert_eq!(git::object_cache::global().stats().blobs, 1);
+
+    repo.write(
+        "preferences.json",
+        r#"{"keybindings": {"diff.nextFile": "Mod+J"}, "unknown": 1}"#,
+    );
+    state.reset(&repo.workdir().join("preferences.json"));
+
+    assert!(state.repo_path.lock().is_none());
+    assert!(state.watcher.lock().is_none());
+    assert!(state.editor_sync.lock().is_none());
+    assert_eq!(git::object_cache::global().stats().blobs, 0);
+    let preferences = state.preferences.lock();
+    assert_eq!(
+        preferences.all().get("keybindings"),
+        Some(&serde_json::json!({ "diff.nextFile": "Mod+J" }))
+    );
+    assert!(!preferences.all().contains_key("unknown"));
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig};
+use std::path::Path;
+
+/// Write `files` into a gzipped tarball under a `project-1.0/` wrapper
+fn write_tarball(path: &Path, files: &[(&str, &str)]) {
+    let file = std::fs::File::create(path).unwrap();
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+    let mut builder = tar::Builder::new(encoder);
+    for (name, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(
+                &mut header,
+                format!("project-1.0/{}", name),
+                contents.as_bytes(),
+            )
+            .unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+}
+
+fn loose_objects(repo: &TestRepo) -> usize {
+    walk(&repo.repo.path().join("objects"))
+}
+
+fn walk(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .map(|path| if path.is_dir() { walk(&path) } else { 1 })
+        .sum()
+}
+
+#[test]
+fn diffs_a_ci_tarball_against_the_working_tree_and_a_ref() {
+    let repo = TestRepo::new();
+    repo.write("src/main.rs", "fn main() {}\n");
+    repo.write("README.md", "hello\n");
+    repo.commit_all("first");
+    repo.write("src/main.rs", "fn main() { run() }\n");
+
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("source.tar.gz");
+    write_tarball(
+        &archive,
+        &[
+            ("src/main.rs", "fn main() {}\n"),
+            ("README.md", "hello\n"),
+            ("generated.txt", "built\n"),
+        ],
+    );
+
+    let before = loose_objects(&repo);
+    let config = DifferConfig::default();
+    let working = git::diff_against_archive(&repo.repo, &archive, None, &config).unwrap();
+    assert_eq!(working.root.as_deref(), Some("project-1.0"));
+    assert_eq!(working.target, "working");
+    let paths: Vec<_> = working.diff.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["generated.txt", "src/main.rs"]);
+    assert_eq!(loose_objects(&repo), before);
+
+    let at_head = git::diff_against_archive(&repo.repo, &archive, Some("HEAD"), &config).unwrap();
+    let paths: Vec<_> = at_head.diff.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["generated.txt"]);
+}
+
+#[test]
+fn rejects_unknown_archive_formats() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+
+    let result = git::diff_against_archive(
+        &repo.repo,
+        Path::new("build.rar"),
+        None,
+        &DifferConfig::default(),
+    );
+    assert!(matches!(result, Err(git::GitError::InvalidArchive(_))));
+}
+
+/// A committed repository with `run.sh` executable and `docs` linking to
+/// `README.md`
+#[cfg(unix)]
+fn repo_with_link() -> TestRepo {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    repo.write("README.md", "hello\n");
+    repo.write("run.sh", "#!/bin/sh\n");
+    let script = repo.workdir().join("run.sh");
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::os::unix::fs::symlink("README.md", repo.workdir().join("docs")).unwrap();
+    repo.commit_all("first");
+    repo
+}
+
+#[cfg(unix)]
+#[test]
+fn stores_symlinks_as_their_target() {
+    let repo = repo_with_link();
+    let outside = tempfile::tempdir().unwrap();
+    let secret = outside.path().join("secret.txt");
+    std::fs::write(&secret, "top secret\n").unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("source.tar");
+    let mut builder = tar::Builder::new(std::fs::File::create(&archive).unwrap());
+    for (name, contents, mode) in [
+        ("README.md", "hello\n", 0o644),
+        ("run.sh", "#!/bin/sh\n", 0o755),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(mode);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, contents.as_bytes())
+            .unwrap();
+    }
+    for (name, target) in [("docs", Path::new("README.md")), ("leak", secret.as_path())] {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, name, target).unwrap();
+    }
+    builder.finish().unwrap();
+
+    let diff =
+        git::diff_against_archive(&repo.repo, &archive, Some("HEAD"), &DifferConfig::default())
+            .unwrap();
+    // Only the extra link differs, and never with what it points at
+    let paths: Vec<_> = diff.diff.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["leak"]);
+    assert!(!serde_json::to_string(&diff).unwrap().contains("top secret"));
+}
+
+#[cfg(unix)]
+#[test]
+fn unpacks_zip_links_and_modes() {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    let repo = repo_with_link();
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("source.zip");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+    let options = SimpleFileOptions::default();
+    writer
+        .start_file("project/README.md", options.unix_permissions(0o644))
+        .unwrap();
+    writer.write_all(b"hello\n").unwrap();
+    writer
+        .start_file("project/run.sh", options.unix_permissions(0o755))
+        .unwrap();
+    writer.write_all(b"#!/bin/sh\n").unwrap();
+    writer
+        .add_symlink("project/docs", "README.md", options)
+        .unwrap();
+    writer.finish().unwrap();
+
+    let diff =
+        git::diff_against_archive(&repo.repo, &archive, Some("HEAD"), &DifferConfig::default())
+            .unwrap();
+    assert_eq!(diff.root.as_deref(), Some("project"));
+    assert!(diff.diff.files.is_empty(), "{:?}", diff.diff.files);
+}
+
+#[test]
+fn refuses_archives_that_unpack_too_large() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    let config = DifferConfig {
+        max_archive_bytes: 100,
+        ..Default::default()
+    };
+    let big = "x".repeat(1000);
+
+    let dir = tempfile::tempdir().unwrap();
+    let tarball = dir.path().join("source.tar.gz");
+    write_tarball(&tarball, &[("a.txt", "one\n"), ("big.txt", &big)]);
+
+    let zip_path = dir.path().join("source.zip");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+    writer
+        .start_file("big.txt", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    std::io::Write::write_all(&mut writer, big.as_bytes()).unwrap();
+    writer.finish().unwrap();
+
+    for archive in [tarball, zip_path] {
+        match git::diff_against_archive(&repo.repo, &archive, None, &config) {
+            Err(git::GitError::InvalidArchive(detail)) => {
+                assert!(
+                    detail.ends_with("unpacks to more than 100 bytes"),
+                    "{}",
+                    detail
+                )
+            }
+            other => panic!("expected a size error, got {:?}", other.map(|d| d.root)),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, RefSnapshot};
+use std::io::Write;
+
+#[test]
+fn records_moved_refs_and_touched_paths() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+
+    let before = RefSnapshot::new(&repo.repo).unwrap();
+    repo.write("a.txt", "two\n");
+    repo.write("b.txt", "new\n");
+    let second = repo.commit_all("second");
+    let entry =
+        git::record_operation(&repo.repo, "commit", &before, &["c.txt".to_string()], None).unwrap();
+
+    assert_eq!(entry.operation, "commit");
+    let refs: Vec<_> = entry
+        .refs
+        .iter()
+        .map(|r| (r.name.as_str(), r.old.clone(), r.new.clone()))
+        .collect();
+    let (old, new) = (Some(first.to_string()), Some(second.to_string()));
+    assert_eq!(
+        refs,
+        [
+            ("HEAD", old.clone(), new.clone()),
+            ("refs/heads/master", old, new),
+        ]
+    );
+    assert_eq!(entry.paths, ["a.txt", "b.txt", "c.txt"]);
+    assert_eq!(entry.omitted_paths, 0);
+    assert!(entry.error.is_none());
+
+    // A created ref has no old sha; a failure keeps its error
+    let before = RefSnapshot::new(&repo.repo).unwrap();
+    repo.branch("feature", first);
+    let entry = git::record_operation(
+        &repo.repo,
+        "createBranch",
+        &before,
+        &[],
+        Some("refused".to_string()),
+    )
+    .unwrap();
+    assert_eq!(entry.refs.len(), 1);
+    assert_eq!(entry.refs[0].name, "refs/heads/feature");
+    assert_eq!(entry.refs[0].old, None);
+    assert_eq!(entry.refs[0].new, Some(first.to_string()));
+    assert!(entry.paths.is_empty());
+    assert_eq!(entry.error.as_deref(), Some("refused"));
+}
+
+#[test]
+fn reads_the_log_newest_first() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    assert!(git::get_audit_log(&repo.repo, 10, 0).unwrap().is_empty());
+
+    let before = RefSnapshot::new(&repo.repo).unwrap();
+    for operation in ["stageFile", "commit"] {
+        git::record_operation(&repo.repo, operation, &before, &[], None).unwrap();
+    }
+    // A torn write from a crash is skipped
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(repo.repo.path().join("differ-audit.jsonl"))
+        .unwrap();
+    file.write_all(b"{\"operation\":\"disc").unwrap();
+    drop(file);
+    git::record_operation(&repo.repo, "checkoutBranch", &before, &[], None).unwrap();
+
+    let operations = |limit, offset| -> Vec<String> {
+        git::get_audit_log(&repo.repo, limit, offset)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.operation)
+            .collect()
+    };
+    assert_eq!(operations(10, 0), ["checkoutBranch", "commit", "stageFile"]);
+    assert_eq!(operations(1, 1), ["commit"]);
+}
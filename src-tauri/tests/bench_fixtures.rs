@@ -0,0 +1,46 @@
+//! The benchmark fixtures, at small sizes, have the shape the benchmarks
+//! claim to measure
+
+#[path = "../benches/fixtures/mod.rs"]
+mod fixtures;
+
+use diffy_lib::git::{self, DifferConfig};
+
+#[test]
+fn many_files_modifies_every_other_file() {
+    let fixture = fixtures::many_files(20);
+    let diff = git::get_current_diff(&fixture.repo, None, &DifferConfig::default()).unwrap();
+    assert_eq!(diff.files.len(), 10);
+    assert!(diff
+        .files
+        .iter()
+        .all(|f| f.additions == 1 && f.deletions == 0));
+}
+
+#[test]
+fn many_commits_builds_a_linear_history() {
+    let fixture = fixtures::many_commits(30);
+    let history = git::get_commit_history(&fixture.repo, 20, 0, &DifferConfig::default()).unwrap();
+    assert_eq!(history.total, 30);
+    assert_eq!(history.commits.len(), 20);
+}
+
+#[test]
+fn huge_file_changes_every_tenth_line() {
+    let fixture = fixtures::huge_file(1000);
+    let diff = git::get_current_diff(&fixture.repo, None, &DifferConfig::default()).unwrap();
+    assert_eq!(diff.files.len(), 1);
+    assert_eq!(
+        (diff.files[0].additions, diff.files[0].deletions),
+        (100, 100)
+    );
+}
+
+#[test]
+fn diverged_branches_modify_and_add_files() {
+    let fixture = fixtures::diverged_branches(5);
+    let result =
+        git::compare_branches(&fixture.repo, "base", "head", &DifferConfig::default()).unwrap();
+    assert_eq!(result.files.len(), 10);
+    assert_eq!(result.commit_count, 1);
+}
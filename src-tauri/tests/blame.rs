@@ -0,0 +1,150 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+fn summaries(blame: &git::FileBlame) -> Vec<(&str, bool)> {
+    blame
+        .lines
+        .iter()
+        .map(|l| (l.summary.as_str(), l.adjusted))
+        .collect()
+}
+
+#[test]
+fn blames_each_line_at_a_revision() {
+    let repo = TestRepo::new();
+    repo.write("f.txt", "a\nb\n");
+    repo.commit_all("first");
+    repo.write("f.txt", "a\nB\nc\n");
+    repo.commit_all("second");
+
+    let head = git::get_blame(&repo.repo, "f.txt", None).unwrap();
+    assert_eq!(
+        summaries(&head),
+        [("first", false), ("second", false), ("second", false)]
+    );
+
+    let earlier = git::get_blame(&repo.repo, "f.txt", Some("HEAD~1")).unwrap();
+    assert_eq!(summaries(&earlier), [("first", false), ("first", false)]);
+}
+
+#[test]
+fn looks_through_ignored_revisions() {
+    let repo = TestRepo::new();
+    repo.write("f.txt", "a\nb\nc\nd\n");
+    repo.commit_all("original");
+    // Reindents two lines and adds one
+    repo.write("f.txt", "a\n  b\n  c\nnew\nd\n");
+    let reformat = repo.commit_all("reformat");
+    repo.write("f.txt", "a\n  b\n  c\nnew\nd\ne\n");
+    repo.commit_all("append");
+
+    repo.write(
+        ".git-blame-ignore-revs",
+        &format!("# formatting\n{}\n", reformat),
+    );
+    let blame = git::get_blame(&repo.repo, "f.txt", None).unwrap();
+
+    assert_eq!(
+        summaries(&blame),
+        [
+            ("original", false),
+            ("original", true),
+            ("original", true),
+            // Added outright by the ignored commit, so it keeps the blame
+            ("reformat", false),
+            ("original", false),
+            ("append", false),
+        ]
+    );
+    assert_eq!(
+        blame.lines[1].ignored_sha.as_deref(),
+        Some(reformat.to_string().as_str())
+    );
+    assert!(blame
+        .ignore_revs_file
+        .unwrap()
+        .ends_with(".git-blame-ignore-revs"));
+}
+
+#[test]
+fn reblames_the_same_line_at_the_parent() {
+    let repo = TestRepo::new();
+    repo.write("old.txt", "keep\ntarget\ntail\n");
+    let first = repo.commit_all("first");
+    // Renames the file, inserts two lines above the target and edits it
+    repo.remove("old.txt");
+    repo.write("new.txt", "top\nmore\nkeep\ntarget!\ntail\n");
+    let second = repo.commit_all("second");
+
+    let blame = git::get_blame(&repo.repo, "new.txt", None).unwrap();
+    let line = &blame.lines[3];
+    assert_eq!((line.summary.as_str(), line.orig_line), ("second", 4));
+
+    let previous =
+        git::get_blame_at_parent(&repo.repo, "new.txt", line.orig_line, &line.sha).unwrap();
+    assert_eq!(previous.parent, Some(first.to_string()));
+    assert_eq!(previous.path.as_deref(), Some("old.txt"));
+    assert_eq!(previous.line, Some(2));
+    let earlier = previous.blame.unwrap();
+    assert_eq!((earlier.summary.as_str(), earlier.line), ("first", 2));
+
+    // A line the commit added outright has nothing before it
+    let added = git::get_blame_at_parent(&repo.repo, "new.txt", 1, &second.to_string()).unwrap();
+    assert_eq!(added.line, None);
+    assert!(added.blame.is_none());
+}
+
+#[test]
+fn attributes_each_line_to_its_commit_and_author() {
+    let repo = TestRepo::new();
+    repo.write("f.txt", "a\nb\n");
+    let first = repo.commit_all("first\n\nWith a body");
+    repo.write("f.txt", "new\na\nb\n");
+    let second = repo.commit_all("second");
+    repo.branch("release", first);
+
+    let blame = git::get_blame(&repo.repo, "f.txt", None).unwrap();
+    assert_eq!(blame.path, "f.txt");
+    assert_eq!(blame.git_ref, "HEAD");
+    assert!(blame.ignore_revs_file.is_none());
+
+    let fields: Vec<(usize, &str, usize)> = blame
+        .lines
+        .iter()
+        .map(|l| (l.line, l.sha.as_str(), l.orig_line))
+        .collect();
+    let (first, second) = (first.to_string(), second.to_string());
+    assert_eq!(
+        fields,
+        [
+            (1, second.as_str(), 1),
+            (2, first.as_str(), 1),
+            (3, first.as_str(), 2)
+        ]
+    );
+
+    let line = &blame.lines[1];
+    assert_eq!(line.short_sha, first[..7]);
+    assert_eq!(line.author, "Test");
+    assert_eq!(line.author_email, "test@example.com");
+    assert_eq!(line.date, "2023-11-14T22:13:20Z");
+    assert_eq!(line.summary, "first");
+    assert!(line.orig_path.is_none());
+
+    // Any revision works, not just HEAD
+    let release = git::get_blame(&repo.repo, "f.txt", Some("release")).unwrap();
+    assert_eq!(release.git_ref, "release");
+    assert_eq!(release.lines.len(), 2);
+}
+
+#[test]
+fn fails_for_unknown_paths_and_revisions() {
+    let repo = TestRepo::new();
+    repo.write("f.txt", "a\n");
+    repo.commit_all("first");
+
+    assert!(git::get_blame(&repo.repo, "missing.txt", None).is_err());
+    assert!(git::get_blame(&repo.repo, "f.txt", Some("no-such-ref")).is_err());
+}
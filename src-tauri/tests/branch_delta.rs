@@ -0,0 +1,70 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, FileStatus};
+
+/// `main` with two files, and `feature` checked out with one commit that
+/// changes `a.txt`
+fn feature_repo() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "one\n");
+    let root = repo.commit_all("first");
+    repo.rename_head("main");
+    repo.branch("feature", root);
+    repo.checkout("feature");
+    repo.write("a.txt", "two\n");
+    repo.commit_all("feature work");
+    repo
+}
+
+#[test]
+fn flags_local_edits_to_files_the_branch_changed() {
+    let repo = feature_repo();
+    repo.write("a.txt", "three\n");
+    repo.write("b.txt", "two\n");
+    repo.write("c.txt", "new\n");
+
+    let delta = git::get_branch_delta(&repo.repo, None).unwrap();
+    assert_eq!(delta.base_ref, "main");
+    assert_eq!(
+        delta.merge_base,
+        repo.repo.revparse_single("main").unwrap().id().to_string()
+    );
+    assert_eq!(delta.branch_files, vec!["a.txt"]);
+
+    let files: Vec<(&str, bool)> = delta
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.changed_on_branch))
+        .collect();
+    assert_eq!(
+        files,
+        vec![("a.txt", true), ("b.txt", false), ("c.txt", false)]
+    );
+    assert!(matches!(delta.files[2].status, FileStatus::Added));
+}
+
+#[test]
+fn compares_against_an_explicit_base() {
+    let repo = feature_repo();
+    repo.write("b.txt", "local\n");
+    repo.commit_all("more feature work");
+    repo.write("b.txt", "again\n");
+
+    // Against its own first commit, only the second commit counts
+    let delta = git::get_branch_delta(&repo.repo, Some("feature~1")).unwrap();
+    assert_eq!(delta.base_ref, "feature~1");
+    assert_eq!(delta.branch_files, vec!["b.txt"]);
+    assert!(delta.files[0].changed_on_branch);
+}
+
+#[test]
+fn needs_something_to_compare_against_on_the_default_branch() {
+    let repo = feature_repo();
+    repo.checkout("main");
+    repo.write("a.txt", "local\n");
+
+    // `main` has no upstream, and comparing it with itself says nothing
+    assert!(git::get_branch_delta(&repo.repo, None).is_err());
+}
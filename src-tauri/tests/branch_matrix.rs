@@ -0,0 +1,84 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+/// `main` with 2 commits past the root, `develop` with 1 of its own on top
+/// of main's first, and release branches cut from the root, `release/1.1`
+/// with a hotfix
+fn release_repo() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "0\n");
+    let root = repo.commit_all("root");
+    repo.rename_head("main");
+    repo.branch("release/1.0", root);
+    repo.branch("release/1.1", root);
+
+    repo.write("a.txt", "1\n");
+    let first = repo.commit_all("main 1");
+    repo.branch("develop", first);
+    repo.write("a.txt", "2\n");
+    repo.commit_all("main 2");
+
+    repo.checkout("develop");
+    repo.write("b.txt", "dev\n");
+    repo.commit_all("develop 1");
+    repo.checkout("release/1.1");
+    repo.write("c.txt", "fix\n");
+    repo.commit_all("hotfix");
+    repo.checkout("main");
+    repo
+}
+
+fn cell(matrix: &git::BranchMatrix, base: &str, head: &str) -> (usize, usize) {
+    let cell = matrix
+        .cells
+        .iter()
+        .find(|c| c.base == base && c.head == head)
+        .unwrap_or_else(|| panic!("no cell for {}..{}", base, head));
+    (cell.ahead, cell.behind)
+}
+
+#[test]
+fn counts_both_directions_of_every_pair() {
+    let repo = release_repo();
+    let matrix =
+        git::compare_branch_matrix(&repo.repo, &["main".to_string(), "develop".to_string()])
+            .unwrap();
+    assert_eq!(matrix.branches, vec!["main", "develop"]);
+    assert_eq!(matrix.cells.len(), 2);
+    assert_eq!(cell(&matrix, "main", "develop"), (1, 1));
+    assert_eq!(cell(&matrix, "develop", "main"), (1, 1));
+}
+
+#[test]
+fn expands_globs_in_name_order_without_duplicates() {
+    let repo = release_repo();
+    let entries = ["main", "release/*", "release/1.0", "HEAD~2"].map(String::from);
+    let matrix = git::compare_branch_matrix(&repo.repo, &entries).unwrap();
+    assert_eq!(
+        matrix.branches,
+        vec!["main", "release/1.0", "release/1.1", "HEAD~2"]
+    );
+    // n branches give n * (n - 1) cells
+    assert_eq!(matrix.cells.len(), 12);
+
+    assert_eq!(cell(&matrix, "release/1.0", "main"), (2, 0));
+    assert_eq!(cell(&matrix, "main", "release/1.1"), (1, 2));
+    // HEAD~2 is the root, like release/1.0
+    assert_eq!(cell(&matrix, "release/1.0", "HEAD~2"), (0, 0));
+}
+
+#[test]
+fn fails_on_unknown_revisions() {
+    let repo = release_repo();
+    assert!(
+        git::compare_branch_matrix(&repo.repo, &["main".to_string(), "nope".to_string()]).is_err()
+    );
+    // A glob matching nothing just adds no branches
+    let matrix =
+        git::compare_branch_matrix(&repo.repo, &["main".to_string(), "hotfix/*".to_string()])
+            .unwrap();
+    assert_eq!(matrix.branches, vec!["main"]);
+    assert!(matrix.cells.is_empty());
+}
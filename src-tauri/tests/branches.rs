@@ -0,0 +1,223 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+/// Write `names` straight into `packed-refs`, as a Gerrit mirror or
+/// `git pack-refs` would leave them
+fn pack_branches(repo: &TestRepo, names: &[String], oid: git2::Oid) {
+    let mut packed = String::from("# pack-refs with: peeled fully-peeled sorted \n");
+    for name in names {
+        packed.push_str(&format!("{} refs/heads/{}\n", oid, name));
+    }
+    std::fs::write(repo.repo.path().join("packed-refs"), packed).unwrap();
+}
+
+#[test]
+fn pages_through_filtered_packed_branches() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    let names: Vec<String> = (0..30).map(|n| format!("changes/{:02}", n)).collect();
+    pack_branches(&repo, &names, first);
+
+    let query = git::BranchQuery {
+        filter: Some("CHANGES/".to_string()),
+        offset: 10,
+        limit: Some(5),
+    };
+    let page = git::get_branch_page(&repo.repo, &query).unwrap();
+    assert_eq!(page.total, 30);
+    let listed: Vec<&str> = page.branches.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(
+        listed,
+        [
+            "changes/10",
+            "changes/11",
+            "changes/12",
+            "changes/13",
+            "changes/14"
+        ]
+    );
+
+    // Unfiltered, the checked-out branch is listed too
+    let all = git::get_branches(&repo.repo).unwrap();
+    assert_eq!(all.total, 31);
+    assert!(all
+        .branches
+        .iter()
+        .any(|b| b.current && b.name == all.current));
+}
+
+#[test]
+fn loose_refs_override_packed_ones() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    let second = repo.commit_all("second");
+    pack_branches(&repo, &["topic".to_string()], first);
+    repo.repo
+        .reference("refs/heads/topic", second, true, "move topic")
+        .unwrap();
+
+    let query = git::BranchQuery {
+        filter: Some("topic".to_string()),
+        ..Default::default()
+    };
+    let page = git::get_branch_page(&repo.repo, &query).unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.branches[0].commit, second.to_string()[..7]);
+}
+
+#[test]
+fn creates_branches_from_any_revision() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo.commit_all("second");
+    repo.repo
+        .remote("origin", "https://example.com/repo.git")
+        .unwrap();
+    repo.repo
+        .reference("refs/remotes/origin/main", first, true, "fetch")
+        .unwrap();
+
+    let info = git::create_branch(&repo.repo, "fix", "HEAD~1").unwrap();
+    assert_eq!(info.name, "fix");
+    assert_eq!(info.commit, first.to_string()[..7]);
+    assert!(!info.current);
+    assert!(info.merged_into_head);
+
+    // Remote-tracking starts become the upstream
+    git::create_branch(&repo.repo, "tracking", "origin/main").unwrap();
+    let branch = repo
+        .repo
+        .find_branch("tracking", git2::BranchType::Local)
+        .unwrap();
+    assert_eq!(
+        branch.upstream().unwrap().name().unwrap(),
+        Some("origin/main")
+    );
+
+    assert!(matches!(
+        git::create_branch(&repo.repo, "fix", "HEAD"),
+        Err(git::GitError::BranchExists(name)) if name == "fix"
+    ));
+    assert!(matches!(
+        git::create_branch(&repo.repo, "bad..name", "HEAD"),
+        Err(git::GitError::InvalidBranchName(_))
+    ));
+}
+
+#[test]
+fn deletes_only_merged_branches_unless_forced() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.branch("merged", first);
+    repo.branch("feature", first);
+    repo.checkout("feature");
+    repo.write("a.txt", "feature\n");
+    let feature_tip = repo.commit_all("feature work");
+    repo.checkout("master");
+
+    assert_eq!(
+        git::delete_branch(&repo.repo, "merged", false).unwrap(),
+        first.to_string()
+    );
+    assert!(matches!(
+        git::delete_branch(&repo.repo, "feature", false),
+        Err(git::GitError::BranchNotMerged(name)) if name == "feature"
+    ));
+    assert_eq!(
+        git::delete_branch(&repo.repo, "feature", true).unwrap(),
+        feature_tip.to_string()
+    );
+    assert!(matches!(
+        git::delete_branch(&repo.repo, "master", true),
+        Err(git::GitError::BranchCheckedOut(_))
+    ));
+    assert!(matches!(
+        git::delete_branch(&repo.repo, "feature", true),
+        Err(git::GitError::BranchNotFound(_))
+    ));
+}
+
+#[test]
+fn renames_branches_and_keeps_head_on_them() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.branch("other", first);
+
+    let info = git::rename_branch(&repo.repo, "master", "main", false).unwrap();
+    assert_eq!(info.name, "main");
+    assert!(info.current);
+    assert_eq!(repo.repo.head().unwrap().shorthand(), Some("main"));
+    assert!(repo
+        .repo
+        .find_branch("master", git2::BranchType::Local)
+        .is_err());
+
+    assert!(matches!(
+        git::rename_branch(&repo.repo, "other", "main", false),
+        Err(git::GitError::BranchExists(name)) if name == "main"
+    ));
+    let renamed = git::rename_branch(&repo.repo, "other", "renamed", false).unwrap();
+    assert!(!renamed.current);
+}
+
+#[test]
+fn annotates_merge_state_orphans_and_last_commit() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let root = repo.commit_all("first");
+    repo.rename_head("main");
+    repo.branch("done", root);
+    repo.branch("wip", root);
+    repo.write("a.txt", "two\n");
+    repo.commit_all("main work");
+
+    repo.checkout("wip");
+    repo.write("b.txt", "wip\n");
+    repo.commit_all("wip work");
+    repo.checkout("main");
+
+    // A branch with unrelated history, like `gh-pages`
+    let sig = git2::Signature::new("Pages", "pages@example.com", &git2::Time::new(0, 0)).unwrap();
+    let tree = repo
+        .repo
+        .find_tree(repo.repo.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    repo.repo
+        .commit(Some("refs/heads/pages"), &sig, &sig, "site", &tree, &[])
+        .unwrap();
+
+    let list = git::get_branches(&repo.repo).unwrap();
+    assert_eq!(list.default_branch.as_deref(), Some("main"));
+    let branch = |name: &str| list.branches.iter().find(|b| b.name == name).unwrap();
+
+    let done = branch("done");
+    assert_eq!(done.merged_into_default, Some(true));
+    assert!(done.merged_into_head);
+    assert!(!done.orphan);
+    assert_eq!(done.last_commit_author, "Test");
+    assert_eq!(done.last_commit_date, "2023-11-14T22:13:20Z");
+
+    let wip = branch("wip");
+    assert_eq!(wip.merged_into_default, Some(false));
+    assert!(!wip.merged_into_head);
+    assert!(!wip.orphan);
+
+    let pages = branch("pages");
+    assert_eq!(pages.merged_into_default, Some(false));
+    assert!(pages.orphan);
+    assert_eq!(pages.last_commit_author, "Pages");
+    assert_eq!(pages.last_commit_date, "1970-01-01T00:00:00Z");
+
+    let main = branch("main");
+    assert!(main.current);
+    assert_eq!(main.merged_into_default, Some(true));
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+/// `master` with a.txt at "one", `feature` one commit ahead with a.txt at
+/// "two" and a new b.txt; `master` is checked out
+fn two_branches() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.write("c.txt", "shared\n");
+    let first = repo.commit_all("first");
+    repo.branch("feature", first);
+    repo.checkout("feature");
+    repo.write("a.txt", "two\n");
+    repo.write("b.txt", "feature only\n");
+    repo.commit_all("feature work");
+    repo.checkout("master");
+    repo
+}
+
+fn read(repo: &TestRepo, path: &str) -> Option<String> {
+    std::fs::read_to_string(repo.workdir().join(path)).ok()
+}
+
+#[test]
+fn switches_branch_and_working_tree() {
+    let mut repo = two_branches();
+    let feature_tip = repo
+        .repo
+        .revparse_single("feature")
+        .unwrap()
+        .id()
+        .to_string();
+
+    let result = git::checkout_branch(&mut repo.repo, "feature", false).unwrap();
+    assert_eq!(result.branch, "feature");
+    assert_eq!(result.previous.as_deref(), Some("master"));
+    assert_eq!(result.sha, feature_tip);
+    assert!(result.auto_stash.is_none());
+    assert_eq!(repo.repo.head().unwrap().shorthand(), Some("feature"));
+    assert_eq!(read(&repo, "a.txt").as_deref(), Some("two\n"));
+    assert_eq!(read(&repo, "b.txt").as_deref(), Some("feature only\n"));
+
+    git::checkout_branch(&mut repo.repo, "master", false).unwrap();
+    assert_eq!(read(&repo, "a.txt").as_deref(), Some("one\n"));
+    assert!(read(&repo, "b.txt").is_none());
+
+    assert!(matches!(
+        git::checkout_branch(&mut repo.repo, "nope", false),
+        Err(git::GitError::BranchNotFound(name)) if name == "nope"
+    ));
+}
+
+#[test]
+fn refuses_a_dirty_worktree_without_auto_stash() {
+    let mut repo = two_branches();
+    repo.write("c.txt", "edited\n");
+
+    match git::checkout_branch(&mut repo.repo, "feature", false) {
+        Err(git::GitError::UncommittedChanges(files)) => assert_eq!(files, ["c.txt"]),
+        other => panic!("expected a refusal, got {:?}", other.map(|r| r.sha)),
+    }
+    assert_eq!(repo.repo.head().unwrap().shorthand(), Some("master"));
+    assert_eq!(read(&repo, "c.txt").as_deref(), Some("edited\n"));
+
+    // Untracked files don't count as local changes
+    repo.write("c.txt", "shared\n");
+    repo.write("notes.txt", "scratch\n");
+    git::checkout_branch(&mut repo.repo, "feature", false).unwrap();
+    assert_eq!(read(&repo, "notes.txt").as_deref(), Some("scratch\n"));
+}
+
+#[test]
+fn auto_stash_carries_changes_to_the_new_branch() {
+    let mut repo = two_branches();
+    repo.write("c.txt", "edited\n");
+
+    let result = git::checkout_branch(&mut repo.repo, "feature", true).unwrap();
+    let stash = result.auto_stash.expect("changes were stashed");
+    assert!(stash.dropped);
+    assert!(stash.conflicts.is_empty());
+    assert_eq!(repo.repo.head().unwrap().shorthand(), Some("feature"));
+    assert_eq!(read(&repo, "a.txt").as_deref(), Some("two\n"));
+    assert_eq!(read(&repo, "c.txt").as_deref(), Some("edited\n"));
+    assert!(git::get_stashes(&mut repo.repo).unwrap().stashes.is_empty());
+}
+
+#[test]
+fn conflicting_auto_stash_is_kept() {
+    let mut repo = two_branches();
+    repo.write("a.txt", "local\n");
+
+    let result = git::checkout_branch(&mut repo.repo, "feature", true).unwrap();
+    let stash = result.auto_stash.expect("changes were stashed");
+    assert!(!stash.dropped);
+    assert_eq!(stash.conflicts, ["a.txt"]);
+    assert_eq!(repo.repo.head().unwrap().shorthand(), Some("feature"));
+    assert_eq!(git::get_stashes(&mut repo.repo).unwrap().stashes.len(), 1);
+}
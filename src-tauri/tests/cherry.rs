@@ -0,0 +1,103 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, Degradation, DifferConfig, LargeRepoMode};
+
+/// `main` and `feature` diverged from one commit; `feature` has three
+/// commits, the second of which was then cherry-picked onto `main`
+fn picked_onto_main() -> (TestRepo, git2::Oid, git2::Oid) {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "b\n");
+    let base = repo.commit_all("base");
+    repo.branch("main", base);
+    repo.branch("feature", base);
+
+    repo.checkout("feature");
+    repo.write("a.txt", "one\ntwo\n");
+    repo.commit_all("feature one");
+    repo.write("b.txt", "b\nfix\n");
+    let fix = repo.commit_all("fix b");
+    repo.write("c.txt", "c\n");
+    repo.commit_all("feature three");
+
+    repo.checkout("main");
+    repo.write("d.txt", "main only\n");
+    repo.commit_all("main work");
+    let picked = git::cherry_pick(&repo.repo, &fix.to_string())
+        .unwrap()
+        .commit
+        .unwrap();
+    (repo, fix, git2::Oid::from_str(&picked.sha).unwrap())
+}
+
+#[test]
+fn marks_commits_already_in_base() {
+    let (repo, fix, picked) = picked_onto_main();
+    assert_ne!(fix, picked);
+
+    let result =
+        git::compare_branches(&repo.repo, "main", "feature", &DifferConfig::default()).unwrap();
+    let marks: Vec<_> = result
+        .commits
+        .iter()
+        .map(|c| (c.message.as_str(), c.applied_in.clone()))
+        .collect();
+    assert_eq!(
+        marks,
+        [
+            ("feature one", None),
+            ("fix b", Some(picked.to_string())),
+            ("feature three", None),
+        ]
+    );
+
+    // Later pages are marked the same way
+    let page = git::get_compare_commits(
+        &repo.repo,
+        "main",
+        "feature",
+        1,
+        1,
+        &DifferConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(page.commits[0].applied_in, Some(picked.to_string()));
+
+    // Serialized only when there's a match
+    let value = serde_json::to_value(&result.commits).unwrap();
+    assert!(value[0].get("appliedIn").is_none());
+    assert_eq!(value[1]["appliedIn"], picked.to_string());
+}
+
+#[test]
+fn huge_repositories_skip_the_marks() {
+    let (repo, _, _) = picked_onto_main();
+    let mut config = DifferConfig::default();
+    config.large_repo.mode = LargeRepoMode::On;
+
+    let page = git::get_compare_commits(&repo.repo, "main", "feature", 10, 0, &config).unwrap();
+    assert_eq!(page.degraded, [Degradation::CommitStats]);
+    assert!(page.commits.iter().all(|c| c.applied_in.is_none()));
+}
+
+#[test]
+fn reports_when_the_base_is_too_far_ahead_to_check() {
+    let (repo, _, _) = picked_onto_main();
+    let config = DifferConfig::default();
+    let result = git::compare_branches(&repo.repo, "main", "feature", &config).unwrap();
+    assert!(!result.applied_in_truncated);
+
+    // The pick is now older than the base commits that get checked
+    for i in 0..git::COMPARE_COMMIT_LIMIT {
+        repo.write("d.txt", &format!("main {}\n", i));
+        repo.commit_all("more main work");
+    }
+    let result = git::compare_branches(&repo.repo, "main", "feature", &config).unwrap();
+    assert!(result.applied_in_truncated);
+    assert!(result.commits.iter().all(|c| c.applied_in.is_none()));
+
+    let page = git::get_compare_commits(&repo.repo, "main", "feature", 10, 0, &config).unwrap();
+    assert!(page.applied_in_truncated);
+}
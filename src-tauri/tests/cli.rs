@@ -0,0 +1,81 @@
+mod common;
+
+use common::TestRepo;
+use serde_json::Value;
+
+/// Exit code, stdout and stderr of `diffy json <args>`
+fn run(args: &[&str]) -> (i32, String, String) {
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let code = diffy_lib::run_json_to(&args, &mut out, &mut err);
+    (
+        code,
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
+fn repo_with_changes() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo
+}
+
+#[test]
+fn prints_results_as_json() {
+    let repo = repo_with_changes();
+    let path = repo.workdir().to_str().unwrap();
+
+    let (code, out, err) = run(&["--repo", path, "current-diff"]);
+    assert_eq!((code, err.as_str()), (0, ""));
+    assert_eq!(out.lines().count(), 1);
+    let diff: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(diff["files"][0]["path"], "a.txt");
+
+    let (code, out, _) = run(&["--pretty", "--repo", path, "commits", "--limit", "1"]);
+    assert_eq!(code, 0);
+    assert!(out.lines().count() > 1);
+    let history: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(history["commits"][0]["message"], "first");
+}
+
+#[test]
+fn reports_usage_errors_with_exit_code_2() {
+    for args in [
+        &[][..],
+        &["--repo"][..],
+        &["frobnicate"][..],
+        &["compare", "main"][..],
+        &["commits", "--page", "0"][..],
+        &["tags", "--sort", "name"][..],
+    ] {
+        let (code, out, err) = run(args);
+        assert_eq!(code, 2, "{:?}", args);
+        assert!(out.is_empty());
+        assert!(err.starts_with("diffy json: "), "{}", err);
+        assert!(err.contains("Usage: diffy json"));
+    }
+
+    let (code, out, _) = run(&["--help"]);
+    assert_eq!(code, 0);
+    assert!(out.starts_with("Usage: diffy json"));
+}
+
+#[test]
+fn reports_failures_as_json_on_stderr() {
+    let repo = repo_with_changes();
+    let path = repo.workdir().to_str().unwrap();
+
+    let (code, out, err) = run(&["--repo", path, "commit", "not-a-commit"]);
+    assert_eq!(code, 1);
+    assert!(out.is_empty());
+    let body: Value = serde_json::from_str(&err).unwrap();
+    assert!(body["error"]["key"].is_string(), "{}", err);
+
+    let dir = tempfile::tempdir().unwrap();
+    let (code, _, err) = run(&["--repo", dir.path().to_str().unwrap(), "status"]);
+    assert_eq!(code, 1);
+    assert!(err.contains("\"error\""));
+}
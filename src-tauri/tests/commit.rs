@@ -0,0 +1,391 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+#[test]
+fn commits_only_what_is_staged() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+
+    repo.write("a.txt", "two\n");
+    repo.write("b.txt", "new\n");
+    repo.stage("a.txt");
+
+    let info = git::create_commit(
+        &repo.repo,
+        "Update a\n\n# a comment\n",
+        &git::HookPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(info.message, "Update a\n");
+    assert_eq!(info.author, "Test");
+    assert_eq!(info.author_email, "test@example.com");
+    assert_eq!(info.stats.files, 1);
+
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.id().to_string(), info.sha);
+    assert_eq!(head.parent_id(0).unwrap(), first);
+    // b.txt was never staged
+    assert!(head
+        .tree()
+        .unwrap()
+        .get_path(std::path::Path::new("b.txt"))
+        .is_err());
+}
+
+#[test]
+fn creates_the_first_commit() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.stage("a.txt");
+
+    let info = git::create_commit(&repo.repo, "Initial", &git::HookPolicy::default()).unwrap();
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.id().to_string(), info.sha);
+    assert_eq!(head.parent_count(), 0);
+}
+
+#[test]
+fn rejects_empty_commits_and_messages() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    let policy = git::HookPolicy::default();
+
+    // Unstaged edits alone are not enough
+    repo.write("a.txt", "two\n");
+    assert!(matches!(
+        git::create_commit(&repo.repo, "Nothing", &policy),
+        Err(git::GitError::NothingToCommit)
+    ));
+
+    repo.stage("a.txt");
+    assert!(matches!(
+        git::create_commit(&repo.repo, "\n# only a comment\n", &policy),
+        Err(git::GitError::EmptyMessage)
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_pre_commit_hook_aborts() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo.stage("a.txt");
+
+    let hook = repo.repo.path().join("hooks/pre-commit");
+    std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    std::fs::write(&hook, "#!/bin/sh\necho 'lint failed' >&2\nexit 1\n").unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let result = git::create_commit(&repo.repo, "Blocked", &git::HookPolicy::default());
+    match result {
+        Err(git::GitError::HookFailed(hook)) => assert_eq!(hook.stderr, "lint failed\n"),
+        other => panic!("expected a hook failure, got {:?}", other.map(|c| c.sha)),
+    }
+    assert_eq!(repo.repo.head().unwrap().target().unwrap(), first);
+}
+
+#[test]
+fn amend_keeps_the_author_and_takes_staged_changes() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    let second = repo.commit_all("Second");
+    repo.set_identity();
+    repo.write("b.txt", "forgotten\n");
+    repo.stage("b.txt");
+
+    let info = git::amend_commit(&repo.repo, None, false, &git::HookPolicy::default()).unwrap();
+    assert_ne!(info.sha, second.to_string());
+    assert_eq!(info.message, "Second\n");
+
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    let original = repo.repo.find_commit(second).unwrap();
+    assert_eq!(head.parent_id(0).unwrap(), first);
+    assert_eq!(head.author().name(), original.author().name());
+    assert_eq!(head.committer().name(), Some("Test"));
+    assert!(head
+        .tree()
+        .unwrap()
+        .get_path(std::path::Path::new("b.txt"))
+        .is_ok());
+
+    let reworded = git::amend_commit(
+        &repo.repo,
+        Some("Second, reworded"),
+        false,
+        &git::HookPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(reworded.message, "Second, reworded\n");
+}
+
+#[test]
+fn amend_refuses_pushed_commits_unless_forced() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.repo
+        .reference("refs/remotes/origin/main", first, true, "fetch")
+        .unwrap();
+    let policy = git::HookPolicy::default();
+
+    match git::amend_commit(&repo.repo, Some("Rewritten"), false, &policy) {
+        Err(git::GitError::AlreadyPushed(remote)) => assert_eq!(remote, "origin/main"),
+        other => panic!("expected a refusal, got {:?}", other.map(|c| c.sha)),
+    }
+    assert_eq!(repo.repo.head().unwrap().target().unwrap(), first);
+
+    let forced = git::amend_commit(&repo.repo, Some("Rewritten"), true, &policy).unwrap();
+    assert_eq!(forced.message, "Rewritten\n");
+}
+
+/// Point `gpg.program` at a script standing in for gpg
+#[cfg(unix)]
+fn fake_gpg(repo: &TestRepo, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let program = repo.repo.path().join("fake-gpg");
+    std::fs::write(&program, script).unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let mut config = repo.repo.config().unwrap();
+    config.set_bool("commit.gpgsign", true).unwrap();
+    config
+        .set_str("gpg.program", program.to_str().unwrap())
+        .unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn signs_commits_when_gpgsign_is_set() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.stage("a.txt");
+    fake_gpg(
+        &repo,
+        "#!/bin/sh\ncat > /dev/null\necho '[GNUPG:] SIG_CREATED D 1 8 00 0 KEY' >&2\n\
+         printf -- '-----BEGIN PGP SIGNATURE-----\\nsigned by %s\\n-----END PGP SIGNATURE-----\\n' \"$3\"\n",
+    );
+
+    let info = git::create_commit(&repo.repo, "Signed", &git::HookPolicy::default()).unwrap();
+    let oid = git2::Oid::from_str(&info.sha).unwrap();
+    let (signature, _) = repo.repo.extract_signature(&oid, None).unwrap();
+    assert!(String::from_utf8_lossy(&signature).contains("signed by Test <test@example.com>"));
+    assert_eq!(repo.repo.head().unwrap().target(), Some(oid));
+    assert_eq!(repo.repo.head().unwrap().shorthand(), Some("master"));
+
+    let amended = git::amend_commit(
+        &repo.repo,
+        Some("Signed again"),
+        false,
+        &git::HookPolicy::default(),
+    )
+    .unwrap();
+    let amended_oid = git2::Oid::from_str(&amended.sha).unwrap();
+    assert!(repo.repo.extract_signature(&amended_oid, None).is_ok());
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.id(), amended_oid);
+    assert_eq!(head.parent_count(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn signing_failures_abort_the_commit() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo.stage("a.txt");
+    fake_gpg(
+        &repo,
+        "#!/bin/sh\ncat > /dev/null\necho 'gpg: signing failed: No secret key' >&2\nexit 2\n",
+    );
+
+    match git::create_commit(&repo.repo, "Unsigned", &git::HookPolicy::default()) {
+        Err(git::GitError::SigningFailed { format, detail }) => {
+            assert_eq!(format, "openpgp");
+            assert_eq!(detail, "gpg: signing failed: No secret key");
+        }
+        other => panic!("expected a signing failure, got {:?}", other.map(|c| c.sha)),
+    }
+    assert_eq!(repo.repo.head().unwrap().target(), Some(first));
+}
+
+#[test]
+fn unsigned_commits_verify_as_unsigned() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+
+    let info = git::get_commit_diff(
+        &repo.repo,
+        &first.to_string(),
+        &git::DifferConfig::default(),
+    )
+    .unwrap()
+    .commit;
+    assert!(!info.signed);
+    let signature = git::verify_commit(&repo.repo, "HEAD").unwrap();
+    assert_eq!(signature.status, git::SignatureStatus::Unsigned);
+    assert_eq!(signature.sha, first.to_string());
+    assert!(signature.format.is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn reports_gpg_verification_results() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.stage("a.txt");
+    // Signs with any key; verifies only signatures made by "trusted"
+    fake_gpg(
+        &repo,
+        "#!/bin/sh\nif [ \"$3\" = --verify ]; then\n  cat > /dev/null\n\
+         if grep -q trusted \"$4\"; then\n\
+         echo '[GNUPG:] GOODSIG 0123456789ABCDEF Test <test@example.com>'\n\
+         echo '[GNUPG:] VALIDSIG FINGERPRINT0123456789ABCDEF 2024-01-01'\n\
+         echo '[GNUPG:] TRUST_ULTIMATE 0 pgp'\n\
+         else\n\
+         echo '[GNUPG:] ERRSIG FEDCBA9876543210 1 8 00 1700000000 9'\n\
+         echo '[GNUPG:] NO_PUBKEY FEDCBA9876543210'\n\
+         echo 'gpg: Can'\"'\"'t check signature: No public key' >&2\n\
+         exit 2\n  fi\n  exit 0\nfi\n\
+         cat > /dev/null\necho '[GNUPG:] SIG_CREATED D 1 8 00 0 KEY' >&2\n\
+         printf -- '-----BEGIN PGP SIGNATURE-----\\n%s\\n-----END PGP SIGNATURE-----\\n' \"$3\"\n",
+    );
+
+    let info = git::create_commit(&repo.repo, "Signed", &git::HookPolicy::default()).unwrap();
+    assert!(info.signed);
+    let unknown = git::verify_commit(&repo.repo, &info.sha).unwrap();
+    assert_eq!(unknown.status, git::SignatureStatus::UnknownKey);
+    assert_eq!(unknown.format.as_deref(), Some("openpgp"));
+    assert_eq!(unknown.key.as_deref(), Some("FEDCBA9876543210"));
+    assert_eq!(
+        unknown.detail.as_deref(),
+        Some("gpg: Can't check signature: No public key")
+    );
+
+    repo.repo
+        .config()
+        .unwrap()
+        .set_str("user.signingkey", "trusted")
+        .unwrap();
+    let trusted = git::amend_commit(&repo.repo, None, false, &git::HookPolicy::default()).unwrap();
+    let good = git::verify_commit(&repo.repo, &trusted.sha).unwrap();
+    assert_eq!(good.status, git::SignatureStatus::Good);
+    assert_eq!(good.signer.as_deref(), Some("Test <test@example.com>"));
+    assert_eq!(good.key.as_deref(), Some("FINGERPRINT0123456789ABCDEF"));
+    assert_eq!(good.trust.as_deref(), Some("ultimate"));
+    assert!(good.detail.is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn verifies_ssh_signatures_against_allowed_signers() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    let key = repo.repo.path().join("signing-key");
+    let generated = std::process::Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "test", "-f"])
+        .arg(&key)
+        .output();
+    if !generated.is_ok_and(|output| output.status.success()) {
+        eprintln!("skipping: ssh-keygen is not available");
+        return;
+    }
+    let mut config = repo.repo.config().unwrap();
+    config.set_bool("commit.gpgsign", true).unwrap();
+    config.set_str("gpg.format", "ssh").unwrap();
+    config
+        .set_str("user.signingkey", key.to_str().unwrap())
+        .unwrap();
+
+    repo.write("a.txt", "one\n");
+    repo.stage("a.txt");
+    let info = git::create_commit(&repo.repo, "Signed", &git::HookPolicy::default()).unwrap();
+
+    // Intact, but nobody vouches for the key yet
+    let unknown = git::verify_commit(&repo.repo, &info.sha).unwrap();
+    assert_eq!(unknown.status, git::SignatureStatus::UnknownKey);
+    assert_eq!(unknown.format.as_deref(), Some("ssh"));
+    assert!(unknown.key.as_deref().unwrap().starts_with("SHA256:"));
+
+    let public_key = std::fs::read_to_string(key.with_extension("pub")).unwrap();
+    let allowed = repo.repo.path().join("allowed_signers");
+    std::fs::write(&allowed, format!("test@example.com {}", public_key)).unwrap();
+    config
+        .set_str("gpg.ssh.allowedSignersFile", allowed.to_str().unwrap())
+        .unwrap();
+    let good = git::verify_commit(&repo.repo, &info.sha).unwrap();
+    assert_eq!(good.status, git::SignatureStatus::Good);
+    assert_eq!(good.signer.as_deref(), Some("test@example.com"));
+    assert_eq!(good.key, unknown.key);
+}
+
+#[cfg(unix)]
+#[test]
+fn hooks_read_large_input_and_time_out() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    let hooks = repo.repo.path().join("hooks");
+    std::fs::create_dir_all(&hooks).unwrap();
+    let install = |name: &str, script: &str| {
+        let hook = hooks.join(name);
+        std::fs::write(&hook, script).unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+    };
+
+    // More input than a pipe buffers, from a hook that writes before reading
+    install("pre-push", "#!/bin/sh\necho start\nwc -c\n");
+    let input = "x".repeat(1 << 20);
+    let policy = git::HookPolicy::default();
+    let result = git::run_hook(&repo.repo, &policy, "pre-push", &[], Some(&input))
+        .unwrap()
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(
+        result.stdout.split_whitespace().collect::<Vec<_>>(),
+        ["start", "1048576"]
+    );
+
+    // Output written before the timeout is kept
+    install("pre-commit", "#!/bin/sh\necho partial\nexec sleep 5\n");
+    let policy = git::HookPolicy {
+        timeout_secs: 1,
+        ..Default::default()
+    };
+    let result = git::run_hook(&repo.repo, &policy, "pre-commit", &[], None)
+        .unwrap()
+        .unwrap();
+    assert!(!result.success);
+    assert_eq!(result.exit_code, None);
+    assert_eq!(result.stdout, "partial\n");
+    assert!(result.stderr.contains("timed out"), "{}", result.stderr);
+    assert!(result.duration_ms < 5000);
+
+    // A background process holding the pipes doesn't keep the hook running
+    install("post-commit", "#!/bin/sh\necho done\nsleep 5 &\n");
+    let result = git::run_hook(&repo.repo, &policy, "post-commit", &[], None)
+        .unwrap()
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.stdout, "done\n");
+    assert!(result.duration_ms < 5000);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, PreparedMessageSource};
+
+fn repo_with_template() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write(".gitmessage", "Summary\n\n# Why:\n");
+    repo.repo
+        .config()
+        .unwrap()
+        .set_str("commit.template", ".gitmessage")
+        .unwrap();
+    repo
+}
+
+#[test]
+fn starts_empty_without_a_template() {
+    let repo = TestRepo::new();
+    let template = git::get_commit_template(&repo.repo).unwrap();
+    assert_eq!(template.message, "");
+    assert!(template.template.is_none());
+    assert!(template.prepared_source.is_none());
+    assert_eq!(template.comment_char, "#");
+}
+
+#[test]
+fn reads_the_template_relative_to_the_repository() {
+    let repo = repo_with_template();
+    let template = git::get_commit_template(&repo.repo).unwrap();
+    assert_eq!(template.template.as_deref(), Some("Summary\n\n# Why:\n"));
+    assert_eq!(template.message, "Summary\n\n# Why:\n");
+    assert!(template
+        .template_path
+        .as_deref()
+        .unwrap()
+        .ends_with(".gitmessage"));
+
+    repo.remove(".gitmessage");
+    assert!(git::get_commit_template(&repo.repo).is_err());
+}
+
+#[test]
+fn prepared_messages_win_over_the_template() {
+    let repo = repo_with_template();
+    let git_dir = repo.repo.path().to_path_buf();
+
+    std::fs::write(
+        git_dir.join("SQUASH_MSG"),
+        "Squashed commit of the following:\n",
+    )
+    .unwrap();
+    let squash = git::get_commit_template(&repo.repo).unwrap();
+    assert!(matches!(
+        squash.prepared_source,
+        Some(PreparedMessageSource::Squash)
+    ));
+    assert_eq!(squash.message, "Squashed commit of the following:\n");
+    // The template is still reported for the UI to offer
+    assert!(squash.template.is_some());
+
+    std::fs::write(git_dir.join("MERGE_MSG"), "Merge branch 'topic'\n").unwrap();
+    let merge = git::get_commit_template(&repo.repo).unwrap();
+    assert!(matches!(
+        merge.prepared_source,
+        Some(PreparedMessageSource::Merge)
+    ));
+    assert_eq!(merge.message, "Merge branch 'topic'\n");
+}
+
+#[test]
+fn honors_the_comment_char() {
+    let repo = repo_with_template();
+    let mut config = repo.repo.config().unwrap();
+    config.set_str("core.commentChar", ";").unwrap();
+    assert_eq!(
+        git::get_commit_template(&repo.repo).unwrap().comment_char,
+        ";"
+    );
+
+    config.set_str("core.commentChar", "auto").unwrap();
+    assert_eq!(
+        git::get_commit_template(&repo.repo).unwrap().comment_char,
+        "#"
+    );
+}
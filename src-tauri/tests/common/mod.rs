@@ -0,0 +1,104 @@
+//! Helpers for building throwaway repositories in tests
+
+#![allow(dead_code)]
+
+use git2::{Oid, Repository, Signature, Time};
+use std::path::Path;
+use tempfile::TempDir;
+
+pub struct TestRepo {
+    pub repo: Repository,
+    // Held so the directory is removed when the repo is dropped
+    _dir: TempDir,
+}
+
+impl TestRepo {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let repo = Repository::init(dir.path()).expect("init repo");
+        Self { repo, _dir: dir }
+    }
+
+    /// Set `user.name` / `user.email` for operations that sign with the
+    /// repository's configured identity
+    pub fn set_identity(&self) {
+        let mut config = self.repo.config().expect("open config");
+        config.set_str("user.name", "Test").expect("set name");
+        config
+            .set_str("user.email", "test@example.com")
+            .expect("set email");
+    }
+
+    pub fn workdir(&self) -> &Path {
+        self.repo.workdir().expect("non-bare repo")
+    }
+
+    pub fn write(&self, path: &str, contents: &str) {
+        let full = self.workdir().join(path);
+        if let Some(parent) = full.parent() {
+            std::fs::create_dir_all(parent).expect("create dirs");
+        }
+        std::fs::write(full, contents).expect("write file");
+    }
+
+    pub fn remove(&self, path: &str) {
+        std::fs::remove_file(self.workdir().join(path)).expect("remove file");
+    }
+
+    pub fn stage(&self, path: &str) {
+        let mut index = self.repo.index().expect("open index");
+        index.add_path(Path::new(path)).expect("stage file");
+        index.write().expect("write index");
+    }
+
+    /// Stage all changes, including deletions, and commit them on HEAD with a
+    /// fixed author and timestamp so results are reproducible
+    pub fn commit_all(&self, message: &str) -> Oid {
+        let mut index = self.repo.index().expect("open index");
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .expect("stage files");
+        index.update_all(["*"], None).expect("stage deletions");
+        index.write().expect("write index");
+        let tree = self
+            .repo
+            .find_tree(index.write_tree().expect("write tree"))
+            .expect("find tree");
+
+        let sig = Signature::new("Test", "test@example.com", &Time::new(1_700_000_000, 0))
+            .expect("signature");
+        let parent = self.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        self.repo
+            .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .expect("commit")
+    }
+
+    pub fn branch(&self, name: &str, target: Oid) {
+        let commit = self.repo.find_commit(target).expect("find commit");
+        self.repo
+            .branch(name, &commit, true)
+            .expect("create branch");
+    }
+
+    pub fn checkout(&self, name: &str) {
+        let refname = format!("refs/heads/{}", name);
+        let obj = self.repo.revparse_single(&refname).expect("find branch");
+        self.repo
+            .checkout_tree(&obj, Some(git2::build::CheckoutBuilder::new().force()))
+            .expect("checkout");
+        self.repo.set_head(&refname).expect("set head");
+    }
+
+    /// Rename the checked-out branch, so tests don't depend on the
+    /// `init.defaultBranch` of whoever runs them
+    pub fn rename_head(&self, name: &str) {
+        let head = self.repo.head().expect("head");
+        let current = head.shorthand().expect("branch name").to_string();
+        self.repo
+            .find_branch(&current, git2::BranchType::Local)
+            .expect("find branch")
+            .rename(name, true)
+            .expect("rename branch");
+    }
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, ConflictKind, ConflictResolution};
+
+/// `master` and `feature` forked from a commit with `base`, each applying
+/// its own changes. Merging `feature` stops with the conflicts.
+fn conflicted(
+    repo: &TestRepo,
+    base: &[(&str, &str)],
+    ours: &[(&str, Option<&str>)],
+    theirs: &[(&str, Option<&str>)],
+) {
+    repo.set_identity();
+    for (path, content) in base {
+        repo.write(path, content);
+    }
+    let fork = repo.commit_all("base");
+    repo.branch("feature", fork);
+    let apply = |changes: &[(&str, Option<&str>)]| {
+        for (path, content) in changes {
+            match content {
+                Some(content) => repo.write(path, content),
+                None => repo.remove(path),
+            }
+        }
+    };
+    repo.checkout("feature");
+    apply(theirs);
+    repo.commit_all("theirs");
+    repo.checkout("master");
+    apply(ours);
+    repo.commit_all("ours");
+    let result = git::merge_branch(&repo.repo, "feature", &git::HookPolicy::default()).unwrap();
+    assert_eq!(result.outcome, git::MergeOutcome::Conflicted);
+}
+
+#[test]
+fn lists_each_side_of_a_conflict() {
+    let repo = TestRepo::new();
+    conflicted(
+        &repo,
+        &[("a.txt", "one\n"), ("gone.txt", "keep\n")],
+        &[
+            ("a.txt", Some("ours\n")),
+            ("gone.txt", None),
+            ("new.txt", Some("ours new\n")),
+        ],
+        &[
+            ("a.txt", Some("theirs\n")),
+            ("gone.txt", Some("changed\n")),
+            ("new.txt", Some("theirs new\n")),
+        ],
+    );
+
+    let files = git::get_conflicts(&repo.repo).unwrap().files;
+    let paths: Vec<_> = files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["a.txt", "gone.txt", "new.txt"]);
+
+    let a = &files[0];
+    assert_eq!(a.kind, ConflictKind::BothModified);
+    assert_eq!(a.ancestor.as_deref(), Some("one\n"));
+    assert_eq!(a.ours.as_deref(), Some("ours\n"));
+    assert_eq!(a.theirs.as_deref(), Some("theirs\n"));
+    let working = a.working.as_deref().unwrap();
+    assert!(working.starts_with("<<<<<<< "));
+    assert!(working.contains("ours\n=======\ntheirs\n>>>>>>> "));
+    assert!(!a.binary);
+
+    let gone = &files[1];
+    assert_eq!(gone.kind, ConflictKind::DeletedByUs);
+    assert_eq!(gone.ancestor.as_deref(), Some("keep\n"));
+    assert!(gone.ours.is_none());
+    assert_eq!(gone.theirs.as_deref(), Some("changed\n"));
+
+    let new = &files[2];
+    assert_eq!(new.kind, ConflictKind::BothAdded);
+    assert!(new.ancestor.is_none());
+    assert_eq!(new.ours.as_deref(), Some("ours new\n"));
+
+    // Resolved and staged files are no longer conflicts
+    for path in ["a.txt", "gone.txt", "new.txt"] {
+        repo.write(path, "resolved\n");
+        repo.stage(path);
+    }
+    assert!(git::get_conflicts(&repo.repo).unwrap().files.is_empty());
+}
+
+#[test]
+fn leaves_out_binary_contents() {
+    let repo = TestRepo::new();
+    conflicted(
+        &repo,
+        &[("image.bin", "\0base")],
+        &[("image.bin", Some("\0ours"))],
+        &[("image.bin", Some("\0theirs"))],
+    );
+
+    let files = git::get_conflicts(&repo.repo).unwrap().files;
+    assert_eq!(files.len(), 1);
+    assert!(files[0].binary);
+    assert!(files[0].ancestor.is_none() && files[0].ours.is_none() && files[0].working.is_none());
+}
+
+#[test]
+fn resolves_with_ours_theirs_or_merged_content() {
+    let repo = TestRepo::new();
+    conflicted(
+        &repo,
+        &[
+            ("a.txt", "one\n"),
+            ("b.txt", "one\n"),
+            ("c.txt", "one\n"),
+            ("gone.txt", "keep\n"),
+        ],
+        &[
+            ("a.txt", Some("ours a\n")),
+            ("b.txt", Some("ours b\n")),
+            ("c.txt", Some("ours c\n")),
+            ("gone.txt", None),
+        ],
+        &[
+            ("a.txt", Some("theirs a\n")),
+            ("b.txt", Some("theirs b\n")),
+            ("c.txt", Some("theirs c\n")),
+            ("gone.txt", Some("changed\n")),
+        ],
+    );
+    let read = |path: &str| std::fs::read_to_string(repo.workdir().join(path)).unwrap();
+
+    let remaining = git::resolve_conflict(&repo.repo, "a.txt", &ConflictResolution::Ours).unwrap();
+    assert_eq!(remaining, ["b.txt", "c.txt", "gone.txt"]);
+    assert_eq!(read("a.txt"), "ours a\n");
+
+    git::resolve_conflict(&repo.repo, "b.txt", &ConflictResolution::Theirs).unwrap();
+    assert_eq!(read("b.txt"), "theirs b\n");
+
+    let merged = ConflictResolution::Content {
+        text: "ours c\ntheirs c\n".to_string(),
+    };
+    git::resolve_conflict(&repo.repo, "c.txt", &merged).unwrap();
+    assert_eq!(read("c.txt"), "ours c\ntheirs c\n");
+
+    // Our side deleted it, so taking ours deletes it
+    let remaining =
+        git::resolve_conflict(&repo.repo, "gone.txt", &ConflictResolution::Ours).unwrap();
+    assert!(remaining.is_empty());
+    assert!(!repo.workdir().join("gone.txt").exists());
+
+    assert!(matches!(
+        git::resolve_conflict(&repo.repo, "a.txt", &ConflictResolution::Theirs),
+        Err(git::GitError::NotConflicted(path)) if path == "a.txt"
+    ));
+
+    // The index holds each resolution, ready to conclude the merge
+    let index = repo.repo.index().unwrap();
+    assert!(!index.has_conflicts());
+    let staged = |path: &str| {
+        let entry = index.get_path(path.as_ref(), 0).unwrap();
+        let blob = repo.repo.find_blob(entry.id).unwrap();
+        String::from_utf8(blob.content().to_vec()).unwrap()
+    };
+    assert_eq!(staged("a.txt"), "ours a\n");
+    assert_eq!(staged("b.txt"), "theirs b\n");
+    assert_eq!(staged("c.txt"), "ours c\ntheirs c\n");
+    assert!(index.get_path("gone.txt".as_ref(), 0).is_none());
+    git::create_commit(&repo.repo, "Merge feature", &git::HookPolicy::default()).unwrap();
+}
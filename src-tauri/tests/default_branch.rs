@@ -0,0 +1,130 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DefaultBranchSource};
+
+fn default_branch(repo: &TestRepo) -> (Option<String>, Option<DefaultBranchSource>) {
+    let status = git::get_repo_status(&repo.repo).unwrap();
+    (status.default_branch, status.default_branch_source)
+}
+
+/// A repository whose only branch is `name`, checked out
+fn repo_on(name: &str) -> TestRepo {
+    let repo = TestRepo::new();
+    // Candidates that `init.defaultBranch` would try first shouldn't exist
+    repo.repo
+        .config()
+        .unwrap()
+        .set_str("init.defaultBranch", "no-such-branch")
+        .unwrap();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.rename_head(name);
+    repo
+}
+
+#[test]
+fn falls_back_to_conventional_names() {
+    let repo = repo_on("trunk");
+    let head = repo.repo.head().unwrap().target().unwrap();
+    repo.branch("feature", head);
+    repo.branch("master", head);
+
+    assert_eq!(
+        default_branch(&repo),
+        (
+            Some("master".to_string()),
+            Some(DefaultBranchSource::Heuristic)
+        )
+    );
+
+    // Tracking branches count when there is no local one
+    let repo = repo_on("feature");
+    repo.repo
+        .reference("refs/remotes/origin/main", head_of(&repo), true, "test")
+        .unwrap();
+    assert_eq!(
+        default_branch(&repo),
+        (
+            Some("origin/main".to_string()),
+            Some(DefaultBranchSource::Heuristic)
+        )
+    );
+}
+
+#[test]
+fn prefers_origin_head() {
+    let repo = repo_on("main");
+    let head = head_of(&repo);
+    repo.branch("develop", head);
+    repo.repo
+        .reference("refs/remotes/origin/develop", head, true, "test")
+        .unwrap();
+    repo.repo
+        .reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/develop",
+            true,
+            "test",
+        )
+        .unwrap();
+
+    // The local branch of the same name wins over the remote one
+    assert_eq!(
+        default_branch(&repo),
+        (
+            Some("develop".to_string()),
+            Some(DefaultBranchSource::Remote)
+        )
+    );
+
+    repo.repo
+        .find_branch("develop", git2::BranchType::Local)
+        .unwrap()
+        .delete()
+        .unwrap();
+    assert_eq!(
+        default_branch(&repo),
+        (
+            Some("origin/develop".to_string()),
+            Some(DefaultBranchSource::Remote)
+        )
+    );
+}
+
+#[test]
+fn repo_config_overrides_detection() {
+    let repo = repo_on("main");
+    repo.write(".diffyrc.json", r#"{ "defaultBranch": "release" }"#);
+    assert_eq!(
+        default_branch(&repo),
+        (
+            Some("release".to_string()),
+            Some(DefaultBranchSource::Config)
+        )
+    );
+
+    // An empty override is ignored
+    repo.write(".diffyrc.json", r#"{ "defaultBranch": "" }"#);
+    assert_eq!(
+        default_branch(&repo),
+        (
+            Some("main".to_string()),
+            Some(DefaultBranchSource::Heuristic)
+        )
+    );
+}
+
+#[test]
+fn reports_nothing_without_a_candidate() {
+    let repo = repo_on("work");
+    assert_eq!(default_branch(&repo), (None, None));
+
+    let status = git::get_repo_status(&repo.repo).unwrap();
+    assert_eq!(status.current_branch.as_deref(), Some("work"));
+    assert!(!status.is_detached);
+}
+
+fn head_of(repo: &TestRepo) -> git2::Oid {
+    repo.repo.head().unwrap().target().unwrap()
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DependencyChange, DependencyChangeKind};
+
+fn find<'a>(changes: &'a [DependencyChange], name: &str) -> &'a DependencyChange {
+    changes
+        .iter()
+        .find(|c| c.name == name)
+        .unwrap_or_else(|| panic!("no change for {}", name))
+}
+
+fn cargo_lock(packages: &[(&str, &str)]) -> String {
+    packages
+        .iter()
+        .map(|(name, version)| {
+            format!(
+                "[[package]]\nname = \"{}\"\nversion = \"{}\"\n\n",
+                name, version
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn prefers_resolved_lockfile_versions() {
+    let repo = TestRepo::new();
+    repo.write(
+        "Cargo.toml",
+        "[package]\nname = \"app\"\n\n[dependencies]\nserde = \"1\"\nlog = \"0.4\"\n",
+    );
+    repo.write(
+        "Cargo.lock",
+        &cargo_lock(&[("serde", "1.0.100"), ("log", "0.4.20"), ("itoa", "1.0.0")]),
+    );
+    let base = repo.commit_all("first").to_string();
+
+    repo.write(
+        "Cargo.toml",
+        "[package]\nname = \"app\"\n\n[dependencies]\nserde = \"1.0.200\"\nlog = \"0.4.17\"\n",
+    );
+    repo.write(
+        "Cargo.lock",
+        &cargo_lock(&[("serde", "1.0.200"), ("log", "0.4.20"), ("ryu", "1.0.0")]),
+    );
+    let head = repo.commit_all("bump").to_string();
+
+    let analysis = git::analyze_dependency_changes(&repo.repo, &base, &head).unwrap();
+    assert_eq!(analysis.manifests, vec!["Cargo.toml"]);
+
+    let serde = find(&analysis.changes, "serde");
+    assert!(matches!(serde.kind, DependencyChangeKind::Upgraded));
+    assert_eq!(serde.old_version.as_deref(), Some("1.0.100"));
+    assert_eq!(serde.new_version.as_deref(), Some("1.0.200"));
+    assert_eq!(serde.manifest, "Cargo.lock");
+    assert!(serde.direct);
+
+    let itoa = find(&analysis.changes, "itoa");
+    assert!(matches!(itoa.kind, DependencyChangeKind::Removed));
+    assert!(!itoa.direct);
+    assert!(matches!(
+        find(&analysis.changes, "ryu").kind,
+        DependencyChangeKind::Added
+    ));
+
+    // The requirement moved but the resolved version did not
+    let log = find(&analysis.changes, "log");
+    assert!(matches!(log.kind, DependencyChangeKind::Upgraded));
+    assert_eq!(log.manifest, "Cargo.toml");
+    assert_eq!(log.new_version.as_deref(), Some("0.4.17"));
+}
+
+#[test]
+fn falls_back_to_manifest_requirements_without_a_lockfile() {
+    let repo = TestRepo::new();
+    repo.write(
+        "web/package.json",
+        r#"{"dependencies": {"react": "^18.2.0", "left-pad": "1.0.0"}}"#,
+    );
+    let base = repo.commit_all("first").to_string();
+    repo.write(
+        "web/package.json",
+        r#"{"dependencies": {"react": "^17.0.0"}, "devDependencies": {"vitest": "^1.0.0"}}"#,
+    );
+    let head = repo.commit_all("change").to_string();
+
+    let analysis = git::analyze_dependency_changes(&repo.repo, &base, &head).unwrap();
+    assert_eq!(analysis.manifests, vec!["web/package.json"]);
+    assert_eq!(analysis.changes.len(), 3);
+    assert!(matches!(
+        find(&analysis.changes, "react").kind,
+        DependencyChangeKind::Downgraded
+    ));
+    assert!(matches!(
+        find(&analysis.changes, "left-pad").kind,
+        DependencyChangeKind::Removed
+    ));
+    let vitest = find(&analysis.changes, "vitest");
+    assert!(matches!(vitest.kind, DependencyChangeKind::Added));
+    assert_eq!(vitest.manifest, "web/package.json");
+}
+
+#[test]
+fn marks_indirect_go_requirements_as_transitive() {
+    let repo = TestRepo::new();
+    repo.write(
+        "go.mod",
+        "module example.com/app\n\nrequire (\n\tgithub.com/a/lib v1.2.0\n\tgithub.com/b/dep v0.1.0 // indirect\n)\n",
+    );
+    let base = repo.commit_all("first").to_string();
+    repo.write(
+        "go.mod",
+        "module example.com/app\n\nrequire (\n\tgithub.com/a/lib v1.3.0\n\tgithub.com/b/dep v0.2.0 // indirect\n)\n",
+    );
+    let head = repo.commit_all("bump").to_string();
+
+    let analysis = git::analyze_dependency_changes(&repo.repo, &base, &head).unwrap();
+    let lib = find(&analysis.changes, "github.com/a/lib");
+    assert!(matches!(lib.kind, DependencyChangeKind::Upgraded));
+    assert!(lib.direct);
+    let dep = find(&analysis.changes, "github.com/b/dep");
+    assert!(matches!(dep.kind, DependencyChangeKind::Upgraded));
+    assert!(!dep.direct);
+}
+
+#[test]
+fn ignores_comparisons_that_touch_no_manifest() {
+    let repo = TestRepo::new();
+    repo.write("Cargo.toml", "[dependencies]\nserde = \"1\"\n");
+    repo.write("src/main.rs", "fn main() {}\n");
+    let base = repo.commit_all("first").to_string();
+    repo.write("src/main.rs", "fn main() { println!(); }\n");
+    let head = repo.commit_all("code").to_string();
+
+    let analysis = git::analyze_dependency_changes(&repo.repo, &base, &head).unwrap();
+    assert!(analysis.manifests.is_empty());
+    assert!(analysis.changes.is_empty());
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DiffSnapshot, DifferConfig};
+
+fn snapshot(repo: &TestRepo) -> (DiffSnapshot, git::DiffResult) {
+    let diff = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    (DiffSnapshot::new(&diff), diff)
+}
+
+fn id(diff: &git::DiffResult, path: &str) -> String {
+    diff.files
+        .iter()
+        .find(|f| f.path == path)
+        .unwrap()
+        .id
+        .clone()
+}
+
+#[test]
+fn reports_files_changed_since_the_last_load() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "two\n");
+    repo.write("c.txt", "three\n");
+    repo.commit_all("initial");
+    repo.write("a.txt", "one!\n");
+    repo.write("b.txt", "two!\n");
+
+    let (first, first_diff) = snapshot(&repo);
+    let delta = first.delta(None);
+    assert!(delta.full);
+    assert_eq!(
+        delta.added,
+        [id(&first_diff, "a.txt"), id(&first_diff, "b.txt")]
+    );
+
+    // Nothing happened between loads
+    let (again, _) = snapshot(&repo);
+    let delta = again.delta(Some(&first));
+    assert!(!delta.full);
+    assert!(delta.added.is_empty() && delta.removed.is_empty() && delta.modified.is_empty());
+    assert_eq!(delta.unchanged, 2);
+
+    repo.write("a.txt", "one!!\n");
+    repo.write("b.txt", "two\n");
+    repo.write("c.txt", "three!\n");
+    let (next, next_diff) = snapshot(&repo);
+    let delta = next.delta(Some(&again));
+    assert_eq!(delta.modified, [id(&first_diff, "a.txt")]);
+    assert_eq!(delta.removed, [id(&first_diff, "b.txt")]);
+    assert_eq!(delta.added, [id(&next_diff, "c.txt")]);
+    assert_eq!(delta.unchanged, 0);
+}
+
+#[test]
+fn moved_hunks_and_binary_files_count_as_modified() {
+    let repo = TestRepo::new();
+    let lines: Vec<String> = (1..=30).map(|i| format!("line {}\n", i)).collect();
+    repo.write("a.txt", &lines.concat());
+    repo.write("b.bin", "\0one\0");
+    repo.commit_all("initial");
+
+    let mut edited = lines.clone();
+    edited[25] = "changed\n".to_string();
+    repo.write("a.txt", &edited.concat());
+    repo.write("b.bin", "\0two\0");
+    let (before, diff) = snapshot(&repo);
+
+    // Same hunk, further down the file
+    edited.insert(0, "new first line\n".to_string());
+    repo.write("a.txt", &edited.concat());
+    let (after, _) = snapshot(&repo);
+    let delta = after.delta(Some(&before));
+    assert_eq!(delta.modified, [id(&diff, "a.txt"), id(&diff, "b.bin")]);
+    assert_eq!(delta.unchanged, 0);
+}
@@ -0,0 +1,615 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig};
+use serde_json::{json, Value};
+
+/// Serialize a diff with the file and hunk ids taken out, after checking
+/// they are there; their values are covered by the anchor tests
+fn without_ids(diff: &impl serde::Serialize) -> Value {
+    let mut value = serde_json::to_value(diff).unwrap();
+    for file in value["files"].as_array_mut().unwrap() {
+        let file = file.as_object_mut().unwrap();
+        assert!(file.remove("id").unwrap().is_string());
+        for hunk in file["hunks"].as_array_mut().unwrap() {
+            assert!(hunk
+                .as_object_mut()
+                .unwrap()
+                .remove("id")
+                .unwrap()
+                .is_string());
+        }
+    }
+    value
+}
+
+#[test]
+fn working_directory_diff_round_trip() {
+    let repo = TestRepo::new();
+    repo.write("src/main.rs", "fn main() {\n    println!(\"hi\");\n}\n");
+    repo.write("README.md", "# Demo\n");
+    repo.commit_all("initial");
+
+    repo.write("src/main.rs", "fn main() {\n    println!(\"hello\");\n}\n");
+    repo.write(
+        "src/lib.rs",
+        "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n",
+    );
+    repo.remove("README.md");
+    // New files only carry content once staged; untracked ones are listed empty
+    repo.stage("src/lib.rs");
+
+    let diff = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+
+    assert_eq!(
+        without_ids(&diff),
+        json!({
+            "files": [
+                {
+                    "path": "README.md",
+                    "status": "deleted",
+                    "additions": 0,
+                    "deletions": 1,
+                    "patch": "-# Demo\n",
+                    "isLarge": false,
+                    "minimap": "d1",
+                    "hunks": [{ "oldStart": 1, "newStart": 0 }],
+                },
+                {
+                    "path": "src/lib.rs",
+                    "status": "added",
+                    "additions": 3,
+                    "deletions": 0,
+                    "patch": "+pub fn add(a: i32, b: i32) -> i32 {\n+    a + b\n+}\n",
+                    "isLarge": false,
+                    "minimap": "a3",
+                    "hunks": [{ "oldStart": 0, "newStart": 1 }],
+                },
+                {
+                    "path": "src/main.rs",
+                    "status": "modified",
+                    "additions": 1,
+                    "deletions": 1,
+                    "patch": " fn main() {\n-    println!(\"hi\");\n+    println!(\"hello\");\n }\n",
+                    "isLarge": false,
+                    "minimap": "c1d1a1c1",
+                    "hunks": [{ "oldStart": 1, "newStart": 1 }],
+                },
+            ],
+            "stats": { "additions": 4, "deletions": 2, "files": 3 },
+            "warnings": [],
+            "complexity": {
+                "totalChangedLines": 6,
+                "filesChanged": 3,
+                "largeFiles": [],
+                "size": "small",
+                "suggestSplit": false,
+            },
+            "navigation": {
+                "changes": [
+                    { "file": 0, "hunk": 0 },
+                    { "file": 1, "hunk": 0 },
+                    { "file": 2, "hunk": 0 },
+                ],
+                "fileStarts": [0, 1, 2, 3],
+            },
+        })
+    );
+}
+
+#[test]
+fn clean_working_directory_has_no_files() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "a\n");
+    repo.commit_all("initial");
+
+    let diff = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    assert!(diff.files.is_empty());
+    assert_eq!(diff.stats.files, 0);
+}
+
+#[test]
+fn working_directory_diff_against_other_ref() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("base");
+    repo.branch("main", base);
+    repo.write("a.txt", "one\ntwo\n");
+    repo.commit_all("feature commit");
+    repo.write("a.txt", "one\ntwo\nthree\n");
+
+    let config = DifferConfig::default();
+    let vs_head = git::get_current_diff(&repo.repo, None, &config).unwrap();
+    let vs_main = git::get_current_diff(&repo.repo, Some("main"), &config).unwrap();
+    let vs_parent = git::get_current_diff(&repo.repo, Some("HEAD~1"), &config).unwrap();
+
+    assert_eq!(
+        vs_head.files[0].patch.as_deref(),
+        Some(" one\n two\n+three\n")
+    );
+    assert_eq!(
+        vs_main.files[0].patch.as_deref(),
+        Some(" one\n+two\n+three\n")
+    );
+    assert_eq!(vs_parent.files[0].additions, 2);
+    let lazy_patch = git::get_file_patch(&repo.repo, "a.txt", Some("main")).unwrap();
+    assert!(lazy_patch.ends_with("@@ -1 +1,3 @@\n one\n+two\n+three\n"));
+    assert!(git::get_current_diff(&repo.repo, Some("no-such-ref"), &config).is_err());
+}
+
+#[test]
+fn staged_diff_ignores_unstaged_edits() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("base");
+    repo.branch("main", base);
+    repo.write("a.txt", "one\ntwo\n");
+    repo.commit_all("feature commit");
+
+    repo.write("a.txt", "one\ntwo\nstaged\n");
+    repo.stage("a.txt");
+    repo.write("a.txt", "one\ntwo\nstaged\nunstaged\n");
+
+    let config = DifferConfig::default();
+    let vs_head = git::get_staged_diff(&repo.repo, None, &config).unwrap();
+    let vs_main = git::get_staged_diff(&repo.repo, Some("main"), &config).unwrap();
+
+    assert_eq!(
+        vs_head.files[0].patch.as_deref(),
+        Some(" one\n two\n+staged\n")
+    );
+    assert_eq!(
+        vs_main.files[0].patch.as_deref(),
+        Some(" one\n+two\n+staged\n")
+    );
+}
+
+#[test]
+fn working_changes_split_staged_from_unstaged() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "b\n");
+    repo.commit_all("base");
+
+    repo.write("a.txt", "one\nstaged\n");
+    repo.stage("a.txt");
+    repo.write("a.txt", "one\nstaged\nunstaged\n");
+    repo.write("b.txt", "b2\n");
+    repo.write("new.txt", "untracked\n");
+
+    let changes = git::get_working_changes(&repo.repo, &DifferConfig::default()).unwrap();
+    let staged: Vec<_> = changes
+        .staged
+        .files
+        .iter()
+        .map(|f| f.path.as_str())
+        .collect();
+    let unstaged: Vec<_> = changes
+        .unstaged
+        .files
+        .iter()
+        .map(|f| f.path.as_str())
+        .collect();
+    assert_eq!(staged, ["a.txt"]);
+    assert_eq!(unstaged, ["a.txt", "b.txt", "new.txt"]);
+    assert_eq!(changes.partially_staged, ["a.txt"]);
+    assert_eq!(
+        changes.staged.files[0].patch.as_deref(),
+        Some(" one\n+staged\n")
+    );
+    assert_eq!(
+        changes.unstaged.files[0].patch.as_deref(),
+        Some(" one\n staged\n+unstaged\n")
+    );
+}
+
+#[test]
+fn oversized_patches_are_dropped_but_counted() {
+    let repo = TestRepo::new();
+    repo.write("big.txt", "");
+    repo.commit_all("initial");
+
+    let contents: String = (0..1000).map(|i| format!("line {}\n", i)).collect();
+    repo.write("big.txt", &contents);
+
+    let head = repo.repo.head().unwrap().peel_to_tree().unwrap();
+    let raw = repo
+        .repo
+        .diff_tree_to_workdir_with_index(Some(&head), None)
+        .unwrap();
+    let diff = git::parse_diff(&raw, 1024).unwrap();
+
+    let file = &diff.files[0];
+    assert_eq!(file.additions, 1000);
+    assert_eq!(file.is_large, Some(true));
+    assert_eq!(file.patch.as_deref(), Some(""));
+    assert_eq!(file.minimap.as_deref(), Some("a1000"));
+}
+
+#[test]
+fn commit_diff_round_trip() {
+    let repo = TestRepo::new();
+    repo.write("notes.txt", "one\ntwo\nthree\n");
+    repo.commit_all("initial");
+    repo.write("notes.txt", "one\n2\nthree\nfour\n");
+    let sha = repo.commit_all("update notes").to_string();
+
+    let diff = git::get_commit_diff(&repo.repo, &sha, &DifferConfig::default()).unwrap();
+
+    assert_eq!(
+        without_ids(&diff),
+        json!({
+            "commit": {
+                "sha": sha,
+                "shortSha": &sha[..7],
+                "message": "update notes",
+                "subject": "update notes",
+                "author": "Test",
+                "authorEmail": "test@example.com",
+                "date": "2023-11-14T22:13:20Z",
+                "stats": { "additions": 2, "deletions": 1, "files": 1 },
+                "signed": false,
+            },
+            "description": {
+                "subject": [{ "kind": "text", "text": "update notes" }],
+                "body": [],
+            },
+            "files": [
+                {
+                    "path": "notes.txt",
+                    "status": "modified",
+                    "additions": 2,
+                    "deletions": 1,
+                    "patch": " one\n-two\n+2\n three\n+four\n",
+                    "isLarge": false,
+                    "minimap": "c1d1a1c1a1",
+                    "hunks": [{ "oldStart": 1, "newStart": 1 }],
+                },
+            ],
+            "warnings": [],
+            "navigation": {
+                "changes": [{ "file": 0, "hunk": 0 }],
+                "fileStarts": [0, 1],
+            },
+        })
+    );
+}
+
+#[test]
+fn initial_commit_diffs_against_empty_tree() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "a\nb\n");
+    let sha = repo.commit_all("initial").to_string();
+
+    let diff = git::get_commit_diff(&repo.repo, &sha, &DifferConfig::default()).unwrap();
+    assert_eq!(diff.files.len(), 1);
+    assert_eq!(diff.commit.stats.additions, 2);
+    assert!(matches!(diff.files[0].status, git::FileStatus::Added));
+}
+
+#[test]
+fn commit_history_pages() {
+    let repo = TestRepo::new();
+    for i in 0..5 {
+        repo.write("counter.txt", &format!("{}\n", i));
+        repo.commit_all(&format!("commit {}", i));
+    }
+
+    let page = git::get_commit_history(&repo.repo, 2, 1, &DifferConfig::default()).unwrap();
+    assert_eq!(page.total, 5);
+    assert_eq!(page.commits.len(), 2);
+
+    let past_end = git::get_commit_history(&repo.repo, 2, 10, &DifferConfig::default()).unwrap();
+    assert_eq!(past_end.total, 5);
+    assert!(past_end.commits.is_empty());
+}
+
+#[test]
+fn compare_branches_round_trip() {
+    let repo = TestRepo::new();
+    repo.write("app.py", "def run():\n    return 1\n");
+    let base = repo.commit_all("base");
+    repo.branch("main", base);
+
+    repo.branch("feature", base);
+    repo.checkout("feature");
+    repo.write("app.py", "def run():\n    return 2\n");
+    repo.write(".env", "SECRET=1\n");
+    repo.commit_all("feature work");
+
+    let result =
+        git::compare_branches(&repo.repo, "main", "feature", &DifferConfig::default()).unwrap();
+    let mut value = without_ids(&result);
+
+    // Risk depends on blame and history heuristics; covered separately
+    for file in value["files"].as_array_mut().unwrap() {
+        assert!(file["risk"]["score"].is_u64());
+        assert_eq!(file["riskStatus"], "scored");
+        let file = file.as_object_mut().unwrap();
+        file.remove("risk");
+        file.remove("riskStatus");
+    }
+    let commits = value.as_object_mut().unwrap().remove("commits").unwrap();
+    assert_eq!(commits[0]["message"], "feature work");
+
+    assert_eq!(
+        value,
+        json!({
+            "files": [
+                {
+                    "path": ".env",
+                    "status": "added",
+                    "additions": 1,
+                    "deletions": 0,
+                    "patch": "+SECRET=1\n",
+                    "isLarge": false,
+                    "minimap": "a1",
+                    "hunks": [{ "oldStart": 0, "newStart": 1 }],
+                },
+                {
+                    "path": "app.py",
+                    "status": "modified",
+                    "additions": 1,
+                    "deletions": 1,
+                    "patch": " def run():\n-    return 1\n+    return 2\n",
+                    "isLarge": false,
+                    "minimap": "c1d1a1",
+                    "hunks": [{ "oldStart": 1, "newStart": 1 }],
+                },
+            ],
+            "stats": { "additions": 2, "deletions": 1, "files": 2 },
+            "commitCount": 1,
+            "appliedInTruncated": false,
+            "warnings": [
+                {
+                    "category": "sensitive_file",
+                    "path": ".env",
+                    "message": "Sensitive file .env added (matches .env)",
+                },
+            ],
+            "complexity": {
+                "totalChangedLines": 3,
+                "filesChanged": 2,
+                "largeFiles": [],
+                "size": "small",
+                "suggestSplit": false,
+            },
+            "byExtension": [
+                {
+                    "extension": "py",
+                    "language": "Python",
+                    "files": 1,
+                    "additions": 1,
+                    "deletions": 1,
+                },
+                {
+                    "extension": ".env",
+                    "language": "Other",
+                    "files": 1,
+                    "additions": 1,
+                    "deletions": 0,
+                },
+            ],
+            "navigation": {
+                "changes": [{ "file": 0, "hunk": 0 }, { "file": 1, "hunk": 0 }],
+                "fileStarts": [0, 1, 2],
+            },
+        })
+    );
+}
+
+#[test]
+fn navigation_steps_through_every_hunk() {
+    let repo = TestRepo::new();
+    let lines: Vec<String> = (1..=30).map(|i| format!("line {}\n", i)).collect();
+    repo.write("a.txt", &lines.concat());
+    repo.write("b.bin", "\0binary\0");
+    repo.write("c.txt", &lines.concat());
+    repo.commit_all("initial");
+
+    // Two hunks far enough apart to stay separate
+    let mut edited = lines.clone();
+    edited[1] = "second\n".to_string();
+    edited[25] = "twenty-sixth\n".to_string();
+    repo.write("a.txt", &edited.concat());
+    repo.write("b.bin", "\0changed\0");
+    repo.write("c.txt", &lines[..29].concat());
+
+    let diff = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    let paths: Vec<_> = diff.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["a.txt", "b.bin", "c.txt"]);
+    let starts: Vec<_> = diff.files[0]
+        .hunks
+        .iter()
+        .map(|h| (h.old_start, h.new_start))
+        .collect();
+    assert_eq!(starts, [(1, 1), (23, 23)]);
+    assert!(diff.files[1].hunks.is_empty());
+
+    let navigation = &diff.navigation;
+    let stops: Vec<_> = navigation
+        .changes
+        .iter()
+        .map(|c| (c.file, c.hunk))
+        .collect();
+    assert_eq!(stops, [(0, 0), (0, 1), (2, 0)]);
+    // A file without hunks starts where the next change is
+    assert_eq!(navigation.file_starts, [0, 2, 2, 3]);
+
+    // Anchors are kept when the patch itself is too large to ship
+    let head = repo.repo.head().unwrap().peel_to_tree().unwrap();
+    let raw = repo
+        .repo
+        .diff_tree_to_workdir_with_index(Some(&head), None)
+        .unwrap();
+    let small = git::parse_diff(&raw, 16).unwrap();
+    assert_eq!(small.files[0].is_large, Some(true));
+    assert_eq!(small.files[0].hunks.len(), 2);
+    assert_eq!(small.navigation, diff.navigation);
+}
+
+#[test]
+fn file_and_hunk_ids_survive_refreshes() {
+    let repo = TestRepo::new();
+    let lines: Vec<String> = (1..=30).map(|i| format!("line {}\n", i)).collect();
+    repo.write("a.txt", &lines.concat());
+    repo.write("b.txt", "same\n");
+    repo.commit_all("initial");
+
+    let mut edited = lines.clone();
+    edited[25] = "twenty-sixth\n".to_string();
+    repo.write("a.txt", &edited.concat());
+    repo.write("b.txt", "changed\n");
+    let config = DifferConfig::default();
+    let before = git::get_current_diff(&repo.repo, None, &config).unwrap();
+
+    // Editing near the top shifts the hunk further down, but it keeps its id
+    edited.insert(1, "inserted\n".to_string());
+    repo.write("a.txt", &edited.concat());
+    let after = git::get_current_diff(&repo.repo, None, &config).unwrap();
+
+    assert_eq!(before.files[0].id, after.files[0].id);
+    assert_eq!(before.files[1].id, after.files[1].id);
+    assert_ne!(before.files[0].id, before.files[1].id);
+    assert_eq!(after.files[0].hunks.len(), 2);
+    let moved = &after.files[0].hunks[1];
+    assert_eq!(moved.id, before.files[0].hunks[0].id);
+    assert_eq!(moved.new_start, before.files[0].hunks[0].new_start + 1);
+    assert_ne!(after.files[0].hunks[0].id, moved.id);
+
+    // Changing a hunk's lines gives it a new id
+    edited[26] = "26\n".to_string();
+    repo.write("a.txt", &edited.concat());
+    let rewritten = git::get_current_diff(&repo.repo, None, &config).unwrap();
+    assert_eq!(rewritten.files[0].id, before.files[0].id);
+    assert_ne!(rewritten.files[0].hunks[1].id, moved.id);
+}
+
+#[test]
+fn compare_refs_accepts_any_revision() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.repo
+        .tag_lightweight("v1", &repo.repo.find_object(first, None).unwrap(), false)
+        .unwrap();
+    repo.write("a.txt", "two\n");
+    repo.commit_all("second");
+    repo.write("b.txt", "new\n");
+    let third = repo.commit_all("third");
+
+    let config = DifferConfig::default();
+    let by_tag = git::compare_refs(&repo.repo, "v1", &third.to_string(), &config).unwrap();
+    let by_ancestry = git::compare_refs(&repo.repo, "HEAD~2", "HEAD", &config).unwrap();
+
+    for result in [&by_tag, &by_ancestry] {
+        let paths: Vec<_> = result.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+        assert_eq!(result.commit_count, 2);
+        let messages: Vec<_> = result.commits.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(messages, ["second", "third"]);
+    }
+    assert!(git::compare_branches(&repo.repo, "HEAD~2", "HEAD", &config).is_err());
+
+    let page =
+        git::get_compare_commits(&repo.repo, "v1", "HEAD", 1, 1, &DifferConfig::default()).unwrap();
+    assert_eq!(page.total, 2);
+    assert_eq!(page.commits.len(), 1);
+    assert_eq!(page.commits[0].message, "third");
+}
+
+#[test]
+fn case_only_renames_are_one_entry() {
+    let repo = TestRepo::new();
+    repo.write("src/Foo.ts", "export const a = 1;\nexport const b = 2;\n");
+    repo.commit_all("initial");
+
+    std::fs::rename(
+        repo.workdir().join("src/Foo.ts"),
+        repo.workdir().join("src/foo.ts"),
+    )
+    .unwrap();
+    repo.write("src/foo.ts", "export const a = 1;\nexport const b = 3;\n");
+    let mut index = repo.repo.index().unwrap();
+    index
+        .remove_path(std::path::Path::new("src/Foo.ts"))
+        .unwrap();
+    index.add_path(std::path::Path::new("src/foo.ts")).unwrap();
+    index.write().unwrap();
+
+    let diff = git::get_staged_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    assert_eq!(diff.files.len(), 1);
+    let file = &diff.files[0];
+    assert_eq!(file.path, "src/foo.ts");
+    assert_eq!(file.old_path.as_deref(), Some("src/Foo.ts"));
+    assert!(matches!(file.status, git::FileStatus::Renamed));
+    assert_eq!(file.case_rename, Some(true));
+    assert_eq!((file.additions, file.deletions), (1, 1));
+    assert_eq!(
+        file.patch.as_deref(),
+        Some(" export const a = 1;\n-export const b = 2;\n+export const b = 3;\n")
+    );
+    assert_eq!((diff.stats.files, diff.stats.additions), (1, 1));
+}
+
+#[test]
+fn file_contents_resolve_casing_when_git_ignores_case() {
+    let repo = TestRepo::new();
+    repo.write("docs/Readme.md", "# Hi\n");
+    repo.commit_all("initial");
+
+    // Exact paths only, as on a case-sensitive filesystem
+    assert!(
+        git::read_file_at(&repo.repo, "DOCS/README.md", Some("HEAD"))
+            .unwrap()
+            .is_none()
+    );
+
+    repo.repo
+        .config()
+        .unwrap()
+        .set_bool("core.ignorecase", true)
+        .unwrap();
+    for git_ref in [Some("HEAD"), None] {
+        assert_eq!(
+            git::get_file_contents(&repo.repo, "DOCS/README.md", git_ref).unwrap(),
+            "# Hi\n"
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_files_are_marked_instead_of_failing_the_diff() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.write("secret.txt", "s\n");
+    repo.commit_all("initial");
+    repo.write("a.txt", "two\n");
+    repo.write("secret.txt", "changed\n");
+
+    let secret = repo.workdir().join("secret.txt");
+    std::fs::set_permissions(&secret, std::fs::Permissions::from_mode(0o000)).unwrap();
+    // Privileged users read the file regardless, leaving nothing to test
+    if std::fs::read(&secret).is_ok() {
+        return;
+    }
+
+    let diff = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    std::fs::set_permissions(&secret, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    assert_eq!(diff.files.len(), 2);
+    assert_eq!(diff.files[0].path, "a.txt");
+    assert_eq!(diff.files[0].patch.as_deref(), Some("-one\n+two\n"));
+    assert!(diff.files[0].error.is_none());
+    let unreadable = &diff.files[1];
+    assert_eq!(unreadable.path, "secret.txt");
+    assert!(unreadable
+        .error
+        .as_deref()
+        .is_some_and(|e| e.contains("Permission denied")));
+    assert_eq!(unreadable.patch, None);
+    assert_eq!((diff.stats.additions, diff.stats.deletions), (1, 1));
+}
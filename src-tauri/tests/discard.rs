@@ -0,0 +1,79 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+fn read(repo: &TestRepo, path: &str) -> Option<String> {
+    std::fs::read_to_string(repo.workdir().join(path)).ok()
+}
+
+#[test]
+fn discards_to_the_staged_state_and_restores() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "staged\n");
+    repo.stage("a.txt");
+    repo.write("a.txt", "unstaged\n");
+
+    let backup = git::discard_file(&repo.repo, "a.txt").unwrap();
+    assert!(backup.existed);
+    assert_eq!(read(&repo, "a.txt").as_deref(), Some("staged\n"));
+    assert!(repo
+        .repo
+        .find_reference(&format!("refs/diffy/discarded/{}", backup.id))
+        .is_ok());
+
+    git::restore_discarded(&repo.repo, &backup.id).unwrap();
+    assert_eq!(read(&repo, "a.txt").as_deref(), Some("unstaged\n"));
+    // The backup is used up
+    assert!(matches!(
+        git::restore_discarded(&repo.repo, &backup.id),
+        Err(git::GitError::BackupNotFound(_))
+    ));
+}
+
+#[test]
+fn discards_untracked_files_and_deletions() {
+    let repo = TestRepo::new();
+    repo.write("kept.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("dir/new.txt", "draft\n");
+    repo.remove("kept.txt");
+
+    let untracked = git::discard_file(&repo.repo, "dir/new.txt").unwrap();
+    assert_eq!(read(&repo, "dir/new.txt"), None);
+    let deleted = git::discard_file(&repo.repo, "kept.txt").unwrap();
+    assert!(!deleted.existed);
+    assert_eq!(read(&repo, "kept.txt").as_deref(), Some("one\n"));
+
+    git::restore_discarded(&repo.repo, &untracked.id).unwrap();
+    git::restore_discarded(&repo.repo, &deleted.id).unwrap();
+    assert_eq!(read(&repo, "dir/new.txt").as_deref(), Some("draft\n"));
+    assert_eq!(read(&repo, "kept.txt"), None);
+}
+
+#[test]
+fn discards_one_hunk_and_restores_it() {
+    let repo = TestRepo::new();
+    let original: String = (1..=30).map(|n| format!("line {}\n", n)).collect();
+    repo.write("a.txt", &original);
+    repo.commit_all("first");
+    let edited = original
+        .replace("line 2\n", "dbg!(line 2)\n")
+        .replace("line 28\n", "line twenty-eight\n");
+    repo.write("a.txt", &edited);
+
+    let hunks = git::get_file_hunks(&repo.repo, "a.txt", "unstaged", 0, 100)
+        .unwrap()
+        .hunks;
+    let debug = hunks.iter().find(|h| h.content.contains("dbg!")).unwrap();
+    let backup = git::discard_hunk(&repo.repo, "a.txt", &debug.id).unwrap();
+    assert_eq!(
+        read(&repo, "a.txt").unwrap(),
+        original.replace("line 28\n", "line twenty-eight\n")
+    );
+
+    git::restore_discarded(&repo.repo, &backup.id).unwrap();
+    assert_eq!(read(&repo, "a.txt").unwrap(), edited);
+}
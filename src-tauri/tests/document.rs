@@ -0,0 +1,104 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, GitError};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// A minimal docx holding one paragraph per line of `paragraphs`
+fn docx(paragraphs: &[&str]) -> Vec<u8> {
+    let body: String = paragraphs
+        .iter()
+        .map(|p| format!("<w:p><w:r><w:t>{}</w:t></w:r></w:p>", p))
+        .collect();
+    let xml = format!(
+        r#"<?xml version="1.0"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+        body
+    );
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file(
+        "word/document.xml",
+        zip::write::SimpleFileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(xml.as_bytes()).unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+fn write_bytes(repo: &TestRepo, path: &str, bytes: &[u8]) {
+    std::fs::write(repo.workdir().join(path), bytes).unwrap();
+}
+
+#[test]
+fn extracts_docx_paragraphs_without_a_converter() {
+    let repo = TestRepo::new();
+    write_bytes(&repo, "spec.docx", &docx(&["Intro", "Old rule"]));
+    repo.commit_all("first");
+    write_bytes(&repo, "spec.docx", &docx(&["Intro", "New rule"]));
+
+    let diff = git::get_document_diff(&repo.repo, "spec.docx", Some("HEAD"), None, &HashMap::new())
+        .unwrap();
+    assert_eq!(diff.converter, "docx");
+    assert_eq!((diff.additions, diff.deletions), (1, 1));
+    assert!(diff.patch.contains("-Old rule"));
+    assert!(diff.patch.contains("+New rule"));
+}
+
+#[test]
+fn refuses_extensions_without_a_converter() {
+    let repo = TestRepo::new();
+    write_bytes(&repo, "report.pdf", b"%PDF-1.4");
+    repo.commit_all("first");
+
+    let err = git::get_document_diff(
+        &repo.repo,
+        "report.pdf",
+        Some("HEAD"),
+        None,
+        &HashMap::new(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, GitError::Conversion(_)));
+}
+
+#[cfg(unix)]
+#[test]
+fn runs_converters_with_quoted_arguments() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tools = tempfile::tempdir().unwrap();
+    let dir = tools.path().join("my tools");
+    std::fs::create_dir(&dir).unwrap();
+    let script = dir.join("upper.sh");
+    std::fs::write(&script, "#!/bin/sh\ntr a-z A-Z < \"$2\"\necho \"$1\"\n").unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let repo = TestRepo::new();
+    write_bytes(&repo, "notes.bin", b"alpha\n");
+    repo.commit_all("first");
+    write_bytes(&repo, "notes.bin", b"beta\n");
+
+    let converters = HashMap::from([(
+        "bin".to_string(),
+        format!("'{}' 'two words' %f", script.display()),
+    )]);
+    let diff =
+        git::get_document_diff(&repo.repo, "notes.bin", Some("HEAD"), None, &converters).unwrap();
+    assert!(diff.patch.contains("-ALPHA"));
+    assert!(diff.patch.contains("+BETA"));
+    // The quoted argument arrives as one word
+    assert!(!diff.patch.contains("+two words"));
+    assert!(diff.patch.contains(" two words"));
+}
+
+#[test]
+fn rejects_unbalanced_quotes_in_converter_commands() {
+    let repo = TestRepo::new();
+    write_bytes(&repo, "notes.bin", b"alpha\n");
+    repo.commit_all("first");
+
+    let converters = HashMap::from([("bin".to_string(), "cat 'unterminated %f".to_string())]);
+    let err = git::get_document_diff(&repo.repo, "notes.bin", Some("HEAD"), None, &converters)
+        .unwrap_err();
+    assert!(matches!(err, GitError::Conversion(message) if message.contains("Invalid converter")));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DriftMonitor, DriftTracker};
+
+fn monitor(max_commits: Option<usize>, max_files: Option<usize>) -> DriftMonitor {
+    DriftMonitor {
+        base: "main".to_string(),
+        head: "release".to_string(),
+        max_commits,
+        max_files,
+        notify: true,
+    }
+}
+
+/// `release` cut from `main`, then one fix on `release` and two commits
+/// touching two files on `main`
+fn diverged_repo() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "0\n");
+    let root = repo.commit_all("root");
+    repo.rename_head("main");
+    repo.branch("release", root);
+    repo.write("a.txt", "1\n");
+    repo.commit_all("main 1");
+    repo.write("b.txt", "1\n");
+    repo.commit_all("main 2");
+    repo.checkout("release");
+    repo.write("c.txt", "fix\n");
+    repo.commit_all("fix");
+    repo.checkout("main");
+    repo
+}
+
+#[test]
+fn measures_commits_and_files_between_the_tips() {
+    let repo = diverged_repo();
+    let report = git::check_drift(&repo.repo, &monitor(None, None)).unwrap();
+    assert_eq!((report.ahead, report.behind), (1, 2));
+    assert_eq!(report.changed_files, 3);
+    assert!(report.notify);
+    // No thresholds, never exceeded
+    assert!(!report.exceeded);
+}
+
+#[test]
+fn exceeds_when_either_threshold_is_passed() {
+    let repo = diverged_repo();
+    let exceeded = |m: DriftMonitor| git::check_drift(&repo.repo, &m).unwrap().exceeded;
+    assert!(!exceeded(monitor(Some(3), Some(3))));
+    assert!(exceeded(monitor(Some(2), None)));
+    assert!(exceeded(monitor(None, Some(2))));
+    assert!(exceeded(monitor(Some(10), Some(2))));
+}
+
+#[test]
+fn reports_every_pair_in_order_and_fails_on_unknown_refs() {
+    let repo = diverged_repo();
+    let reversed = DriftMonitor {
+        base: "release".to_string(),
+        head: "main".to_string(),
+        ..monitor(None, None)
+    };
+    let reports = git::get_branch_drift(&repo.repo, &[monitor(None, None), reversed]).unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!((reports[1].ahead, reports[1].behind), (2, 1));
+
+    let missing = DriftMonitor {
+        head: "release/9".to_string(),
+        ..monitor(None, None)
+    };
+    assert!(git::get_branch_drift(&repo.repo, &[missing]).is_err());
+}
+
+#[test]
+fn announces_drift_once_until_it_changes() {
+    let repo = diverged_repo();
+    let mut tracker = DriftTracker::new();
+    let check = || git::check_drift(&repo.repo, &monitor(Some(1), None)).unwrap();
+
+    assert!(tracker.update(check()).is_some());
+    assert!(tracker.update(check()).is_none());
+
+    // More drift is news
+    repo.write("d.txt", "1\n");
+    repo.commit_all("main 3");
+    let report = tracker.update(check()).unwrap();
+    assert_eq!(report.behind, 3);
+    assert!(tracker.update(check()).is_none());
+
+    // Back under the threshold, then over again
+    let calm = git::check_drift(&repo.repo, &monitor(None, None)).unwrap();
+    assert!(tracker.update(calm).is_none());
+    assert!(tracker.update(check()).is_some());
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig};
+
+#[test]
+fn groups_comparison_stats_by_extension() {
+    let repo = TestRepo::new();
+    repo.write("README.md", "one\n");
+    let base = repo.commit_all("first");
+
+    repo.write("src/a.rs", "fn a() {}\nfn b() {}\nfn c() {}\n");
+    repo.write("src/b.RS", "fn d() {}\n");
+    repo.write("web/app.tsx", "export {}\n");
+    repo.write("Makefile", "all:\n");
+    repo.write("README.md", "two\n");
+    let head = repo.commit_all("second");
+
+    let result = git::compare_refs(
+        &repo.repo,
+        &base.to_string(),
+        &head.to_string(),
+        &DifferConfig::default(),
+    )
+    .unwrap();
+    let summary: Vec<(&str, &str, usize, usize, usize)> = result
+        .by_extension
+        .iter()
+        .map(|s| {
+            (
+                s.extension.as_str(),
+                s.language.as_str(),
+                s.files,
+                s.additions,
+                s.deletions,
+            )
+        })
+        .collect();
+
+    // Largest change first, ties broken by extension
+    assert_eq!(
+        summary,
+        vec![
+            ("rs", "Rust", 2, 4, 0),
+            ("md", "Docs", 1, 1, 1),
+            ("Makefile", "Other", 1, 1, 0),
+            ("tsx", "TypeScript", 1, 1, 0),
+        ]
+    );
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig, FileStatus};
+
+#[test]
+fn follows_renames_and_pages() {
+    let repo = TestRepo::new();
+    repo.write("old.txt", "one\ntwo\nthree\nfour\n");
+    repo.write("other.txt", "x\n");
+    repo.commit_all("add old");
+    repo.write("old.txt", "one\ntwo\nthree\nfour\nfive\n");
+    repo.commit_all("edit old");
+    repo.write("other.txt", "y\n");
+    repo.commit_all("unrelated");
+    repo.remove("old.txt");
+    repo.write("new.txt", "one\ntwo\nthree\nfour\nfive\n");
+    repo.commit_all("rename");
+    repo.write("new.txt", "zero\none\ntwo\nthree\nfour\nfive\n");
+    repo.commit_all("edit new");
+
+    let history =
+        git::get_file_history(&repo.repo, "new.txt", 10, 0, &DifferConfig::default()).unwrap();
+    let entries: Vec<_> = history
+        .entries
+        .iter()
+        .map(|e| {
+            (
+                e.commit.message.trim(),
+                e.path.as_str(),
+                e.old_path.as_deref(),
+                e.status.clone(),
+            )
+        })
+        .collect();
+
+    assert_eq!(history.total, 4);
+    assert!(matches!(
+        entries.as_slice(),
+        [
+            ("edit new", "new.txt", None, FileStatus::Modified),
+            ("rename", "new.txt", Some("old.txt"), FileStatus::Renamed),
+            ("edit old", "old.txt", None, FileStatus::Modified),
+            ("add old", "old.txt", None, FileStatus::Added),
+        ]
+    ));
+
+    let page =
+        git::get_file_history(&repo.repo, "new.txt", 2, 2, &DifferConfig::default()).unwrap();
+    assert_eq!(page.total, 4);
+    let messages: Vec<_> = page
+        .entries
+        .iter()
+        .map(|e| e.commit.message.trim())
+        .collect();
+    assert_eq!(messages, ["edit old", "add old"]);
+}
+
+#[test]
+fn traces_a_function_through_edits_and_moves() {
+    let repo = TestRepo::new();
+    repo.write("lib.rs", "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n");
+    repo.commit_all("add");
+    repo.write(
+        "lib.rs",
+        "fn add(a: i32, b: i32) -> i32 {\n    a.wrapping_add(b)\n}\n",
+    );
+    repo.commit_all("wrap");
+    // Pushes the function down and renames the file without touching it
+    repo.remove("lib.rs");
+    repo.write(
+        "math.rs",
+        "use std::ops::Add;\n\nfn add(a: i32, b: i32) -> i32 {\n    a.wrapping_add(b)\n}\n",
+    );
+    repo.commit_all("move");
+    repo.write(
+        "math.rs",
+        "use std::ops::Add;\n\nfn add(a: i32, b: i32) -> i32 {\n    a.wrapping_add(b)\n}\n\nfn sub() {}\n",
+    );
+    repo.commit_all("unrelated");
+
+    let history = git::get_line_history(
+        &repo.repo,
+        "math.rs",
+        git::RegionSelector::Function(r"^fn add\b"),
+        10,
+    )
+    .unwrap();
+
+    assert_eq!((history.start_line, history.end_line), (3, 5));
+    let entries: Vec<_> = history
+        .entries
+        .iter()
+        .map(|e| {
+            (
+                e.commit.message.trim(),
+                e.path.as_str(),
+                e.start_line,
+                e.end_line,
+            )
+        })
+        .collect();
+    assert_eq!(entries, [("wrap", "lib.rs", 1, 3), ("add", "lib.rs", 1, 3)]);
+    assert_eq!(
+        history.entries[0].patch,
+        "@@ -2 +2 @@ fn add(a: i32, b: i32) -> i32 {\n-    a + b\n+    a.wrapping_add(b)\n"
+    );
+    assert!(history.entries[1]
+        .patch
+        .starts_with("@@ -0,0 +1,3 @@\n+fn add"));
+
+    let single = git::get_line_history(
+        &repo.repo,
+        "math.rs",
+        git::RegionSelector::Lines { start: 7, end: 7 },
+        10,
+    )
+    .unwrap();
+    let messages: Vec<_> = single
+        .entries
+        .iter()
+        .map(|e| e.commit.message.trim())
+        .collect();
+    assert_eq!(messages, ["unrelated"]);
+}
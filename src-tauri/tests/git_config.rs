@@ -0,0 +1,76 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+#[test]
+fn reports_local_and_included_values_with_their_source() {
+    let repo = TestRepo::new();
+    repo.write("extra.gitconfig", "[diffy]\n\tlabel = included\n");
+    let mut config = repo.repo.config().unwrap();
+    config.set_str("diffy.label", "local").unwrap();
+    config
+        .set_str("include.path", "../extra.gitconfig")
+        .unwrap();
+
+    let value = git::get_git_config(&repo.repo, "diffy.label").unwrap();
+    // The include comes after the local value, so it wins
+    assert_eq!(value.value.as_deref(), Some("included"));
+    assert_eq!(value.entries.len(), 2);
+    assert!(value
+        .entries
+        .iter()
+        .all(|e| e.scope == git::GitConfigScope::Local));
+    let local_file = repo.repo.path().join("config");
+    assert_eq!(
+        value.entries[0].file.as_deref(),
+        Some(local_file.to_str().unwrap())
+    );
+    assert!(!value.entries[0].included);
+    assert!(value.entries[1].included);
+
+    let missing = git::get_git_config(&repo.repo, "diffy.missing").unwrap();
+    assert!(missing.value.is_none());
+    assert!(missing.entries.is_empty());
+}
+
+#[test]
+fn resolves_relative_template_paths_from_the_worktree() {
+    let repo = TestRepo::new();
+    repo.write("meta/template.txt", "Summary\n");
+    repo.repo
+        .config()
+        .unwrap()
+        .set_str("commit.template", "meta/template.txt")
+        .unwrap();
+
+    let template = git::get_commit_template(&repo.repo).unwrap();
+    assert_eq!(template.template.as_deref(), Some("Summary\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn runs_hooks_from_core_hooks_path() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    repo.write("githooks/post-checkout", "#!/bin/sh\necho shared\n");
+    let hook = repo.workdir().join("githooks/post-checkout");
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let policy = git::HookPolicy::default();
+
+    // Not configured yet, and .git/hooks has no such hook
+    let before = git::run_hook(&repo.repo, &policy, "post-checkout", &[], None).unwrap();
+    assert!(before.is_none());
+
+    repo.repo
+        .config()
+        .unwrap()
+        .set_str("core.hooksPath", "githooks")
+        .unwrap();
+    let result = git::run_hook(&repo.repo, &policy, "post-checkout", &[], None)
+        .unwrap()
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.stdout, "shared\n");
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+/// 100 lines with every 10th one changed, giving 10 separate hunks
+fn repo_with_ten_hunks() -> TestRepo {
+    let repo = TestRepo::new();
+    let lines: Vec<String> = (0..100).map(|i| format!("line {}\n", i)).collect();
+    repo.write("big.txt", &lines.concat());
+    repo.commit_all("first");
+    let changed: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if i % 10 == 5 {
+                format!("edited {}\n", i)
+            } else {
+                line.clone()
+            }
+        })
+        .collect();
+    repo.write("big.txt", &changed.concat());
+    repo
+}
+
+#[test]
+fn pages_through_a_files_hunks() {
+    let repo = repo_with_ten_hunks();
+
+    let page = git::get_file_hunks(&repo.repo, "big.txt", "working", 4, 3).unwrap();
+    assert_eq!(page.total, 10);
+    assert_eq!(page.offset, 4);
+    let indexes: Vec<usize> = page.hunks.iter().map(|h| h.index).collect();
+    assert_eq!(indexes, vec![4, 5, 6]);
+
+    let hunk = &page.hunks[0];
+    assert_eq!(hunk.header, "@@ -43,7 +43,7 @@ line 41");
+    assert_eq!((hunk.old_start, hunk.old_lines), (43, 7));
+    assert!(hunk.content.contains("-line 45\n+edited 45\n"));
+
+    let tail = git::get_file_hunks(&repo.repo, "big.txt", "working", 8, 5).unwrap();
+    assert_eq!(tail.hunks.len(), 2);
+    let past_end = git::get_file_hunks(&repo.repo, "big.txt", "working", 20, 5).unwrap();
+    assert!(past_end.hunks.is_empty());
+    assert_eq!(past_end.total, 10);
+}
+
+#[test]
+fn keeps_hunk_ids_stable_across_pages_and_targets() {
+    let repo = repo_with_ten_hunks();
+
+    let all = git::get_file_hunks(&repo.repo, "big.txt", "working", 0, 10).unwrap();
+    let page = git::get_file_hunks(&repo.repo, "big.txt", "working", 7, 2).unwrap();
+    assert_eq!(page.hunks[0].id, all.hunks[7].id);
+    assert_eq!(page.hunks[1].id, all.hunks[8].id);
+
+    // The same change reads the same once committed
+    let head = repo.commit_all("second");
+    let committed = git::get_file_hunks(&repo.repo, "big.txt", &head.to_string(), 7, 1).unwrap();
+    assert_eq!(committed.hunks[0].id, all.hunks[7].id);
+}
+
+#[test]
+fn returns_an_empty_page_for_unchanged_files() {
+    let repo = repo_with_ten_hunks();
+    repo.write("other.txt", "same\n");
+    repo.commit_all("second");
+
+    let page = git::get_file_hunks(&repo.repo, "other.txt", "working", 0, 10).unwrap();
+    assert_eq!(page.total, 0);
+    assert!(page.hunks.is_empty());
+}
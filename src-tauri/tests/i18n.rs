@@ -0,0 +1,112 @@
+use diffy_lib::git::GitError;
+use diffy_lib::i18n::{message_catalog, set_locale, translate, CommandError};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+/// The locale is process-wide, so tests that change it take turns
+static LOCALE: Mutex<()> = Mutex::new(());
+
+fn with_locale<T>(locale: &str, f: impl FnOnce() -> T) -> T {
+    let _guard = LOCALE.lock().unwrap_or_else(|e| e.into_inner());
+    set_locale(locale);
+    let result = f();
+    set_locale("en");
+    result
+}
+
+fn placeholders(message: &str) -> BTreeSet<String> {
+    message
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name.to_string()))
+        .collect()
+}
+
+#[test]
+fn translates_and_fills_placeholders() {
+    with_locale("de", || {
+        assert_eq!(
+            translate("error.noRepository", &[]),
+            "Kein Repository ausgewählt"
+        );
+        assert_eq!(
+            translate("error.git", &[("detail", "boom")]),
+            "Git-Fehler: boom"
+        );
+    });
+}
+
+#[test]
+fn falls_back_from_region_to_language_to_english() {
+    with_locale("fr-CA", || {
+        assert_eq!(
+            translate("error.noRepository", &[]),
+            "Aucun dépôt sélectionné"
+        );
+    });
+    with_locale("pt_BR", || {
+        assert_eq!(
+            translate("error.noRepository", &[]),
+            "No repository selected"
+        );
+    });
+    with_locale("", || {
+        assert_eq!(
+            translate("error.noRepository", &[]),
+            "No repository selected"
+        );
+    });
+}
+
+#[test]
+fn unknown_keys_come_back_as_the_key() {
+    with_locale("es", || {
+        assert_eq!(translate("error.doesNotExist", &[]), "error.doesNotExist");
+    });
+}
+
+#[test]
+fn catalog_merges_the_fallback_chain() {
+    let english = with_locale("en", message_catalog);
+    let canadian = with_locale("fr-CA", message_catalog);
+    assert_eq!(canadian.len(), english.len());
+    assert_eq!(canadian["error.noRepository"], "Aucun dépôt sélectionné");
+}
+
+#[test]
+fn command_errors_carry_the_key_and_localized_text() {
+    let error = with_locale("fr", || CommandError::from(GitError::NothingToCommit));
+    assert_eq!(error.key, "error.nothingToCommit");
+    assert_eq!(error.message, "Aucune modification indexée à valider");
+    assert!(error.hook.is_none());
+
+    let json = serde_json::to_value(&error).unwrap();
+    assert_eq!(json["key"], "error.nothingToCommit");
+    assert!(json.get("hook").is_none());
+}
+
+#[test]
+fn translations_use_the_same_placeholders_as_english() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("locales");
+    let load = |locale: &str| -> HashMap<String, String> {
+        let text = std::fs::read_to_string(dir.join(format!("{}.json", locale))).unwrap();
+        serde_json::from_str(&text).unwrap()
+    };
+    let english = load("en");
+
+    for locale in ["de", "es", "fr"] {
+        let catalog = load(locale);
+        for (key, message) in &english {
+            let translated = catalog
+                .get(key)
+                .unwrap_or_else(|| panic!("{} is missing {}", locale, key));
+            assert_eq!(
+                placeholders(translated),
+                placeholders(message),
+                "{} {}",
+                locale,
+                key
+            );
+        }
+    }
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+#[test]
+fn inspects_a_repository_in_place() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo.write("sub/b.txt", "new\n");
+
+    let workdir = repo.workdir().to_str().unwrap().to_string();
+    let inspection = git::inspect_repo(&workdir).unwrap();
+    assert_eq!(
+        std::fs::canonicalize(&inspection.path).unwrap(),
+        std::fs::canonicalize(repo.workdir()).unwrap()
+    );
+    assert!(!inspection.bare);
+    assert_eq!(inspection.status.current_branch.as_deref(), Some("master"));
+    let line = inspection.line.unwrap();
+    assert_eq!((line.unstaged, line.untracked), (1, 1));
+
+    // A directory inside the repository is not the repository
+    let nested = repo.workdir().join("sub");
+    assert!(matches!(
+        git::inspect_repo(nested.to_str().unwrap()),
+        Err(git::GitError::RepoNotFound(_))
+    ));
+}
+
+#[test]
+fn inspects_bare_repositories() {
+    let dir = tempfile::tempdir().unwrap();
+    let bare = git2::Repository::init_bare(dir.path()).unwrap();
+    drop(bare);
+
+    let inspection = git::inspect_repo(dir.path().to_str().unwrap()).unwrap();
+    assert!(inspection.bare);
+    assert!(inspection.line.is_none());
+    assert!(inspection.status.head_commit.is_none());
+}
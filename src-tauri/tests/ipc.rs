@@ -0,0 +1,110 @@
+use base64::Engine;
+use diffy_lib::git::{CompressionEncoding, DifferConfig};
+use diffy_lib::ipc::{clear_payloads, encode_response, init_payload_dir, release_payload};
+use serde_json::{json, Value};
+use std::io::Read;
+
+fn payload() -> Value {
+    json!({
+        "files": (0..50)
+            .map(|i| json!({ "path": format!("src/file-{}.rs", i), "additions": i }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn compressing(encoding: CompressionEncoding, threshold_bytes: usize) -> DifferConfig {
+    let mut config = DifferConfig::default();
+    config.ipc_compression.enabled = true;
+    config.ipc_compression.encoding = encoding;
+    config.ipc_compression.threshold_bytes = threshold_bytes;
+    config
+}
+
+fn encoded(config: &DifferConfig) -> Value {
+    serde_json::to_value(encode_response(payload(), config).unwrap()).unwrap()
+}
+
+fn decompress(envelope: &Value) -> Value {
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(envelope["data"].as_str().unwrap())
+        .unwrap();
+    let json = match envelope["encoding"].as_str().unwrap() {
+        "gzip" => {
+            let mut out = Vec::new();
+            flate2::read::GzDecoder::new(data.as_slice())
+                .read_to_end(&mut out)
+                .unwrap();
+            out
+        }
+        "zstd" => zstd::decode_all(data.as_slice()).unwrap(),
+        other => panic!("unexpected encoding {}", other),
+    };
+    assert_eq!(
+        envelope["originalSize"].as_u64().unwrap() as usize,
+        json.len()
+    );
+    serde_json::from_slice(&json).unwrap()
+}
+
+#[test]
+fn sends_plain_json_by_default() {
+    assert_eq!(encoded(&DifferConfig::default()), payload());
+}
+
+#[test]
+fn sends_plain_json_below_the_threshold() {
+    let config = compressing(CompressionEncoding::Gzip, usize::MAX);
+    assert_eq!(encoded(&config), payload());
+}
+
+#[test]
+fn compresses_large_responses() {
+    for encoding in [CompressionEncoding::Gzip, CompressionEncoding::Zstd] {
+        let envelope = encoded(&compressing(encoding, 16));
+        assert_eq!(envelope["compressed"], json!(true));
+        let original = serde_json::to_vec(&payload()).unwrap();
+        assert!(envelope["data"].as_str().unwrap().len() < original.len());
+        assert_eq!(decompress(&envelope), payload());
+    }
+}
+
+#[test]
+fn hands_off_huge_responses_through_a_private_directory() {
+    let cache = tempfile::tempdir().unwrap();
+    let dir = init_payload_dir(cache.path()).unwrap();
+    assert!(dir.starts_with(cache.path()));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    let mut config = compressing(CompressionEncoding::Gzip, 16);
+    config.ipc_handoff.enabled = true;
+    config.ipc_handoff.threshold_bytes = 16;
+    let envelope = encoded(&config);
+    assert_eq!(envelope["handoff"], json!(true));
+    let path = envelope["path"].as_str().unwrap();
+    assert_eq!(std::path::Path::new(path).parent(), Some(dir));
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(written, payload());
+    assert_eq!(
+        envelope["size"].as_u64().unwrap() as usize,
+        serde_json::to_vec(&payload()).unwrap().len()
+    );
+
+    // Nothing outside this process's directory can be released
+    let outside = cache.path().join("other.json");
+    std::fs::write(&outside, "{}").unwrap();
+    assert!(release_payload(outside.to_str().unwrap()).is_err());
+    assert!(outside.exists());
+
+    release_payload(path).unwrap();
+    assert!(!std::path::Path::new(path).exists());
+
+    encoded(&config);
+    clear_payloads();
+    assert!(!dir.exists());
+    assert!(outside.exists());
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, Degradation, DifferConfig, FileStatus, LargeRepoMode};
+
+fn config(mode: LargeRepoMode) -> DifferConfig {
+    let mut config = DifferConfig::default();
+    config.large_repo.mode = mode;
+    config
+}
+
+#[test]
+fn sizes_up_the_repository() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "two\n");
+    repo.commit_all("first");
+
+    let status = git::get_large_repo_status(&repo.repo, &DifferConfig::default().large_repo);
+    assert!(!status.huge);
+    assert_eq!(status.files, 2);
+    assert!(status.degraded.is_empty());
+
+    // Crossing either threshold is enough
+    let mut settings = DifferConfig::default().large_repo;
+    settings.file_threshold = 1;
+    let status = git::get_large_repo_status(&repo.repo, &settings);
+    assert!(status.huge);
+    assert_eq!(status.degraded, Degradation::ALL);
+
+    // Packed objects are counted from the pack index
+    let packed = std::process::Command::new("git")
+        .args(["repack", "-adq"])
+        .current_dir(repo.workdir())
+        .status();
+    if packed.is_ok_and(|status| status.success()) {
+        let status = git::get_large_repo_status(&repo.repo, &DifferConfig::default().large_repo);
+        // Two blobs, a tree and a commit
+        assert_eq!(status.objects, 4);
+    }
+}
+
+#[test]
+fn huge_mode_degrades_expensive_defaults() {
+    let repo = TestRepo::new();
+    repo.write("old.txt", "one\ntwo\nthree\nfour\n");
+    repo.commit_all("add old");
+    repo.remove("old.txt");
+    repo.write("new.txt", "one\ntwo\nthree\nfour\n");
+    repo.commit_all("rename");
+    repo.write("scratch/a.txt", "a\n");
+    repo.write("scratch/b.txt", "b\n");
+
+    let full = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    assert_eq!(full.files.len(), 2);
+    assert!(full.degraded.is_empty());
+
+    let huge = config(LargeRepoMode::On);
+    let diff = git::get_current_diff(&repo.repo, None, &huge).unwrap();
+    let paths: Vec<_> = diff.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["scratch/"]);
+    assert_eq!(diff.degraded, [Degradation::UntrackedRecursion]);
+
+    let history = git::get_commit_history(&repo.repo, 10, 0, &huge).unwrap();
+    assert_eq!(history.degraded, [Degradation::CommitStats]);
+    assert!(history.commits.iter().all(|c| c.stats.files == 0));
+
+    let file_history = git::get_file_history(&repo.repo, "new.txt", 10, 0, &huge).unwrap();
+    assert_eq!(file_history.total, 1);
+    assert!(matches!(file_history.entries[0].status, FileStatus::Added));
+    assert_eq!(
+        file_history.degraded,
+        [Degradation::CommitStats, Degradation::RenameDetection]
+    );
+}
+
+#[test]
+fn kept_features_and_off_mode_stay_at_full_fidelity() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("scratch/a.txt", "a\n");
+
+    let mut kept = config(LargeRepoMode::On);
+    kept.large_repo.keep = vec![Degradation::CommitStats];
+    let history = git::get_commit_history(&repo.repo, 10, 0, &kept).unwrap();
+    assert!(history.degraded.is_empty());
+    assert_eq!(history.commits[0].stats.files, 1);
+    let status = git::get_large_repo_status(&repo.repo, &kept.large_repo);
+    assert_eq!(
+        status.degraded,
+        [
+            Degradation::UntrackedRecursion,
+            Degradation::RenameDetection
+        ]
+    );
+
+    let mut off = config(LargeRepoMode::Off);
+    off.large_repo.file_threshold = 0;
+    let diff = git::get_current_diff(&repo.repo, None, &off).unwrap();
+    assert_eq!(diff.files[0].path, "scratch/a.txt");
+    assert!(diff.degraded.is_empty());
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig, MergeOutcome};
+use git2::RepositoryState;
+
+fn read(repo: &TestRepo, path: &str) -> String {
+    std::fs::read_to_string(repo.workdir().join(path)).unwrap()
+}
+
+#[test]
+fn fast_forwards_when_possible() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("first");
+    repo.branch("feature", base);
+    repo.checkout("feature");
+    repo.write("a.txt", "two\n");
+    let tip = repo.commit_all("second");
+    repo.checkout("master");
+
+    let result = git::merge_branch(&repo.repo, "feature", &git::HookPolicy::default()).unwrap();
+    assert_eq!(result.outcome, MergeOutcome::FastForward);
+    assert_eq!(result.sha, tip.to_string());
+    assert!(result.commit.is_none() && result.message.is_none());
+    assert_eq!(repo.repo.head().unwrap().shorthand(), Some("master"));
+    assert_eq!(repo.repo.head().unwrap().target(), Some(tip));
+    assert_eq!(read(&repo, "a.txt"), "two\n");
+
+    // Merging it again has nothing to do
+    let again = git::merge_branch(&repo.repo, "feature", &git::HookPolicy::default()).unwrap();
+    assert_eq!(again.outcome, MergeOutcome::UpToDate);
+    assert_eq!(again.sha, tip.to_string());
+}
+
+#[test]
+fn merges_diverged_branches_with_a_merge_commit() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("first");
+    repo.branch("feature", base);
+    repo.checkout("feature");
+    repo.write("b.txt", "feature\n");
+    let theirs = repo.commit_all("add b");
+    repo.checkout("master");
+    repo.write("c.txt", "master\n");
+    let ours = repo.commit_all("add c");
+
+    let result = git::merge_branch(&repo.repo, "feature", &git::HookPolicy::default()).unwrap();
+    assert_eq!(result.outcome, MergeOutcome::Merged);
+    assert!(result.conflicts.is_empty());
+    assert_eq!(result.message.as_deref(), Some("Merge branch 'feature'\n"));
+
+    let commit = result.commit.expect("merge was committed");
+    assert_eq!(commit.sha, result.sha);
+    let merge = repo
+        .repo
+        .find_commit(git2::Oid::from_str(&commit.sha).unwrap())
+        .unwrap();
+    assert_eq!(merge.parent_ids().collect::<Vec<_>>(), [ours, theirs]);
+    assert_eq!(repo.repo.head().unwrap().target(), Some(merge.id()));
+    assert_eq!(read(&repo, "b.txt"), "feature\n");
+    assert_eq!(read(&repo, "c.txt"), "master\n");
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+}
+
+#[test]
+fn conflicting_merge_stops_for_resolution() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("first");
+    repo.branch("feature", base);
+    repo.checkout("feature");
+    repo.write("a.txt", "theirs\n");
+    let theirs = repo.commit_all("theirs");
+    repo.checkout("master");
+    repo.write("a.txt", "ours\n");
+    let ours = repo.commit_all("ours");
+
+    let result = git::merge_branch(&repo.repo, "feature", &git::HookPolicy::default()).unwrap();
+    assert_eq!(result.outcome, MergeOutcome::Conflicted);
+    assert_eq!(result.conflicts, ["a.txt"]);
+    assert!(result.commit.is_none());
+    assert_eq!(result.sha, ours.to_string());
+    assert_eq!(repo.repo.state(), RepositoryState::Merge);
+    assert!(read(&repo, "a.txt").contains("<<<<<<<"));
+
+    // Another merge waits until this one is finished
+    assert!(matches!(
+        git::merge_branch(&repo.repo, "feature", &git::HookPolicy::default()),
+        Err(git::GitError::MergeInProgress)
+    ));
+
+    // Resolving and committing concludes the merge
+    repo.write("a.txt", "both\n");
+    repo.stage("a.txt");
+    let message = result.message.unwrap();
+    let commit = git::create_commit(&repo.repo, &message, &git::HookPolicy::default()).unwrap();
+    let merge = repo
+        .repo
+        .find_commit(git2::Oid::from_str(&commit.sha).unwrap())
+        .unwrap();
+    assert_eq!(merge.parent_ids().collect::<Vec<_>>(), [ours, theirs]);
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+}
+
+#[test]
+fn refuses_with_local_changes_or_unknown_branches() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("first");
+    repo.branch("feature", base);
+
+    assert!(matches!(
+        git::merge_branch(&repo.repo, "nope", &git::HookPolicy::default()),
+        Err(git::GitError::BranchNotFound(name)) if name == "nope"
+    ));
+
+    repo.write("a.txt", "dirty\n");
+    assert!(matches!(
+        git::merge_branch(&repo.repo, "feature", &git::HookPolicy::default()),
+        Err(git::GitError::UncommittedChanges(files)) if files == ["a.txt"]
+    ));
+}
+
+#[test]
+fn previews_a_merge_without_touching_the_repository() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "one\n");
+    let base = repo.commit_all("first");
+    repo.branch("feature", base);
+    repo.checkout("feature");
+    repo.write("a.txt", "theirs\n");
+    repo.write("c.txt", "new\n");
+    let theirs = repo.commit_all("theirs");
+    repo.checkout("master");
+    repo.write("b.txt", "ours\n");
+    let ours = repo.commit_all("ours");
+
+    let config = DifferConfig::default();
+    let clean = git::preview_merge(&repo.repo, "master", "feature", &config).unwrap();
+    assert_eq!(clean.outcome, MergeOutcome::Merged);
+    assert_eq!(clean.ours, ours.to_string());
+    assert_eq!(clean.theirs, theirs.to_string());
+    assert!(clean.conflicts.is_empty());
+    let paths: Vec<_> = clean.diff.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, ["a.txt", "c.txt"]);
+
+    // A conflicting change on our side
+    repo.write("a.txt", "ours\n");
+    let head = repo.commit_all("conflict");
+    let conflicted = git::preview_merge(&repo.repo, "HEAD", "feature", &config).unwrap();
+    assert_eq!(conflicted.outcome, MergeOutcome::Conflicted);
+    assert_eq!(conflicted.conflicts, ["a.txt"]);
+    assert!(conflicted.diff.files.iter().any(|f| f.path == "c.txt"));
+
+    // Nothing was merged
+    assert_eq!(repo.repo.head().unwrap().target(), Some(head));
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+    assert_eq!(read(&repo, "a.txt"), "ours\n");
+    assert!(!repo.workdir().join("c.txt").exists());
+    assert!(repo
+        .repo
+        .index()
+        .unwrap()
+        .get_path("c.txt".as_ref(), 0)
+        .is_none());
+
+    let forward = git::preview_merge(&repo.repo, "feature~1", "feature", &config).unwrap();
+    assert_eq!(forward.outcome, MergeOutcome::FastForward);
+    assert_eq!(forward.diff.files.len(), 2);
+    let current = git::preview_merge(&repo.repo, "feature", "feature~1", &config).unwrap();
+    assert_eq!(current.outcome, MergeOutcome::UpToDate);
+    assert!(current.diff.files.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_pre_merge_commit_hook_leaves_the_merge_uncommitted() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("first");
+    repo.branch("feature", base);
+    repo.checkout("feature");
+    repo.write("b.txt", "feature\n");
+    repo.commit_all("add b");
+    repo.checkout("master");
+    repo.write("c.txt", "master\n");
+    let ours = repo.commit_all("add c");
+
+    let hook = repo.repo.path().join("hooks/pre-merge-commit");
+    std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    std::fs::write(&hook, "#!/bin/sh\necho 'not today' >&2\nexit 1\n").unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    match git::merge_branch(&repo.repo, "feature", &git::HookPolicy::default()) {
+        Err(git::GitError::HookFailed(hook)) => {
+            assert_eq!(hook.hook, "pre-merge-commit");
+            assert_eq!(hook.stderr, "not today\n");
+        }
+        other => panic!("expected a hook failure, got {:?}", other.map(|r| r.sha)),
+    }
+    // Like git, the merged result waits in the index to be committed
+    assert_eq!(repo.repo.head().unwrap().target(), Some(ours));
+    assert_eq!(repo.repo.state(), RepositoryState::Merge);
+    assert_eq!(read(&repo, "b.txt"), "feature\n");
+}
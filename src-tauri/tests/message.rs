@@ -0,0 +1,203 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig, GitProvider, MessageBlock, MessageSpan, RemoteInfo};
+
+fn text(text: &str) -> MessageSpan {
+    MessageSpan::Text {
+        text: text.to_string(),
+    }
+}
+
+fn remote(provider: GitProvider, url: &str) -> RemoteInfo {
+    RemoteInfo {
+        url: url.to_string(),
+        provider,
+        owner: "acme".to_string(),
+        repo: "app".to_string(),
+    }
+}
+
+#[test]
+fn splits_subject_and_body() {
+    assert_eq!(
+        git::split_message("Fix the parser\n\nIt broke.\n"),
+        ("Fix the parser".to_string(), "It broke.".to_string())
+    );
+    // A wrapped subject is one line, as git log shows it
+    assert_eq!(
+        git::split_message("Fix the parser when\nthe input is empty\n\nBody\n\n\n"),
+        (
+            "Fix the parser when the input is empty".to_string(),
+            "Body".to_string()
+        )
+    );
+    assert_eq!(
+        git::split_message("Only a subject\n"),
+        ("Only a subject".to_string(), String::new())
+    );
+}
+
+#[test]
+fn recognizes_paragraphs_lists_and_code() {
+    let message = "Rework config loading\n\
+        \n\
+        The loader used to read every file twice, once to\n\
+        validate and once to merge.\n\
+        \n\
+        Changes:\n\
+        - read each file once\n\
+        - merge while\n  \
+          validating\n\
+        \n\
+        - report all errors\n\
+        \n\
+        1. first\n\
+        2) second\n\
+        \n\
+        \x20   $ differ --check\n\
+        \x20   ok\n\
+        \n\
+        ```\n\
+        let x = 1;\n\
+        \n\
+        let y = 2;\n\
+        ```\n";
+
+    let parsed = git::parse_commit_message(message, None);
+    assert_eq!(parsed.subject, [text("Rework config loading")]);
+    assert_eq!(
+        parsed.body,
+        [
+            MessageBlock::Paragraph {
+                spans: vec![text(
+                    "The loader used to read every file twice, once to validate and once to merge."
+                )],
+            },
+            MessageBlock::Paragraph {
+                spans: vec![text("Changes:")],
+            },
+            MessageBlock::List {
+                ordered: false,
+                items: vec![
+                    vec![text("read each file once")],
+                    vec![text("merge while validating")],
+                    vec![text("report all errors")],
+                ],
+            },
+            MessageBlock::List {
+                ordered: true,
+                items: vec![vec![text("first")], vec![text("second")]],
+            },
+            MessageBlock::Code {
+                text: "$ differ --check\nok".to_string(),
+            },
+            MessageBlock::Code {
+                text: "let x = 1;\n\nlet y = 2;".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn links_urls_and_issue_references() {
+    let github = remote(GitProvider::Github, "https://github.com/acme/app");
+    let parsed = git::parse_commit_message(
+        "Fix crash (#12)\n\nSee https://example.com/a_(b). Also other/lib#3, not a#4 or &#39;.",
+        Some(&github),
+    );
+    assert_eq!(
+        parsed.subject,
+        [
+            text("Fix crash ("),
+            MessageSpan::Issue {
+                text: "#12".to_string(),
+                number: 12,
+                url: Some("https://github.com/acme/app/issues/12".to_string()),
+            },
+            text(")"),
+        ]
+    );
+    let MessageBlock::Paragraph { spans } = &parsed.body[0] else {
+        panic!("expected a paragraph");
+    };
+    assert_eq!(
+        spans,
+        &[
+            text("See "),
+            MessageSpan::Link {
+                text: "https://example.com/a_(b)".to_string(),
+                url: "https://example.com/a_(b)".to_string(),
+            },
+            text(". Also "),
+            MessageSpan::Issue {
+                text: "other/lib#3".to_string(),
+                number: 3,
+                url: Some("https://github.com/other/lib/issues/3".to_string()),
+            },
+            text(", not a#4 or &#39;."),
+        ]
+    );
+
+    // Merge requests are GitLab's; without a remote references aren't linked
+    let gitlab = remote(GitProvider::Gitlab, "https://gitlab.com/acme/app");
+    let parsed = git::parse_commit_message("Land !7 for #8", Some(&gitlab));
+    let urls: Vec<_> = parsed
+        .subject
+        .iter()
+        .filter_map(|span| match span {
+            MessageSpan::Issue { url, .. } => url.clone(),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        urls,
+        [
+            "https://gitlab.com/acme/app/-/merge_requests/7",
+            "https://gitlab.com/acme/app/-/issues/8",
+        ]
+    );
+    let parsed = git::parse_commit_message("Land !7 for #8", None);
+    assert_eq!(
+        parsed.subject,
+        [
+            text("Land !7 for "),
+            MessageSpan::Issue {
+                text: "#8".to_string(),
+                number: 8,
+                url: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn commit_diffs_carry_the_parsed_message() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let sha = repo.commit_all("Add a\n\nCloses #5.\n").to_string();
+    repo.repo
+        .remote("origin", "git@github.com:acme/app.git")
+        .unwrap();
+
+    let diff = git::get_commit_diff(&repo.repo, &sha, &DifferConfig::default()).unwrap();
+    assert_eq!(diff.commit.subject, "Add a");
+    assert_eq!(diff.commit.body, "Closes #5.");
+    assert_eq!(
+        diff.description.body,
+        [MessageBlock::Paragraph {
+            spans: vec![
+                text("Closes "),
+                MessageSpan::Issue {
+                    text: "#5".to_string(),
+                    number: 5,
+                    url: Some("https://github.com/acme/app/issues/5".to_string()),
+                },
+                text("."),
+            ],
+        }]
+    );
+
+    let history = git::get_commit_history(&repo.repo, 10, 0, &DifferConfig::default()).unwrap();
+    assert_eq!(history.commits[0].subject, "Add a");
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::migrations::check_migrations;
+use diffy_lib::git::{DiffWarning, WarningCategory};
+
+/// Warnings for `head` against `base`, checking `db/migrations`
+fn check(repo: &TestRepo, base: &str, head: &str) -> Vec<DiffWarning> {
+    let tree = |name: &str| {
+        repo.repo
+            .revparse_single(name)
+            .unwrap()
+            .peel_to_tree()
+            .unwrap()
+    };
+    check_migrations(
+        &repo.repo,
+        &tree(base),
+        &tree(head),
+        &["db/migrations/".to_string()],
+    )
+    .unwrap()
+}
+
+/// `main` with migrations 0001 and 0002, and `feature` branched from it
+fn branched_repo() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.write("db/migrations/0001_init.sql", "create table a();\n");
+    repo.write("db/migrations/0002_users.sql", "create table b();\n");
+    let root = repo.commit_all("first");
+    repo.branch("main", root);
+    repo.branch("feature", root);
+    repo
+}
+
+#[test]
+fn flags_migrations_that_share_a_number_with_the_base() {
+    let repo = branched_repo();
+    repo.checkout("main");
+    repo.write("db/migrations/0003_orders.sql", "create table c();\n");
+    repo.commit_all("main migration");
+    repo.checkout("feature");
+    repo.write("db/migrations/0003_invoices.sql", "create table d();\n");
+    repo.commit_all("feature migration");
+
+    let warnings = check(&repo, "main", "feature");
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(
+        warnings[0].category,
+        WarningCategory::MigrationCollision
+    ));
+    assert_eq!(warnings[0].path, "db/migrations/0003_invoices.sql");
+    assert!(warnings[0].message.contains("0003_orders.sql"));
+}
+
+#[test]
+fn flags_migrations_older_than_ones_only_on_the_base() {
+    let repo = branched_repo();
+    repo.checkout("main");
+    repo.write(
+        "db/migrations/20240301000000_orders.sql",
+        "create table c();\n",
+    );
+    repo.commit_all("main migration");
+    repo.checkout("feature");
+    repo.write(
+        "db/migrations/2024-02-01-000000_invoices.sql",
+        "create table d();\n",
+    );
+    repo.commit_all("feature migration");
+
+    let warnings = check(&repo, "main", "feature");
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(
+        warnings[0].category,
+        WarningCategory::MigrationOrder
+    ));
+    assert!(warnings[0]
+        .message
+        .contains("db/migrations/20240301000000_orders.sql"));
+}
+
+#[test]
+fn accepts_migrations_that_sort_after_the_base() {
+    let repo = branched_repo();
+    repo.checkout("feature");
+    repo.write("db/migrations/0003_invoices.sql", "create table d();\n");
+    repo.write("db/migrations/README.md", "Run in order\n");
+    repo.commit_all("feature migration");
+
+    assert!(check(&repo, "main", "feature").is_empty());
+}
+
+#[test]
+fn ignores_repositories_without_the_directory() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+
+    assert!(check(&repo, "HEAD", "HEAD").is_empty());
+}
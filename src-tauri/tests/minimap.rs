@@ -0,0 +1,53 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig};
+
+fn numbered(count: usize) -> Vec<String> {
+    (1..=count).map(|i| format!("line {}\n", i)).collect()
+}
+
+fn minimap(repo: &TestRepo, path: &str) -> Option<String> {
+    git::get_current_diff(&repo.repo, None, &DifferConfig::default())
+        .unwrap()
+        .files
+        .into_iter()
+        .find(|f| f.path == path)
+        .unwrap()
+        .minimap
+}
+
+#[test]
+fn encodes_runs_and_gaps_between_hunks() {
+    let repo = TestRepo::new();
+    let mut lines = numbered(30);
+    repo.write("a.txt", &lines.concat());
+    repo.commit_all("first");
+
+    lines[14] = "changed\n".to_string();
+    lines.push("appended\n".to_string());
+    repo.write("a.txt", &lines.concat());
+
+    // 11 skipped lines, the change with 3 lines of context on each side,
+    // 9 more skipped, then the append after 3 lines of context
+    assert_eq!(
+        minimap(&repo, "a.txt").as_deref(),
+        Some("g11c3d1a1c3g9c3a1")
+    );
+}
+
+#[test]
+fn encodes_insertions_at_the_top_and_new_files() {
+    let repo = TestRepo::new();
+    let lines = numbered(10);
+    repo.write("a.txt", &lines.concat());
+    repo.commit_all("first");
+
+    repo.write("a.txt", &format!("new\n{}", lines.concat()));
+    assert_eq!(minimap(&repo, "a.txt").as_deref(), Some("a1c3"));
+
+    repo.commit_all("second");
+    repo.write("b.txt", "one\ntwo\n");
+    repo.stage("b.txt");
+    assert_eq!(minimap(&repo, "b.txt").as_deref(), Some("a2"));
+}
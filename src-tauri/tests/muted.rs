@@ -0,0 +1,140 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig, FileDiffInfo, MutedComparison, MutedStore};
+use std::collections::BTreeSet;
+
+fn muted_paths(files: &[FileDiffInfo]) -> Vec<&str> {
+    files
+        .iter()
+        .filter(|f| f.muted == Some(true))
+        .map(|f| f.path.as_str())
+        .collect()
+}
+
+fn current_muted(repo: &TestRepo) -> Vec<String> {
+    let diff = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    muted_paths(&diff.files)
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn flags_muted_files_in_the_working_diff_only() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.write("gen.lock", "one\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo.write("gen.lock", "two\n");
+    repo.stage("gen.lock");
+
+    assert_eq!(
+        git::mute_file(&repo.repo, "", "gen.lock").unwrap(),
+        vec!["gen.lock"]
+    );
+    assert_eq!(current_muted(&repo), vec!["gen.lock"]);
+
+    let changes = git::get_working_changes(&repo.repo, &DifferConfig::default()).unwrap();
+    assert_eq!(muted_paths(&changes.staged.files), vec!["gen.lock"]);
+
+    // The staged view is a different comparison
+    let staged = git::get_staged_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    assert!(muted_paths(&staged.files).is_empty());
+    assert!(git::list_muted(&repo.repo, "staged").unwrap().is_empty());
+
+    assert!(git::unmute_file(&repo.repo, "working", "gen.lock")
+        .unwrap()
+        .is_empty());
+    assert!(current_muted(&repo).is_empty());
+}
+
+#[test]
+fn working_mutes_end_when_head_moves() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    git::mute_file(&repo.repo, "working", "a.txt").unwrap();
+    assert_eq!(current_muted(&repo), vec!["a.txt"]);
+
+    repo.commit_all("second");
+    repo.write("a.txt", "three\n");
+    assert!(current_muted(&repo).is_empty());
+    assert!(git::list_muted(&repo.repo, "working").unwrap().is_empty());
+}
+
+#[test]
+fn range_mutes_follow_the_base_not_the_head() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "one\n");
+    let root = repo.commit_all("first");
+    repo.branch("main", root);
+    repo.branch("feature", root);
+    repo.checkout("feature");
+    repo.write("a.txt", "two\n");
+    repo.write("b.txt", "two\n");
+    repo.commit_all("feature work");
+
+    git::mute_file(&repo.repo, "main..feature", "b.txt").unwrap();
+    let compare =
+        || git::compare_refs(&repo.repo, "main", "feature", &DifferConfig::default()).unwrap();
+    assert_eq!(muted_paths(&compare().files), vec!["b.txt"]);
+    let branches =
+        git::compare_branches(&repo.repo, "main", "feature", &DifferConfig::default()).unwrap();
+    assert_eq!(muted_paths(&branches.files), vec!["b.txt"]);
+
+    // New commits on the reviewed branch are the same review
+    repo.write("b.txt", "three\n");
+    repo.commit_all("more feature work");
+    assert_eq!(muted_paths(&compare().files), vec!["b.txt"]);
+
+    // A new base is a new review
+    repo.checkout("main");
+    repo.write("c.txt", "one\n");
+    repo.commit_all("main work");
+    assert!(muted_paths(&compare().files).is_empty());
+}
+
+#[test]
+fn flags_muted_files_in_commit_diffs() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo.write("b.txt", "two\n");
+    let sha = repo.commit_all("second").to_string();
+
+    git::mute_file(&repo.repo, &sha, "a.txt").unwrap();
+    let diff = git::get_commit_diff(&repo.repo, &sha, &DifferConfig::default()).unwrap();
+    assert_eq!(muted_paths(&diff.files), vec!["a.txt"]);
+}
+
+#[test]
+fn drops_expired_comparisons() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    let head = repo.repo.head().unwrap().target().unwrap().to_string();
+
+    let entry = |muted_at: i64| MutedComparison {
+        base: Some(head.clone()),
+        muted_at,
+        paths: BTreeSet::from(["a.txt".to_string()]),
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let mut store = MutedStore::new();
+    store.insert("working".to_string(), entry(now - 60 * 24 * 60 * 60));
+    store.insert("HEAD".to_string(), entry(now));
+    git::replace_muted(&repo.repo, &store).unwrap();
+
+    let kept = git::all_muted(&repo.repo).unwrap();
+    assert_eq!(kept.keys().collect::<Vec<_>>(), vec!["HEAD"]);
+    assert!(git::list_muted(&repo.repo, "working").unwrap().is_empty());
+    assert_eq!(git::list_muted(&repo.repo, "HEAD").unwrap(), vec!["a.txt"]);
+}
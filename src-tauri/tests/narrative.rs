@@ -0,0 +1,105 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, NarrativeEntry, NarrativeKind};
+
+/// Narrative entries for a file rewritten from `before` to `after`
+fn narrate(before: &str, after: &str) -> Vec<NarrativeEntry> {
+    let repo = TestRepo::new();
+    repo.write("file.rs", before);
+    repo.commit_all("first");
+    repo.write("file.rs", after);
+    git::get_diff_narrative(&repo.repo, "file.rs", "working")
+        .unwrap()
+        .entries
+}
+
+fn descriptions(entries: &[NarrativeEntry]) -> Vec<&str> {
+    entries.iter().map(|e| e.description.as_str()).collect()
+}
+
+#[test]
+fn describes_renamed_declarations() {
+    let entries = narrate(
+        "fn foo() {\n    1\n}\n\nfn main() {\n    foo();\n}\n",
+        "fn bar() {\n    1\n}\n\nfn main() {\n    bar();\n}\n",
+    );
+    assert_eq!(
+        descriptions(&entries),
+        vec![
+            "Function foo renamed to bar on line 1",
+            "foo renamed to bar on line 6",
+        ]
+    );
+    assert!(entries
+        .iter()
+        .all(|e| matches!(e.kind, NarrativeKind::Renamed)));
+}
+
+#[test]
+fn describes_removed_lines_and_the_declarations_they_held() {
+    let entries = narrate("a\nb\nfn old() {}\nstruct Gone;\nc\n", "a\nb\nc\n");
+    assert_eq!(entries.len(), 1);
+    let entry = &entries[0];
+    assert!(matches!(entry.kind, NarrativeKind::Removed));
+    assert_eq!(
+        entry.description,
+        "Lines 3–4 removed. Removes function old, struct Gone"
+    );
+    let old = entry.old_lines.as_ref().unwrap();
+    assert_eq!((old.start, old.end), (3, 4));
+    assert!(entry.new_lines.is_none());
+    assert_eq!((entry.lines_added, entry.lines_removed), (0, 2));
+}
+
+#[test]
+fn says_where_added_lines_go() {
+    let entries = narrate("a\nb\n", "// header\na\nb\nc\nd\n");
+    assert_eq!(
+        descriptions(&entries),
+        vec![
+            "1 line added at the start of the file (now line 1)",
+            "2 lines added after original line 2 (now lines 4–5)",
+        ]
+    );
+    assert!(entries.iter().all(|e| e.old_lines.is_none()));
+}
+
+#[test]
+fn separates_whitespace_only_edits_from_real_ones() {
+    let entries = narrate(
+        "let x = 1;\nkeep\nlet y = 2;\n",
+        "let  x = 1;\nkeep\nlet y = compute(2, 3);\n",
+    );
+    assert!(matches!(entries[0].kind, NarrativeKind::Whitespace));
+    assert!(matches!(entries[1].kind, NarrativeKind::Modified));
+    assert_eq!(
+        descriptions(&entries),
+        vec![
+            "Whitespace changed on line 1",
+            "Line 3 replaced with 1 line (now line 3)",
+        ]
+    );
+}
+
+#[test]
+fn summarizes_the_file() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\ntwo\nthree\n");
+    repo.write("same.txt", "same\n");
+    let base = repo.commit_all("first");
+    repo.write("a.txt", "one\n2\nthree\nfour\n");
+    let head = repo.commit_all("second");
+
+    let range = format!("{}..{}", base, head);
+    let narrative = git::get_diff_narrative(&repo.repo, "a.txt", &range).unwrap();
+    assert_eq!(narrative.path, "a.txt");
+    assert_eq!(
+        narrative.summary,
+        "2 changes in a.txt: 2 lines added, 1 line removed."
+    );
+
+    let unchanged = git::get_diff_narrative(&repo.repo, "same.txt", &range).unwrap();
+    assert!(unchanged.entries.is_empty());
+    assert_eq!(unchanged.summary, "No text changes in same.txt.");
+}
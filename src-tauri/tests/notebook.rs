@@ -0,0 +1,118 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, CellStatus, GitError};
+use serde_json::json;
+
+fn notebook(cells: &[(&str, &str, serde_json::Value)]) -> String {
+    let cells: Vec<_> = cells
+        .iter()
+        .map(|(cell_type, source, outputs)| {
+            json!({
+                "cell_type": cell_type,
+                "source": source.split_inclusive('\n').collect::<Vec<_>>(),
+                "outputs": outputs,
+                "execution_count": null,
+            })
+        })
+        .collect();
+    json!({ "cells": cells, "nbformat": 4 }).to_string()
+}
+
+#[test]
+fn diffs_notebooks_cell_by_cell() {
+    let repo = TestRepo::new();
+    repo.write(
+        "analysis.ipynb",
+        &notebook(&[
+            ("markdown", "# Title\n", json!(null)),
+            ("code", "x = 1\n", json!([])),
+            ("code", "print(x)\n", json!([])),
+        ]),
+    );
+    repo.commit_all("first");
+
+    repo.write(
+        "analysis.ipynb",
+        &notebook(&[
+            ("markdown", "# Title\n", json!(null)),
+            ("code", "x = 2\n", json!([])),
+            ("code", "print(x)\n", json!([])),
+            ("code", "plot(x)\n", json!([])),
+        ]),
+    );
+
+    let diff =
+        git::get_notebook_diff(&repo.repo, "analysis.ipynb", Some("HEAD"), None, true).unwrap();
+    let statuses: Vec<_> = diff.cells.iter().map(|c| c.status.clone()).collect();
+    assert!(matches!(
+        statuses.as_slice(),
+        [
+            CellStatus::Unchanged,
+            CellStatus::Modified,
+            CellStatus::Unchanged,
+            CellStatus::Added
+        ]
+    ));
+
+    let changed = &diff.cells[1];
+    assert_eq!(changed.old_source.as_deref(), Some("x = 1\n"));
+    assert_eq!(changed.new_source.as_deref(), Some("x = 2\n"));
+    assert!(changed.patch.as_deref().unwrap().contains("+x = 2"));
+    assert_eq!(diff.additions, 2);
+    assert_eq!(diff.deletions, 1);
+}
+
+#[test]
+fn ignores_output_changes_only_when_stripping_outputs() {
+    let repo = TestRepo::new();
+    repo.write("run.ipynb", &notebook(&[("code", "print(1)\n", json!([]))]));
+    repo.commit_all("first");
+    repo.write(
+        "run.ipynb",
+        &notebook(&[(
+            "code",
+            "print(1)\n",
+            json!([{ "output_type": "stream", "text": "1\n" }]),
+        )]),
+    );
+
+    let stripped =
+        git::get_notebook_diff(&repo.repo, "run.ipynb", Some("HEAD"), None, true).unwrap();
+    assert!(matches!(stripped.cells[0].status, CellStatus::Unchanged));
+    assert!(stripped.outputs_stripped);
+
+    let kept = git::get_notebook_diff(&repo.repo, "run.ipynb", Some("HEAD"), None, false).unwrap();
+    assert!(matches!(kept.cells[0].status, CellStatus::Modified));
+    assert!(kept.cells[0].outputs_changed);
+    assert!(kept.cells[0].patch.is_none());
+}
+
+#[test]
+fn treats_a_new_notebook_as_all_added() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write(
+        "new.ipynb",
+        &notebook(&[("code", "a\n", json!([])), ("markdown", "b\n", json!(null))]),
+    );
+
+    let diff = git::get_notebook_diff(&repo.repo, "new.ipynb", Some("HEAD"), None, true).unwrap();
+    assert_eq!(diff.cells.len(), 2);
+    assert!(diff
+        .cells
+        .iter()
+        .all(|c| matches!(c.status, CellStatus::Added) && c.old_index.is_none()));
+}
+
+#[test]
+fn rejects_documents_without_cells() {
+    let repo = TestRepo::new();
+    repo.write("broken.ipynb", "{\"metadata\": {}}");
+    repo.commit_all("first");
+
+    let err =
+        git::get_notebook_diff(&repo.repo, "broken.ipynb", Some("HEAD"), None, true).unwrap_err();
+    assert!(matches!(err, GitError::InvalidNotebook(_)));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, object_cache::ObjectCache};
+
+#[test]
+fn serves_repeat_reads_from_memory() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    let blob = repo.repo.revparse_single("HEAD:a.txt").unwrap().id();
+
+    let cache = ObjectCache::new(1024);
+    assert_eq!(&*cache.blob(&repo.repo, blob).unwrap(), b"one\n");
+    assert_eq!(&*cache.blob(&repo.repo, blob).unwrap(), b"one\n");
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+    assert_eq!((stats.blobs, stats.bytes), (1, 4));
+
+    // Paths resolve per tree, so a later commit can't be answered from an
+    // earlier one's entry
+    let tree = repo.repo.find_commit(first).unwrap().tree_id();
+    let mut lookups = 0;
+    for _ in 0..2 {
+        let found = cache.tree_entry(tree, "a.txt", || {
+            lookups += 1;
+            Ok(blob)
+        });
+        assert_eq!(found.unwrap(), blob);
+    }
+    assert_eq!(lookups, 1);
+
+    cache.clear();
+    assert_eq!(cache.stats(), Default::default());
+}
+
+#[test]
+fn evicts_least_recently_used_blobs() {
+    let repo = TestRepo::new();
+    let blobs: Vec<_> = (0..9)
+        .map(|n| repo.repo.blob(format!("blob {:05}", n).as_bytes()).unwrap())
+        .collect();
+
+    // Room for eight ten-byte blobs
+    let cache = ObjectCache::new(80);
+    for oid in &blobs[..8] {
+        cache.blob(&repo.repo, *oid).unwrap();
+    }
+    // Using the first again makes the second the oldest
+    cache.blob(&repo.repo, blobs[0]).unwrap();
+    cache.blob(&repo.repo, blobs[8]).unwrap();
+    assert_eq!(cache.stats().bytes, 80);
+
+    let misses = cache.stats().misses;
+    cache.blob(&repo.repo, blobs[0]).unwrap();
+    cache.blob(&repo.repo, blobs[2]).unwrap();
+    assert_eq!(cache.stats().misses, misses);
+    cache.blob(&repo.repo, blobs[1]).unwrap();
+    assert_eq!(cache.stats().misses, misses + 1);
+
+    // More than an eighth of the capacity: read, but not kept
+    let big = repo.repo.blob(b"eleven byte").unwrap();
+    assert_eq!(cache.blob(&repo.repo, big).unwrap().len(), 11);
+    assert_eq!(cache.stats().blobs, 8);
+}
+
+#[test]
+fn file_contents_follow_the_revision() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    assert_eq!(
+        git::get_file_contents(&repo.repo, "a.txt", Some("HEAD")).unwrap(),
+        "one\n"
+    );
+
+    repo.write("a.txt", "two\n");
+    repo.commit_all("second");
+    assert_eq!(
+        git::get_file_contents(&repo.repo, "a.txt", Some("HEAD")).unwrap(),
+        "two\n"
+    );
+    assert_eq!(
+        git::get_file_contents(&repo.repo, "a.txt", Some("HEAD~1")).unwrap(),
+        "one\n"
+    );
+    assert_eq!(
+        git::read_bytes_at(&repo.repo, "missing.txt", Some("HEAD")).unwrap(),
+        None
+    );
+}
@@ -0,0 +1,98 @@
+use diffy_lib::operations::{OperationStatus, Operations};
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Poll until `check` holds, failing after a few seconds
+fn eventually(check: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !check() {
+        assert!(Instant::now() < deadline, "condition never held");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn writes_wait_for_reads_and_hold_new_reads_back() {
+    let operations = Arc::new(Operations::default());
+    let repo = Path::new("/repo");
+
+    let first_read = operations.read(repo);
+    let second_read = operations.read(repo);
+    assert_eq!(operations.status(repo).readers, 2);
+
+    let (done, finished) = channel();
+    let writer = {
+        let operations = operations.clone();
+        let done = done.clone();
+        std::thread::spawn(move || {
+            let _op = operations.write(Path::new("/repo"), "commit");
+            done.send("write").unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+        })
+    };
+    eventually(|| operations.status(repo).queued == ["commit"]);
+
+    // A read arriving behind a queued write waits for it
+    let reader = {
+        let operations = operations.clone();
+        std::thread::spawn(move || {
+            let _op = operations.read(Path::new("/repo"));
+            done.send("read").unwrap();
+        })
+    };
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(finished.try_recv().is_err());
+
+    drop(first_read);
+    drop(second_read);
+    assert_eq!(finished.recv().unwrap(), "write");
+    assert_eq!(
+        operations.status(repo),
+        OperationStatus {
+            running: Some("commit".to_string()),
+            queued: Vec::new(),
+            readers: 0,
+        }
+    );
+    assert_eq!(finished.recv().unwrap(), "read");
+    writer.join().unwrap();
+    reader.join().unwrap();
+    eventually(|| operations.status(repo) == OperationStatus::default());
+}
+
+#[test]
+fn writes_run_one_at_a_time_in_arrival_order() {
+    let operations = Arc::new(Operations::default());
+    let repo = Path::new("/repo");
+    let held = operations.write(repo, "checkoutBranch");
+
+    let (order, ran) = channel();
+    let mut writers = Vec::new();
+    for name in ["stageFile", "commit"] {
+        let shared = operations.clone();
+        let order = order.clone();
+        writers.push(std::thread::spawn(move || {
+            let _op = shared.write(Path::new("/repo"), name);
+            order.send(name).unwrap();
+        }));
+        // Queue them in a known order
+        eventually(|| {
+            operations.status(Path::new("/repo")).queued.last() == Some(&name.to_string())
+        });
+    }
+    let status = operations.status(repo);
+    assert_eq!(status.running.as_deref(), Some("checkoutBranch"));
+    assert_eq!(status.queued, ["stageFile", "commit"]);
+
+    // Another repository doesn't wait
+    drop(operations.write(Path::new("/other"), "commit"));
+
+    drop(held);
+    assert_eq!(ran.recv().unwrap(), "stageFile");
+    assert_eq!(ran.recv().unwrap(), "commit");
+    for writer in writers {
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, paths, DifferConfig};
+use std::path::Path;
+
+#[test]
+fn verbatim_paths_round_trip() {
+    assert_eq!(paths::to_verbatim(r"C:\repo\src"), r"\\?\C:\repo\src");
+    assert_eq!(paths::to_verbatim("C:/repo/./a/../src"), r"\\?\C:\repo\src");
+    assert_eq!(paths::to_verbatim(r"C:\"), r"\\?\C:\");
+    assert_eq!(
+        paths::to_verbatim(r"\\build\mono\packages\app"),
+        r"\\?\UNC\build\mono\packages\app"
+    );
+    assert_eq!(paths::to_verbatim("//build/mono"), r"\\?\UNC\build\mono");
+    // Nothing to anchor a verbatim path to
+    assert_eq!(paths::to_verbatim(r"relative\path"), r"relative\path");
+    assert_eq!(paths::to_verbatim(r"\\?\C:\repo"), r"\\?\C:\repo");
+
+    assert_eq!(paths::strip_verbatim(r"\\?\C:\repo"), r"C:\repo");
+    assert_eq!(
+        paths::strip_verbatim(r"\\?\UNC\build\mono\app"),
+        r"\\build\mono\app"
+    );
+    assert_eq!(
+        paths::strip_verbatim(r"\\?\Volume{1234}\repo"),
+        r"\\?\Volume{1234}\repo"
+    );
+    assert_eq!(paths::strip_verbatim("/home/me/repo"), "/home/me/repo");
+}
+
+#[test]
+fn recognizes_network_shares() {
+    assert!(paths::is_unc(r"\\build\mono"));
+    assert!(paths::is_unc("//build/mono"));
+    assert!(paths::is_unc(r"\\?\UNC\build\mono"));
+    assert!(!paths::is_unc(r"\\?\C:\repo"));
+    assert!(!paths::is_unc(r"\\.\pipe\x"));
+    assert!(!paths::is_unc(r"C:\repo"));
+    assert!(!paths::is_unc("/srv/repo"));
+}
+
+#[test]
+fn reads_files_deep_in_a_monorepo() {
+    let repo = TestRepo::new();
+    let deep: Vec<String> = (0..12)
+        .map(|n| format!("package-with-a-long-name-{:02}", n))
+        .collect();
+    let relative = format!("{}/index.ts", deep.join("/"));
+    let absolute = paths::join_relative(repo.workdir(), &relative);
+    assert!(absolute.to_string_lossy().len() > 260);
+
+    repo.write(&relative, "export {};\n");
+    repo.commit_all("deep");
+    repo.write(&relative, "export const x = 1;\n");
+
+    let diff = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    assert_eq!(diff.files.len(), 1);
+    assert_eq!(diff.files[0].path, relative);
+    assert_eq!(
+        git::get_file_contents(&repo.repo, &relative, Some("HEAD")).unwrap(),
+        "export {};\n"
+    );
+    assert_eq!(
+        git::get_file_contents(&repo.repo, &relative, None).unwrap(),
+        "export const x = 1;\n"
+    );
+
+    // Opening from a deep subdirectory finds the repository root
+    let nested = absolute.parent().unwrap();
+    let opened = git::open_repo(nested.to_str().unwrap()).unwrap();
+    assert_eq!(
+        opened.workdir().map(Path::canonicalize).unwrap().unwrap(),
+        repo.workdir().canonicalize().unwrap()
+    );
+}
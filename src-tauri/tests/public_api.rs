@@ -0,0 +1,119 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, ApiChangeKind, PublicApiDiff, SemverImpact};
+
+fn change<'a>(diff: &'a PublicApiDiff, path: &str) -> &'a ApiChangeKind {
+    &diff
+        .changes
+        .iter()
+        .find(|c| c.path == path)
+        .unwrap_or_else(|| panic!("no change for {} in {:?}", path, diff.changes))
+        .change
+}
+
+#[test]
+fn reports_breaking_changes_through_public_modules() {
+    let repo = TestRepo::new();
+    repo.write(
+        "core/src/lib.rs",
+        "pub mod parse;\nmod internal;\n\n/// Docs\npub fn run(input: &str) -> bool { true }\n",
+    );
+    repo.write(
+        "core/src/parse.rs",
+        "pub struct Parser { pub strict: bool, cache: Vec<u8> }\n",
+    );
+    repo.write("core/src/internal.rs", "pub fn helper() {}\n");
+    let base = repo.commit_all("first").to_string();
+
+    repo.write(
+        "core/src/lib.rs",
+        "pub mod parse;\nmod internal;\n\n/// New docs\npub fn run(input: &str) -> bool { input.is_empty() }\npub fn check() {}\n",
+    );
+    repo.write(
+        "core/src/parse.rs",
+        "pub struct Parser { pub strict: bool, buffer: String }\n",
+    );
+    repo.write("core/src/internal.rs", "pub fn helper(x: u8) {}\n");
+    let unchanged_surface = repo.commit_all("bodies and private items").to_string();
+
+    let diff = git::diff_public_api(&repo.repo, &base, &unchanged_surface, "core").unwrap();
+    // Doc comments, bodies, private fields and private modules don't count
+    assert_eq!(diff.changes.len(), 1);
+    assert!(matches!(
+        change(&diff, "crate::check"),
+        ApiChangeKind::Added
+    ));
+    assert!(matches!(diff.semver_impact, SemverImpact::Minor));
+
+    repo.write(
+        "core/src/parse.rs",
+        "pub struct Parser { pub strict: bool, pub lenient: bool }\n",
+    );
+    repo.write(
+        "core/src/lib.rs",
+        "pub mod parse;\nmod internal;\n\npub fn run(input: &[u8]) -> bool { true }\n",
+    );
+    let breaking = repo.commit_all("breaking").to_string();
+
+    let diff = git::diff_public_api(&repo.repo, &base, &breaking, "core").unwrap();
+    assert!(matches!(
+        change(&diff, "crate::parse::Parser"),
+        ApiChangeKind::Changed
+    ));
+    assert!(matches!(
+        change(&diff, "crate::run"),
+        ApiChangeKind::Changed
+    ));
+    assert!(matches!(diff.semver_impact, SemverImpact::Major));
+    assert!(diff.errors.is_empty());
+}
+
+#[test]
+fn tracks_methods_reexports_and_trait_impls() {
+    let repo = TestRepo::new();
+    repo.write(
+        "src/lib.rs",
+        "mod shapes;\npub use shapes::Circle;\n\npub struct Point;\nimpl Point { pub fn new() -> Self { Point } fn private(&self) {} }\n",
+    );
+    repo.write("src/shapes.rs", "pub struct Circle;\n");
+    let base = repo.commit_all("first").to_string();
+
+    repo.write(
+        "src/lib.rs",
+        "mod shapes;\n\npub struct Point;\nimpl Point { pub fn origin() -> Self { Point } }\nimpl Default for Point { fn default() -> Self { Point } }\n",
+    );
+    let head = repo.commit_all("second").to_string();
+
+    let diff = git::diff_public_api(&repo.repo, &base, &head, "").unwrap();
+    assert!(matches!(
+        change(&diff, "crate::Point::new"),
+        ApiChangeKind::Removed
+    ));
+    assert!(matches!(
+        change(&diff, "crate::Point::origin"),
+        ApiChangeKind::Added
+    ));
+    assert!(matches!(
+        change(&diff, "crate::{shapes::Circle}"),
+        ApiChangeKind::Removed
+    ));
+    assert!(matches!(
+        change(&diff, "impl Default for Point"),
+        ApiChangeKind::Added
+    ));
+    assert_eq!(diff.changes.len(), 4);
+}
+
+#[test]
+fn collects_parse_errors_without_failing() {
+    let repo = TestRepo::new();
+    repo.write("src/lib.rs", "pub fn ok() {}\n");
+    let base = repo.commit_all("first").to_string();
+    repo.write("src/lib.rs", "pub fn broken( {\n");
+    let head = repo.commit_all("second").to_string();
+
+    let diff = git::diff_public_api(&repo.repo, &base, &head, "").unwrap();
+    assert_eq!(diff.errors.len(), 1);
+    assert!(diff.errors[0].starts_with("src/lib.rs"));
+}
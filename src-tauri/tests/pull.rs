@@ -0,0 +1,209 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, Credentials, GitError, HookPolicy, PullOutcome, PullStrategy};
+use git2::{Repository, RepositoryState};
+use tempfile::TempDir;
+
+/// A clone of `origin` tracking its master, with an identity to commit as
+struct Clone {
+    repo: Repository,
+    _dir: TempDir,
+}
+
+impl Clone {
+    fn of(origin: &TestRepo) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::clone(origin.workdir().to_str().unwrap(), dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        Self { repo, _dir: dir }
+    }
+
+    fn write(&self, path: &str, contents: &str) {
+        std::fs::write(self.repo.workdir().unwrap().join(path), contents).unwrap();
+    }
+
+    fn read(&self, path: &str) -> String {
+        std::fs::read_to_string(self.repo.workdir().unwrap().join(path)).unwrap()
+    }
+
+    fn commit_all(&self, message: &str) -> git2::Oid {
+        let mut index = self.repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = self.repo.signature().unwrap();
+        let parent = self.repo.head().unwrap().peel_to_commit().unwrap();
+        self.repo
+            .commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent])
+            .unwrap()
+    }
+
+    fn head(&self) -> git2::Oid {
+        self.repo.head().unwrap().target().unwrap()
+    }
+}
+
+fn origin() -> TestRepo {
+    let origin = TestRepo::new();
+    origin.write("a.txt", "one\n");
+    origin.commit_all("first");
+    origin
+}
+
+#[test]
+fn fast_forwards_to_new_upstream_commits() {
+    let origin = origin();
+    let clone = Clone::of(&origin);
+    let again = git::pull(
+        &clone.repo,
+        None,
+        &Credentials::default(),
+        &HookPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(again.outcome, PullOutcome::UpToDate);
+
+    origin.write("a.txt", "two\n");
+    let tip = origin.commit_all("second");
+
+    let result = git::pull(
+        &clone.repo,
+        None,
+        &Credentials::default(),
+        &HookPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(result.strategy, PullStrategy::Merge);
+    assert_eq!(result.outcome, PullOutcome::FastForward);
+    assert_eq!(result.branch, "master");
+    assert_eq!(result.upstream, "origin/master");
+    assert_eq!(result.sha, tip.to_string());
+    assert_eq!(clone.head(), tip);
+    assert_eq!(clone.read("a.txt"), "two\n");
+}
+
+#[test]
+fn merges_or_refuses_diverged_branches() {
+    let origin = origin();
+    let clone = Clone::of(&origin);
+    origin.write("b.txt", "theirs\n");
+    let theirs = origin.commit_all("add b");
+    clone.write("c.txt", "ours\n");
+    let ours = clone.commit_all("add c");
+
+    let refused = git::pull(
+        &clone.repo,
+        Some(PullStrategy::FfOnly),
+        &Credentials::default(),
+        &HookPolicy::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(refused, GitError::NotFastForward(ref u) if u == "origin/master"));
+    assert_eq!(clone.head(), ours);
+
+    let result = git::pull(
+        &clone.repo,
+        Some(PullStrategy::Merge),
+        &Credentials::default(),
+        &HookPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(result.outcome, PullOutcome::Merged);
+    let merge = clone.repo.find_commit(clone.head()).unwrap();
+    assert_eq!(result.commit.unwrap().sha, merge.id().to_string());
+    assert_eq!(merge.parent_ids().collect::<Vec<_>>(), [ours, theirs]);
+    assert_eq!(clone.read("b.txt"), "theirs\n");
+}
+
+#[test]
+fn rebases_when_configured() {
+    let origin = origin();
+    let clone = Clone::of(&origin);
+    clone
+        .repo
+        .config()
+        .unwrap()
+        .set_bool("pull.rebase", true)
+        .unwrap();
+    origin.write("b.txt", "theirs\n");
+    let theirs = origin.commit_all("add b");
+    clone.write("c.txt", "ours\n");
+    clone.commit_all("add c");
+
+    let result = git::pull(
+        &clone.repo,
+        None,
+        &Credentials::default(),
+        &HookPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(result.strategy, PullStrategy::Rebase);
+    assert_eq!(result.outcome, PullOutcome::Rebased);
+    assert_eq!(result.rebased, 1);
+    let head = clone.repo.find_commit(clone.head()).unwrap();
+    assert_eq!(result.sha, head.id().to_string());
+    assert_eq!(head.summary(), Some("add c"));
+    assert_eq!(head.parent_ids().collect::<Vec<_>>(), [theirs]);
+    assert_eq!(clone.repo.head().unwrap().shorthand(), Some("master"));
+    assert_eq!(clone.read("b.txt"), "theirs\n");
+    assert_eq!(clone.repo.state(), RepositoryState::Clean);
+}
+
+#[test]
+fn reports_conflicts() {
+    let origin = origin();
+    let clone = Clone::of(&origin);
+    origin.write("a.txt", "theirs\n");
+    origin.commit_all("theirs");
+    clone.write("a.txt", "ours\n");
+    let ours = clone.commit_all("ours");
+
+    // A rebase stops and puts the branch back
+    let rebased = git::pull(
+        &clone.repo,
+        Some(PullStrategy::Rebase),
+        &Credentials::default(),
+        &HookPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(rebased.outcome, PullOutcome::Conflicted);
+    assert_eq!(rebased.conflicts, ["a.txt"]);
+    assert_eq!(rebased.stopped_at, Some(ours.to_string()));
+    assert_eq!(clone.head(), ours);
+    assert_eq!(clone.repo.head().unwrap().shorthand(), Some("master"));
+    assert_eq!(clone.repo.state(), RepositoryState::Clean);
+    assert_eq!(clone.read("a.txt"), "ours\n");
+
+    // A merge is left to resolve
+    let merged = git::pull(
+        &clone.repo,
+        Some(PullStrategy::Merge),
+        &Credentials::default(),
+        &HookPolicy::default(),
+    )
+    .unwrap();
+    assert_eq!(merged.outcome, PullOutcome::Conflicted);
+    assert_eq!(merged.conflicts, ["a.txt"]);
+    assert_eq!(merged.sha, ours.to_string());
+    assert_eq!(clone.repo.state(), RepositoryState::Merge);
+}
+
+#[test]
+fn needs_an_upstream() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    let err = git::pull(
+        &repo.repo,
+        None,
+        &Credentials::default(),
+        &HookPolicy::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, GitError::NoUpstream(ref b) if b == "master"));
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, Credentials, GitError, HookPolicy, PushStage};
+use git2::Repository;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+/// A repository with a bare `origin` to push to
+fn with_origin() -> (TestRepo, Repository, TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let origin = Repository::init_bare(dir.path()).unwrap();
+    let repo = TestRepo::new();
+    repo.repo
+        .remote("origin", dir.path().to_str().unwrap())
+        .unwrap();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    (repo, origin, dir)
+}
+
+fn remote_master(origin: &Repository) -> Option<git2::Oid> {
+    origin.refname_to_id("refs/heads/master").ok()
+}
+
+/// Reset master to a new commit that doesn't contain the pushed one
+fn rewrite(repo: &TestRepo, message: &str) -> git2::Oid {
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    let sig = head.author();
+    let oid = repo
+        .repo
+        .commit(None, &sig, &sig, message, &head.tree().unwrap(), &[])
+        .unwrap();
+    repo.repo
+        .reference("refs/heads/master", oid, true, "rewrite")
+        .unwrap();
+    oid
+}
+
+#[test]
+fn pushes_the_current_branch_and_reports_progress() {
+    let (repo, origin, _dir) = with_origin();
+    let head = repo.repo.head().unwrap().target().unwrap();
+
+    let mut progress = Vec::new();
+    let result = git::push(
+        &repo.repo,
+        None,
+        None,
+        false,
+        &HookPolicy::default(),
+        &Credentials::default(),
+        |p| progress.push(p),
+    )
+    .unwrap();
+
+    assert_eq!(result.remote, "origin");
+    assert!(!result.forced);
+    assert_eq!(result.refs.len(), 1);
+    assert_eq!(result.refs[0].name, "refs/heads/master");
+    assert_eq!(result.refs[0].old, None);
+    assert_eq!(result.refs[0].new, Some(head.to_string()));
+    assert_eq!(remote_master(&origin), Some(head));
+    // The remote-tracking branch follows
+    assert_eq!(
+        repo.repo.refname_to_id("refs/remotes/origin/master").ok(),
+        Some(head)
+    );
+    assert!(progress.iter().any(|p| p.stage == PushStage::Packing));
+}
+
+#[test]
+fn pushes_and_deletes_named_refs() {
+    let (repo, origin, _dir) = with_origin();
+    let head = repo.repo.head().unwrap().target().unwrap();
+    let hooks = HookPolicy::default();
+
+    git::push(
+        &repo.repo,
+        Some("origin"),
+        Some("master:release"),
+        false,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(origin.refname_to_id("refs/heads/release").ok(), Some(head));
+    assert_eq!(remote_master(&origin), None);
+
+    let deleted = git::push(
+        &repo.repo,
+        Some("origin"),
+        Some(":release"),
+        false,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(deleted.refs[0].old, Some(head.to_string()));
+    assert_eq!(deleted.refs[0].new, None);
+    assert!(origin.find_reference("refs/heads/release").is_err());
+
+    let err = git::push(
+        &repo.repo,
+        None,
+        Some(":"),
+        false,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, GitError::InvalidRefspec(_)));
+}
+
+#[test]
+fn forces_only_with_a_current_lease() {
+    let (repo, origin, _dir) = with_origin();
+    let hooks = HookPolicy::default();
+    let first = repo.repo.head().unwrap().target().unwrap();
+    git::push(
+        &repo.repo,
+        None,
+        None,
+        false,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap();
+
+    let rewritten = rewrite(&repo, "rewritten");
+    let err = git::push(
+        &repo.repo,
+        None,
+        None,
+        false,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, GitError::NotFastForward(ref name) if name == "origin/master"));
+    assert_eq!(remote_master(&origin), Some(first));
+
+    let result = git::push(
+        &repo.repo,
+        None,
+        None,
+        true,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap();
+    assert!(result.forced);
+    assert_eq!(remote_master(&origin), Some(rewritten));
+
+    // Someone else pushes; our tracking ref doesn't know
+    git::push(
+        &repo.repo,
+        None,
+        Some("master:other"),
+        false,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap();
+    let theirs = rewrite(&repo, "theirs");
+    git::push(
+        &repo.repo,
+        None,
+        Some("master:other"),
+        true,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap();
+    origin
+        .reference("refs/heads/master", theirs, true, "someone else")
+        .unwrap();
+    repo.repo
+        .reference("refs/heads/master", rewritten, true, "back")
+        .unwrap();
+    rewrite(&repo, "ours");
+    let err = git::push(
+        &repo.repo,
+        None,
+        None,
+        true,
+        &hooks,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, GitError::StaleLease(ref name) if name == "master"));
+    assert_eq!(remote_master(&origin), Some(theirs));
+}
+
+#[test]
+fn runs_the_pre_push_hook() {
+    let (repo, origin, dir) = with_origin();
+    let head = repo.repo.head().unwrap().target().unwrap();
+    let hook = repo.repo.path().join("hooks/pre-push");
+    std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    std::fs::write(&hook, "#!/bin/sh\necho \"$1 $2\"\ncat\nexit 1\n").unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let err = git::push(
+        &repo.repo,
+        None,
+        None,
+        false,
+        &HookPolicy::default(),
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap_err();
+    let GitError::HookFailed(result) = err else {
+        panic!("expected the hook to stop the push, got {:?}", err);
+    };
+    assert_eq!(
+        result.stdout,
+        format!(
+            "origin {}\nrefs/heads/master {} refs/heads/master {}\n",
+            dir.path().display(),
+            head,
+            git2::Oid::zero()
+        )
+    );
+    assert_eq!(remote_master(&origin), None);
+
+    // Skipping the hook lets it through
+    let policy = HookPolicy {
+        skip: vec!["pre-push".to_string()],
+        ..Default::default()
+    };
+    let result = git::push(
+        &repo.repo,
+        None,
+        None,
+        false,
+        &policy,
+        &Credentials::default(),
+        |_| {},
+    )
+    .unwrap();
+    assert!(result.hook.is_none());
+    assert_eq!(remote_master(&origin), Some(head));
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig};
+
+#[test]
+fn repositories_opt_in_through_their_config_file() {
+    let repo = TestRepo::new();
+    assert!(!git::is_read_only(&repo.repo));
+
+    repo.write(".diffyrc.json", r#"{"defaultBranch": "main"}"#);
+    assert!(!git::is_read_only(&repo.repo));
+    repo.write(".diffyrc.json", r#"{"readOnly": "yes"}"#);
+    assert!(!git::is_read_only(&repo.repo));
+
+    repo.write(".diffyrc.json", r#"{"readOnly": true}"#);
+    assert!(git::is_read_only(&repo.repo));
+}
+
+#[test]
+fn the_app_setting_is_off_unless_set() {
+    let config: DifferConfig = serde_json::from_str("{}").unwrap();
+    assert!(!config.read_only);
+    let config: DifferConfig = serde_json::from_str(r#"{"readOnly": true}"#).unwrap();
+    assert!(config.read_only);
+}
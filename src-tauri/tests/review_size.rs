@@ -0,0 +1,91 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig, ReviewSize, ReviewThresholds};
+
+fn lines(count: usize) -> String {
+    (0..count).map(|i| format!("line {}\n", i)).collect()
+}
+
+fn config(max_files: usize) -> DifferConfig {
+    DifferConfig {
+        review_thresholds: ReviewThresholds {
+            small_lines: 5,
+            medium_lines: 20,
+            large_lines: 50,
+            large_file_lines: 15,
+            max_files,
+        },
+        ..DifferConfig::default()
+    }
+}
+
+#[test]
+fn sizes_change_sets_by_changed_lines() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "");
+    repo.commit_all("first");
+
+    let cases = [
+        (3, ReviewSize::Small),
+        (20, ReviewSize::Medium),
+        (40, ReviewSize::Large),
+        (51, ReviewSize::XLarge),
+    ];
+    for (count, expected) in cases {
+        repo.write("a.txt", &lines(count));
+        let diff = git::get_current_diff(&repo.repo, None, &config(10)).unwrap();
+        let complexity = diff.complexity.unwrap();
+        assert_eq!(complexity.total_changed_lines, count);
+        assert_eq!(
+            std::mem::discriminant(&complexity.size),
+            std::mem::discriminant(&expected),
+            "{} lines",
+            count
+        );
+        assert_eq!(
+            complexity.suggest_split,
+            matches!(expected, ReviewSize::XLarge)
+        );
+    }
+}
+
+#[test]
+fn lists_large_files_and_suggests_splitting_wide_changes() {
+    let repo = TestRepo::new();
+    for name in ["big.txt", "small-1.txt", "small-2.txt"] {
+        repo.write(name, "");
+    }
+    repo.commit_all("first");
+    repo.write("big.txt", &lines(16));
+    repo.write("small-1.txt", "a\n");
+    repo.write("small-2.txt", "b\n");
+
+    let complexity = git::get_current_diff(&repo.repo, None, &config(2))
+        .unwrap()
+        .complexity
+        .unwrap();
+    assert_eq!(complexity.files_changed, 3);
+    assert_eq!(complexity.large_files, vec!["big.txt"]);
+    assert!(matches!(complexity.size, ReviewSize::Medium));
+    assert!(complexity.suggest_split);
+}
+
+#[test]
+fn reports_complexity_for_branch_comparisons() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("first");
+    repo.write("a.txt", &lines(30));
+    let head = repo.commit_all("second");
+
+    let result = git::compare_refs(
+        &repo.repo,
+        &base.to_string(),
+        &head.to_string(),
+        &config(10),
+    )
+    .unwrap();
+    assert_eq!(result.complexity.total_changed_lines, 31);
+    assert!(matches!(result.complexity.size, ReviewSize::Large));
+}
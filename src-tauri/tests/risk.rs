@@ -0,0 +1,101 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{
+    self, Degradation, DifferConfig, FileDiffInfo, FileRisk, LargeRepoMode, RiskLevel, RiskStatus,
+};
+use git2::{Oid, Signature, Time};
+
+/// Commit everything in the working tree as `author`
+fn commit_as(repo: &TestRepo, author: &str, message: &str) -> Oid {
+    let mut index = repo.repo.index().unwrap();
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree = repo.repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = Signature::new(
+        author,
+        &format!("{}@example.com", author),
+        &Time::new(1_700_000_000, 0),
+    )
+    .unwrap();
+    let parent = repo.repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.repo
+        .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+        .unwrap()
+}
+
+fn risk_for<'a>(files: &'a [FileDiffInfo], path: &str) -> &'a FileRisk {
+    let file = files.iter().find(|f| f.path == path).unwrap();
+    assert_eq!(file.risk_status, Some(RiskStatus::Scored));
+    file.risk.as_ref().unwrap()
+}
+
+#[test]
+fn scores_churn_ownership_and_bug_prone_paths() {
+    let repo = TestRepo::new();
+    repo.write("src/core.rs", "fn a() {}\n");
+    repo.write("src/quiet.rs", "fn q() {}\n");
+    let base = commit_as(&repo, "alice", "first").to_string();
+
+    for (i, author) in ["bob", "carol", "dave", "erin"].iter().enumerate() {
+        repo.write("src/core.rs", &format!("fn a() {{}}\n// {}\n", i));
+        commit_as(&repo, author, "churn");
+    }
+    repo.write("src/quiet.rs", "fn q() { 1 }\n");
+    repo.write("src/new.rs", "fn n() {}\n");
+    let head = commit_as(&repo, "alice", "last").to_string();
+
+    let config = DifferConfig {
+        risk_paths: vec!["src/quiet.rs".to_string()],
+        ..DifferConfig::default()
+    };
+    let result = git::compare_refs(&repo.repo, &base, &head, &config).unwrap();
+    assert!(result.degraded.is_empty());
+    assert_eq!(result.files.len(), 3);
+
+    let core = risk_for(&result.files, "src/core.rs");
+    assert_eq!(core.recent_authors, 5);
+    assert_eq!(core.recent_commits, 5);
+    assert!(core
+        .reasons
+        .contains(&"5 authors changed it recently".to_string()));
+    assert!(core.top_owner_share.is_some());
+    assert_eq!(core.test_correlation, Some(0.0));
+
+    let quiet = risk_for(&result.files, "src/quiet.rs");
+    assert!(quiet.bug_prone);
+    assert!(quiet
+        .reasons
+        .contains(&"Path is marked as bug-prone".to_string()));
+
+    // Added files have no history worth blaming
+    let new = risk_for(&result.files, "src/new.rs");
+    assert_eq!(new.top_owner_share, None);
+    assert!(!new.bug_prone);
+    assert!(matches!(new.level, RiskLevel::Low));
+
+    assert!(core.score > new.score);
+}
+
+#[test]
+fn skips_scoring_on_huge_repositories() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("first").to_string();
+    repo.write("a.txt", "two\n");
+    let head = repo.commit_all("second").to_string();
+
+    let mut config = DifferConfig::default();
+    config.large_repo.mode = LargeRepoMode::On;
+    let result = git::compare_refs(&repo.repo, &base, &head, &config).unwrap();
+    assert_eq!(result.degraded, vec![Degradation::CommitStats]);
+    assert_eq!(result.files[0].risk_status, Some(RiskStatus::Skipped));
+    assert!(result.files[0].risk.is_none());
+
+    config.large_repo.keep = vec![Degradation::CommitStats];
+    let result = git::compare_refs(&repo.repo, &base, &head, &config).unwrap();
+    risk_for(&result.files, "a.txt");
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig, WarningCategory};
+
+fn warned_paths(repo: &TestRepo, config: &DifferConfig) -> Vec<String> {
+    let diff = git::get_current_diff(&repo.repo, None, config).unwrap();
+    assert!(diff
+        .warnings
+        .iter()
+        .all(|w| matches!(w.category, WarningCategory::SensitiveFile)));
+    let mut paths: Vec<String> = diff.warnings.into_iter().map(|w| w.path).collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn flags_credentials_by_name_but_not_templates() {
+    let repo = TestRepo::new();
+    repo.write("README.md", "hello\n");
+    repo.commit_all("first");
+
+    repo.write(".env", "TOKEN=1\n");
+    repo.write(".env.example", "TOKEN=\n");
+    repo.write("certs/server.pem", "-----BEGIN-----\n");
+    repo.write("home/me/.kube/config", "clusters: []\n");
+    repo.write("src/keys.rs", "fn main() {}\n");
+
+    assert_eq!(
+        warned_paths(&repo, &DifferConfig::default()),
+        vec![".env", "certs/server.pem", "home/me/.kube/config"]
+    );
+}
+
+#[test]
+fn extends_the_built_in_list_from_config() {
+    let repo = TestRepo::new();
+    repo.write("README.md", "hello\n");
+    repo.commit_all("first");
+    repo.write("deploy/secrets.yaml", "password: x\n");
+    repo.write("secrets.yaml", "password: y\n");
+
+    let config = DifferConfig {
+        sensitive_patterns: vec!["deploy/*.yaml".to_string()],
+        ..DifferConfig::default()
+    };
+    assert_eq!(warned_paths(&repo, &config), vec!["deploy/secrets.yaml"]);
+}
+
+#[test]
+fn says_what_happened_to_the_file() {
+    let repo = TestRepo::new();
+    repo.write("id_rsa", "key\n");
+    repo.commit_all("first");
+    repo.remove("id_rsa");
+
+    let diff = git::get_current_diff(&repo.repo, None, &DifferConfig::default()).unwrap();
+    assert_eq!(diff.warnings.len(), 1);
+    assert_eq!(
+        diff.warnings[0].message,
+        "Sensitive file id_rsa deleted (matches id_rsa*)"
+    );
+}
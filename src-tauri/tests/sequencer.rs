@@ -0,0 +1,173 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, SequencerOperation};
+use git2::RepositoryState;
+
+fn read(repo: &TestRepo, path: &str) -> String {
+    std::fs::read_to_string(repo.workdir().join(path)).unwrap()
+}
+
+#[test]
+fn reverts_a_commit_cleanly() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "keep\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    let bad = repo.commit_all("Break a");
+    repo.write("b.txt", "kept\n");
+    repo.commit_all("later work");
+
+    let result = git::revert_commit(&repo.repo, &bad.to_string()).unwrap();
+    assert_eq!(result.operation, SequencerOperation::Revert);
+    assert_eq!(result.source, bad.to_string());
+    assert!(result.conflicts.is_empty());
+    assert_eq!(
+        result.message,
+        format!("Revert \"Break a\"\n\nThis reverts commit {}.\n", bad)
+    );
+
+    let commit = result.commit.expect("revert was committed");
+    assert_eq!(commit.message, result.message);
+    assert_eq!(commit.author, "Test");
+    assert_eq!(
+        repo.repo.head().unwrap().target().unwrap().to_string(),
+        commit.sha
+    );
+    assert_eq!(read(&repo, "a.txt"), "one\n");
+    assert_eq!(read(&repo, "b.txt"), "kept\n");
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+}
+
+#[test]
+fn conflicting_revert_stops_for_resolution() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    let bad = repo.commit_all("second");
+    repo.write("a.txt", "three\n");
+    let head = repo.commit_all("third");
+
+    let result = git::revert_commit(&repo.repo, "HEAD~1").unwrap();
+    assert!(result.commit.is_none());
+    assert_eq!(result.conflicts, ["a.txt"]);
+    assert!(result.message.contains(&bad.to_string()));
+    assert_eq!(repo.repo.state(), RepositoryState::Revert);
+    assert_eq!(repo.repo.head().unwrap().target(), Some(head));
+    assert!(read(&repo, "a.txt").contains("<<<<<<<"));
+
+    // Another operation waits until this one is finished
+    assert!(matches!(
+        git::revert_commit(&repo.repo, "HEAD"),
+        Err(git::GitError::MergeInProgress)
+    ));
+
+    // Resolving and committing concludes the revert
+    repo.write("a.txt", "one\n");
+    repo.stage("a.txt");
+    let policy = git::HookPolicy::default();
+    let commit = git::create_commit(&repo.repo, &result.message, &policy).unwrap();
+    assert!(commit.message.starts_with("Revert \"second\""));
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+}
+
+#[test]
+fn revert_refuses_local_changes() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "b\n");
+    repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    let head = repo.commit_all("second");
+    repo.write("b.txt", "edited\n");
+
+    match git::revert_commit(&repo.repo, "HEAD") {
+        Err(git::GitError::UncommittedChanges(files)) => assert_eq!(files, ["b.txt"]),
+        other => panic!("expected a refusal, got {:?}", other.map(|r| r.commit)),
+    }
+    assert_eq!(repo.repo.head().unwrap().target(), Some(head));
+    assert_eq!(read(&repo, "b.txt"), "edited\n");
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+}
+
+/// `master` with a.txt, and `feature` one commit ahead that edits a.txt and
+/// adds b.txt, authored by "Test"; commits made afterwards are by "Picker"
+fn feature_commit(repo: &TestRepo, edit: &str) -> git2::Oid {
+    repo.write("a.txt", "one\n");
+    let base = repo.commit_all("first");
+    repo.branch("feature", base);
+    repo.checkout("feature");
+    repo.write("a.txt", edit);
+    repo.write("b.txt", "feature only\n");
+    let pick = repo.commit_all("Feature work\n\nWith a body.");
+    repo.checkout("master");
+    let mut config = repo.repo.config().unwrap();
+    config.set_str("user.name", "Picker").unwrap();
+    config.set_str("user.email", "picker@example.com").unwrap();
+    pick
+}
+
+#[test]
+fn cherry_picks_a_commit_with_its_author() {
+    let repo = TestRepo::new();
+    let pick = feature_commit(&repo, "two\n");
+    repo.write("c.txt", "master only\n");
+    let head = repo.commit_all("master work");
+
+    let result = git::cherry_pick(&repo.repo, "feature").unwrap();
+    assert_eq!(result.operation, SequencerOperation::CherryPick);
+    assert_eq!(result.source, pick.to_string());
+    assert!(result.conflicts.is_empty());
+    assert_eq!(result.message, "Feature work\n\nWith a body.\n");
+
+    let commit = result.commit.expect("pick was committed");
+    assert_eq!(commit.author, "Test");
+    let made = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(made.id().to_string(), commit.sha);
+    assert_eq!(made.committer().name(), Some("Picker"));
+    assert_eq!(made.parent_id(0).unwrap(), head);
+    assert_eq!(repo.repo.head().unwrap().shorthand(), Some("master"));
+    assert_eq!(read(&repo, "a.txt"), "two\n");
+    assert_eq!(read(&repo, "b.txt"), "feature only\n");
+    assert_eq!(read(&repo, "c.txt"), "master only\n");
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+
+    // Picking it again changes nothing
+    assert!(matches!(
+        git::cherry_pick(&repo.repo, &pick.to_string()),
+        Err(git::GitError::NothingToCommit)
+    ));
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+}
+
+#[test]
+fn conflicting_cherry_pick_keeps_the_author_when_concluded() {
+    let repo = TestRepo::new();
+    feature_commit(&repo, "two\n");
+    repo.write("a.txt", "master\n");
+    let head = repo.commit_all("master edit");
+
+    let result = git::cherry_pick(&repo.repo, "feature").unwrap();
+    assert!(result.commit.is_none());
+    assert_eq!(result.conflicts, ["a.txt"]);
+    assert_eq!(repo.repo.state(), RepositoryState::CherryPick);
+    assert_eq!(repo.repo.head().unwrap().target(), Some(head));
+    // The clean part of the pick is already staged
+    assert_eq!(read(&repo, "b.txt"), "feature only\n");
+
+    repo.write("a.txt", "both\n");
+    repo.stage("a.txt");
+    let policy = git::HookPolicy::default();
+    let commit = git::create_commit(&repo.repo, &result.message, &policy).unwrap();
+    assert_eq!(commit.author, "Test");
+    assert_eq!(commit.message, "Feature work\n\nWith a body.\n");
+    assert_eq!(repo.repo.state(), RepositoryState::Clean);
+    let made = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(made.committer().name(), Some("Picker"));
+    assert_eq!(made.parent_count(), 1);
+}
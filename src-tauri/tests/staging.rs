@@ -0,0 +1,200 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig};
+
+fn section_paths(repo: &TestRepo) -> (Vec<String>, Vec<String>) {
+    let changes = git::get_working_changes(&repo.repo, &DifferConfig::default()).unwrap();
+    let paths = |files: Vec<git::FileDiffInfo>| files.into_iter().map(|f| f.path).collect();
+    (paths(changes.staged.files), paths(changes.unstaged.files))
+}
+
+#[test]
+fn stages_and_unstages_modified_untracked_and_deleted_files() {
+    let repo = TestRepo::new();
+    repo.write("kept.txt", "one\n");
+    repo.write("gone.txt", "bye\n");
+    repo.commit_all("first");
+
+    repo.write("kept.txt", "two\n");
+    repo.write("new.txt", "hello\n");
+    repo.remove("gone.txt");
+
+    for path in ["kept.txt", "new.txt", "gone.txt"] {
+        git::stage_file(&repo.repo, path).unwrap();
+    }
+    let (staged, unstaged) = section_paths(&repo);
+    assert_eq!(staged, ["gone.txt", "kept.txt", "new.txt"]);
+    assert!(unstaged.is_empty());
+
+    for path in ["kept.txt", "new.txt", "gone.txt"] {
+        git::unstage_file(&repo.repo, path).unwrap();
+    }
+    let (staged, unstaged) = section_paths(&repo);
+    assert!(staged.is_empty());
+    assert_eq!(unstaged, ["gone.txt", "kept.txt", "new.txt"]);
+    // Unstaging never touches the working tree
+    assert_eq!(
+        std::fs::read_to_string(repo.workdir().join("kept.txt")).unwrap(),
+        "two\n"
+    );
+}
+
+#[test]
+fn unstages_before_the_first_commit() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    git::stage_file(&repo.repo, "a.txt").unwrap();
+
+    git::unstage_file(&repo.repo, "a.txt").unwrap();
+    let (staged, unstaged) = section_paths(&repo);
+    assert!(staged.is_empty());
+    assert_eq!(unstaged, ["a.txt"]);
+}
+
+#[test]
+fn staging_an_unknown_path_fails() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+
+    assert!(matches!(
+        git::stage_file(&repo.repo, "missing.txt"),
+        Err(git::GitError::PathNotFound(_))
+    ));
+}
+
+fn hunk_ids(repo: &TestRepo, path: &str, target: &str) -> Vec<String> {
+    git::get_file_hunks(&repo.repo, path, target, 0, 100)
+        .unwrap()
+        .hunks
+        .into_iter()
+        .map(|h| h.id)
+        .collect()
+}
+
+#[test]
+fn stages_and_unstages_single_hunks() {
+    let repo = TestRepo::new();
+    let original: String = (1..=30).map(|n| format!("line {}\n", n)).collect();
+    repo.write("a.txt", &original);
+    repo.commit_all("first");
+
+    let edited = original
+        .replace("line 2\n", "line two\n")
+        .replace("line 28\n", "line twenty-eight\n");
+    repo.write("a.txt", &edited);
+
+    let unstaged = hunk_ids(&repo, "a.txt", "unstaged");
+    assert_eq!(unstaged.len(), 2);
+    git::stage_hunk(&repo.repo, "a.txt", &unstaged[1]).unwrap();
+
+    // The other hunk keeps its id after the index changed
+    assert_eq!(hunk_ids(&repo, "a.txt", "unstaged"), [unstaged[0].clone()]);
+    let staged = git::get_file_hunks(&repo.repo, "a.txt", "staged", 0, 100)
+        .unwrap()
+        .hunks;
+    assert_eq!(staged.len(), 1);
+    assert!(staged[0].content.contains("+line twenty-eight\n"));
+    assert_eq!(staged[0].id, unstaged[1]);
+
+    git::stage_hunk(&repo.repo, "a.txt", &unstaged[0]).unwrap();
+    let staged = hunk_ids(&repo, "a.txt", "staged");
+    assert_eq!(staged.len(), 2);
+    assert!(hunk_ids(&repo, "a.txt", "unstaged").is_empty());
+
+    git::unstage_hunk(&repo.repo, "a.txt", &staged[0]).unwrap();
+    let remaining = git::get_file_hunks(&repo.repo, "a.txt", "staged", 0, 100)
+        .unwrap()
+        .hunks;
+    assert_eq!(remaining.len(), 1);
+    assert!(remaining[0].content.contains("+line twenty-eight\n"));
+    assert_eq!(
+        std::fs::read_to_string(repo.workdir().join("a.txt")).unwrap(),
+        edited
+    );
+}
+
+#[test]
+fn stages_the_hunk_of_an_untracked_file() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("new.txt", "hello\n");
+
+    let ids = hunk_ids(&repo, "new.txt", "unstaged");
+    git::stage_hunk(&repo.repo, "new.txt", &ids[0]).unwrap();
+    let (staged, unstaged) = section_paths(&repo);
+    assert_eq!(staged, ["new.txt"]);
+    assert!(unstaged.is_empty());
+
+    assert!(matches!(
+        git::stage_hunk(&repo.repo, "new.txt", &ids[0]),
+        Err(git::GitError::HunkNotFound(_))
+    ));
+}
+
+fn staged_content(repo: &TestRepo, path: &str) -> String {
+    let index = repo.repo.index().unwrap();
+    let entry = index.get_path(std::path::Path::new(path), 0).unwrap();
+    let blob = repo.repo.find_blob(entry.id).unwrap();
+    String::from_utf8(blob.content().to_vec()).unwrap()
+}
+
+#[test]
+fn stages_and_unstages_selected_lines() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\ntwo\nthree\nfour\n");
+    repo.commit_all("first");
+    // Replace `two` and `three`, each with a new line
+    repo.write("a.txt", "one\n2\n3\nfour\n");
+
+    let ids = hunk_ids(&repo, "a.txt", "unstaged");
+    // Remove `three` (old line 3) and add `2` (new line 2)
+    let selection = git::LineSelection {
+        deletions: vec![3],
+        additions: vec![2],
+    };
+    git::stage_lines(&repo.repo, "a.txt", &ids[0], &selection).unwrap();
+    assert_eq!(staged_content(&repo, "a.txt"), "one\ntwo\n2\nfour\n");
+    assert_eq!(
+        std::fs::read_to_string(repo.workdir().join("a.txt")).unwrap(),
+        "one\n2\n3\nfour\n"
+    );
+
+    // Take the added `2` (index line 3) back out
+    let staged = hunk_ids(&repo, "a.txt", "staged");
+    let selection = git::LineSelection {
+        deletions: vec![],
+        additions: vec![3],
+    };
+    git::unstage_lines(&repo.repo, "a.txt", &staged[0], &selection).unwrap();
+    assert_eq!(staged_content(&repo, "a.txt"), "one\ntwo\nfour\n");
+
+    let nothing = git::LineSelection::default();
+    assert!(matches!(
+        git::stage_lines(
+            &repo.repo,
+            "a.txt",
+            &hunk_ids(&repo, "a.txt", "unstaged")[0],
+            &nothing
+        ),
+        Err(git::GitError::InvalidRange(_))
+    ));
+}
+
+#[test]
+fn stages_selected_lines_of_an_untracked_file() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.write("new.txt", "alpha\nbeta\ngamma");
+
+    let ids = hunk_ids(&repo, "new.txt", "unstaged");
+    let selection = git::LineSelection {
+        deletions: vec![],
+        additions: vec![1, 3],
+    };
+    git::stage_lines(&repo.repo, "new.txt", &ids[0], &selection).unwrap();
+    assert_eq!(staged_content(&repo, "new.txt"), "alpha\ngamma");
+}
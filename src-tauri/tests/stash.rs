@@ -0,0 +1,97 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig};
+use git2::{Signature, Time};
+
+#[test]
+fn lists_stashes_newest_first() {
+    let mut repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.branch("main", repo.repo.head().unwrap().target().unwrap());
+    repo.checkout("main");
+
+    let sig = Signature::new("Test", "test@example.com", &Time::new(1_700_000_100, 0)).unwrap();
+    repo.write("a.txt", "two\n");
+    repo.repo.stash_save(&sig, "first try", None).unwrap();
+    repo.write("a.txt", "three\n");
+    repo.repo.stash_save(&sig, "second try", None).unwrap();
+
+    let stashes = git::get_stashes(&mut repo.repo).unwrap().stashes;
+    let summary: Vec<_> = stashes
+        .iter()
+        .map(|s| (s.index, s.reference.as_str(), s.message.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (0, "stash@{0}", "second try"),
+            (1, "stash@{1}", "first try")
+        ]
+    );
+    assert!(stashes.iter().all(|s| s.branch.as_deref() == Some("main")));
+    assert_eq!(stashes[0].date, "2023-11-14T22:15:00Z");
+}
+
+#[test]
+fn stash_diff_includes_untracked_files() {
+    let mut repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+
+    let sig = Signature::new("Test", "test@example.com", &Time::new(1_700_000_100, 0)).unwrap();
+    repo.write("a.txt", "two\n");
+    repo.write("new.txt", "fresh\n");
+    repo.repo
+        .stash_save(
+            &sig,
+            "with untracked",
+            Some(git2::StashFlags::INCLUDE_UNTRACKED),
+        )
+        .unwrap();
+    assert!(!repo.workdir().join("new.txt").exists());
+
+    let diff = git::get_stash_diff(&repo.repo, 0, &DifferConfig::default()).unwrap();
+    let files: Vec<_> = diff
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.additions, f.deletions))
+        .collect();
+    assert_eq!(files, [("a.txt", 1, 1), ("new.txt", 1, 0)]);
+
+    assert!(git::get_stash_diff(&repo.repo, 1, &DifferConfig::default()).is_err());
+}
+
+#[test]
+fn pop_keeps_conflicting_stash() {
+    let mut repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "b\n");
+    repo.commit_all("first");
+
+    repo.write("a.txt", "two\n");
+    let saved = git::stash_save(&mut repo.repo, Some("shelve a"), false).unwrap();
+    assert_eq!(saved.message, "shelve a");
+    assert_eq!(
+        std::fs::read_to_string(repo.workdir().join("a.txt")).unwrap(),
+        "one\n"
+    );
+
+    repo.write("b.txt", "b2\n");
+    git::stash_save(&mut repo.repo, None, false).unwrap();
+    let clean = git::stash_apply(&mut repo.repo, 0, true).unwrap();
+    assert!(clean.dropped && clean.conflicts.is_empty());
+    repo.commit_all("b2");
+
+    repo.write("a.txt", "three\n");
+    repo.commit_all("second");
+    let result = git::stash_apply(&mut repo.repo, 0, true).unwrap();
+    assert!(!result.dropped);
+    assert_eq!(result.conflicts, ["a.txt"]);
+    assert_eq!(git::get_stashes(&mut repo.repo).unwrap().stashes.len(), 1);
+
+    let remaining = git::stash_drop(&mut repo.repo, 0).unwrap();
+    assert!(remaining.stashes.is_empty());
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+
+#[test]
+fn counts_changes_and_upstream_divergence() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.write("b.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.branch("main", first);
+    repo.checkout("main");
+
+    repo.repo
+        .remote("origin", "https://example.com/repo.git")
+        .unwrap();
+    repo.repo
+        .reference("refs/remotes/origin/main", first, true, "fetch")
+        .unwrap();
+    let mut config = repo.repo.config().unwrap();
+    config.set_str("branch.main.remote", "origin").unwrap();
+    config
+        .set_str("branch.main.merge", "refs/heads/main")
+        .unwrap();
+
+    repo.write("a.txt", "two\n");
+    repo.commit_all("second");
+
+    repo.write("a.txt", "three\n");
+    repo.stage("a.txt");
+    repo.write("b.txt", "two\n");
+    repo.write("new/one.txt", "x\n");
+    repo.write("new/two.txt", "x\n");
+
+    let line = git::get_status_line(&repo.repo).unwrap();
+    assert_eq!(line.branch.as_deref(), Some("main"));
+    assert!(!line.is_detached);
+    assert_eq!(
+        (line.staged, line.unstaged, line.untracked, line.conflicted),
+        (1, 1, 1, 0)
+    );
+    assert_eq!(line.upstream.as_deref(), Some("origin/main"));
+    assert_eq!((line.ahead, line.behind), (1, 0));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, svg::sanitize_svg};
+
+fn sanitize(markup: &str) -> String {
+    sanitize_svg(markup).expect("well-formed svg")
+}
+
+#[test]
+fn drops_scripts_styles_and_event_handlers() {
+    let out = sanitize(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" onload="alert(1)"><script>alert(2)</script><style>@import url(http://x/a.css);</style><rect width="10" onclick="alert(3)"/></svg>"#,
+    );
+    assert_eq!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg"><rect width="10"/></svg>"#
+    );
+}
+
+#[test]
+fn checks_attribute_values_after_decoding_entities() {
+    let out = sanitize(
+        r##"<svg><a href="javascript&#58;alert(1)"><text>a</text></a><a href="java&#x09;script:alert(1)"/><rect fill="&#117;rl(http://x/p)"/><a href="#ok"/></svg>"##,
+    );
+    assert_eq!(
+        out,
+        r##"<svg><a><text>a</text></a><a/><rect/><a href="#ok"/></svg>"##
+    );
+}
+
+#[test]
+fn allows_only_fragment_urls() {
+    let out = sanitize(
+        r##"<svg><rect fill="url(#a)"/><rect fill="url(#a) url(http://x)"/><rect style="fill: url('#b')"/><rect style="fill: u\72l(http://x)"/></svg>"##,
+    );
+    assert_eq!(
+        out,
+        r##"<svg><rect fill="url(#a)"/><rect/><rect style="fill: url('#b')"/><rect/></svg>"##
+    );
+}
+
+#[test]
+fn allows_inline_raster_images_but_not_nested_svg() {
+    let out = sanitize(
+        r#"<svg><image href="data:image/png;base64,AAAA"/><image href="data:image/svg+xml;base64,PHN2Zz4="/><image href="https://example.com/a.png"/></svg>"#,
+    );
+    assert_eq!(
+        out,
+        r#"<svg><image href="data:image/png;base64,AAAA"/><image/><image/></svg>"#
+    );
+}
+
+#[test]
+fn drops_animations_that_rewrite_links() {
+    let out = sanitize(
+        r##"<svg><a href="#x"><set attributeName="href" to="javascript:alert(1)"/><animate attributeName="opacity" to="0"/></a></svg>"##,
+    );
+    assert_eq!(
+        out,
+        r##"<svg><a href="#x"><animate attributeName="opacity" to="0"/></a></svg>"##
+    );
+}
+
+#[test]
+fn rejects_malformed_markup() {
+    assert_eq!(sanitize_svg("<svg><g></svg>"), None);
+}
+
+#[test]
+fn pairs_sanitized_markup_with_the_text_diff() {
+    let repo = TestRepo::new();
+    repo.write("icon.svg", "<svg><circle r=\"1\"/></svg>\n");
+    repo.commit_all("first");
+    repo.write(
+        "icon.svg",
+        "<svg><circle r=\"2\"/><script>alert(1)</script></svg>\n",
+    );
+
+    let pair = git::get_svg_render_pair(&repo.repo, "icon.svg", Some("HEAD"), None).unwrap();
+    assert_eq!(
+        pair.old_markup.as_deref(),
+        Some("<svg><circle r=\"1\"/></svg>\n")
+    );
+    assert_eq!(
+        pair.new_markup.as_deref(),
+        Some("<svg><circle r=\"2\"/></svg>\n")
+    );
+    assert_eq!((pair.additions, pair.deletions), (1, 1));
+    assert!(pair.patch.contains("+<svg><circle r=\"2\"/><script>"));
+}
@@ -0,0 +1,313 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::{self, DifferConfig};
+use git2::{Signature, Time};
+
+#[test]
+fn lists_lightweight_and_annotated_tags() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    let second = repo.commit_all("second");
+
+    let first_obj = repo.repo.find_object(first, None).unwrap();
+    repo.repo
+        .tag_lightweight("v0.1", &first_obj, false)
+        .unwrap();
+
+    let tagger = Signature::new(
+        "Release Bot",
+        "bot@example.com",
+        &Time::new(1_800_000_000, 0),
+    )
+    .unwrap();
+    let second_obj = repo.repo.find_object(second, None).unwrap();
+    repo.repo
+        .tag("v1.0", &second_obj, &tagger, "Release 1.0\n", false)
+        .unwrap();
+
+    let tags = git::get_tags(&repo.repo).unwrap().tags;
+    let names: Vec<_> = tags.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["v1.0", "v0.1"]);
+
+    let release = &tags[0];
+    assert!(release.annotated);
+    assert_eq!(release.sha, second.to_string());
+    assert_eq!(release.tagger.as_deref(), Some("Release Bot"));
+    assert_eq!(release.message.as_deref(), Some("Release 1.0"));
+    assert_eq!(release.date, "2027-01-15T08:00:00Z");
+
+    let light = &tags[1];
+    assert!(!light.annotated);
+    assert_eq!(light.sha, first.to_string());
+    assert!(light.tagger.is_none() && light.message.is_none());
+    assert_eq!(light.date, "2023-11-14T22:13:20Z");
+}
+
+#[test]
+fn compares_two_tags() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo.commit_all("fix: bump a\n\nlonger body");
+    repo.write("b.txt", "new\n");
+    let third = repo.commit_all("feat: add b");
+
+    let tagger = Signature::new(
+        "Release Bot",
+        "bot@example.com",
+        &Time::new(1_800_000_000, 0),
+    )
+    .unwrap();
+    for (name, oid) in [("v1", first), ("v2", third)] {
+        let obj = repo.repo.find_object(oid, None).unwrap();
+        repo.repo.tag(name, &obj, &tagger, name, false).unwrap();
+    }
+
+    let config = DifferConfig::default();
+    let result = git::compare_tags(&repo.repo, "v1", "refs/tags/v2", &config).unwrap();
+    assert_eq!(result.from.name, "v1");
+    assert_eq!(result.to.name, "v2");
+    let paths: Vec<_> = result
+        .comparison
+        .files
+        .iter()
+        .map(|f| f.path.as_str())
+        .collect();
+    assert_eq!(paths, ["a.txt", "b.txt"]);
+    let summaries: Vec<_> = result
+        .changelog
+        .iter()
+        .map(|c| c.summary.as_str())
+        .collect();
+    assert_eq!(summaries, ["fix: bump a", "feat: add b"]);
+
+    // Branches are not tags
+    assert!(git::compare_tags(&repo.repo, "v1", "HEAD", &config).is_err());
+}
+
+#[test]
+fn creates_lightweight_and_annotated_tags() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    let second = repo.commit_all("second");
+
+    let light = git::create_tag(&repo.repo, "v0.1", "HEAD~1", None, None).unwrap();
+    assert!(!light.annotated);
+    assert_eq!(light.sha, first.to_string());
+    let reference = repo.repo.find_reference("refs/tags/v0.1").unwrap();
+    assert_eq!(reference.target(), Some(first));
+
+    let annotated = git::create_tag(
+        &repo.repo,
+        "v1.0",
+        "master",
+        Some("Release 1.0\n\n\n"),
+        None,
+    )
+    .unwrap();
+    assert!(annotated.annotated);
+    assert_eq!(annotated.sha, second.to_string());
+    assert_eq!(annotated.tagger.as_deref(), Some("Test"));
+    assert_eq!(annotated.message.as_deref(), Some("Release 1.0"));
+    let tag = repo
+        .repo
+        .revparse_single("refs/tags/v1.0")
+        .unwrap()
+        .into_tag()
+        .unwrap();
+    assert_eq!(tag.message(), Some("Release 1.0\n"));
+}
+
+#[test]
+fn rejects_existing_and_invalid_tag_names() {
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    git::create_tag(&repo.repo, "v1", "HEAD", None, None).unwrap();
+
+    assert!(matches!(
+        git::create_tag(&repo.repo, "v1", "HEAD", Some("again"), None),
+        Err(git::GitError::TagExists(name)) if name == "v1"
+    ));
+    assert!(matches!(
+        git::create_tag(&repo.repo, "bad..name", "HEAD", None, None),
+        Err(git::GitError::InvalidTagName(_))
+    ));
+    assert!(matches!(
+        git::create_tag(&repo.repo, "v2", "HEAD", Some("  \n"), None),
+        Err(git::GitError::EmptyMessage)
+    ));
+    assert!(repo.repo.find_reference("refs/tags/v2").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn signs_annotated_tags() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    let head = repo.commit_all("first");
+
+    let program = repo.repo.path().join("fake-gpg");
+    std::fs::write(
+        &program,
+        "#!/bin/sh\ncat > /dev/null\necho '[GNUPG:] SIG_CREATED D 1 8 00 0 KEY' >&2\n\
+         printf -- '-----BEGIN PGP SIGNATURE-----\\nsigned by %s\\n-----END PGP SIGNATURE-----\\n' \"$3\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let mut config = repo.repo.config().unwrap();
+    config
+        .set_str("gpg.program", program.to_str().unwrap())
+        .unwrap();
+
+    // Signing needs something to sign
+    assert!(matches!(
+        git::create_tag(&repo.repo, "v1", "HEAD", None, Some(true)),
+        Err(git::GitError::EmptyMessage)
+    ));
+
+    let info = git::create_tag(&repo.repo, "v1", "HEAD", Some("Release"), Some(true)).unwrap();
+    assert!(info.annotated);
+    assert_eq!(info.sha, head.to_string());
+    assert_eq!(info.message.as_deref(), Some("Release"));
+
+    let tag = repo
+        .repo
+        .revparse_single("refs/tags/v1")
+        .unwrap()
+        .into_tag()
+        .unwrap();
+    assert_eq!(tag.target_id(), head);
+    assert_eq!(tag.name(), Some("v1"));
+    let odb = repo.repo.odb().unwrap();
+    let raw = odb.read(tag.id()).unwrap();
+    let raw = String::from_utf8_lossy(raw.data()).into_owned();
+    assert!(raw.contains("\ntag v1\ntagger Test <test@example.com> "));
+    assert!(raw.ends_with("signed by Test <test@example.com>\n-----END PGP SIGNATURE-----\n"));
+
+    // tag.gpgSign turns signing on when the caller doesn't say
+    config.set_bool("tag.gpgSign", true).unwrap();
+    git::create_tag(&repo.repo, "v2", "HEAD", Some("Next"), None).unwrap();
+    let raw = odb
+        .read(repo.repo.refname_to_id("refs/tags/v2").unwrap())
+        .unwrap();
+    assert!(String::from_utf8_lossy(raw.data()).contains("BEGIN PGP SIGNATURE"));
+}
+
+#[cfg(unix)]
+#[test]
+fn verifies_signed_tags_in_the_list() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = TestRepo::new();
+    repo.set_identity();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+
+    // Good only when gpg is handed the tag without its signature
+    let program = repo.repo.path().join("fake-gpg");
+    std::fs::write(
+        &program,
+        "#!/bin/sh\nif [ \"$3\" = --verify ]; then\n\
+         if grep -q 'BEGIN PGP' -; then echo '[GNUPG:] BADSIG 0123456789ABCDEF Test'; exit 1; fi\n\
+         echo '[GNUPG:] GOODSIG 0123456789ABCDEF Test <test@example.com>'\n\
+         echo '[GNUPG:] TRUST_ULTIMATE 0 pgp'\n  exit 0\nfi\n\
+         cat > /dev/null\necho '[GNUPG:] SIG_CREATED D 1 8 00 0 KEY' >&2\n\
+         printf -- '-----BEGIN PGP SIGNATURE-----\\nsig\\n-----END PGP SIGNATURE-----\\n'\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    repo.repo
+        .config()
+        .unwrap()
+        .set_str("gpg.program", program.to_str().unwrap())
+        .unwrap();
+
+    git::create_tag(&repo.repo, "v1", "HEAD", Some("Signed"), Some(true)).unwrap();
+    git::create_tag(&repo.repo, "v2", "HEAD", Some("Plain"), Some(false)).unwrap();
+    git::create_tag(&repo.repo, "v3", "HEAD", None, None).unwrap();
+
+    let tags = git::get_tags(&repo.repo).unwrap().tags;
+    let tag = |name: &str| tags.iter().find(|t| t.name == name).unwrap();
+
+    let signed = tag("v1").signature.as_ref().unwrap();
+    assert_eq!(signed.status, git::SignatureStatus::Good);
+    assert_eq!(signed.format.as_deref(), Some("openpgp"));
+    assert_eq!(signed.signer.as_deref(), Some("Test <test@example.com>"));
+    assert_eq!(signed.trust.as_deref(), Some("ultimate"));
+    assert_eq!(
+        signed.sha,
+        repo.repo.refname_to_id("refs/tags/v1").unwrap().to_string()
+    );
+    assert_eq!(tag("v1").message.as_deref(), Some("Signed"));
+    assert_eq!(tag("v1").tagger.as_deref(), Some("Test"));
+
+    assert!(tag("v2").signature.is_none());
+    assert!(tag("v3").signature.is_none());
+}
+
+#[test]
+fn release_delta_orders_tags_and_groups_changelog() {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    let first = repo.commit_all("first");
+    repo.write("a.txt", "two\n");
+    repo.commit_all("fix(a): bump a");
+    repo.write("b.txt", "new\n");
+    repo.commit_all("docs: describe b");
+    repo.write("c.txt", "new\n");
+    repo.commit_all("feat!: add c");
+    repo.write("a.txt", "three\n");
+    let last = repo.commit_all("Tidy up");
+
+    for (name, oid) in [("v1", first), ("v2", last)] {
+        let obj = repo.repo.find_object(oid, None).unwrap();
+        repo.repo.tag_lightweight(name, &obj, false).unwrap();
+    }
+
+    let config = DifferConfig::default();
+    let forward = git::get_release_delta(&repo.repo, "v1", "v2", &config).unwrap();
+    assert!(!forward.swapped);
+    let delta = git::get_release_delta(&repo.repo, "v2", "v1", &config).unwrap();
+    assert!(delta.swapped);
+    assert_eq!(delta.from.name, "v1");
+    assert_eq!(delta.to.name, "v2");
+
+    let paths: Vec<_> = delta
+        .comparison
+        .files
+        .iter()
+        .map(|f| f.path.as_str())
+        .collect();
+    assert_eq!(paths, ["a.txt", "b.txt", "c.txt"]);
+    assert_eq!(delta.changelog.len(), 4);
+
+    let sections: Vec<_> = delta
+        .sections
+        .iter()
+        .map(|s| {
+            let summaries: Vec<_> = s.entries.iter().map(|e| e.summary.as_str()).collect();
+            (s.kind.as_str(), summaries)
+        })
+        .collect();
+    assert_eq!(
+        sections,
+        [
+            ("feat", vec!["feat!: add c"]),
+            ("fix", vec!["fix(a): bump a"]),
+            ("docs", vec!["docs: describe b"]),
+            ("other", vec!["Tidy up"]),
+        ]
+    );
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git::WatcherSettings;
+use diffy_lib::watcher::FileWatcher;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The churn test counts the process's threads, so watchers from the other
+/// test mustn't come and go meanwhile
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn reports_changes_until_dropped() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+
+    let (tx, rx) = channel();
+    let watcher =
+        FileWatcher::with_handler(repo.workdir(), &WatcherSettings::default(), move |event| {
+            let _ = tx.send(event);
+        })
+        .unwrap();
+    assert!(watcher.status().active);
+
+    repo.write("a.txt", "two\n");
+    let event = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(event.event_type, "change");
+    assert_eq!(event.file.trim_start_matches('/'), "a.txt");
+
+    // Dropping joins the event thread, which lets go of the handler
+    drop(watcher);
+    loop {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(_) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => panic!("handler outlived the watcher"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn switching_repositories_leaves_no_threads_behind() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let thread_count = || std::fs::read_dir("/proc/self/task").unwrap().count();
+    let repos: Vec<TestRepo> = (0..3).map(|_| TestRepo::new()).collect();
+    let baseline = thread_count();
+
+    let mut current = None;
+    for round in 0..30 {
+        let settings = WatcherSettings {
+            polling: round % 2 == 1,
+            poll_interval_ms: 50,
+        };
+        // Replaced the way switching repositories replaces it
+        drop(current.take());
+        current = Some(
+            FileWatcher::with_handler(repos[round % repos.len()].workdir(), &settings, |_| {})
+                .unwrap(),
+        );
+    }
+    drop(current);
+
+    // notify's own threads wind down just after their watchers are dropped
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while thread_count() > baseline && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    // Test threads finishing meanwhile can only lower the count
+    assert!(thread_count() <= baseline);
+}
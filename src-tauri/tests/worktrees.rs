@@ -0,0 +1,216 @@
+mod common;
+
+use common::TestRepo;
+use diffy_lib::git;
+use git2::{Repository, Signature, Time};
+use std::path::Path;
+
+/// Commit a change to `file` in the repository at `path` at `time`
+fn commit_in(path: &Path, file: &str, time: i64) -> git2::Oid {
+    let repo = Repository::open(path).unwrap();
+    std::fs::write(path.join(file), file).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = Signature::new("Test", "test@example.com", &Time::new(time, 0)).unwrap();
+    let parent = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, file, &tree, &[&parent])
+        .unwrap()
+}
+
+/// Link a worktree at `<dir>/<name>` with a new branch `name` at HEAD
+fn link_worktree(repo: &TestRepo, dir: &Path, name: &str) -> std::path::PathBuf {
+    let head = repo.repo.head().unwrap().peel_to_commit().unwrap();
+    let branch = repo.repo.branch(name, &head, false).unwrap();
+    let path = dir.join(name);
+    let mut opts = git2::WorktreeAddOptions::new();
+    opts.reference(Some(branch.get()));
+    repo.repo.worktree(name, &path, Some(&opts)).unwrap();
+    path
+}
+
+fn main_repo() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.write("a.txt", "one\n");
+    repo.commit_all("first");
+    repo.rename_head("main");
+    repo
+}
+
+#[test]
+fn lists_linked_worktrees_most_recent_first() {
+    let repo = main_repo();
+    let dir = tempfile::tempdir().unwrap();
+    let feature = link_worktree(&repo, dir.path(), "feature");
+    let stale = link_worktree(&repo, dir.path(), "stale");
+    commit_in(&feature, "f1.txt", 1_800_000_000);
+    commit_in(&feature, "f2.txt", 1_800_000_100);
+    commit_in(repo.workdir(), "m.txt", 1_750_000_000);
+    commit_in(&stale, "s.txt", 1_600_000_000);
+
+    let list = git::get_worktrees(&repo.repo).unwrap();
+    let summary: Vec<(&str, bool, usize, usize)> = list
+        .worktrees
+        .iter()
+        .map(|w| {
+            (
+                w.branch.as_str(),
+                w.is_current,
+                w.ahead_of_main,
+                w.behind_main,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("feature", false, 2, 1),
+            ("main", true, 0, 0),
+            ("stale", false, 1, 1),
+        ]
+    );
+    assert_eq!(list.worktrees[0].last_activity, "2027-01-15T08:01:40Z");
+    assert_eq!(list.worktrees[1].path, list.current);
+    assert_eq!(
+        list.worktrees[0].path,
+        feature.to_string_lossy().trim_end_matches('/')
+    );
+}
+
+#[test]
+fn lists_the_same_worktrees_from_inside_a_linked_one() {
+    let repo = main_repo();
+    let dir = tempfile::tempdir().unwrap();
+    let feature = link_worktree(&repo, dir.path(), "feature");
+
+    let linked = Repository::open(&feature).unwrap();
+    let list = git::get_worktrees(&linked).unwrap();
+    let mut branches: Vec<&str> = list.worktrees.iter().map(|w| w.branch.as_str()).collect();
+    branches.sort();
+    assert_eq!(branches, vec!["feature", "main"]);
+    let current: Vec<&str> = list
+        .worktrees
+        .iter()
+        .filter(|w| w.is_current)
+        .map(|w| w.branch.as_str())
+        .collect();
+    assert_eq!(current, vec!["feature"]);
+}
+
+#[test]
+fn reports_detached_heads_and_skips_missing_directories() {
+    let repo = main_repo();
+    let dir = tempfile::tempdir().unwrap();
+    let detached = link_worktree(&repo, dir.path(), "detached");
+    let gone = link_worktree(&repo, dir.path(), "gone");
+
+    let linked = Repository::open(&detached).unwrap();
+    let head = linked.head().unwrap().target().unwrap();
+    linked.set_head_detached(head).unwrap();
+    std::fs::remove_dir_all(&gone).unwrap();
+
+    let list = git::get_worktrees(&repo.repo).unwrap();
+    let mut branches: Vec<&str> = list.worktrees.iter().map(|w| w.branch.as_str()).collect();
+    branches.sort();
+    assert_eq!(branches, vec!["(detached)", "main"]);
+}
+
+#[test]
+fn adds_worktrees_for_new_and_existing_branches() {
+    let repo = main_repo();
+    let first = repo.repo.head().unwrap().target().unwrap();
+    repo.write("a.txt", "two\n");
+    repo.commit_all("second");
+    repo.branch("existing", first);
+    let dir = tempfile::tempdir().unwrap();
+
+    let path = dir.path().join("topic");
+    let list =
+        git::add_worktree(&repo.repo, path.to_str().unwrap(), "topic", Some("HEAD~1")).unwrap();
+    let topic = list.worktrees.iter().find(|w| w.branch == "topic").unwrap();
+    assert_eq!(topic.behind_main, 1);
+    assert_eq!(
+        std::fs::read_to_string(path.join("a.txt")).unwrap(),
+        "one\n"
+    );
+
+    let path = dir.path().join("existing");
+    git::add_worktree(&repo.repo, path.to_str().unwrap(), "existing", None).unwrap();
+    let linked = Repository::open(&path).unwrap();
+    assert_eq!(linked.head().unwrap().target(), Some(first));
+
+    // A branch can only be checked out once, and directories must be empty
+    let again = dir.path().join("again");
+    assert!(git::add_worktree(&repo.repo, again.to_str().unwrap(), "topic", None).is_err());
+    let occupied = dir.path().join("occupied");
+    std::fs::create_dir(&occupied).unwrap();
+    std::fs::write(occupied.join("x"), "x").unwrap();
+    assert!(matches!(
+        git::add_worktree(&repo.repo, occupied.to_str().unwrap(), "new", None),
+        Err(git::GitError::Worktree(_))
+    ));
+}
+
+#[test]
+fn removes_clean_worktrees_unless_forced() {
+    let repo = main_repo();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("feature");
+    git::add_worktree(&repo.repo, path.to_str().unwrap(), "feature", None).unwrap();
+    std::fs::write(path.join("scratch.txt"), "wip").unwrap();
+
+    let dirty = git::remove_worktree(&repo.repo, path.to_str().unwrap(), false);
+    assert!(matches!(dirty, Err(git::GitError::Worktree(m)) if m.contains("uncommitted")));
+
+    repo.repo
+        .find_worktree("feature")
+        .unwrap()
+        .lock(Some("on a USB drive"))
+        .unwrap();
+    std::fs::remove_file(path.join("scratch.txt")).unwrap();
+    let locked = git::remove_worktree(&repo.repo, path.to_str().unwrap(), false);
+    assert!(matches!(locked, Err(git::GitError::Worktree(m)) if m.contains("USB drive")));
+
+    let list = git::remove_worktree(&repo.repo, path.to_str().unwrap(), true).unwrap();
+    assert_eq!(list.worktrees.len(), 1);
+    assert!(!path.exists());
+    // The branch itself is kept
+    assert!(repo
+        .repo
+        .find_branch("feature", git2::BranchType::Local)
+        .is_ok());
+
+    // The main worktree is not a linked one
+    assert!(git::remove_worktree(&repo.repo, repo.workdir().to_str().unwrap(), true).is_err());
+}
+
+#[test]
+fn prunes_entries_whose_directory_is_gone() {
+    let repo = main_repo();
+    let dir = tempfile::tempdir().unwrap();
+    let gone = link_worktree(&repo, dir.path(), "gone");
+    let kept = link_worktree(&repo, dir.path(), "kept");
+    let locked = link_worktree(&repo, dir.path(), "locked");
+    repo.repo
+        .find_worktree("locked")
+        .unwrap()
+        .lock(None)
+        .unwrap();
+    std::fs::remove_dir_all(&gone).unwrap();
+    std::fs::remove_dir_all(&locked).unwrap();
+
+    assert_eq!(git::prune_worktrees(&repo.repo).unwrap(), vec!["gone"]);
+    let mut names: Vec<String> = repo
+        .repo
+        .worktrees()
+        .unwrap()
+        .iter()
+        .flatten()
+        .map(String::from)
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["kept", "locked"]);
+    assert!(kept.exists());
+    assert!(git::prune_worktrees(&repo.repo).unwrap().is_empty());
+}